@@ -0,0 +1,53 @@
+//! 同一形状反复证明和验证时复用密钥的收益: 每次重新生成参数和密钥, 与经注册表的密钥缓存对比
+//!
+//! cargo bench --bench key_reuse -- [n]
+
+use std::time::{Duration, Instant};
+
+use halo2_fib::dsl::Statement;
+use halo2_fib::prover::{keygen_with_retry, prove, setup, verify};
+use halo2_fib::registry::CircuitRegistry;
+use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{keygen_vk, Circuit};
+
+const RUNS: u64 = 5;
+
+fn main() {
+    let n: usize = std::env::args().skip(1).find_map(|arg| arg.parse().ok()).unwrap_or(1000);
+
+    // 每次证明和验证都重新生成
+    let (mut fresh, mut fresh_verify) = (Duration::ZERO, Duration::ZERO);
+    for a in 1..=RUNS {
+        let witness = FibWitness::new(Fp::from(a), Fp::one());
+        let statement = FibStatement::from_witness(n, &witness).expect("n至少为3");
+        let circuit = FibCircuit::new(&statement, &witness);
+        let start = Instant::now();
+        let (k, params, pk) = keygen_with_retry(FibCircuit::min_k(n), 20, &circuit).expect("生成密钥失败");
+        let proof = prove(&params, &pk, &circuit, &statement.public_inputs()).expect("生成证明失败");
+        fresh += start.elapsed();
+        let start = Instant::now();
+        let params = setup(k);
+        let vk = keygen_vk(&params, &circuit.without_witnesses()).expect("生成验证密钥失败");
+        assert!(verify(&params, &vk, &statement.public_inputs(), &proof).is_valid());
+        fresh_verify += start.elapsed();
+    }
+
+    // 注册表按形状缓存, 只有第一次生成密钥
+    let registry = CircuitRegistry::builtin();
+    let (mut cached, mut cached_verify) = (Duration::ZERO, Duration::ZERO);
+    for a in 1..=RUNS {
+        let statement = Statement::parse(&format!("fib(n={}, a={})", n, a)).expect("命题不合法");
+        let start = Instant::now();
+        let proved = registry.prove(&statement).expect("生成证明失败");
+        cached += start.elapsed();
+        let start = Instant::now();
+        assert!(registry.verify(&statement, &proved.proof).expect("验证失败").is_valid());
+        cached_verify += start.elapsed();
+    }
+
+    let per_run = |time: Duration| time.as_secs_f64() * 1e3 / RUNS as f64;
+    println!("n = {}, {} 个命题", n, RUNS);
+    println!("每次生成密钥: {:>10.1} ms/证明 {:>10.1} ms/验证", per_run(fresh), per_run(fresh_verify));
+    println!("密钥缓存:     {:>10.1} ms/证明 {:>10.1} ms/验证 (证明密钥命中/未命中 {:?}, 验证密钥 {:?})", per_run(cached), per_run(cached_verify), registry.key_cache().stats(), registry.key_cache().vk_stats());
+}
@@ -0,0 +1,56 @@
+//! 见证生成耗时: 保留每一项单元格的`assign_sequence`与只保留最后一项的`assign_last`对比
+//!
+//! cargo bench --bench witness -- [n]
+
+use std::time::Duration;
+
+use halo2_fib::profile::profile;
+use halo2_fib::{FibChip, FibCircuit, FibConfig, FibStatement, FibWitness, SharedColumns};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+/// 改动前的写法: 收集全部单元格后只公开最后一项
+struct AllTermsCircuit {
+    a: Value<Fp>,
+    b: Value<Fp>,
+    n: usize,
+}
+
+impl Circuit<Fp> for AllTermsCircuit {
+    type Config = FibConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown(), n: self.n }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        FibChip::configure(meta, &shared)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fib = FibChip::construct(config);
+        let terms = fib.assign_sequence(layouter.namespace(|| "填写数列"), self.a, self.b, self.n)?;
+        fib.expose_public(layouter, &terms[terms.len() - 1], 0)
+    }
+}
+
+/// 多次合成取最短时间
+fn fastest<C: Circuit<Fp>>(circuit: &C, instances: &[Fp], runs: usize) -> Duration {
+    (0..runs).map(|_| profile(circuit, vec![instances.to_vec()]).expect("合成失败").total()).min().expect("至少运行一次")
+}
+
+fn main() {
+    let n = std::env::args().skip(1).find_map(|arg| arg.parse().ok()).unwrap_or(1 << 16);
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(n, &witness).expect("n至少为3");
+    let public_inputs = statement.public_inputs();
+
+    let all = fastest(&AllTermsCircuit { a: Value::known(Fp::one()), b: Value::known(Fp::one()), n }, &public_inputs, 5);
+    let last = fastest(&FibCircuit::new(&statement, &witness), &public_inputs, 5);
+    println!("n = {}", n);
+    println!("assign_sequence: {:>10.2} ms", all.as_secs_f64() * 1e3);
+    println!("assign_last:     {:>10.2} ms ({:+.1}%)", last.as_secs_f64() * 1e3, (last.as_secs_f64() / all.as_secs_f64() - 1.0) * 100.0);
+}
@@ -0,0 +1,192 @@
+//! 布隆过滤器成员证明: 证明私有元素x在过滤器中, 过滤器的256位作为固定查找表写进验证密钥
+//!
+//! - 第i个下标是 H(x, i) 的最低字节: 门约束 h = low + 256 * high,
+//!   high经31个limb的范围检查并且不超过 (p - 1) / 256 - 1, 所以 low + 256 * high < p, 分解唯一
+//! - (low, 1)查(下标, 位)表. 表里另有一行(0, 0)给未启用的行用, 启用的行要求位为1, 不受影响
+//! - 公开x的承诺 H(x, salt)
+//!
+//! 用法: cargo run --release --example bloom_filter
+
+use ff::{Field, PrimeField};
+use halo2_fib::gadgets::comparison::{ComparisonChip, ComparisonConfig};
+use halo2_fib::gadgets::poseidon::{hash2, PoseidonChip, PoseidonConfig};
+use halo2_fib::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, Expression, Selector, TableColumn};
+use halo2_proofs::poly::Rotation;
+
+/// 三次下标各有两次31个limb的范围检查, 加上四次Poseidon哈希不到1024行
+const K: u32 = 10;
+/// 过滤器的位数, 下标取哈希的最低字节
+const BITS: usize = 256;
+/// 哈希函数个数
+const HASHES: u64 = 3;
+const HIGH_LIMBS: usize = 31;
+
+/// x的各个下标
+fn indices(x: Fp) -> Vec<usize> {
+    (0..HASHES).map(|i| hash2(x, Fp::from(i)).to_repr()[0] as usize).collect()
+}
+
+/// high的上界, 使 low + 256 * high <= p - 2
+fn high_bound() -> Fp {
+    -Fp::one() * Fp::from(256).invert().unwrap() - Fp::one()
+}
+
+#[derive(Clone, Debug)]
+struct BloomConfig {
+    shared: SharedColumns,
+    /// advice列依次为h、low、high
+    q_split: Selector,
+    index_table: TableColumn,
+    bit_table: TableColumn,
+    range: RangeCheckConfig,
+    cmp: ComparisonConfig,
+    poseidon: PoseidonConfig,
+}
+
+/// 实例列为x的承诺; 过滤器是电路的一部分, 不同的过滤器对应不同的验证密钥
+struct BloomCircuit {
+    filter: [bool; BITS],
+    x: Value<Fp>,
+    salt: Value<Fp>,
+}
+
+impl BloomCircuit {
+    /// 把元素加入过滤器
+    fn insert(filter: &mut [bool; BITS], x: Fp) {
+        for index in indices(x) {
+            filter[index] = true;
+        }
+    }
+
+    /// 取哈希的最低字节并查过滤器
+    fn check_bit(config: &BloomConfig, mut layouter: impl Layouter<Fp>, h: &AssignedCell<Fp, Fp>, bound: &AssignedCell<Fp, Fp>) -> Result<(), Error> {
+        let [h_col, low_col, high_col] = config.shared.advice;
+        let high = layouter.assign_region(|| "取最低字节", |mut region| {
+            config.q_split.enable(&mut region, 0)?;
+            let h = h.copy_advice(|| "拷贝h", &mut region, h_col, 0)?;
+            let low = h.value().map(|h| Fp::from(h.to_repr()[0] as u64));
+            region.assign_advice(|| "low", low_col, 0, || low)?;
+            let high = (h.value().copied() - low).map(|v| v * Fp::from(256).invert().unwrap());
+            region.assign_advice(|| "high", high_col, 0, || high)
+        })?;
+        RangeCheckChip::construct(config.range).copy_check(layouter.namespace(|| "high的范围"), &high, HIGH_LIMBS)?;
+        ComparisonChip::construct(config.cmp).assert_ge(layouter.namespace(|| "high不超过上界"), bound, &high, HIGH_LIMBS)
+    }
+}
+
+impl Circuit<Fp> for BloomCircuit {
+    type Config = BloomConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { filter: self.filter, x: Value::unknown(), salt: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let range = RangeCheckChip::configure(meta, &shared);
+        let cmp = ComparisonChip::configure(meta, &shared, range);
+        let poseidon = PoseidonChip::configure(meta, &shared);
+
+        let q_split = meta.complex_selector();
+        let index_table = meta.lookup_table_column();
+        let bit_table = meta.lookup_table_column();
+        let [h, low, high] = shared.advice;
+        meta.create_gate("low_byte", |meta| {
+            let q = meta.query_selector(q_split);
+            let h = meta.query_advice(h, Rotation::cur());
+            let low = meta.query_advice(low, Rotation::cur());
+            let high = meta.query_advice(high, Rotation::cur());
+            vec![("h = low + 256 * high", q * (h - low - high * Expression::Constant(Fp::from(256))))]
+        });
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_split);
+            let low = meta.query_advice(low, Rotation::cur());
+            vec![(q.clone() * low, index_table), (q, bit_table)]
+        });
+        BloomConfig { shared, q_split, index_table, bit_table, range, cmp, poseidon }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        RangeCheckChip::<Fp>::construct(config.range).load_table(layouter.namespace(|| "加载查找表"))?;
+        layouter.assign_table(|| "过滤器", |mut table| {
+            table.assign_cell(|| "下标", config.index_table, 0, || Value::known(Fp::zero()))?;
+            table.assign_cell(|| "位", config.bit_table, 0, || Value::known(Fp::zero()))?;
+            for (i, bit) in self.filter.iter().enumerate() {
+                table.assign_cell(|| "下标", config.index_table, i + 1, || Value::known(Fp::from(i as u64)))?;
+                table.assign_cell(|| "位", config.bit_table, i + 1, || Value::known(Fp::from(*bit as u64)))?;
+            }
+            Ok(())
+        })?;
+
+        let (x, salt, bound) = layouter.assign_region(|| "输入", |mut region| {
+            let x = region.assign_advice(|| "x", config.shared.advice[0], 0, || self.x)?;
+            let salt = region.assign_advice(|| "salt", config.shared.advice[1], 0, || self.salt)?;
+            let bound = region.assign_advice_from_constant(|| "high的上界", config.shared.advice[2], 0, high_bound())?;
+            Ok((x, salt, bound))
+        })?;
+        for i in 0..HASHES {
+            let tag = layouter.assign_region(|| "哈希编号", |mut region| region.assign_advice_from_constant(|| "i", config.shared.advice[0], 0, Fp::from(i)))?;
+            let h = poseidon.hash2(layouter.namespace(|| "H(x, i)"), &x, &tag)?;
+            Self::check_bit(&config, layouter.namespace(|| "查过滤器"), &h, &bound)?;
+        }
+
+        let commitment = poseidon.hash2(layouter.namespace(|| "承诺"), &x, &salt)?;
+        layouter.constrain_instance(commitment.cell(), config.shared.instance, 0)
+    }
+}
+
+fn main() {
+    let mut filter = [false; BITS];
+    for x in [3u64, 17, 42, 1001, 65537] {
+        BloomCircuit::insert(&mut filter, Fp::from(x));
+    }
+    println!("过滤器中有 {} 位为1", filter.iter().filter(|&&bit| bit).count());
+
+    let salt = Fp::from(0xb100);
+    let x = Fp::from(42);
+    let circuit = BloomCircuit { filter, x: Value::known(x), salt: Value::known(salt) };
+    let instance = vec![hash2(x, salt)];
+    let prover = MockProver::run(K, &circuit, vec![instance.clone()]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+
+    // 取一个不会误判的元素
+    let absent = (100u64..).map(Fp::from).find(|&y| indices(y).iter().any(|&i| !filter[i])).expect("找不到不在过滤器中的元素");
+    let circuit_absent = BloomCircuit { filter, x: Value::known(absent), salt: Value::known(salt) };
+    let prover = MockProver::run(K, &circuit_absent, vec![vec![hash2(absent, salt)]]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "不在过滤器中的元素通过了检查");
+
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit, &instance).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &instance, &proof).into_result().expect("验证失败");
+    println!("证明 {} 字节", proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 过滤器固定在验证密钥里; 偶数组取过滤器中的元素, 奇数组取随机元素, 按电路外查过滤器的结果计数
+    let mut filter = [false; BITS];
+    let members = [3u64, 17, 42, 1001, 65537].map(Fp::from);
+    for x in members {
+        BloomCircuit::insert(&mut filter, x);
+    }
+    let (mut case, mut expected) = (0, 0);
+    let accepted = check_differential(K, 4, |rng| {
+        case += 1;
+        let x = if case % 2 == 0 { members[rng.next_u32() as usize % members.len()] } else { Fp::random(&mut *rng) };
+        expected += indices(x).iter().all(|&i| filter[i]) as usize;
+        let salt = Fp::from(rng.next_u64());
+        (BloomCircuit { filter, x: Value::known(x), salt: Value::known(salt) }, vec![hash2(x, salt)])
+    });
+    assert_eq!(accepted, expected);
+}
@@ -0,0 +1,150 @@
+//! ChaCha20四分之一轮示例: 用按位运算芯片和32位字芯片证明一次ARX(加法、循环移位、异或)计算
+//!
+//! 四个输入字和四个输出字都公开, 按RFC 7539第2.1.1节的测试向量检查
+//!
+//! 用法: cargo run --release --example chacha20
+
+use halo2_fib::gadgets::bitwise::{BitOp, BitwiseChip, BitwiseConfig};
+use halo2_fib::gadgets::word32::{WordChip, WordConfig, WordOp};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+/// 查找表占768行
+const K: u32 = 10;
+
+/// 电路外的四分之一轮
+fn quarter_round([mut a, mut b, mut c, mut d]: [u32; 4]) -> [u32; 4] {
+    a = a.wrapping_add(b); d ^= a; d = d.rotate_left(16);
+    c = c.wrapping_add(d); b ^= c; b = b.rotate_left(12);
+    a = a.wrapping_add(b); d ^= a; d = d.rotate_left(8);
+    c = c.wrapping_add(d); b ^= c; b = b.rotate_left(7);
+    [a, b, c, d]
+}
+
+#[derive(Clone, Debug)]
+struct ChaChaConfig {
+    shared: SharedColumns,
+    word: WordConfig,
+    bitwise: BitwiseConfig,
+}
+
+/// 实例列依次为输入a、b、c、d和输出a、b、c、d
+struct QuarterRoundCircuit {
+    state: Value<[u32; 4]>,
+}
+
+type Word = AssignedCell<Fp, Fp>;
+
+struct ArxChips {
+    word: WordChip<Fp>,
+    bitwise: BitwiseChip<Fp>,
+}
+
+impl ArxChips {
+    /// 拆成字节逐字节异或后再拼回
+    fn xor(&self, mut layouter: impl Layouter<Fp>, x: &Word, y: &Word) -> Result<Word, Error> {
+        let xs = self.word.to_bytes(layouter.namespace(|| "拆分x"), x)?;
+        let ys = self.word.to_bytes(layouter.namespace(|| "拆分y"), y)?;
+        let zs = self.bitwise.op_bytes(layouter.namespace(|| "逐字节异或"), BitOp::Xor, &xs, &ys)?;
+        let zs: [Word; 4] = zs.try_into().expect("异或结果不是4个字节");
+        self.word.from_bytes(layouter.namespace(|| "拼接结果"), &zs)
+    }
+
+    /// x += y; z ^= x; z <<<= r
+    fn step(&self, mut layouter: impl Layouter<Fp>, x: &Word, y: &Word, z: &Word, r: u32) -> Result<(Word, Word), Error> {
+        let x = self.word.add(layouter.namespace(|| "相加"), x, y)?;
+        let z = self.xor(layouter.namespace(|| "异或"), z, &x)?;
+        let z = self.word.apply(layouter.namespace(|| "循环左移"), WordOp::RotateLeft(r), &z)?;
+        Ok((x, z))
+    }
+}
+
+impl Circuit<Fp> for QuarterRoundCircuit {
+    type Config = ChaChaConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { state: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let word = WordChip::configure(meta, &shared);
+        let bitwise = BitwiseChip::configure(meta, &shared);
+        ChaChaConfig { shared, word, bitwise }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let chips = ArxChips { word: WordChip::construct(config.word), bitwise: BitwiseChip::construct(config.bitwise) };
+        chips.bitwise.load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let mut state = Vec::with_capacity(4);
+        for i in 0..4 {
+            state.push(chips.word.witness(layouter.namespace(|| "输入字"), self.state.map(|s| s[i]))?);
+        }
+        for (row, cell) in state.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.shared.instance, row)?;
+        }
+
+        let [a, b, c, d]: [Word; 4] = state.try_into().expect("状态不是4个字");
+        let (a, d) = chips.step(layouter.namespace(|| "a += b; d ^= a; d <<<= 16"), &a, &b, &d, 16)?;
+        let (c, b) = chips.step(layouter.namespace(|| "c += d; b ^= c; b <<<= 12"), &c, &d, &b, 12)?;
+        let (a, d) = chips.step(layouter.namespace(|| "a += b; d ^= a; d <<<= 8"), &a, &b, &d, 8)?;
+        let (c, b) = chips.step(layouter.namespace(|| "c += d; b ^= c; b <<<= 7"), &c, &d, &b, 7)?;
+
+        for (row, cell) in [a, b, c, d].iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.shared.instance, 4 + row)?;
+        }
+        Ok(())
+    }
+}
+
+fn public_inputs(input: [u32; 4], output: [u32; 4]) -> Vec<Fp> {
+    input.iter().chain(&output).map(|&w| Fp::from(w as u64)).collect()
+}
+
+fn main() {
+    // RFC 7539 2.1.1
+    let input = [0x11111111, 0x01020304, 0x9b8d6f43, 0x01234567];
+    let expected = [0xea2a92f4, 0xcb1cf8ce, 0x4581472e, 0x5881c4bb];
+    assert_eq!(quarter_round(input), expected, "电路外的四分之一轮与测试向量不符");
+
+    let circuit = QuarterRoundCircuit { state: Value::known(input) };
+    let public_inputs = public_inputs(input, expected);
+    let prover = MockProver::run(K, &circuit, vec![public_inputs.clone()]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+
+    let mut wrong = public_inputs.clone();
+    wrong[7] += Fp::one();
+    let prover = MockProver::run(K, &circuit, vec![wrong.clone()]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "错误的输出通过了检查");
+
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit, &public_inputs).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &public_inputs, &proof).into_result().expect("验证失败");
+    assert!(!verify(&params, pk.get_vk(), &wrong, &proof).is_valid());
+    println!("四分之一轮: {:08x?} -> {:08x?}, 证明 {} 字节", input, expected, proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 随机输入, 奇数组翻转某个输出字的一位
+    let mut case = 0;
+    let accepted = check_differential(K, 4, |rng| {
+        let input = [(); 4].map(|_| rng.next_u32());
+        let mut output = quarter_round(input);
+        case += 1;
+        if case % 2 == 1 {
+            output[rng.next_u32() as usize % 4] ^= 1 << (rng.next_u32() % 32);
+        }
+        (QuarterRoundCircuit { state: Value::known(input) }, public_inputs(input, output))
+    });
+    assert_eq!(accepted, 2);
+}
@@ -0,0 +1,176 @@
+//! 承诺/揭示示例: 先证明"我知道一个承诺所对应的(n, F(n))", 之后再用另一个电路打开承诺
+//!
+//! - 承诺电路: 公开 commitment = Poseidon(Poseidon(n, F(n)), r), F(n)和随机数r都是私有的
+//! - 揭示电路: 公开commitment、n、F(n)以及nullifier = Poseidon(r, 1), 同一个承诺只能揭示出同一个nullifier, 可以用来防止重复揭示
+//!
+//! 注意电路形状由n决定, 验证密钥本身就暴露了n, 承诺真正隐藏的是F(n)(当a、b私有时)
+//!
+//! 用法: cargo run --release --example commit_reveal -- [n]
+
+use ff::Field;
+use halo2_fib::fib::{FibChip, FibConfig};
+use halo2_fib::gadgets::poseidon::{hash2, PoseidonChip, PoseidonConfig};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::{FibStatement, FibWitness, SharedColumns};
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+use rand_core::OsRng;
+
+/// nullifier的域分隔常量
+const NULLIFIER_DOMAIN: u64 = 1;
+
+#[derive(Clone, Debug)]
+struct CommitRevealConfig {
+    shared: SharedColumns,
+    fib: FibConfig,
+    poseidon: PoseidonConfig,
+}
+
+fn configure(meta: &mut ConstraintSystem<Fp>) -> CommitRevealConfig {
+    let shared = SharedColumns::configure(meta);
+    let fib = FibChip::configure(meta, &shared);
+    let poseidon = PoseidonChip::configure(meta, &shared);
+    CommitRevealConfig { shared, fib, poseidon }
+}
+
+fn commitment(n: usize, target: Fp, r: Fp) -> Fp {
+    hash2(hash2(Fp::from(n as u64), target), r)
+}
+
+fn nullifier(r: Fp) -> Fp {
+    hash2(r, Fp::from(NULLIFIER_DOMAIN))
+}
+
+/// 在电路内计算承诺, 两个电路共用
+fn commit_cells(config: &CommitRevealConfig, mut layouter: impl Layouter<Fp>, n: &AssignedCell<Fp, Fp>, target: &AssignedCell<Fp, Fp>, r: &AssignedCell<Fp, Fp>) -> Result<AssignedCell<Fp, Fp>, Error> {
+    let poseidon = PoseidonChip::construct(config.poseidon.clone());
+    let inner = poseidon.hash2(layouter.namespace(|| "哈希(n, F(n))"), n, target)?;
+    poseidon.hash2(layouter.namespace(|| "加入随机数"), &inner, r)
+}
+
+/// 承诺电路, 实例列只有一行commitment
+struct CommitCircuit {
+    witness: Value<FibWitness<Fp>>,
+    n: usize,
+    r: Value<Fp>,
+}
+
+impl Circuit<Fp> for CommitCircuit {
+    type Config = CommitRevealConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { witness: Value::unknown(), n: self.n, r: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fib = FibChip::construct(config.fib);
+        let terms = fib.assign_sequence(layouter.namespace(|| "填写数列"), self.witness.map(|w| w.a), self.witness.map(|w| w.b), self.n)?;
+        let (n, r) = layouter.assign_region(|| "加载n和随机数", |mut region| {
+            let n = region.assign_advice_from_constant(|| "n", config.shared.advice[0], 0, Fp::from(self.n as u64))?;
+            let r = region.assign_advice(|| "r", config.shared.advice[1], 0, || self.r)?;
+            Ok((n, r))
+        })?;
+        let commitment = commit_cells(&config, layouter.namespace(|| "承诺"), &n, &terms[self.n - 1], &r)?;
+        fib.expose_public(layouter.namespace(|| "暴露承诺"), &commitment, 0)
+    }
+}
+
+/// 揭示电路, 实例列依次为commitment、n、F(n)、nullifier
+struct RevealCircuit {
+    r: Value<Fp>,
+}
+
+impl Circuit<Fp> for RevealCircuit {
+    type Config = CommitRevealConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { r: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fib = FibChip::construct(config.fib);
+        let (n, target, r, domain) = layouter.assign_region(|| "加载揭示的值", |mut region| {
+            let [a, b, c] = config.shared.advice;
+            let n = region.assign_advice_from_instance(|| "n", config.shared.instance, 1, a, 0)?;
+            let target = region.assign_advice_from_instance(|| "F(n)", config.shared.instance, 2, b, 0)?;
+            let r = region.assign_advice(|| "r", c, 0, || self.r)?;
+            let domain = region.assign_advice_from_constant(|| "域分隔", a, 1, Fp::from(NULLIFIER_DOMAIN))?;
+            Ok((n, target, r, domain))
+        })?;
+        let commitment = commit_cells(&config, layouter.namespace(|| "重算承诺"), &n, &target, &r)?;
+        fib.expose_public(layouter.namespace(|| "约束承诺"), &commitment, 0)?;
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let nullifier = poseidon.hash2(layouter.namespace(|| "nullifier"), &r, &domain)?;
+        fib.expose_public(layouter.namespace(|| "暴露nullifier"), &nullifier, 3)
+    }
+}
+
+fn main() {
+    let n: usize = std::env::args().nth(1).map(|s| s.parse().expect("n必须是整数")).unwrap_or(20);
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(n, &witness).expect("n至少为3");
+    let r = Fp::random(OsRng);
+    let commitment = commitment(n, statement.target, r);
+    let params = setup(10);
+
+    // 第一步: 只公开承诺
+    let circuit = CommitCircuit { witness: Value::known(witness), n, r: Value::known(r) };
+    let pk = keygen(&params, &circuit).expect("生成承诺电路密钥失败");
+    let proof = prove(&params, &pk, &circuit, &[commitment]).expect("生成承诺证明失败");
+    verify(&params, pk.get_vk(), &[commitment], &proof).into_result().expect("验证承诺证明失败");
+    println!("承诺: {:?}", commitment);
+
+    // 第二步: 打开承诺, 公开n、F(n)和nullifier
+    let circuit = RevealCircuit { r: Value::known(r) };
+    let public_inputs = [commitment, Fp::from(n as u64), statement.target, nullifier(r)];
+    let pk = keygen(&params, &circuit).expect("生成揭示电路密钥失败");
+    let proof = prove(&params, &pk, &circuit, &public_inputs).expect("生成揭示证明失败");
+    verify(&params, pk.get_vk(), &public_inputs, &proof).into_result().expect("验证揭示证明失败");
+    println!("揭示: n = {}, F(n) = {:?}, nullifier = {:?}", n, statement.target, public_inputs[3]);
+
+    // 揭示成其他值时验证失败
+    let mut forged = public_inputs;
+    forged[2] += Fp::one();
+    assert!(!verify(&params, pk.get_vk(), &forged, &proof).is_valid());
+    println!("篡改F(n)后验证失败");
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 承诺电路: 随机初值和随机数, 奇数组公开另一个随机数的承诺
+    let n = 20;
+    let mut case = 0;
+    let accepted = check_differential(10, 4, |rng| {
+        let witness = FibWitness::new(Fp::random(&mut *rng), Fp::random(&mut *rng));
+        let target = FibStatement::from_witness(n, &witness).expect("n至少为3").target;
+        let r = Fp::random(&mut *rng);
+        case += 1;
+        let committed = if case % 2 == 0 { r } else { Fp::random(&mut *rng) };
+        (CommitCircuit { witness: Value::known(witness), n, r: Value::known(r) }, vec![commitment(n, target, committed)])
+    });
+    assert_eq!(accepted, 2);
+
+    // 揭示电路: 奇数组揭示的F(n)加一
+    let mut case = 0;
+    let accepted = check_differential(10, 4, |rng| {
+        let target = Fp::random(&mut *rng);
+        let r = Fp::random(&mut *rng);
+        case += 1;
+        let revealed = if case % 2 == 0 { target } else { target + Fp::one() };
+        (RevealCircuit { r: Value::known(r) }, vec![commitment(n, target, r), Fp::from(n as u64), revealed, nullifier(r)])
+    });
+    assert_eq!(accepted, 2);
+}
@@ -0,0 +1,45 @@
+//! 端到端跑一遍 参数生成 -> 密钥生成 -> 证明 -> 验证, 打印每个阶段的耗时和证明大小
+//!
+//! 用法: cargo run --release --example e2e -- [n]
+
+use std::time::{Duration, Instant};
+
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::Error;
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let out = f();
+    (out, start.elapsed())
+}
+
+fn main() {
+    let n: usize = std::env::args().nth(1).map(|s| s.parse().expect("n必须是整数")).unwrap_or(1000);
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(n, &witness).expect("n至少为3");
+    let circuit = FibCircuit::new(&statement, &witness);
+    let public_inputs = statement.public_inputs();
+
+    // 从扣除盲化行后能放下n-2行的最小k开始, 行数不够时再加一
+    let mut k = FibCircuit::min_k(n);
+    let ((params, pk), setup_time, keygen_time) = loop {
+        let (params, setup_time) = timed(|| setup(k));
+        match timed(|| keygen(&params, &circuit)) {
+            (Ok(pk), keygen_time) => break ((params, pk), setup_time, keygen_time),
+            (Err(Error::NotEnoughRowsAvailable { .. }), _) => k += 1,
+            (Err(e), _) => panic!("生成密钥失败: {:?}", e),
+        }
+    };
+    let (proof, prove_time) = timed(|| prove(&params, &pk, &circuit, &public_inputs).expect("生成证明失败"));
+    let (result, verify_time) = timed(|| verify(&params, pk.get_vk(), &public_inputs, &proof));
+    result.into_result().expect("验证失败");
+
+    println!("n = {}, k = {}", n, k);
+    println!("{:<12}{:>12}", "阶段", "耗时(ms)");
+    for (phase, time) in [("参数生成", setup_time), ("密钥生成", keygen_time), ("证明", prove_time), ("验证", verify_time)] {
+        println!("{:<12}{:>12.1}", phase, time.as_secs_f64() * 1000.0);
+    }
+    println!("证明大小: {} 字节", proof.len());
+}
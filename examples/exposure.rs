@@ -0,0 +1,141 @@
+//! 公开整条斐波那契轨迹: 比较`constrain_instance`与用门公开的开销
+//!
+//! 轨迹放在一列中, 门约束 t[i] + t[i+1] = t[i+2], 第i行公开到实例列的第i行.
+//! 拷贝约束的做法要让轨迹列和实例列都进置换, 每个公开值一条边; 用门公开时置换为空
+//!
+//! 用法: cargo run --release --example exposure
+
+use std::time::Instant;
+
+use halo2_fib::cost::CircuitStats;
+use halo2_fib::gadgets::public_gate::{PublicGateChip, PublicGateConfig};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, Selector};
+use halo2_proofs::poly::Rotation;
+
+/// 公开前n项的轨迹电路, GATE为true时用门公开
+struct TraceCircuit<const GATE: bool> {
+    n: usize,
+}
+
+impl<const GATE: bool> Circuit<Fp> for TraceCircuit<GATE> {
+    type Config = (PublicGateConfig, Selector);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { n: self.n }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let trace = meta.advice_column();
+        let instance = meta.instance_column();
+        let q_fib = meta.selector();
+        meta.create_gate("trace_add", |meta| {
+            let q = meta.query_selector(q_fib);
+            let t0 = meta.query_advice(trace, Rotation::cur());
+            let t1 = meta.query_advice(trace, Rotation::next());
+            let t2 = meta.query_advice(trace, Rotation(2));
+            vec![("t0 + t1 = t2", q * (t0 + t1 - t2))]
+        });
+        if !GATE {
+            meta.enable_equality(trace);
+            meta.enable_equality(instance);
+        }
+        // 拷贝约束的做法也配置这个门, 只是不打开选择器, 两边列数相同
+        (PublicGateChip::configure(meta, trace, instance), q_fib)
+    }
+
+    fn synthesize(&self, (config, q_fib): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let chip = PublicGateChip::construct(config);
+        let terms = trace(self.n);
+        // 轨迹是唯一的区域, 从第0行开始
+        let cells = layouter.assign_region(|| "轨迹", |mut region| {
+            let mut cells = Vec::with_capacity(self.n);
+            for (row, term) in terms.iter().enumerate() {
+                if row + 2 < self.n {
+                    q_fib.enable(&mut region, row)?;
+                }
+                cells.push(region.assign_advice(|| "项", config.value, row, || Value::known(*term))?);
+                if GATE {
+                    chip.expose_in_place(&mut region, row)?;
+                }
+            }
+            Ok(cells)
+        })?;
+        if !GATE {
+            for (row, cell) in cells.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn trace(n: usize) -> Vec<Fp> {
+    let mut terms = vec![Fp::one(), Fp::one()];
+    while terms.len() < n {
+        terms.push(terms[terms.len() - 1] + terms[terms.len() - 2]);
+    }
+    terms.truncate(n);
+    terms
+}
+
+/// 返回(证明毫秒数, 验证毫秒数, 证明字节数)
+fn measure<const GATE: bool>(k: u32, n: usize) -> (u128, u128, usize) {
+    let circuit = TraceCircuit::<GATE> { n };
+    let instance = trace(n);
+    let prover = MockProver::run(k, &circuit, vec![instance.clone()]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+
+    let params = setup(k);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let start = Instant::now();
+    let proof = prove(&params, &pk, &circuit, &instance).expect("生成证明失败");
+    let prove_ms = start.elapsed().as_millis();
+    let start = Instant::now();
+    verify(&params, pk.get_vk(), &instance, &proof).into_result().expect("验证失败");
+    (prove_ms, start.elapsed().as_millis(), proof.len())
+}
+
+fn main() {
+    let copy = CircuitStats::of::<TraceCircuit<false>>();
+    let gate = CircuitStats::of::<TraceCircuit<true>>();
+    println!("置换列数: 拷贝约束 {}, 用门 {}", copy.permutation_columns, gate.permutation_columns);
+
+    println!("{:>3} {:>6} {:>14} {:>14} {:>12}", "k", "n", "证明(拷贝/门)", "验证(拷贝/门)", "字节(拷贝/门)");
+    for k in [8, 12, 14] {
+        // 留出盲化行
+        let n = (1 << k) - 16;
+        let (copy_prove, copy_verify, copy_len) = measure::<false>(k, n);
+        let (gate_prove, gate_verify, gate_len) = measure::<true>(k, n);
+        println!(
+            "{:>3} {:>6} {:>7}/{:<6} {:>7}/{:<6} {:>6}/{:<5}",
+            k, n, copy_prove, gate_prove, copy_verify, gate_verify, copy_len, gate_len
+        );
+    }
+}
+
+#[test]
+fn test_differential() {
+    // 奇数组随机改一个公开值, 两种公开方式都检查
+    fn check<const GATE: bool>() {
+        use halo2_fib::testing::check_differential;
+
+        let n = (1 << 8) - 16;
+        let mut case = 0;
+        let accepted = check_differential(8, 4, |rng| {
+            let mut instance = trace(n);
+            case += 1;
+            if case % 2 == 1 {
+                instance[rng.next_u32() as usize % n] += Fp::one();
+            }
+            (TraceCircuit::<GATE> { n }, instance)
+        });
+        assert_eq!(accepted, 2);
+    }
+    check::<false>();
+    check::<true>();
+}
@@ -0,0 +1,121 @@
+//! 斐波那契数列的整除性质: m | n 时 F(m) | F(n)
+//!
+//! 电路从常量1, 1开始用斐波那契芯片算到第n项, 再用带余除法芯片证明F(m)整除F(n), 公开商F(n) / F(m).
+//! n <= 93时各项小于2^64, 用8个limb做除法不会回绕.
+//! 反过来, F(n)为素数时n必为素数(n = 4除外), 所以"F(m) | F(n)且1 < F(m) < F(n)"也是F(n)不是素数的证据
+//!
+//! 用法: cargo run --release --example fib_divisibility
+
+use halo2_fib::fib::{FibChip, FibConfig};
+use halo2_fib::gadgets::division::{DivisionChip, DivisionConfig};
+use halo2_fib::gadgets::range_check::RangeCheckChip;
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+const K: u32 = 9;
+
+/// 小于2^64的数
+const LIMBS: usize = 8;
+
+#[derive(Clone, Debug)]
+struct DivisibilityConfig {
+    fib: FibConfig,
+    division: DivisionConfig,
+}
+
+/// 证明F(m) | F(n), 实例列为商; 数列从常量开始, 没有私有输入
+struct DivisibilityCircuit {
+    m: usize,
+    n: usize,
+}
+
+impl Circuit<Fp> for DivisibilityCircuit {
+    type Config = DivisibilityConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { m: self.m, n: self.n }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let fib = FibChip::configure(meta, &shared);
+        let range = RangeCheckChip::configure(meta, &shared);
+        let division = DivisionChip::configure(meta, &shared, range);
+        DivisibilityConfig { fib, division }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fib = FibChip::construct(config.fib);
+        let division = DivisionChip::construct(config.division);
+        RangeCheckChip::<Fp>::construct(config.division.range).load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let one = Value::known(Fp::one());
+        let terms = fib.assign_sequence(layouter.namespace(|| "填写数列"), one, one, self.n)?;
+        fib.assert_equal_const(layouter.namespace(|| "F(1) = 1"), &terms[0], Fp::one())?;
+        fib.assert_equal_const(layouter.namespace(|| "F(2) = 1"), &terms[1], Fp::one())?;
+
+        let quotient = division.assert_divides(layouter.namespace(|| "F(m) | F(n)"), &terms[self.m - 1], &terms[self.n - 1], LIMBS)?;
+        fib.expose_public(layouter.namespace(|| "公开商"), &quotient, 0)
+    }
+}
+
+/// F(1)..=F(93)
+fn terms() -> Vec<u64> {
+    let mut terms = vec![1u64, 1];
+    while terms.len() < 93 {
+        terms.push(terms[terms.len() - 1] + terms[terms.len() - 2]);
+    }
+    terms
+}
+
+fn main() {
+    let terms = terms();
+
+    for (m, n) in [(6, 12), (7, 91), (31, 93)] {
+        let quotient = terms[n - 1] / terms[m - 1];
+        println!("F({}) = {} 整除 F({}) = {}, 商 {}", m, terms[m - 1], n, terms[n - 1], quotient);
+        let circuit = DivisibilityCircuit { m, n };
+        let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(quotient)]]).expect("运行MockProver失败");
+        prover.assert_satisfied();
+    }
+
+    // 5不整除12, F(5) = 5也不整除F(12) = 144
+    let prover = MockProver::run(K, &DivisibilityCircuit { m: 5, n: 12 }, vec![vec![Fp::from(144 / 5)]]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "不整除的命题通过了检查");
+
+    let circuit = DivisibilityCircuit { m: 6, n: 12 };
+    let instance = vec![Fp::from(18)];
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit, &instance).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &instance, &proof).into_result().expect("验证失败");
+    println!("证明 {} 字节", proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+    use rand_core::{OsRng, RngCore};
+
+    // 电路形状由m、n决定, 每对随机的(m, n)单独生成密钥; 偶数组公开F(n) / F(m)取整, 奇数组公开随机的商
+    let terms = terms();
+    let mut rng = OsRng;
+    for multiple in [true, false] {
+        let m = 3 + rng.next_u32() as usize % 18;
+        let q = 1 + rng.next_u32() as usize % (93 / m - 1);
+        let n = if multiple { m * (q + 1) } else { m * q + 1 + rng.next_u32() as usize % (m - 1) };
+        let mut case = 0;
+        let accepted = check_differential(K, 2, |rng| {
+            case += 1;
+            let quotient = if case % 2 == 0 { terms[n - 1] / terms[m - 1] } else { rng.next_u64() };
+            (DivisibilityCircuit { m, n }, vec![Fp::from(quotient)])
+        });
+        // m >= 3时F(m) > 1, F(m) | F(n)当且仅当m | n
+        assert_eq!(accepted, (n % m == 0) as usize, "m = {}, n = {}", m, n);
+    }
+}
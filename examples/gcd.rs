@@ -0,0 +1,151 @@
+//! 最大公约数示例: 证明gcd(a, b) = g, 贝祖系数s、t为私有见证
+//!
+//! - g整除a和b, 由带余除法芯片检查, 所以g是公约数
+//! - a * s + b * t = g, 由内积芯片计算<(a, b), (s, t)>, 所以任何公约数都整除g, g就是最大公约数
+//! - a、b、g经64位范围检查; 扩展欧几里得算出的|s| <= b / 2g、|t| <= a / 2g, 都小于2^63,
+//!   把s + 2^63、t + 2^63做64位范围检查即可, a * s + b * t不会在域上回绕
+//!
+//! 用法: cargo run --release --example gcd
+
+use ff::PrimeField;
+use halo2_fib::fib::{FibChip, FibConfig};
+use halo2_fib::gadgets::division::{DivisionChip, DivisionConfig};
+use halo2_fib::gadgets::dot_product::{DotProductChip, DotProductConfig};
+use halo2_fib::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+const K: u32 = 9;
+const LIMBS: usize = 8;
+
+/// 扩展欧几里得算法, 返回(g, s, t)使 a * s + b * t = g
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, s, t) = extended_gcd(b, a % b);
+        (g, t, s - (a / b) * t)
+    }
+}
+
+fn field(v: i128) -> Fp {
+    if v < 0 { -Fp::from_u128(v.unsigned_abs()) } else { Fp::from_u128(v as u128) }
+}
+
+#[derive(Clone, Debug)]
+struct GcdConfig {
+    shared: SharedColumns,
+    fib: FibConfig,
+    range: RangeCheckConfig,
+    dot: DotProductConfig,
+    division: DivisionConfig,
+}
+
+/// 实例列为a、b、g
+struct GcdCircuit {
+    s: Value<Fp>,
+    t: Value<Fp>,
+}
+
+impl Circuit<Fp> for GcdCircuit {
+    type Config = GcdConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { s: Value::unknown(), t: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let fib = FibChip::configure(meta, &shared);
+        let range = RangeCheckChip::configure(meta, &shared);
+        let dot = DotProductChip::configure(meta, &shared);
+        let division = DivisionChip::configure(meta, &shared, range);
+        GcdConfig { shared, fib, range, dot, division }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fib = FibChip::construct(config.fib);
+        let range = RangeCheckChip::construct(config.range);
+        let dot = DotProductChip::construct(config.dot);
+        let division = DivisionChip::construct(config.division);
+        range.load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let (a, b, g) = layouter.assign_region(|| "公开输入", |mut region| {
+            let a = region.assign_advice_from_instance(|| "a", config.shared.instance, 0, config.shared.advice[0], 0)?;
+            let b = region.assign_advice_from_instance(|| "b", config.shared.instance, 1, config.shared.advice[1], 0)?;
+            let g = region.assign_advice_from_instance(|| "g", config.shared.instance, 2, config.shared.advice[2], 0)?;
+            Ok((a, b, g))
+        })?;
+        for cell in [&a, &b, &g] {
+            range.copy_check(layouter.namespace(|| "64位"), cell, LIMBS)?;
+        }
+
+        // s、t加上2^63后不为负且小于2^64
+        let (s, t, offset) = layouter.assign_region(|| "贝祖系数", |mut region| {
+            let s = region.assign_advice(|| "s", config.shared.advice[0], 0, || self.s)?;
+            let t = region.assign_advice(|| "t", config.shared.advice[1], 0, || self.t)?;
+            let offset = region.assign_advice_from_constant(|| "2^63", config.shared.advice[2], 0, Fp::from(1u64 << 63))?;
+            Ok((s, t, offset))
+        })?;
+        for cell in [&s, &t] {
+            let shifted = fib.add(layouter.namespace(|| "加2^63"), cell, &offset)?;
+            range.copy_check(layouter.namespace(|| "有符号64位"), &shifted, LIMBS)?;
+        }
+
+        let combination = dot.dot(layouter.namespace(|| "a * s + b * t"), &[a.clone(), b.clone()], &[s, t])?;
+        fib.assert_equal(layouter.namespace(|| "等于g"), &combination, &g)?;
+        division.assert_divides(layouter.namespace(|| "g | a"), &g, &a, LIMBS)?;
+        division.assert_divides(layouter.namespace(|| "g | b"), &g, &b, LIMBS)?;
+        Ok(())
+    }
+}
+
+fn main() {
+    let (a, b) = (1_134_903_170u64, 701_408_733 * 6);
+    let (g, s, t) = extended_gcd(a as i128, b as i128);
+    println!("gcd({}, {}) = {} = {} * {} + {} * {}", a, b, g, a, s, b, t);
+
+    let circuit = GcdCircuit { s: Value::known(field(s)), t: Value::known(field(t)) };
+    let instance = vec![Fp::from(a), Fp::from(b), field(g)];
+    let prover = MockProver::run(K, &circuit, vec![instance.clone()]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+
+    // g的倍数不是最大公约数: 2g不整除a时除法失败, 整除时贝祖等式失败
+    let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(a), Fp::from(b), field(2 * g)]]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "错误的最大公约数通过了检查");
+    // 1也是公约数, 但a * s + b * t = g != 1
+    let prover = MockProver::run(K, &circuit, vec![vec![Fp::from(a), Fp::from(b), Fp::one()]]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "错误的最大公约数通过了检查");
+
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit, &instance).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &instance, &proof).into_result().expect("验证失败");
+    println!("证明 {} 字节", proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 随机的a、b带一个随机公因子; 第二组公开2g, 第三组的贝祖系数s加一
+    let mut case = 0;
+    let accepted = check_differential(K, 6, |rng| {
+        let factor = 1 + (rng.next_u32() >> 16) as u64;
+        let (a, b) = (factor * (1 + (rng.next_u64() >> 24)), factor * (1 + (rng.next_u64() >> 24)));
+        let (g, s, t) = extended_gcd(a as i128, b as i128);
+        case += 1;
+        let (g, s) = match case % 3 {
+            1 => (g, s),
+            2 => (2 * g, s),
+            _ => (g, s + 1),
+        };
+        (GcdCircuit { s: Value::known(field(s)), t: Value::known(field(t)) }, vec![Fp::from(a), Fp::from(b), field(g)])
+    });
+    assert_eq!(accepted, 2);
+}
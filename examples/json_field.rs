@@ -0,0 +1,190 @@
+//! JSON字段提取示例(简化的zk-regex): 证明私有文档在某个承诺的位置上含有`"age":30`
+//!
+//! - 文档至多62字节, 用字节串芯片分成两块打包, 每个字节查范围检查的表;
+//!   公开文档承诺 H(H(c0, c1), salt) 和位置承诺 H(pos, salt)
+//! - 位置用独热向量sel选择: sel_i为0或1, 前缀和S_i最后为1, 所以恰有一个1;
+//!   P_i = P_{i-1} + 1 - S_i 数出1之前的个数, 最后等于pos
+//! - 第j个提取的字节等于 <sel, doc[j..j + M]>, 提取结果再由字节串芯片约束等于常量模式
+//!
+//! 用法: cargo run --release --example json_field
+
+use halo2_fib::gadgets::bytes::{pack, BytesChip, BytesConfig, MAX_LEN};
+use halo2_fib::gadgets::dot_product::{DotProductChip, DotProductConfig};
+use halo2_fib::gadgets::poseidon::{hash2, PoseidonChip, PoseidonConfig};
+use halo2_fib::gadgets::range_check::RangeCheckChip;
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, Expression, Selector};
+use halo2_proofs::poly::Rotation;
+
+/// 内积共8 * 56行, 加上打包和三次Poseidon哈希不到1024行
+const K: u32 = 10;
+const CAPACITY: usize = 2 * MAX_LEN;
+const PATTERN: &[u8] = b"\"age\":30";
+/// 可选的起始位置个数
+const M: usize = CAPACITY - PATTERN.len() + 1;
+
+#[derive(Clone, Debug)]
+struct JsonFieldConfig {
+    shared: SharedColumns,
+    /// 独热选择, advice列依次为sel、前缀和S、位置P
+    q_select: Selector,
+    bytes: BytesConfig,
+    dot: DotProductConfig,
+    poseidon: PoseidonConfig,
+}
+
+/// 实例列为文档承诺和位置承诺
+struct JsonFieldCircuit {
+    doc: Value<Vec<u8>>,
+    pos: Value<usize>,
+    salt: Value<Fp>,
+}
+
+impl JsonFieldCircuit {
+    /// 填写独热向量, 返回sel和pos
+    fn select(config: &JsonFieldConfig, mut layouter: impl Layouter<Fp>, pos: Value<usize>) -> Result<(Vec<AssignedCell<Fp, Fp>>, AssignedCell<Fp, Fp>), Error> {
+        let [sel_col, sum_col, pos_col] = config.shared.advice;
+        layouter.assign_region(|| "独热选择", |mut region| {
+            let mut sum = region.assign_advice_from_constant(|| "S初值", sum_col, 0, Fp::zero())?;
+            let mut count = region.assign_advice_from_constant(|| "P初值", pos_col, 0, Fp::zero())?;
+            let mut sel = Vec::with_capacity(M);
+            for i in 0..M {
+                config.q_select.enable(&mut region, i + 1)?;
+                let bit = pos.map(|pos| if pos == i { Fp::one() } else { Fp::zero() });
+                sel.push(region.assign_advice(|| "sel", sel_col, i + 1, || bit)?);
+                sum = region.assign_advice(|| "S", sum_col, i + 1, || sum.value().copied() + bit)?;
+                count = region.assign_advice(|| "P", pos_col, i + 1, || count.value().copied() + Value::known(Fp::one()) - sum.value().copied())?;
+            }
+            region.constrain_constant(sum.cell(), Fp::one())?;
+            Ok((sel, count))
+        })
+    }
+}
+
+impl Circuit<Fp> for JsonFieldCircuit {
+    type Config = JsonFieldConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { doc: Value::unknown(), pos: Value::unknown(), salt: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let range = RangeCheckChip::configure(meta, &shared);
+        let bytes = BytesChip::configure(meta, &shared, range);
+        let dot = DotProductChip::configure(meta, &shared);
+        let poseidon = PoseidonChip::configure(meta, &shared);
+
+        let q_select = meta.selector();
+        let [sel, sum, pos] = shared.advice;
+        meta.create_gate("one_hot_select", |meta| {
+            let q = meta.query_selector(q_select);
+            let sel = meta.query_advice(sel, Rotation::cur());
+            let sum_prev = meta.query_advice(sum, Rotation::prev());
+            let sum_cur = meta.query_advice(sum, Rotation::cur());
+            let pos_prev = meta.query_advice(pos, Rotation::prev());
+            let pos_cur = meta.query_advice(pos, Rotation::cur());
+            let one = Expression::Constant(Fp::one());
+            vec![
+                ("sel为0或1", q.clone() * sel.clone() * (one.clone() - sel.clone())),
+                ("S = S_prev + sel", q.clone() * (sum_cur.clone() - sum_prev - sel)),
+                ("P = P_prev + 1 - S", q * (pos_cur - pos_prev - one + sum_cur)),
+            ]
+        });
+        JsonFieldConfig { shared, q_select, bytes, dot, poseidon }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let bytes = BytesChip::construct(config.bytes);
+        let dot = DotProductChip::construct(config.dot);
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        RangeCheckChip::<Fp>::construct(config.bytes.range).load_table(layouter.namespace(|| "加载查找表"))?;
+        bytes.load_table(layouter.namespace(|| "加载幂表"))?;
+
+        // 文档分成两块
+        let chunk = |i: usize| self.doc.as_ref().map(|doc| doc.iter().skip(i * MAX_LEN).take(MAX_LEN).copied().collect::<Vec<_>>());
+        let (c0, mut doc) = bytes.witness(layouter.namespace(|| "文档前半"), chunk(0), MAX_LEN)?;
+        let (c1, rest) = bytes.witness(layouter.namespace(|| "文档后半"), chunk(1), MAX_LEN)?;
+        doc.extend(rest);
+
+        // 提取的字段等于模式
+        let field = self.doc.as_ref().zip(self.pos).map(|(doc, pos)| doc[pos..pos + PATTERN.len()].to_vec());
+        let (field, field_bytes) = bytes.witness(layouter.namespace(|| "提取的字段"), field, PATTERN.len())?;
+        bytes.assert_equal_const(layouter.namespace(|| "字段等于模式"), &field, PATTERN)?;
+
+        let (sel, pos) = Self::select(&config, layouter.namespace(|| "选择位置"), self.pos)?;
+        for (j, expected) in field_bytes.iter().enumerate() {
+            let extracted = dot.dot(layouter.namespace(|| "提取字节"), &sel, &doc[j..j + M])?;
+            layouter.assign_region(|| "字节相等", |mut region| region.constrain_equal(extracted.cell(), expected.cell()))?;
+        }
+
+        let salt = layouter.assign_region(|| "盐", |mut region| region.assign_advice(|| "salt", config.shared.advice[0], 0, || self.salt))?;
+        let digest = poseidon.hash2(layouter.namespace(|| "H(c0, c1)"), &c0.packed, &c1.packed)?;
+        let doc_commitment = poseidon.hash2(layouter.namespace(|| "文档承诺"), &digest, &salt)?;
+        let pos_commitment = poseidon.hash2(layouter.namespace(|| "位置承诺"), &pos, &salt)?;
+        layouter.constrain_instance(doc_commitment.cell(), config.shared.instance, 0)?;
+        layouter.constrain_instance(pos_commitment.cell(), config.shared.instance, 1)
+    }
+}
+
+/// 电路外计算两个承诺
+fn commitments(doc: &[u8], pos: usize, salt: Fp) -> Vec<Fp> {
+    let (c0, c1) = doc.split_at(doc.len().min(MAX_LEN));
+    let digest = hash2(pack(c0), pack(c1));
+    vec![hash2(digest, salt), hash2(Fp::from(pos as u64), salt)]
+}
+
+fn main() {
+    let doc = br#"{"name":"alice","age":30,"city":"paris"}"#.to_vec();
+    let pos = doc.windows(PATTERN.len()).position(|w| w == PATTERN).expect("文档中没有该字段");
+    let salt = Fp::from(0x5eed);
+    println!("{} 在第{}字节含有 {}", String::from_utf8_lossy(&doc), pos, String::from_utf8_lossy(PATTERN));
+
+    let circuit = JsonFieldCircuit { doc: Value::known(doc.clone()), pos: Value::known(pos), salt: Value::known(salt) };
+    let instance = commitments(&doc, pos, salt);
+    let prover = MockProver::run(K, &circuit, vec![instance.clone()]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+
+    // 承诺了错误的位置
+    let prover = MockProver::run(K, &circuit, vec![commitments(&doc, pos + 1, salt)]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "错误的位置通过了检查");
+    // 文档里是"age":31
+    let other = br#"{"name":"alice","age":31,"city":"paris"}"#.to_vec();
+    let circuit_other = JsonFieldCircuit { doc: Value::known(other.clone()), pos: Value::known(pos), salt: Value::known(salt) };
+    let prover = MockProver::run(K, &circuit_other, vec![commitments(&other, pos, salt)]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "不含该字段的文档通过了检查");
+
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit, &instance).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &instance, &proof).into_result().expect("验证失败");
+    println!("证明 {} 字节", proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 随机长度的小写字母文档, 在随机位置放入字段; 第二组承诺下一个位置, 第三组的文档里是"age":31
+    let mut case = 0;
+    let accepted = check_differential(K, 6, |rng| {
+        let len = PATTERN.len() + rng.next_u32() as usize % (CAPACITY - PATTERN.len() + 1);
+        let mut doc: Vec<u8> = (0..len).map(|_| b'a' + (rng.next_u32() % 26) as u8).collect();
+        let pos = rng.next_u32() as usize % (len - PATTERN.len() + 1);
+        doc[pos..pos + PATTERN.len()].copy_from_slice(PATTERN);
+        let salt = Fp::from(rng.next_u64());
+        case += 1;
+        let committed = if case % 3 == 2 { pos + 1 } else { pos };
+        if case % 3 == 0 {
+            doc[pos + PATTERN.len() - 1] = b'1';
+        }
+        let instance = commitments(&doc, committed, salt);
+        (JsonFieldCircuit { doc: Value::known(doc), pos: Value::known(pos), salt: Value::known(salt) }, instance)
+    });
+    assert_eq!(accepted, 2);
+}
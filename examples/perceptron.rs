@@ -0,0 +1,163 @@
+//! 量化线性分类器推理示例: 模型公开, 输入私有, 证明输入被分到公开的类别
+//!
+//! - 每个类别的得分 s_i = <W_i, (1, x)>, 偏置并入权重的第0列, 由内积芯片计算
+//! - 输入特征经范围检查为0..=255
+//! - 用one-hot向量e选出类别: 每个e_i经范围检查不为负且小于256, 且和为1, 因此e恰有一个分量为1;
+//!   <e, (0, 1, ..)>等于公开的类别, m = <e, s>为该类得分, 再由比较芯片约束m不小于每个类别的得分
+//!
+//! 用法: cargo run --release --example perceptron
+
+use halo2_fib::gadgets::comparison::{ComparisonChip, ComparisonConfig};
+use halo2_fib::gadgets::dot_product::{DotProductChip, DotProductConfig};
+use halo2_fib::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error};
+
+const K: u32 = 9;
+const FEATURES: usize = 4;
+const CLASSES: usize = 3;
+
+/// 每行为一个类别的(偏置, 权重), 权重在-127..=127之间
+const MODEL: [[i64; FEATURES + 1]; CLASSES] = [
+    [10, 3, -2, 1, 0],
+    [-5, -1, 4, 2, -3],
+    [0, 1, 1, -4, 5],
+];
+
+/// 得分之差小于2^19, 用3个limb比较
+const SCORE_LIMBS: usize = 3;
+
+fn field(v: i64) -> Fp {
+    if v < 0 { -Fp::from(v.unsigned_abs()) } else { Fp::from(v as u64) }
+}
+
+fn scores(x: &[u8; FEATURES]) -> [i64; CLASSES] {
+    MODEL.map(|w| w[0] + w[1..].iter().zip(x).map(|(w, &x)| w * x as i64).sum::<i64>())
+}
+
+/// 得分最高的类别, 并列时取编号小的
+fn classify(x: &[u8; FEATURES]) -> usize {
+    let scores = scores(x);
+    (0..CLASSES).fold(0, |best, i| if scores[i] > scores[best] { i } else { best })
+}
+
+/// 把常量填入advice列, 值由常量约束固定
+fn load_constants(mut layouter: impl Layouter<Fp>, column: Column<Advice>, values: &[Fp]) -> Result<Vec<AssignedCell<Fp, Fp>>, Error> {
+    layouter.assign_region(|| "常量", |mut region| {
+        values.iter().enumerate().map(|(row, v)| region.assign_advice_from_constant(|| "常量", column, row, *v)).collect()
+    })
+}
+
+#[derive(Clone, Debug)]
+struct PerceptronConfig {
+    shared: SharedColumns,
+    dot: DotProductConfig,
+    range: RangeCheckConfig,
+    cmp: ComparisonConfig,
+}
+
+/// 实例列只有一行类别
+struct PerceptronCircuit {
+    x: Value<[u8; FEATURES]>,
+    class: Value<usize>,
+}
+
+impl Circuit<Fp> for PerceptronCircuit {
+    type Config = PerceptronConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { x: Value::unknown(), class: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let dot = DotProductChip::configure(meta, &shared);
+        let range = RangeCheckChip::configure(meta, &shared);
+        let cmp = ComparisonChip::configure(meta, &shared, range);
+        PerceptronConfig { shared, dot, range, cmp }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let dot = DotProductChip::construct(config.dot);
+        let range = RangeCheckChip::construct(config.range);
+        let cmp = ComparisonChip::construct(config.cmp);
+        range.load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let column = config.shared.advice[0];
+        let one = load_constants(layouter.namespace(|| "常数1"), column, &[Fp::one()])?.remove(0);
+        let weights = MODEL.iter().map(|w| load_constants(layouter.namespace(|| "权重"), column, &w.map(field))).collect::<Result<Vec<_>, _>>()?;
+        let indices = load_constants(layouter.namespace(|| "类别编号"), column, &(0..CLASSES as u64).map(Fp::from).collect::<Vec<_>>())?;
+        let ones = load_constants(layouter.namespace(|| "全1向量"), column, &[Fp::one(); CLASSES])?;
+
+        // (1, x), x经范围检查为一个字节
+        let mut input = vec![one.clone()];
+        for i in 0..FEATURES {
+            input.push(range.witness_check(layouter.namespace(|| "特征"), self.x.map(|x| Fp::from(x[i] as u64)), 1)?);
+        }
+        let scores = weights.iter().map(|w| dot.dot(layouter.namespace(|| "得分"), w, &input)).collect::<Result<Vec<_>, _>>()?;
+
+        // one-hot向量, 分量为0..256的整数且和为1
+        let mut onehot = Vec::with_capacity(CLASSES);
+        for i in 0..CLASSES {
+            let e = self.class.map(|class| Fp::from((class == i) as u64));
+            onehot.push(range.witness_check(layouter.namespace(|| "one-hot分量"), e, 1)?);
+        }
+        let sum = dot.dot(layouter.namespace(|| "分量之和"), &onehot, &ones)?;
+        layouter.assign_region(|| "和为1", |mut region| region.constrain_equal(sum.cell(), one.cell()))?;
+        let class = dot.dot(layouter.namespace(|| "类别"), &onehot, &indices)?;
+        layouter.constrain_instance(class.cell(), config.shared.instance, 0)?;
+
+        let best = dot.dot(layouter.namespace(|| "该类得分"), &onehot, &scores)?;
+        for score in &scores {
+            cmp.assert_ge(layouter.namespace(|| "不小于各类得分"), &best, score, SCORE_LIMBS)?;
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let x = [200, 17, 90, 3];
+    let class = classify(&x);
+    println!("得分 {:?}, 类别 {}", scores(&x), class);
+
+    let circuit = PerceptronCircuit { x: Value::known(x), class: Value::known(class) };
+    let instance = vec![Fp::from(class as u64)];
+    let prover = MockProver::run(K, &circuit, vec![instance.clone()]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+
+    // 声称另一个类别时, 该类得分不是最高
+    let other = (class + 1) % CLASSES;
+    let lying = PerceptronCircuit { x: Value::known(x), class: Value::known(other) };
+    let prover = MockProver::run(K, &lying, vec![vec![Fp::from(other as u64)]]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "错误的类别通过了检查");
+
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit, &instance).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &instance, &proof).into_result().expect("验证失败");
+    println!("证明 {} 字节", proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 随机特征; 第一组用真实类别, 第二组声称随机类别, 第三组公开的类别与one-hot向量不一致.
+    // 得分并列时多个类别都成立, 所以按电路外的得分计数
+    let (mut case, mut expected) = (0, 0);
+    let accepted = check_differential(K, 6, |rng| {
+        let x = [(); FEATURES].map(|_| rng.next_u32() as u8);
+        let scores = scores(&x);
+        case += 1;
+        let class = if case % 3 == 1 { classify(&x) } else { rng.next_u32() as usize % CLASSES };
+        let claimed = if case % 3 == 0 { (class + 1) % CLASSES } else { class };
+        expected += (claimed == class && scores.iter().all(|&s| scores[class] >= s)) as usize;
+        (PerceptronCircuit { x: Value::known(x), class: Value::known(class) }, vec![Fp::from(claimed as u64)])
+    });
+    assert_eq!(accepted, expected);
+}
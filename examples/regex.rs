@@ -0,0 +1,299 @@
+//! 正则匹配示例: 把一个小正则编译成DFA转移表, 用状态机芯片证明私有字符串与之匹配
+//!
+//! - 支持字面字符、`\`转义、`[a-z0-9]`字符类、`.`(任意可打印字符)、`*`、`+`、`?`、`|`和括号
+//! - 正则先按Thompson构造成NFA, 再用子集构造得到DFA. 接受状态读入0转到ACCEPT, ACCEPT读入0留在ACCEPT,
+//!   字符串补0到31字节后再读入一个0作为结束符, 最后的状态必须是ACCEPT, 所以恰好31字节的字符串也能匹配
+//! - 字符串用字节串芯片打包, 状态机的输入逐个拷贝自打包的字节, 公开承诺 H(packed, salt)
+//!
+//! 用法: cargo run --release --example regex
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use halo2_fib::gadgets::bytes::{pack, BytesChip, BytesConfig, MAX_LEN};
+use halo2_fib::gadgets::poseidon::{hash2, PoseidonChip, PoseidonConfig};
+use halo2_fib::gadgets::range_check::RangeCheckChip;
+use halo2_fib::gadgets::state_machine::{StateMachineChip, StateMachineConfig, Transition};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+/// 转移表和范围检查表都在512行以内
+const K: u32 = 9;
+const PATTERN: &str = r"[a-z]+@[a-z]+\.(com|org)";
+
+/// Thompson构造的NFA, 边为(字节下界, 字节上界, 目标)
+#[derive(Default)]
+struct Nfa {
+    epsilon: Vec<Vec<usize>>,
+    edges: Vec<Vec<(u8, u8, usize)>>,
+}
+
+/// NFA片段的入口和出口
+#[derive(Clone, Copy)]
+struct Fragment {
+    start: usize,
+    end: usize,
+}
+
+impl Nfa {
+    fn state(&mut self) -> usize {
+        self.epsilon.push(vec![]);
+        self.edges.push(vec![]);
+        self.epsilon.len() - 1
+    }
+
+    fn ranges(&mut self, ranges: &[(u8, u8)]) -> Fragment {
+        let (start, end) = (self.state(), self.state());
+        for &(lo, hi) in ranges {
+            self.edges[start].push((lo, hi, end));
+        }
+        Fragment { start, end }
+    }
+
+    fn closure(&self, states: impl IntoIterator<Item = usize>) -> BTreeSet<usize> {
+        let mut closure = BTreeSet::new();
+        let mut stack: Vec<usize> = states.into_iter().collect();
+        while let Some(s) = stack.pop() {
+            if closure.insert(s) {
+                stack.extend(&self.epsilon[s]);
+            }
+        }
+        closure
+    }
+}
+
+/// 递归下降解析: alt := concat ('|' concat)*, concat := repeat*, repeat := atom ('*' | '+' | '?')*
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Bytes<'a>>,
+    nfa: Nfa,
+}
+
+impl Parser<'_> {
+    fn alt(&mut self) -> Fragment {
+        let mut fragment = self.concat();
+        while self.chars.next_if_eq(&b'|').is_some() {
+            let other = self.concat();
+            let (start, end) = (self.nfa.state(), self.nfa.state());
+            self.nfa.epsilon[start].extend([fragment.start, other.start]);
+            self.nfa.epsilon[fragment.end].push(end);
+            self.nfa.epsilon[other.end].push(end);
+            fragment = Fragment { start, end };
+        }
+        fragment
+    }
+
+    fn concat(&mut self) -> Fragment {
+        let mut fragment = self.nfa.ranges(&[]);
+        self.nfa.epsilon[fragment.start].push(fragment.end);
+        while self.chars.peek().is_some_and(|&c| !matches!(c, b'|' | b')')) {
+            let next = self.repeat();
+            self.nfa.epsilon[fragment.end].push(next.start);
+            fragment.end = next.end;
+        }
+        fragment
+    }
+
+    fn repeat(&mut self) -> Fragment {
+        let mut fragment = self.atom();
+        while let Some(op) = self.chars.next_if(|&c| matches!(c, b'*' | b'+' | b'?')) {
+            let (start, end) = (self.nfa.state(), self.nfa.state());
+            self.nfa.epsilon[start].push(fragment.start);
+            self.nfa.epsilon[fragment.end].push(end);
+            if op != b'+' {
+                self.nfa.epsilon[start].push(end);
+            }
+            if op != b'?' {
+                self.nfa.epsilon[fragment.end].push(fragment.start);
+            }
+            fragment = Fragment { start, end };
+        }
+        fragment
+    }
+
+    fn atom(&mut self) -> Fragment {
+        match self.chars.next().expect("正则意外结束") {
+            b'(' => {
+                let fragment = self.alt();
+                assert_eq!(self.chars.next(), Some(b')'), "括号不匹配");
+                fragment
+            }
+            b'[' => {
+                let mut ranges = vec![];
+                while let Some(lo) = self.chars.next_if(|&c| c != b']') {
+                    let hi = if self.chars.next_if_eq(&b'-').is_some() { self.chars.next().expect("字符类意外结束") } else { lo };
+                    ranges.push((lo, hi));
+                }
+                assert_eq!(self.chars.next(), Some(b']'), "字符类没有结束");
+                self.nfa.ranges(&ranges)
+            }
+            b'.' => self.nfa.ranges(&[(0x20, 0x7e)]),
+            b'\\' => {
+                let c = self.chars.next().expect("转义意外结束");
+                self.nfa.ranges(&[(c, c)])
+            }
+            c => self.nfa.ranges(&[(c, c)]),
+        }
+    }
+}
+
+/// 编译出的DFA: 初始状态为0, accept为ACCEPT状态
+struct Dfa {
+    transitions: Vec<Transition>,
+    accept: u64,
+}
+
+/// 正则编译成DFA转移表, 字符串不能含0字节
+fn compile(pattern: &str) -> Dfa {
+    let mut parser = Parser { chars: pattern.bytes().peekable(), nfa: Nfa::default() };
+    let fragment = parser.alt();
+    assert!(parser.chars.next().is_none(), "正则有多余的字符");
+    let nfa = parser.nfa;
+
+    let mut ids = BTreeMap::new();
+    let mut queue = vec![nfa.closure([fragment.start])];
+    ids.insert(queue[0].clone(), 0u64);
+    let mut transitions = vec![];
+    let mut accepting = vec![];
+    while let Some(set) = queue.pop() {
+        let id = ids[&set];
+        if set.contains(&fragment.end) {
+            accepting.push(id);
+        }
+        for byte in 1..=255u8 {
+            let targets = set.iter().flat_map(|&s| &nfa.edges[s]).filter(|&&(lo, hi, _)| lo <= byte && byte <= hi).map(|&(_, _, t)| t);
+            let next = nfa.closure(targets);
+            if next.is_empty() {
+                continue;
+            }
+            let count = ids.len() as u64;
+            let next_id = *ids.entry(next.clone()).or_insert_with(|| {
+                queue.push(next);
+                count
+            });
+            transitions.push((id, byte as u64, next_id));
+        }
+    }
+    let accept = ids.len() as u64;
+    transitions.extend(accepting.into_iter().map(|s| (s, 0, accept)));
+    transitions.push((accept, 0, accept));
+    Dfa { transitions, accept }
+}
+
+#[derive(Clone, Debug)]
+struct RegexConfig {
+    shared: SharedColumns,
+    bytes: BytesConfig,
+    dfa: StateMachineConfig,
+    poseidon: PoseidonConfig,
+}
+
+/// 实例列为字符串的承诺; 转移表是电路的一部分
+struct RegexCircuit {
+    transitions: Vec<Transition>,
+    accept: u64,
+    text: Value<Vec<u8>>,
+    salt: Value<Fp>,
+}
+
+impl Circuit<Fp> for RegexCircuit {
+    type Config = RegexConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { transitions: self.transitions.clone(), accept: self.accept, text: Value::unknown(), salt: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let range = RangeCheckChip::configure(meta, &shared);
+        let bytes = BytesChip::configure(meta, &shared, range);
+        let dfa = StateMachineChip::configure(meta, &shared);
+        let poseidon = PoseidonChip::configure(meta, &shared);
+        RegexConfig { shared, bytes, dfa, poseidon }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let bytes = BytesChip::construct(config.bytes);
+        let dfa = StateMachineChip::construct(config.dfa, &self.transitions);
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        RangeCheckChip::<Fp>::construct(config.bytes.range).load_table(layouter.namespace(|| "加载查找表"))?;
+        bytes.load_table(layouter.namespace(|| "加载幂表"))?;
+        dfa.load_table(layouter.namespace(|| "加载转移表"))?;
+
+        let (text, text_bytes) = bytes.witness(layouter.namespace(|| "字符串"), self.text.clone(), MAX_LEN)?;
+        // 多出的一个输入是结束符, 字符串占满MAX_LEN字节时也有0可读
+        let inputs: Vec<Value<u64>> = (0..=MAX_LEN).map(|i| self.text.as_ref().map(|text| text.get(i).copied().unwrap_or(0) as u64)).collect();
+        let (input_cells, states) = dfa.run(layouter.namespace(|| "运行DFA"), 0, &inputs)?;
+        layouter.assign_region(|| "输入即字节", |mut region| {
+            for (input, byte) in input_cells.iter().zip(&text_bytes) {
+                region.constrain_equal(input.cell(), byte.cell())?;
+            }
+            region.constrain_constant(input_cells[MAX_LEN].cell(), Fp::zero())?;
+            region.constrain_constant(states.last().expect("至少有初始状态").cell(), Fp::from(self.accept))
+        })?;
+
+        let salt = layouter.assign_region(|| "盐", |mut region| region.assign_advice(|| "salt", config.shared.advice[0], 0, || self.salt))?;
+        let commitment = poseidon.hash2(layouter.namespace(|| "承诺"), &text.packed, &salt)?;
+        layouter.constrain_instance(commitment.cell(), config.shared.instance, 0)
+    }
+}
+
+fn main() {
+    let dfa = compile(PATTERN);
+    println!("{} 编译为 {} 个状态、{} 条转移", PATTERN, dfa.accept + 1, dfa.transitions.len());
+    let salt = Fp::from(0x7e9e);
+    let circuit = |text: &str| RegexCircuit { transitions: dfa.transitions.clone(), accept: dfa.accept, text: Value::known(text.as_bytes().to_vec()), salt: Value::known(salt) };
+    let instance = |text: &str| vec![hash2(pack(text.as_bytes()), salt)];
+
+    // 最后一个恰好占满MAX_LEN字节
+    for text in ["alice@example.com", "bob@zk.org", "abcdefghijklmnopqrst@uvwxyz.com"] {
+        let prover = MockProver::run(K, &circuit(text), vec![instance(text)]).expect("运行MockProver失败");
+        prover.assert_satisfied();
+        println!("{} 匹配", text);
+    }
+    for text in ["alice@example.net", "@example.com", "Alice@example.com", "alice@example.comx"] {
+        let prover = MockProver::run(K, &circuit(text), vec![instance(text)]).expect("运行MockProver失败");
+        assert!(prover.verify().is_err(), "{} 不该匹配", text);
+        println!("{} 不匹配", text);
+    }
+
+    let text = "alice@example.com";
+    let params = setup(K);
+    let pk = keygen(&params, &circuit(text)).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit(text), &instance(text)).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &instance(text), &proof).into_result().expect("验证失败");
+    println!("证明 {} 字节", proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 随机拼出形如邮箱的字符串, 有的顶级域名不对、有的含大写字母; 按电路外运行DFA的结果计数
+    let dfa = compile(PATTERN);
+    let accepts = |text: &[u8]| text.iter().chain(&[0]).try_fold(0, |state, &byte| dfa.transitions.iter().find(|&&(s, b, _)| s == state && b == byte as u64).map(|&(_, _, next)| next)) == Some(dfa.accept);
+    let (mut accepted_natively, mut rejected_natively) = (0, 0);
+    let accepted = check_differential(K, 6, |rng| {
+        let mut word = |len: u32| (0..1 + rng.next_u32() % len).map(|_| (b'a' + (rng.next_u32() % 26) as u8) as char).collect::<String>();
+        let (local, domain) = (word(8), word(8));
+        let tld = ["com", "org", "net"][rng.next_u32() as usize % 3];
+        let mut text = format!("{}@{}.{}", local, domain, tld).into_bytes();
+        if rng.next_u32() % 4 == 0 {
+            let i = rng.next_u32() as usize % text.len();
+            text[i] = text[i].to_ascii_uppercase();
+        }
+        if accepts(&text) {
+            accepted_natively += 1;
+        } else {
+            rejected_natively += 1;
+        }
+        let salt = Fp::from(rng.next_u64());
+        let instance = vec![hash2(pack(&text), salt)];
+        (RegexCircuit { transitions: dfa.transitions.clone(), accept: dfa.accept, text: Value::known(text), salt: Value::known(salt) }, instance)
+    });
+    assert_eq!(accepted, accepted_natively);
+    assert_eq!(accepted + rejected_natively, 6);
+}
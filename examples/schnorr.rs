@@ -0,0 +1,243 @@
+//! Schnorr签名验证示例: 证明"我有公钥P对消息m的一个有效签名", 签名本身是私有的
+//!
+//! 曲线用Pallas, 它的坐标恰好在电路的域Fp上, 点运算不需要模拟非原生域. 签名为(R, s), 挑战值
+//! e = Poseidon(Poseidon(R.x, P.x), m), 验证 s·G = R + e·P; 标量乘法和点加法用halo2_gadgets的ECC芯片.
+//! e和s都按小于p的整数读入做变量基标量乘法, 签名时遇到s不小于p就换一个随机数重签, 这种情况的概率约为2^-158
+//!
+//! 用法: cargo run --release --example schnorr -- [消息]
+
+use ff::{Field, PrimeField};
+use group::{Curve, Group};
+use halo2_fib::gadgets::poseidon::{hash2, PoseidonChip, PoseidonConfig};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_gadgets::ecc::chip::{BaseFieldElem, EccChip, EccConfig, FixedPoint, FullScalar, ShortScalar, H};
+use halo2_gadgets::ecc::{FixedPoints, NonIdentityPoint, ScalarVar};
+use halo2_gadgets::sinsemilla::primitives::K as LOOKUP_BITS;
+use halo2_gadgets::utilities::lookup_range_check::LookupRangeCheckConfig;
+use halo2_proofs::arithmetic::CurveAffine;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::{pallas, Fp};
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, TableColumn};
+use rand_core::OsRng;
+
+/// ECC芯片的查找表占2^10行
+const K: u32 = 11;
+
+/// 这个例子只用变量基标量乘法, 但ECC芯片的类型参数要求给出固定基, 用没有取值的类型占位
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NoFixedBases {}
+
+macro_rules! no_fixed_base {
+    ($name:ident, $kind:ty) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        enum $name {}
+
+        impl FixedPoint<pallas::Affine> for $name {
+            type FixedScalarKind = $kind;
+
+            fn generator(&self) -> pallas::Affine {
+                match *self {}
+            }
+
+            fn u(&self) -> Vec<[[u8; 32]; H]> {
+                match *self {}
+            }
+
+            fn z(&self) -> Vec<u64> {
+                match *self {}
+            }
+        }
+    };
+}
+
+no_fixed_base!(NoFullScalarBase, FullScalar);
+no_fixed_base!(NoShortScalarBase, ShortScalar);
+no_fixed_base!(NoBaseFieldBase, BaseFieldElem);
+
+impl FixedPoints<pallas::Affine> for NoFixedBases {
+    type FullScalar = NoFullScalarBase;
+    type ShortScalar = NoShortScalarBase;
+    type Base = NoBaseFieldBase;
+}
+
+fn generator() -> pallas::Affine {
+    pallas::Point::generator().to_affine()
+}
+
+fn x_coordinate(point: &pallas::Affine) -> Fp {
+    *point.coordinates().expect("不是无穷远点").x()
+}
+
+/// 把小于p的整数看作标量, p小于Pallas的阶q, 总能转换
+fn to_scalar(value: Fp) -> pallas::Scalar {
+    pallas::Scalar::from_repr(value.to_repr()).expect("p < q")
+}
+
+fn challenge(r: &pallas::Affine, pk: &pallas::Affine, message: Fp) -> Fp {
+    hash2(hash2(x_coordinate(r), x_coordinate(pk)), message)
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Signature {
+    r: pallas::Affine,
+    /// 按整数读作标量
+    s: Fp,
+}
+
+fn public_key(secret: pallas::Scalar) -> pallas::Affine {
+    (pallas::Point::generator() * secret).to_affine()
+}
+
+fn sign(secret: pallas::Scalar, message: Fp) -> Signature {
+    let pk = public_key(secret);
+    loop {
+        let nonce = pallas::Scalar::random(OsRng);
+        let r = public_key(nonce);
+        let s = nonce + to_scalar(challenge(&r, &pk, message)) * secret;
+        if let Some(s) = Option::from(Fp::from_repr(s.to_repr())) {
+            return Signature { r, s };
+        }
+    }
+}
+
+/// 电路外验证, 与电路的约束一致
+fn verify_signature(pk: &pallas::Affine, message: Fp, signature: &Signature) -> bool {
+    let e = to_scalar(challenge(&signature.r, pk, message));
+    pallas::Point::generator() * to_scalar(signature.s) == pallas::Point::from(signature.r) + pallas::Point::from(*pk) * e
+}
+
+#[derive(Clone, Debug)]
+struct SchnorrConfig {
+    shared: SharedColumns,
+    ecc: EccConfig<NoFixedBases>,
+    poseidon: PoseidonConfig,
+    table_idx: TableColumn,
+}
+
+/// 实例列依次为P.x、P.y、m
+struct SchnorrCircuit {
+    pk: Value<pallas::Affine>,
+    signature: Value<Signature>,
+}
+
+impl Circuit<Fp> for SchnorrCircuit {
+    type Config = SchnorrConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { pk: Value::unknown(), signature: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        // ECC芯片要10个advice列, 前三个与共享列重合
+        let extra = [(); 7].map(|_| meta.advice_column());
+        for column in extra {
+            meta.enable_equality(column);
+        }
+        let [a, b, c] = shared.advice;
+        let advices = [a, b, c, extra[0], extra[1], extra[2], extra[3], extra[4], extra[5], extra[6]];
+        let lagrange_coeffs = [(); 8].map(|_| meta.fixed_column());
+        let table_idx = meta.lookup_table_column();
+        let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+        let ecc = EccChip::<NoFixedBases>::configure(meta, advices, lagrange_coeffs, range_check);
+        let poseidon = PoseidonChip::configure(meta, &shared);
+        SchnorrConfig { shared, ecc, poseidon, table_idx }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        layouter.assign_table(|| "10位查找表", |mut table| {
+            for index in 0..1 << LOOKUP_BITS {
+                table.assign_cell(|| "table_idx", config.table_idx, index, || Value::known(Fp::from(index as u64)))?;
+            }
+            Ok(())
+        })?;
+        let chip = EccChip::construct(config.ecc.clone());
+        let instance = config.shared.instance;
+
+        // 生成元也是见证出来的点, 坐标用常量约束固定
+        let g = NonIdentityPoint::new(chip.clone(), layouter.namespace(|| "生成元"), Value::known(generator()))?;
+        let coordinates = generator().coordinates().expect("不是无穷远点");
+        layouter.assign_region(|| "固定生成元", |mut region| {
+            region.constrain_constant(g.inner().x().cell(), *coordinates.x())?;
+            region.constrain_constant(g.inner().y().cell(), *coordinates.y())
+        })?;
+
+        let pk = NonIdentityPoint::new(chip.clone(), layouter.namespace(|| "公钥"), self.pk)?;
+        layouter.constrain_instance(pk.inner().x().cell(), instance, 0)?;
+        layouter.constrain_instance(pk.inner().y().cell(), instance, 1)?;
+        let r = NonIdentityPoint::new(chip.clone(), layouter.namespace(|| "R"), self.signature.map(|sig| sig.r))?;
+        let (message, s) = layouter.assign_region(|| "加载消息和s", |mut region| {
+            let message = region.assign_advice_from_instance(|| "m", instance, 2, config.shared.advice[0], 0)?;
+            let s = region.assign_advice(|| "s", config.shared.advice[1], 0, || self.signature.map(|sig| sig.s))?;
+            Ok((message, s))
+        })?;
+
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let inner = poseidon.hash2(layouter.namespace(|| "哈希(R.x, P.x)"), &r.inner().x(), &pk.inner().x())?;
+        let e = poseidon.hash2(layouter.namespace(|| "挑战值"), &inner, &message)?;
+
+        let e = ScalarVar::from_base(chip.clone(), layouter.namespace(|| "e作为标量"), &e)?;
+        let (e_pk, _) = pk.mul(layouter.namespace(|| "e·P"), e)?;
+        let s = ScalarVar::from_base(chip, layouter.namespace(|| "s作为标量"), &s)?;
+        let (s_g, _) = g.mul(layouter.namespace(|| "s·G"), s)?;
+        let rhs = r.add(layouter.namespace(|| "R + e·P"), &e_pk)?;
+        s_g.constrain_equal(layouter.namespace(|| "s·G = R + e·P"), &rhs)
+    }
+}
+
+fn public_inputs(pk: &pallas::Affine, message: Fp) -> Vec<Fp> {
+    let coordinates = pk.coordinates().expect("不是无穷远点");
+    vec![*coordinates.x(), *coordinates.y(), message]
+}
+
+fn main() {
+    let message = Fp::from(std::env::args().nth(1).map(|s| s.parse::<u64>().expect("消息必须是u64")).unwrap_or(2024));
+    let secret = pallas::Scalar::random(OsRng);
+    let pk = public_key(secret);
+    let signature = sign(secret, message);
+    assert!(verify_signature(&pk, message, &signature));
+    let circuit = |signature: Signature| SchnorrCircuit { pk: Value::known(pk), signature: Value::known(signature) };
+
+    let prover = MockProver::run(K, &circuit(signature), vec![public_inputs(&pk, message)]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+    // 签名不是针对这条消息的
+    let prover = MockProver::run(K, &circuit(signature), vec![public_inputs(&pk, message + Fp::one())]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "换了消息的签名通过了检查");
+    // 篡改s
+    let forged = Signature { s: signature.s + Fp::one(), ..signature };
+    assert!(!verify_signature(&pk, message, &forged));
+    let prover = MockProver::run(K, &circuit(forged), vec![public_inputs(&pk, message)]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "篡改的签名通过了检查");
+
+    let params = setup(K);
+    let proving_key = keygen(&params, &circuit(signature)).expect("生成密钥失败");
+    let instances = public_inputs(&pk, message);
+    let proof = prove(&params, &proving_key, &circuit(signature), &instances).expect("生成证明失败");
+    verify(&params, proving_key.get_vk(), &instances, &proof).into_result().expect("验证失败");
+    println!("消息 {:?} 的签名验证通过, 证明 {} 字节", message, proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 随机私钥和消息; 第二组公开另一条消息, 第三组篡改s. 按电路外验证签名的结果计数
+    let (mut case, mut expected) = (0, 0);
+    let accepted = check_differential(K, 3, |rng| {
+        let secret = pallas::Scalar::random(&mut *rng);
+        let pk = public_key(secret);
+        let message = Fp::from(rng.next_u64());
+        let mut signature = sign(secret, message);
+        case += 1;
+        let signed = if case % 3 == 2 { message + Fp::one() } else { message };
+        if case % 3 == 0 {
+            signature.s += Fp::one();
+        }
+        expected += verify_signature(&pk, signed, &signature) as usize;
+        (SchnorrCircuit { pk: Value::known(pk), signature: Value::known(signature) }, public_inputs(&pk, signed))
+    });
+    assert_eq!((accepted, expected), (1, 1));
+}
@@ -0,0 +1,121 @@
+//! 偿付能力证明示例: 证明一组私有的账户余额之和等于公开的总额, 且每个余额都不为负
+//!
+//! 每个余额经64位范围检查(域上的负数是很大的数, 通不过检查), 再用斐波那契芯片的 a + b = c 门做累加和.
+//! 账户数远小于2^190, 64位余额的和不会在域上回绕
+//!
+//! 用法: cargo run --release --example solvency
+
+use halo2_fib::fib::{FibChip, FibConfig};
+use halo2_fib::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+
+/// 查找表占256行
+const K: u32 = 9;
+
+/// 64位即8个8位limb
+const BALANCE_LIMBS: usize = 8;
+
+#[derive(Clone, Debug)]
+struct SolvencyConfig {
+    shared: SharedColumns,
+    fib: FibConfig,
+    range: RangeCheckConfig,
+}
+
+/// 实例列只有一行总额, 电路形状只和账户数有关
+struct SolvencyCircuit {
+    balances: Vec<Value<Fp>>,
+}
+
+impl SolvencyCircuit {
+    fn new(balances: &[Fp]) -> Self {
+        Self { balances: balances.iter().map(|&b| Value::known(b)).collect() }
+    }
+}
+
+impl Circuit<Fp> for SolvencyCircuit {
+    type Config = SolvencyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { balances: vec![Value::unknown(); self.balances.len()] }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let fib = FibChip::configure(meta, &shared);
+        let range = RangeCheckChip::configure(meta, &shared);
+        SolvencyConfig { shared, fib, range }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fib = FibChip::construct(config.fib);
+        let range = RangeCheckChip::construct(config.range);
+        range.load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let mut total = None;
+        for balance in &self.balances {
+            let balance = range.witness_check(layouter.namespace(|| "余额不为负"), *balance, BALANCE_LIMBS)?;
+            total = Some(match total {
+                None => balance,
+                Some(total) => fib.add(layouter.namespace(|| "累加"), &total, &balance)?,
+            });
+        }
+        let total = total.ok_or(Error::Synthesis)?;
+        fib.expose_public(layouter.namespace(|| "公开总额"), &total, 0)
+    }
+}
+
+fn main() {
+    let balances: Vec<Fp> = [1200u64, 35, 0, 987_654, 42, 7].iter().map(|&b| Fp::from(b)).collect();
+    let total: Fp = balances.iter().sum();
+    let circuit = SolvencyCircuit::new(&balances);
+
+    let prover = MockProver::run(K, &circuit, vec![vec![total]]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+    let prover = MockProver::run(K, &circuit, vec![vec![total + Fp::one()]]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "错误的总额通过了检查");
+
+    // 用一个负余额凑出同样的总额
+    let mut cheating = balances.clone();
+    cheating[2] = -Fp::from(100);
+    cheating[0] += Fp::from(100);
+    let prover = MockProver::run(K, &SolvencyCircuit::new(&cheating), vec![vec![total]]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "负余额通过了检查");
+
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit, &[total]).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &[total], &proof).into_result().expect("验证失败");
+    println!("{}个账户, 总额 {:?}, 证明 {} 字节", balances.len(), total, proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 6个账户, 余额的位数随机; 第二组公开的总额加一, 第三组用一个负余额凑出同样的总额
+    let mut case = 0;
+    let accepted = check_differential(K, 6, |rng| {
+        let mut balances: Vec<Fp> = (0..6).map(|_| Fp::from(rng.next_u64() >> (rng.next_u32() % 64))).collect();
+        let mut total: Fp = balances.iter().sum();
+        case += 1;
+        match case % 3 {
+            2 => total += Fp::one(),
+            0 => {
+                // 第二个余额换成负数, 差额加到第一个上, 总额不变
+                let debt = Fp::from(1 + rng.next_u64() % 1000);
+                balances[0] += balances[1] + debt;
+                balances[1] = -debt;
+            }
+            _ => {}
+        }
+        (SolvencyCircuit::new(&balances), vec![total])
+    });
+    assert_eq!(accepted, 2);
+}
@@ -0,0 +1,170 @@
+//! 排序证明示例: 证明私有列表s是公开列表x排序后的结果
+//!
+//! - 有序: s的每个元素经16位范围检查, 再由比较芯片约束 s_{i+1} >= s_i
+//! - 是x的排列: 置换论证的接线在生成密钥时就固定了, 无法表达"由私有见证决定的排列",
+//!   这个版本的halo2也没有shuffle论证, 所以按shuffle论证的做法在电路内自己做:
+//!   用Poseidon把x和s依次吸收得到挑战r, 再约束 ∏(r - x_i) = ∏(r - s_i).
+//!   两个多项式不同时, 随机的r使两边相等的概率不超过 N / |Fp|
+//!
+//! 用法: cargo run --release --example sorting
+
+use halo2_fib::gadgets::comparison::{ComparisonChip, ComparisonConfig};
+use halo2_fib::gadgets::poseidon::{PoseidonChip, PoseidonConfig};
+use halo2_fib::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::SharedColumns;
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error, Selector};
+use halo2_proofs::poly::Rotation;
+
+/// 2N次Poseidon哈希和查找表合计不到1024行
+const K: u32 = 10;
+const N: usize = 6;
+
+/// 元素小于2^16
+const VALUE_LIMBS: usize = 2;
+
+#[derive(Clone, Debug)]
+struct SortingConfig {
+    shared: SharedColumns,
+    /// 每行 acc_next = acc * (r - v), advice列依次为v、r、acc
+    q_prod: Selector,
+    range: RangeCheckConfig,
+    cmp: ComparisonConfig,
+    poseidon: PoseidonConfig,
+}
+
+/// 实例列为公开列表x, 排序结果s私有
+struct SortingCircuit {
+    sorted: Value<[u64; N]>,
+}
+
+impl SortingCircuit {
+    /// 计算 ∏(r - v_i), r和各v_i都拷贝进来
+    fn product(config: &SortingConfig, mut layouter: impl Layouter<Fp>, values: &[AssignedCell<Fp, Fp>], r: &AssignedCell<Fp, Fp>) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let [v_col, r_col, acc_col] = config.shared.advice;
+        layouter.assign_region(|| "连乘", |mut region| {
+            let mut acc = region.assign_advice_from_constant(|| "acc初值", acc_col, 0, Fp::one())?;
+            for (row, v) in values.iter().enumerate() {
+                config.q_prod.enable(&mut region, row)?;
+                let v = v.copy_advice(|| "拷贝v", &mut region, v_col, row)?;
+                let r = r.copy_advice(|| "拷贝r", &mut region, r_col, row)?;
+                let value = acc.value().zip(r.value()).zip(v.value()).map(|((acc, r), v)| *acc * (*r - v));
+                acc = region.assign_advice(|| "acc", acc_col, row + 1, || value)?;
+            }
+            Ok(acc)
+        })
+    }
+}
+
+impl Circuit<Fp> for SortingCircuit {
+    type Config = SortingConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { sorted: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let range = RangeCheckChip::configure(meta, &shared);
+        let cmp = ComparisonChip::configure(meta, &shared, range);
+        let poseidon = PoseidonChip::configure(meta, &shared);
+
+        let q_prod = meta.selector();
+        let [v, r, acc] = shared.advice;
+        meta.create_gate("running_product", |meta| {
+            let q = meta.query_selector(q_prod);
+            let v = meta.query_advice(v, Rotation::cur());
+            let r = meta.query_advice(r, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            vec![("acc_next = acc * (r - v)", q * (acc_next - acc_cur * (r - v)))]
+        });
+        SortingConfig { shared, q_prod, range, cmp, poseidon }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let range = RangeCheckChip::construct(config.range);
+        let cmp = ComparisonChip::construct(config.cmp);
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        range.load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let public = layouter.assign_region(|| "公开列表", |mut region| {
+            (0..N).map(|i| region.assign_advice_from_instance(|| "x", config.shared.instance, i, config.shared.advice[0], i)).collect::<Result<Vec<_>, _>>()
+        })?;
+        let mut sorted = Vec::with_capacity(N);
+        for i in 0..N {
+            let value = self.sorted.map(|s| Fp::from(s[i]));
+            sorted.push(range.witness_check(layouter.namespace(|| "排序结果"), value, VALUE_LIMBS)?);
+        }
+        for pair in sorted.windows(2) {
+            cmp.assert_ge(layouter.namespace(|| "相邻有序"), &pair[1], &pair[0], VALUE_LIMBS)?;
+        }
+
+        // 挑战r依赖于x和s的全部元素
+        let mut r = public[0].clone();
+        for v in public[1..].iter().chain(&sorted) {
+            r = poseidon.hash2(layouter.namespace(|| "吸收"), &r, v)?;
+        }
+        let lhs = Self::product(&config, layouter.namespace(|| "∏(r - x)"), &public, &r)?;
+        let rhs = Self::product(&config, layouter.namespace(|| "∏(r - s)"), &sorted, &r)?;
+        layouter.assign_region(|| "连乘相等", |mut region| region.constrain_equal(lhs.cell(), rhs.cell()))
+    }
+}
+
+fn main() {
+    let list = [503u64, 17, 4096, 17, 65535, 0];
+    let mut sorted = list;
+    sorted.sort_unstable();
+    println!("{:?} 排序后为 {:?}", list, sorted);
+
+    let instance: Vec<Fp> = list.iter().map(|&x| Fp::from(x)).collect();
+    let circuit = SortingCircuit { sorted: Value::known(sorted) };
+    let prover = MockProver::run(K, &circuit, vec![instance.clone()]).expect("运行MockProver失败");
+    prover.assert_satisfied();
+
+    // 是排列但无序
+    let prover = MockProver::run(K, &SortingCircuit { sorted: Value::known(list) }, vec![instance.clone()]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "无序列表通过了检查");
+    // 有序但不是排列: 重复的17换成18
+    let mut other = sorted;
+    other[2] = 18;
+    let prover = MockProver::run(K, &SortingCircuit { sorted: Value::known(other) }, vec![instance.clone()]).expect("运行MockProver失败");
+    assert!(prover.verify().is_err(), "不是排列的列表通过了检查");
+
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).expect("生成密钥失败");
+    let proof = prove(&params, &pk, &circuit, &instance).expect("生成证明失败");
+    verify(&params, pk.get_vk(), &instance, &proof).into_result().expect("验证失败");
+    println!("证明 {} 字节", proof.len());
+}
+
+#[test]
+fn test_differential() {
+    use halo2_fib::testing::check_differential;
+
+    // 随机的16位列表; 第一组给出排序结果, 第二组给出原列表, 第三组把排序结果的最后一个元素翻转最低位.
+    // 只有等于原列表排序结果的见证成立
+    let (mut case, mut expected) = (0, 0);
+    let accepted = check_differential(K, 6, |rng| {
+        let list = [(); N].map(|_| (rng.next_u32() % (1 << 16)) as u64);
+        let mut sorted = list;
+        sorted.sort_unstable();
+        case += 1;
+        let witness = match case % 3 {
+            1 => sorted,
+            2 => list,
+            _ => {
+                let mut other = sorted;
+                other[N - 1] ^= 1;
+                other
+            }
+        };
+        expected += (witness == sorted) as usize;
+        (SortingCircuit { sorted: Value::known(witness) }, list.iter().map(|&x| Fp::from(x)).collect())
+    });
+    assert_eq!(accepted, expected);
+}
@@ -0,0 +1,55 @@
+#![no_main]
+
+//! 用变异的公开输入验证一份合法证明, 验证不能panic, 且公开输入与原值不等价时必须拒绝
+
+use std::sync::OnceLock;
+
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::ProvingKey;
+use halo2_proofs::poly::commitment::Params;
+use libfuzzer_sys::fuzz_target;
+
+struct Fixture {
+    params: Params<EqAffine>,
+    pk: ProvingKey<EqAffine>,
+    proof: Vec<u8>,
+}
+
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one()));
+        let params = setup(4);
+        let pk = keygen(&params, &circuit).expect("生成密钥失败");
+        let proof = prove(&params, &pk, &circuit, &[Fp::from(55)]).expect("生成证明失败");
+        Fixture { params, pk, proof }
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let fixture = fixture();
+    // 每8个字节作为一行公开输入, 行数也由输入决定
+    let instances: Vec<Fp> = data
+        .chunks(8)
+        .map(|chunk| {
+            let mut bytes = [0u8; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            Fp::from(u64::from_le_bytes(bytes))
+        })
+        .collect();
+
+    let result = verify(&fixture.params, fixture.pk.get_vk(), &instances, &fixture.proof);
+    // 末尾补零不改变实例多项式, 与原公开输入等价
+    let mut trimmed = instances.as_slice();
+    while let [rest @ .., last] = trimmed {
+        if *last != Fp::zero() {
+            break;
+        }
+        trimmed = rest;
+    }
+    if trimmed != [Fp::from(55)] {
+        assert!(!result.is_valid(), "错误的公开输入通过了验证");
+    }
+});
@@ -0,0 +1,48 @@
+#![no_main]
+
+//! 对合法证明逐字节异或变异, 验证不能panic, 且只要证明被改动就必须拒绝
+
+use std::sync::OnceLock;
+
+use halo2_fib::prover::{keygen, prove, setup, verify};
+use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::ProvingKey;
+use halo2_proofs::poly::commitment::Params;
+use libfuzzer_sys::fuzz_target;
+
+struct Fixture {
+    params: Params<EqAffine>,
+    pk: ProvingKey<EqAffine>,
+    proof: Vec<u8>,
+}
+
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one()));
+        let params = setup(4);
+        let pk = keygen(&params, &circuit).expect("生成密钥失败");
+        let proof = prove(&params, &pk, &circuit, &[Fp::from(55)]).expect("生成证明失败");
+        Fixture { params, pk, proof }
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let fixture = fixture();
+    // 前两个字节决定截断后的长度, 其余字节异或到证明上
+    let (len, mask) = match data {
+        [hi, lo, rest @ ..] => (u16::from_be_bytes([*hi, *lo]) as usize, rest),
+        _ => return,
+    };
+    let mut proof = fixture.proof.clone();
+    proof.truncate(len.min(proof.len()));
+    for (byte, m) in proof.iter_mut().zip(mask) {
+        *byte ^= m;
+    }
+
+    let result = verify(&fixture.params, fixture.pk.get_vk(), &[Fp::from(55)], &proof);
+    if proof != fixture.proof {
+        assert!(!result.is_valid(), "被改动的证明通过了验证");
+    }
+});
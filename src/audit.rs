@@ -0,0 +1,184 @@
+//! 证明和验证操作的审计日志: 只追加的JSONL, 每行带上一行的哈希, 改动或删除任一行都会让之后的链条对不上
+//!
+//! 每行形如
+//!
+//! ```text
+//! {"seq": 0, "prev": "00…00", "time": 1700000000, "op": "prove", "circuit": "fib", "layout": "n=10", "k": 4, "instance_hash": "…", "ms": 12.5, "outcome": "ok", "hash": "…"}
+//! ```
+//!
+//! hash为blake2b-256(`, "hash"`之前的全部文本), 第一行的prev为全零. 同一进程内的追加是串行的,
+//! 多个进程写同一个日志时需要调用方自己加锁
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use halo2_proofs::pasta::Fp;
+
+use crate::instance::{encode_instance, json_string};
+use crate::params_file::{parse_hash, to_hex};
+
+#[derive(Debug)]
+pub enum AuditError {
+    Io(io::Error),
+    /// 第line行(从1开始)无法解析
+    Malformed { line: usize },
+    /// 第line行的哈希、prev或序号与链条不符
+    Broken { line: usize },
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditError::Io(e) => write!(f, "读写审计日志失败: {}", e),
+            AuditError::Malformed { line } => write!(f, "审计日志第{}行无法解析", line),
+            AuditError::Broken { line } => write!(f, "审计日志在第{}行断链, 日志可能被改动", line),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+impl From<io::Error> for AuditError {
+    fn from(e: io::Error) -> Self {
+        AuditError::Io(e)
+    }
+}
+
+/// 公开输入的哈希, 日志里不保存公开输入本身
+pub fn instance_hash(public_inputs: &[Fp]) -> [u8; 32] {
+    let mut state = blake2b_simd::Params::new().hash_length(32).personal(b"halo2-fib-inst").to_state();
+    state.update(&(public_inputs.len() as u64).to_le_bytes());
+    for input in public_inputs {
+        state.update(&encode_instance(input));
+    }
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(state.finalize().as_bytes());
+    hash
+}
+
+fn line_hash(body: &str) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(blake2b_simd::Params::new().hash_length(32).personal(b"halo2-fib-audit").hash(body.as_bytes()).as_bytes());
+    hash
+}
+
+/// 一次操作
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditRecord {
+    /// "prove"或"verify"
+    pub op: &'static str,
+    pub circuit: String,
+    pub layout: String,
+    pub k: u32,
+    /// 见[`instance_hash`], 验证方拿不到公开输入时为None
+    pub instance_hash: Option<[u8; 32]>,
+    pub millis: f64,
+    /// 证明为"ok"或错误信息, 验证为[`VerifyOutcome::kind`](crate::prover::VerifyOutcome::kind)
+    pub outcome: String,
+}
+
+/// 拆出一行的序号、prev、参与哈希的部分和hash
+fn parse_line(line: &str) -> Option<(u64, [u8; 32], &str, [u8; 32])> {
+    let (body, rest) = line.rsplit_once(r#", "hash": ""#)?;
+    let hash = parse_hash(rest.strip_suffix("\"}")?)?;
+    let (seq, rest) = body.strip_prefix(r#"{"seq": "#)?.split_once(r#", "prev": ""#)?;
+    let prev = parse_hash(rest.get(..64)?)?;
+    Some((seq.parse().ok()?, prev, body, hash))
+}
+
+/// 逐行检查链条, 返回行数和最后一行的哈希
+pub fn verify_chain(text: &str) -> Result<(u64, [u8; 32]), AuditError> {
+    let mut last = [0u8; 32];
+    let mut count = 0;
+    for (i, line) in text.lines().enumerate() {
+        let (seq, prev, body, hash) = parse_line(line).ok_or(AuditError::Malformed { line: i + 1 })?;
+        if seq != count || prev != last || line_hash(body) != hash {
+            return Err(AuditError::Broken { line: i + 1 });
+        }
+        last = hash;
+        count += 1;
+    }
+    Ok((count, last))
+}
+
+/// 读取并检查日志文件
+pub fn verify_file(path: &Path) -> Result<(u64, [u8; 32]), AuditError> {
+    verify_chain(&fs::read_to_string(path)?)
+}
+
+/// 打开的审计日志, 可以在线程间共享
+pub struct AuditLog {
+    path: PathBuf,
+    /// 下一行的序号和最后一行的哈希
+    state: Mutex<(u64, [u8; 32])>,
+}
+
+impl AuditLog {
+    /// 打开日志, 不存在时创建; 已有的日志先检查链条, 断链时拒绝继续追加
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, AuditError> {
+        let path = path.into();
+        let state = match fs::read_to_string(&path) {
+            Ok(text) => verify_chain(&text)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (0, [0u8; 32]),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, state: Mutex::new(state) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一行, 返回其序号
+    pub fn append(&self, record: &AuditRecord) -> Result<u64, AuditError> {
+        let mut state = self.state.lock().expect("审计日志锁被污染");
+        let (seq, prev) = *state;
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let body = format!(
+            r#"{{"seq": {}, "prev": "{}", "time": {}, "op": {}, "circuit": {}, "layout": {}, "k": {}, "instance_hash": {}, "ms": {:.3}, "outcome": {}"#,
+            seq, to_hex(&prev), time, json_string(record.op), json_string(&record.circuit), json_string(&record.layout),
+            record.k, record.instance_hash.map_or("null".to_string(), |hash| format!("\"{}\"", to_hex(&hash))), record.millis, json_string(&record.outcome),
+        );
+        let hash = line_hash(&body);
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        // 整行一次写入
+        file.write_all(format!("{}, \"hash\": \"{}\"}}\n", body, to_hex(&hash)).as_bytes())?;
+        *state = (seq + 1, hash);
+        Ok(seq)
+    }
+}
+
+#[test]
+fn test_audit_chain() {
+    let path = std::env::temp_dir().join(format!("halo2-fib-audit-test-{}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let record = |op, outcome: &str| AuditRecord {
+        op,
+        circuit: "fib".to_string(),
+        layout: "n=10".to_string(),
+        k: 4,
+        instance_hash: Some(instance_hash(&[Fp::from(55)])),
+        millis: 1.5,
+        outcome: outcome.to_string(),
+    };
+    let log = AuditLog::open(&path).unwrap();
+    assert_eq!(log.append(&record("prove", "ok")).unwrap(), 0);
+    assert_eq!(log.append(&record("verify", "valid")).unwrap(), 1);
+    // 重新打开后接着链条追加, 带引号的错误信息也能解析
+    let log = AuditLog::open(&path).unwrap();
+    assert_eq!(log.append(&record("verify", "bad_proof \"x\", \"hash\": \"")).unwrap(), 2);
+    assert_eq!(verify_file(&path).unwrap().0, 3);
+    assert_ne!(instance_hash(&[Fp::from(56)]), instance_hash(&[Fp::from(55)]));
+
+    let text = fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(matches!(verify_chain(&text.replacen("\"n=10\"", "\"n=11\"", 1)), Err(AuditError::Broken { line: 1 })));
+    assert!(matches!(verify_chain(&lines[1..].join("\n")), Err(AuditError::Broken { line: 1 })));
+    assert!(matches!(verify_chain(&[lines[0], lines[2]].join("\n")), Err(AuditError::Broken { line: 2 })));
+    assert!(matches!(verify_chain("{}"), Err(AuditError::Malformed { line: 1 })));
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,1053 @@
+//! 斐波那契电路的命令行工具
+//!
+//! 用法: cargo run --release --features cli --bin fib -- [--format json] <prove|verify|mock|profile|report|trace|explore|circuits|cache|prove-batch|migrate-proof|params|secret|audit> ...
+//! `explore`需要`--features tui`
+//!
+//! prove、verify、mock的命题也可以写成一段文本, 如`--statement "fib(n=30, a=1, b=1) == 832040"`;
+//! prove和verify的命题可以是[`CircuitRegistry`]中的任一电路, 用`circuits`列出
+//!
+//! 给出`--audit-log`时, prove、verify和prove-batch的每次证明或验证都追加到哈希链审计日志, 用`audit`检查链条
+//!
+//! `--format json`时每个子命令都向标准输出写一个JSON对象, 出错时为`{"command": ..., "error": ...}`
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use halo2_fib::audit::{instance_hash, verify_file, AuditLog, AuditRecord};
+use halo2_fib::cache::{config_path, CacheDirs};
+use halo2_fib::dev::{degree_report, group_failures, region_report, render_failures, selector_report};
+use halo2_fib::dsl::{parse_decimal, Statement};
+use halo2_fib::instance::{instance_to_hex, INSTANCE_ENCODING};
+use halo2_fib::params_file::{params_bytes, params_hash, parse_hash, read_params_file, to_hex};
+use halo2_fib::profile::profile;
+use halo2_fib::proof_file::{decode_proof, encode_proof, migrate_v1, verify_encoded, ProofHeader};
+use halo2_fib::proof_store::{ProofKey, ProofStore};
+use halo2_fib::registry::CircuitRegistry;
+use halo2_fib::sealed::{is_sealed, open, seal, SealKey};
+use halo2_fib::prover::{keygen, keygen_with_retry, prove, prove_zeroizing, setup, ZeroizeWitness};
+use halo2_fib::trace::{trace, HtmlTable};
+use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{keygen_vk, Circuit, ProvingKey, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Parser)]
+#[command(name = "fib", about = "斐波那契电路的命令行工具")]
+struct Cli {
+    /// 输出格式
+    #[arg(long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
+    /// 追加证明和验证记录的审计日志(JSONL)
+    #[arg(long, global = true)]
+    audit_log: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// 证明文件的格式版本
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum ProofVersion {
+    V1,
+    V2,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 证明以a、b开头的数列的第n项, 证明带验证密钥指纹文件头
+    Prove {
+        #[command(flatten)]
+        statement: StatementArgs,
+        /// 证明文件路径
+        #[arg(long)]
+        out: PathBuf,
+        /// 默认为能放下n的最小k
+        #[arg(long)]
+        k: Option<u32>,
+        /// 不查也不写缓存目录下的证明仓库
+        #[arg(long)]
+        no_store: bool,
+    },
+    /// 验证证明, target默认为以a、b开头的数列的第n项
+    Verify {
+        #[arg(long)]
+        proof: PathBuf,
+        #[command(flatten)]
+        statement: StatementArgs,
+        #[arg(long)]
+        k: Option<u32>,
+    },
+    /// 用MockProver检查约束, 失败按门和区域分组列出
+    Mock {
+        #[command(flatten)]
+        statement: StatementArgs,
+        #[arg(long)]
+        k: Option<u32>,
+    },
+    /// 合成一遍电路, 按命名空间和区域统计耗时
+    Profile {
+        #[command(flatten)]
+        statement: StatementArgs,
+        /// 另外写出folded格式, 可交给flamegraph.pl或inferno-flamegraph画火焰图
+        #[arg(long)]
+        folded: Option<PathBuf>,
+    },
+    /// 列出可以用--statement证明的电路及其参数
+    Circuits,
+    /// 管理参数、密钥和证明的缓存目录
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// 导出公共参数文件, 或在使用前检查其完整性和哈希
+    Params {
+        #[command(subcommand)]
+        action: ParamsAction,
+    },
+    /// 打印区域行使用情况和约束次数
+    Report {
+        #[arg(long)]
+        n: usize,
+        #[arg(long)]
+        k: Option<u32>,
+    },
+    /// 把电路每一格填写的值画成HTML表格
+    Trace {
+        #[arg(long)]
+        n: usize,
+        #[arg(long, default_value_t = 1)]
+        a: u64,
+        #[arg(long, default_value_t = 1)]
+        b: u64,
+        /// HTML文件路径
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long)]
+        k: Option<u32>,
+    },
+    /// 在终端里浏览填写矩阵, 可跳转到约束失败
+    #[cfg(feature = "tui")]
+    Explore {
+        #[arg(long)]
+        n: usize,
+        #[arg(long, default_value_t = 1)]
+        a: u64,
+        #[arg(long, default_value_t = 1)]
+        b: u64,
+        #[arg(long)]
+        target: Option<String>,
+        #[arg(long)]
+        k: Option<u32>,
+    },
+    /// 升级旧版证明文件的文件头, 不重新证明; 第1版没有的元数据按n和k重建验证密钥补齐
+    MigrateProof {
+        #[arg(long, value_enum)]
+        from: ProofVersion,
+        #[arg(long, value_enum)]
+        to: ProofVersion,
+        /// 旧版证明文件
+        #[arg(long)]
+        input: PathBuf,
+        /// 新版证明文件路径
+        #[arg(long)]
+        out: PathBuf,
+        /// 生成证明时的n
+        #[arg(long)]
+        n: usize,
+        /// 默认为能放下n的最小k
+        #[arg(long)]
+        k: Option<u32>,
+    },
+    /// 批量证明文件中的命题, n相同的命题共用参数和密钥
+    ProveBatch {
+        /// 命题文件, .csv按"n,a,b"逐行读取, 其余按JSON数组读取
+        #[arg(long)]
+        input: PathBuf,
+        /// 证明输出目录
+        #[arg(long)]
+        out: PathBuf,
+        /// 工作线程数, 默认为CPU核数
+        #[arg(long, value_parser = parse_threads)]
+        threads: Option<usize>,
+        /// k的上限
+        #[arg(long, default_value_t = 20)]
+        max_k: u32,
+        /// 命题文件已用`secret encrypt`加密时的密钥文件
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+    },
+    /// 检查审计日志的哈希链
+    Audit {
+        /// 审计日志文件
+        log: PathBuf,
+    },
+    /// 生成密钥, 加密或解密命题文件, 秘密见证不以明文留在磁盘上
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+}
+
+/// 命题文件中的一条记录: 以a、b(默认为1)开头的数列的第n项
+#[derive(Clone, Debug, Deserialize)]
+struct StatementRecord {
+    n: usize,
+    #[serde(default = "one")]
+    a: u64,
+    #[serde(default = "one")]
+    b: u64,
+}
+
+fn one() -> u64 {
+    1
+}
+
+/// 读取命题文件; 给出密钥时文件必须是加密的, 格式按去掉.sealed后的扩展名判断
+fn read_statements(path: &Path, key: Option<&SealKey>) -> Result<Vec<StatementRecord>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("读取{}失败: {}", path.display(), e))?;
+    let (bytes, name) = match key {
+        Some(key) => {
+            let bytes = open(key, &bytes).map_err(|e| format!("解密{}失败: {}", path.display(), e))?;
+            let name = if path.extension().is_some_and(|ext| ext == "sealed") { path.with_extension("") } else { path.to_path_buf() };
+            (bytes, name)
+        }
+        None if is_sealed(&bytes) => return Err(format!("{}是加密文件, 需要--key-file", path.display())),
+        None => (bytes, path.to_path_buf()),
+    };
+    let mut text = String::from_utf8(bytes).map_err(|_| format!("{}不是UTF-8文本", path.display()))?;
+    let records = if name.extension().map_or(false, |ext| ext == "csv") {
+        parse_csv(&text)
+    } else {
+        serde_json::from_str(&text).map_err(|e| format!("解析JSON失败: {}", e))
+    };
+    zeroize::Zeroize::zeroize(&mut text);
+    records
+}
+
+/// 每行"n,a,b", a、b可省略, 第一行不是数字时当作表头跳过
+fn parse_csv(text: &str) -> Result<Vec<StatementRecord>, String> {
+    let mut records = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields[0].is_empty() || (i == 0 && fields[0].parse::<usize>().is_err()) {
+            continue;
+        }
+        let field = |j: usize| -> Result<u64, String> {
+            match fields.get(j) {
+                None | Some(&"") => Ok(1),
+                Some(s) => s.parse().map_err(|_| format!("第{}行第{}列不是整数: {}", i + 1, j + 1, s)),
+            }
+        };
+        let n = fields[0].parse().map_err(|_| format!("第{}行的n不是整数: {}", i + 1, fields[0]))?;
+        records.push(StatementRecord { n, a: field(1)?, b: field(2)? });
+    }
+    Ok(records)
+}
+
+/// 斐波那契命题: 用--statement的文本格式, 或者分别给出--n、--a、--b、--target
+#[derive(Args)]
+struct StatementArgs {
+    /// 命题文本, 如"fib(n=30, a=1, b=1) == 832040"
+    #[arg(long, conflicts_with_all = ["n", "a", "b", "target"])]
+    statement: Option<String>,
+    #[arg(long, required_unless_present = "statement")]
+    n: Option<usize>,
+    #[arg(long, default_value_t = 1)]
+    a: u64,
+    #[arg(long, default_value_t = 1)]
+    b: u64,
+    /// 十进制的target, 覆盖由a、b算出的值
+    #[arg(long)]
+    target: Option<String>,
+}
+
+impl StatementArgs {
+    /// --statement给出的不是斐波那契电路时, 交给注册表处理
+    fn registered(&self) -> Result<Option<Statement>, String> {
+        let Some(text) = &self.statement else {
+            return Ok(None);
+        };
+        let statement = Statement::parse(text).map_err(|e| e.to_string())?;
+        Ok((statement.circuit != "fib").then_some(statement))
+    }
+
+    fn resolve(&self) -> Result<(FibStatement<Fp>, FibWitness<Fp>), String> {
+        match (&self.statement, self.n) {
+            (Some(text), _) => Statement::parse(text).and_then(|statement| statement.fib()).map_err(|e| e.to_string()),
+            (None, Some(n)) => statement_from_args(n, self.a, self.b, self.target.as_deref()),
+            (None, None) => Err("需要--statement或--n".to_string()),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// 打印缓存目录和占用大小
+    Show,
+    /// 删除整个缓存目录
+    Clean,
+    /// 列出证明仓库中的证明
+    Proofs,
+}
+
+#[derive(Subcommand)]
+enum ParamsAction {
+    /// 检查参数文件的长度、每个点的编码, 以及k和哈希是否与期望一致
+    Verify {
+        /// 参数文件
+        file: PathBuf,
+        /// 公布的blake2b-256哈希, 64个十六进制字符
+        #[arg(long)]
+        expected_hash: Option<String>,
+        #[arg(long)]
+        k: Option<u32>,
+    },
+    /// 生成k对应的参数并写入文件, 打印其哈希
+    Export {
+        #[arg(long)]
+        k: u32,
+        #[arg(long)]
+        out: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecretAction {
+    /// 生成随机密钥并写入新文件
+    Keygen {
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// 加密文件, 输出默认为原路径加.sealed
+    Encrypt {
+        #[arg(long)]
+        key_file: PathBuf,
+        #[arg(long)]
+        input: PathBuf,
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// 解密文件
+    Decrypt {
+        #[arg(long)]
+        key_file: PathBuf,
+        #[arg(long)]
+        input: PathBuf,
+        #[arg(long)]
+        out: PathBuf,
+    },
+}
+
+/// 子命令的结果, 按输出格式打印其中之一
+struct Output {
+    ok: bool,
+    text: String,
+    json: serde_json::Value,
+}
+
+fn millis(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
+
+/// 线程数, 必须为正
+fn parse_threads(s: &str) -> Result<usize, String> {
+    s.parse().ok().filter(|&threads| threads > 0).ok_or_else(|| format!("线程数须为正整数: {:?}", s))
+}
+
+/// 十进制字符串转为域元素, 可以超过u64
+fn parse_field(s: &str) -> Result<Fp, String> {
+    parse_decimal(s).ok_or_else(|| format!("target不是十进制整数: {:?}", s))
+}
+
+/// 由命令行参数得到命题和见证, target给出时覆盖由a、b算出的值
+fn statement_from_args(n: usize, a: u64, b: u64, target: Option<&str>) -> Result<(FibStatement<Fp>, FibWitness<Fp>), String> {
+    let witness = FibWitness::new(Fp::from(a), Fp::from(b));
+    let statement = match target {
+        Some(target) => FibStatement::new(n, parse_field(target)?),
+        None => FibStatement::from_witness(n, &witness),
+    };
+    Ok((statement.map_err(|e| e.to_string())?, witness))
+}
+
+/// 斐波那契电路的证明文件头, 布局变体由n决定
+fn fib_header(n: usize, k: u32, vk: &VerifyingKey<EqAffine>) -> Result<ProofHeader, String> {
+    ProofHeader::new("fib", &format!("n={}", n), k, vk).map_err(|e| e.to_string())
+}
+
+/// `--audit-log`打开的审计日志
+static AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+/// 有审计日志时追加一行
+fn audit(record: AuditRecord) -> Result<(), String> {
+    match AUDIT_LOG.get() {
+        Some(log) => log.append(&record).map(|_| ()).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// 斐波那契电路的一条审计记录
+fn fib_record(op: &'static str, statement: &FibStatement<Fp>, k: u32, time: Duration, outcome: &str) -> AuditRecord {
+    AuditRecord {
+        op,
+        circuit: "fib".to_string(),
+        layout: format!("n={}", statement.n),
+        k,
+        instance_hash: Some(instance_hash(&statement.public_inputs())),
+        millis: millis(time),
+        outcome: outcome.to_string(),
+    }
+}
+
+/// 缓存目录下的证明仓库
+fn open_store() -> Result<ProofStore, String> {
+    let dirs = CacheDirs::resolve().map_err(|e| e.to_string())?;
+    ProofStore::in_cache(&dirs).map_err(|e| format!("打开证明仓库失败: {}", e))
+}
+
+fn prove_cmd(spec: &StatementArgs, out: &Path, k: Option<u32>, no_store: bool) -> Result<Output, String> {
+    if let Some(statement) = spec.registered()? {
+        return registered_prove_cmd(&statement, out);
+    }
+    let (statement, mut witness) = spec.resolve()?;
+    if !witness.satisfies(&statement) {
+        return Err(format!("见证不满足命题: 第{}项不等于{:?}", statement.n, statement.target));
+    }
+    let n = statement.n;
+    let mut circuit = FibCircuit::new(&statement, &witness);
+    witness.zeroize_witness();
+    let k = k.unwrap_or_else(|| FibCircuit::min_k(n));
+
+    let start = Instant::now();
+    let params = setup(k);
+    let pk = keygen(&params, &circuit).map_err(|e| format!("生成密钥失败: {:?}", e))?;
+    let keygen_time = start.elapsed();
+    // 仓库里存裸证明, 文件头每次重新生成
+    let store = if no_store { None } else { Some(open_store()?) };
+    let key = ProofKey::new(pk.get_vk(), &statement.public_inputs());
+    let stored = match &store {
+        Some(store) => store.get(&key).map_err(|e| format!("读取证明仓库失败: {}", e))?,
+        None => None,
+    };
+    let cached = stored.is_some();
+    let start = Instant::now();
+    let result = match stored {
+        Some(proof) => Ok(proof),
+        None => prove(&params, &pk, &circuit, &statement.public_inputs()).map_err(|e| format!("生成证明失败: {:?}", e)),
+    };
+    circuit.zeroize_witness();
+    let prove_time = start.elapsed();
+    let outcome = match &result {
+        Ok(_) if cached => "cached",
+        Ok(_) => "ok",
+        Err(e) => e.as_str(),
+    };
+    audit(fib_record("prove", &statement, k, prove_time, outcome))?;
+    let proof = result?;
+    if let (Some(store), false) = (&store, cached) {
+        store.put(&key, &proof).map_err(|e| format!("写入证明仓库失败: {}", e))?;
+    }
+    fs::write(out, encode_proof(&fib_header(n, k, pk.get_vk())?, &proof)).map_err(|e| format!("写入{}失败: {}", out.display(), e))?;
+
+    let source = if cached { "(取自证明仓库)" } else { "" };
+    Ok(Output {
+        ok: true,
+        text: format!("n = {}, k = {}, target = {:?}\n密钥生成 {:.1} ms, 证明{} {:.1} ms, {} 字节 -> {}", n, k, statement.target, millis(keygen_time), source, millis(prove_time), proof.len(), out.display()),
+        json: json!({
+            "command": "prove", "n": n, "k": k, "target": instance_to_hex(&statement.target), "encoding": INSTANCE_ENCODING,
+            "proof_path": out, "proof_len": proof.len(), "store_key": key.to_string(), "cached": cached,
+            "timings_ms": { "keygen": millis(keygen_time), "prove": millis(prove_time) },
+        }),
+    })
+}
+
+fn verify_cmd(proof_path: &Path, spec: &StatementArgs, k: Option<u32>) -> Result<Output, String> {
+    if let Some(statement) = spec.registered()? {
+        return registered_verify_cmd(proof_path, &statement);
+    }
+    let (statement, witness) = spec.resolve()?;
+    let n = statement.n;
+    let bytes = fs::read(proof_path).map_err(|e| format!("读取{}失败: {}", proof_path.display(), e))?;
+    let k = k.unwrap_or_else(|| FibCircuit::min_k(n));
+
+    let start = Instant::now();
+    let params = setup(k);
+    let vk = keygen_vk(&params, &FibCircuit::new(&statement, &witness).without_witnesses()).map_err(|e| format!("生成验证密钥失败: {:?}", e))?;
+    let keygen_time = start.elapsed();
+    let start = Instant::now();
+    let outcome = verify_encoded(&params, &vk, &fib_header(n, k, &vk)?, &statement.public_inputs(), &bytes);
+    let verify_time = start.elapsed();
+    audit(fib_record("verify", &statement, k, verify_time, outcome.kind()))?;
+
+    let error = (!outcome.is_valid()).then(|| outcome.to_string());
+    Ok(Output {
+        ok: outcome.is_valid(),
+        text: match &error {
+            None => format!("验证通过: n = {}, target = {:?}, {:.1} ms", n, statement.target, millis(verify_time)),
+            Some(e) => format!("验证失败: {}", e),
+        },
+        json: json!({
+            "command": "verify", "n": n, "k": k, "target": instance_to_hex(&statement.target), "encoding": INSTANCE_ENCODING,
+            "proof_path": proof_path, "valid": outcome.is_valid(), "reason": outcome.kind(), "error": error,
+            "timings_ms": { "keygen": millis(keygen_time), "verify": millis(verify_time) },
+        }),
+    })
+}
+
+fn migrate_proof_cmd(from: ProofVersion, to: ProofVersion, input: &Path, out: &Path, n: usize, k: Option<u32>) -> Result<Output, String> {
+    if (from, to) != (ProofVersion::V1, ProofVersion::V2) {
+        return Err(format!("不支持从{:?}升级到{:?}, 目前只支持v1到v2", from, to));
+    }
+    let bytes = fs::read(input).map_err(|e| format!("读取{}失败: {}", input.display(), e))?;
+    let k = k.unwrap_or_else(|| FibCircuit::min_k(n));
+    // 验证密钥只取决于电路形状, 见证随便填
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(n, &witness).map_err(|e| e.to_string())?;
+    let vk = keygen_vk(&setup(k), &FibCircuit::new(&statement, &witness).without_witnesses()).map_err(|e| format!("生成验证密钥失败: {:?}", e))?;
+    let mut header = fib_header(n, k, &vk)?;
+    // 时间戳沿用旧文件的修改时间, 而不是升级的时间
+    if let Some(modified) = fs::metadata(input).and_then(|m| m.modified()).ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()) {
+        header.timestamp = modified.as_secs();
+    }
+    let migrated = migrate_v1(&bytes, &header).map_err(|e| format!("升级{}失败: {}", input.display(), e))?;
+    fs::write(out, &migrated).map_err(|e| format!("写入{}失败: {}", out.display(), e))?;
+    Ok(Output {
+        ok: true,
+        text: format!("{} (v1, {} 字节) -> {} (v2, {} 字节), n = {}, k = {}", input.display(), bytes.len(), out.display(), migrated.len(), n, k),
+        json: json!({
+            "command": "migrate-proof", "from": "v1", "to": "v2", "input": input, "out": out,
+            "n": n, "k": k, "input_len": bytes.len(), "proof_len": migrated.len(),
+        }),
+    })
+}
+
+/// 经注册表证明其他电路, k自动选择
+fn registered_prove_cmd(statement: &Statement, out: &Path) -> Result<Output, String> {
+    let registry = CircuitRegistry::builtin();
+    let start = Instant::now();
+    let result = registry.prove(statement).map_err(|e| format!("生成证明失败: {}", e));
+    let time = start.elapsed();
+    // 参数里有见证, 布局变体只取证明文件头中决定形状的部分
+    let header = result.as_ref().ok().and_then(|proved| decode_proof(&proved.proof).ok()).map(|(header, _)| header);
+    audit(AuditRecord {
+        op: "prove",
+        circuit: statement.circuit.clone(),
+        layout: header.as_ref().map_or(String::new(), |header| header.layout.clone()),
+        k: header.as_ref().map_or(0, |header| header.k),
+        instance_hash: result.as_ref().ok().map(|proved| instance_hash(&proved.public_inputs)),
+        millis: millis(time),
+        outcome: result.as_ref().err().map_or("ok", |e| e.as_str()).to_string(),
+    })?;
+    let proved = result?;
+    fs::write(out, &proved.proof).map_err(|e| format!("写入{}失败: {}", out.display(), e))?;
+    let public_inputs: Vec<String> = proved.public_inputs.iter().map(instance_to_hex).collect();
+    // 注册的电路都只有一个公开输入, 即命题的目标值
+    let public = registry.get(&statement.circuit).expect("证明成功时电路已注册").public_statement(statement, &proved.public_inputs[0]);
+    Ok(Output {
+        ok: true,
+        text: format!(
+            "{}: k = {}, 公开输入 {:?}\n密钥生成和证明 {:.1} ms, {} 字节 -> {}\n验证命题: {}",
+            statement.circuit, proved.k, public_inputs, millis(time), proved.proof.len(), out.display(), public
+        ),
+        json: json!({
+            "command": "prove", "circuit": statement.circuit, "k": proved.k, "public_inputs": public_inputs, "encoding": INSTANCE_ENCODING,
+            "public_statement": public.to_string(),
+            "proof_path": out, "proof_len": proved.proof.len(), "timings_ms": { "total": millis(time) },
+        }),
+    })
+}
+
+fn registered_verify_cmd(proof_path: &Path, statement: &Statement) -> Result<Output, String> {
+    let bytes = fs::read(proof_path).map_err(|e| format!("读取{}失败: {}", proof_path.display(), e))?;
+    let start = Instant::now();
+    let outcome = CircuitRegistry::builtin().verify(statement, &bytes).map_err(|e| format!("验证失败: {}", e))?;
+    let time = start.elapsed();
+    let header = decode_proof(&bytes).ok().map(|(header, _)| header);
+    audit(AuditRecord {
+        op: "verify",
+        circuit: statement.circuit.clone(),
+        layout: header.as_ref().map_or(String::new(), |header| header.layout.clone()),
+        k: header.as_ref().map_or(0, |header| header.k),
+        instance_hash: None,
+        millis: millis(time),
+        outcome: outcome.kind().to_string(),
+    })?;
+    let error = (!outcome.is_valid()).then(|| outcome.to_string());
+    Ok(Output {
+        ok: outcome.is_valid(),
+        text: match &error {
+            None => format!("验证通过: {}, {:.1} ms", statement.circuit, millis(time)),
+            Some(e) => format!("验证失败: {}", e),
+        },
+        json: json!({
+            "command": "verify", "circuit": statement.circuit, "proof_path": proof_path,
+            "valid": outcome.is_valid(), "reason": outcome.kind(), "error": error, "timings_ms": { "total": millis(time) },
+        }),
+    })
+}
+
+fn profile_cmd(spec: &StatementArgs, folded: Option<&Path>) -> Result<Output, String> {
+    let (circuit, profile) = match spec.registered()? {
+        Some(statement) => (statement.circuit.clone(), CircuitRegistry::builtin().profile(&statement).map_err(|e| format!("合成失败: {}", e))?),
+        None => {
+            let (statement, witness) = spec.resolve()?;
+            let profile = profile(&FibCircuit::new(&statement, &witness), vec![statement.public_inputs()]).map_err(|e| format!("合成失败: {:?}", e))?;
+            ("fib".to_string(), profile)
+        }
+    };
+    if let Some(path) = folded {
+        fs::write(path, profile.folded()).map_err(|e| format!("写入{}失败: {}", path.display(), e))?;
+    }
+    let frames: Vec<_> = profile.frames.iter().map(|(stack, frame)| json!({
+        "stack": stack, "calls": frame.calls, "cells": frame.cells, "self_ms": millis(frame.time),
+    })).collect();
+    Ok(Output {
+        ok: true,
+        text: format!("{}: 合成 {:.1} ms\n{}", circuit, millis(profile.total()), profile.report().trim_end()),
+        json: json!({ "command": "profile", "circuit": circuit, "total_ms": millis(profile.total()), "frames": frames, "folded_path": folded }),
+    })
+}
+
+fn circuits_cmd() -> Result<Output, String> {
+    let registry = CircuitRegistry::builtin();
+    let mut text = String::new();
+    for entry in registry.entries() {
+        writeln!(text, "{}: {}", entry.name, entry.doc).expect("写入列表失败");
+        for param in &entry.params {
+            let default = param.default.map_or("必需".to_string(), |d| format!("默认{}", d));
+            writeln!(text, "  {} ({}) {}", param.name, default, param.doc).expect("写入列表失败");
+        }
+    }
+    let circuits: Vec<_> = registry.entries().map(|entry| json!({
+        "name": entry.name, "doc": entry.doc,
+        "params": entry.params.iter().map(|p| json!({ "name": p.name, "default": p.default, "shape": p.shape, "doc": p.doc })).collect::<Vec<_>>(),
+    })).collect();
+    Ok(Output { ok: true, text: text.trim_end().to_string(), json: json!({ "command": "circuits", "circuits": circuits }) })
+}
+
+fn mock_cmd(spec: &StatementArgs, k: Option<u32>) -> Result<Output, String> {
+    let (statement, witness) = spec.resolve()?;
+    let n = statement.n;
+    let k = k.unwrap_or_else(|| FibCircuit::min_k(n));
+
+    let start = Instant::now();
+    let prover = MockProver::run(k, &FibCircuit::new(&statement, &witness), vec![statement.public_inputs()]).map_err(|e| format!("运行MockProver失败: {:?}", e))?;
+    let errors = prover.verify().err().unwrap_or_default();
+    let time = start.elapsed();
+    let failures: Vec<String> = errors.iter().map(|f| f.to_string()).collect();
+    let groups = group_failures(&errors);
+
+    Ok(Output {
+        ok: failures.is_empty(),
+        text: if failures.is_empty() { format!("约束全部满足, {:.1} ms", millis(time)) } else { render_failures(&groups, std::io::stdout().is_terminal()) },
+        json: json!({
+            "command": "mock", "n": n, "k": k, "satisfied": failures.is_empty(), "failures": failures,
+            "groups": groups.iter().map(|g| json!({ "kind": g.kind, "gate": g.gate, "region": g.region, "details": g.details })).collect::<Vec<_>>(),
+            "timings_ms": { "mock": millis(time) },
+        }),
+    })
+}
+
+fn report_cmd(n: usize, k: Option<u32>) -> Result<Output, String> {
+    let (statement, witness) = statement_from_args(n, 1, 1, None)?;
+    let circuit = FibCircuit::new(&statement, &witness);
+    let k = k.unwrap_or_else(|| FibCircuit::min_k(n));
+    let regions = region_report(k, &circuit).map_err(|e| format!("统计区域失败: {:?}", e))?;
+    let degrees = degree_report(k, &circuit).map_err(|e| format!("统计次数失败: {:?}", e))?;
+    let selectors = selector_report(k, &circuit).map_err(|e| format!("统计选择器失败: {:?}", e))?;
+
+    Ok(Output {
+        ok: true,
+        text: format!("{}\n{}\n{}", regions, degrees, selectors),
+        json: json!({
+            "command": "report", "n": n, "k": k,
+            "usable_rows": regions.usable_rows, "used_rows": regions.used_rows(),
+            "gap_rows": regions.gap_rows().len(), "spare_rows": regions.spare_rows(),
+            "regions": regions.regions.iter().map(|r| json!({ "name": r.name, "rows": r.rows, "columns": r.columns, "wasted_cells": r.wasted_cells() })).collect::<Vec<_>>(),
+            "degree": degrees.degree, "compressed_degree": degrees.compressed_degree,
+            "constraints": degrees.constraints.iter().map(|c| json!({ "gate": c.gate, "constraint": c.constraint, "degree": c.degree, "compressed_degree": c.compressed_degree })).collect::<Vec<_>>(),
+            "fixed_columns": selectors.fixed_columns, "selector_columns": selectors.selector_columns,
+            "selectors": selectors.selectors.iter().map(|s| json!({ "index": s.index, "simple": s.simple, "gates": s.gates, "rows": s.rows, "column": s.column, "tag": s.tag })).collect::<Vec<_>>(),
+        }),
+    })
+}
+
+fn trace_cmd(n: usize, a: u64, b: u64, out: &Path, k: Option<u32>) -> Result<Output, String> {
+    let (statement, witness) = statement_from_args(n, a, b, None)?;
+    let k = k.unwrap_or_else(|| FibCircuit::min_k(n));
+    let mut table = HtmlTable::new();
+    trace(k, &FibCircuit::new(&statement, &witness), vec![statement.public_inputs()], &mut table).map_err(|e| format!("合成电路失败: {:?}", e))?;
+    fs::write(out, table.render()).map_err(|e| format!("写入{}失败: {}", out.display(), e))?;
+
+    Ok(Output {
+        ok: true,
+        text: format!("n = {}, k = {} -> {}", n, k, out.display()),
+        json: json!({ "command": "trace", "n": n, "k": k, "out": out }),
+    })
+}
+
+#[cfg(feature = "tui")]
+fn explore_cmd(n: usize, a: u64, b: u64, target: Option<&str>, k: Option<u32>) -> Result<Output, String> {
+    let (statement, witness) = statement_from_args(n, a, b, target)?;
+    let k = k.unwrap_or_else(|| FibCircuit::min_k(n));
+    let circuit = FibCircuit::new(&statement, &witness);
+    let mut explorer = halo2_fib::explore::Explorer::new(k, &circuit, vec![statement.public_inputs()]).map_err(|e| format!("合成电路失败: {:?}", e))?;
+    explorer.run().map_err(|e| format!("终端出错: {}", e))?;
+
+    let failures = explorer.failures().len();
+    Ok(Output {
+        ok: failures == 0,
+        text: format!("n = {}, k = {}, {}个失败", n, k, failures),
+        json: json!({ "command": "explore", "n": n, "k": k, "failures": failures }),
+    })
+}
+
+fn cache_cmd(action: CacheAction) -> Result<Output, String> {
+    let dirs = CacheDirs::resolve().map_err(|e| e.to_string())?;
+    match action {
+        CacheAction::Show => {
+            let size = dirs.size().map_err(|e| e.to_string())?;
+            Ok(Output {
+                ok: true,
+                text: format!("缓存目录: {}\n占用: {} 字节", dirs.root.display(), size),
+                json: json!({ "command": "cache show", "cache_dir": dirs.root, "config": config_path(), "bytes": size }),
+            })
+        }
+        CacheAction::Clean => {
+            let freed = dirs.clean().map_err(|e| e.to_string())?;
+            Ok(Output {
+                ok: true,
+                text: format!("已删除{}, 释放 {} 字节", dirs.root.display(), freed),
+                json: json!({ "command": "cache clean", "cache_dir": dirs.root, "freed_bytes": freed }),
+            })
+        }
+        CacheAction::Proofs => {
+            let store = ProofStore::in_cache(&dirs).map_err(|e| format!("打开证明仓库失败: {}", e))?;
+            let proofs = store.list().map_err(|e| format!("读取证明仓库失败: {}", e))?;
+            let mut text = format!("{} 中有 {} 个证明", store.dir().display(), proofs.len());
+            for (key, len) in &proofs {
+                write!(text, "\n  {} {} 字节", key, len).expect("写入列表失败");
+            }
+            Ok(Output {
+                ok: true,
+                text,
+                json: json!({
+                    "command": "cache proofs", "store_dir": store.dir(),
+                    "proofs": proofs.iter().map(|(key, len)| json!({ "key": key.to_string(), "bytes": len })).collect::<Vec<_>>(),
+                }),
+            })
+        }
+    }
+}
+
+fn params_cmd(action: ParamsAction) -> Result<Output, String> {
+    match action {
+        ParamsAction::Verify { file, expected_hash, k } => {
+            let expected_hash = match expected_hash.as_deref() {
+                Some(hex) => Some(parse_hash(hex).ok_or_else(|| format!("--expected-hash不是64个十六进制字符: {}", hex))?),
+                None => None,
+            };
+            let start = Instant::now();
+            let result = read_params_file(&file, k, expected_hash.as_ref());
+            let time = start.elapsed();
+            Ok(match result {
+                Ok(checked) => Output {
+                    ok: true,
+                    text: format!("{}: k = {}, blake2b-256 = {}, {:.1} ms", file.display(), checked.k, to_hex(&checked.hash), millis(time)),
+                    json: json!({
+                        "command": "params verify", "file": file, "valid": true, "k": checked.k,
+                        "hash": to_hex(&checked.hash), "hash_checked": expected_hash.is_some(), "timings_ms": { "verify": millis(time) },
+                    }),
+                },
+                Err(e) => Output {
+                    ok: false,
+                    text: format!("{}不可用: {}", file.display(), e),
+                    json: json!({ "command": "params verify", "file": file, "valid": false, "error": e.to_string() }),
+                },
+            })
+        }
+        ParamsAction::Export { k, out } => {
+            let bytes = params_bytes(&setup(k));
+            fs::write(&out, &bytes).map_err(|e| format!("写入{}失败: {}", out.display(), e))?;
+            let hash = to_hex(&params_hash(&bytes));
+            Ok(Output {
+                ok: true,
+                text: format!("k = {}, {} 字节 -> {}\nblake2b-256 = {}", k, bytes.len(), out.display(), hash),
+                json: json!({ "command": "params export", "k": k, "out": out, "bytes": bytes.len(), "hash": hash }),
+            })
+        }
+    }
+}
+
+/// 按n索引的k、参数和证明密钥
+/// 每个n的参数和密钥, n不合法或生成密钥失败时为错误信息, 只影响该n的命题
+type Keys = BTreeMap<usize, Result<(u32, Params<EqAffine>, ProvingKey<EqAffine>), String>>;
+
+/// 检查n至少为3且放得下k不超过max_k的电路, 在生成见证之前调用
+fn check_n(n: usize, max_k: u32) -> Result<(), String> {
+    if n < 3 {
+        return Err(format!("n = {}小于3", n));
+    }
+    // 每项至少占一行, 先排除超过2^max_k的n, min_k里的行数计算不会溢出
+    if n > 1 << max_k.min(32) || FibCircuit::min_k(n) > max_k {
+        return Err(format!("n = {}需要的k超过上限{}", n, max_k));
+    }
+    Ok(())
+}
+
+struct Proved {
+    k: u32,
+    target: Fp,
+    time: Duration,
+    size: usize,
+}
+
+fn prove_one(i: usize, record: &StatementRecord, keys: &Keys, out: &Path) -> Result<Proved, String> {
+    let (k, params, pk) = keys.get(&record.n).ok_or("没有对应的密钥")?.as_ref().map_err(|e| e.clone())?;
+    let mut witness = FibWitness::new(Fp::from(record.a), Fp::from(record.b));
+    let statement = FibStatement::from_witness(record.n, &witness).map_err(|e| e.to_string())?;
+
+    let start = Instant::now();
+    let result = prove_zeroizing(params, pk, FibCircuit::new(&statement, &witness), &statement.public_inputs()).map_err(|e| format!("生成证明失败: {:?}", e));
+    witness.zeroize_witness();
+    let time = start.elapsed();
+    audit(fib_record("prove", &statement, *k, time, result.as_ref().err().map_or("ok", |e| e.as_str())))?;
+    let proof = result?;
+    fs::write(out.join(format!("{}.proof", i)), encode_proof(&fib_header(record.n, *k, pk.get_vk())?, &proof)).map_err(|e| format!("写入证明失败: {}", e))?;
+    Ok(Proved { k: *k, target: statement.target, time, size: proof.len() })
+}
+
+fn audit_cmd(log: &Path) -> Result<Output, String> {
+    Ok(match verify_file(log) {
+        Ok((lines, last)) => {
+            let last = to_hex(&last);
+            Output {
+                ok: true,
+                text: format!("{}: {} 条记录, 哈希链完整, 最后一条 {}", log.display(), lines, last),
+                json: json!({ "command": "audit", "log": log, "valid": true, "records": lines, "last_hash": last }),
+            }
+        }
+        Err(e) => Output {
+            ok: false,
+            text: format!("{}: {}", log.display(), e),
+            json: json!({ "command": "audit", "log": log, "valid": false, "error": e.to_string() }),
+        },
+    })
+}
+
+fn read_key(path: &Path) -> Result<SealKey, String> {
+    SealKey::read_file(path).map_err(|e| format!("读取密钥{}失败: {}", path.display(), e))
+}
+
+fn secret_cmd(action: SecretAction) -> Result<Output, String> {
+    match action {
+        SecretAction::Keygen { out } => {
+            SealKey::generate().write_file(&out).map_err(|e| format!("写入密钥{}失败: {}", out.display(), e))?;
+            Ok(Output {
+                ok: true,
+                text: format!("密钥 -> {}", out.display()),
+                json: json!({ "command": "secret keygen", "out": out }),
+            })
+        }
+        SecretAction::Encrypt { key_file, input, out } => {
+            let key = read_key(&key_file)?;
+            let out = out.unwrap_or_else(|| {
+                let mut name = input.clone().into_os_string();
+                name.push(".sealed");
+                PathBuf::from(name)
+            });
+            let mut plaintext = fs::read(&input).map_err(|e| format!("读取{}失败: {}", input.display(), e))?;
+            let sealed = seal(&key, &plaintext);
+            zeroize::Zeroize::zeroize(&mut plaintext);
+            fs::write(&out, &sealed).map_err(|e| format!("写入{}失败: {}", out.display(), e))?;
+            Ok(Output {
+                ok: true,
+                text: format!("{} -> {} ({} 字节), 明文文件请自行删除", input.display(), out.display(), sealed.len()),
+                json: json!({ "command": "secret encrypt", "input": input, "out": out, "bytes": sealed.len() }),
+            })
+        }
+        SecretAction::Decrypt { key_file, input, out } => {
+            let key = read_key(&key_file)?;
+            let sealed = fs::read(&input).map_err(|e| format!("读取{}失败: {}", input.display(), e))?;
+            let mut plaintext = open(&key, &sealed).map_err(|e| format!("解密{}失败: {}", input.display(), e))?;
+            let written = fs::write(&out, &plaintext);
+            let len = plaintext.len();
+            zeroize::Zeroize::zeroize(&mut plaintext);
+            written.map_err(|e| format!("写入{}失败: {}", out.display(), e))?;
+            Ok(Output {
+                ok: true,
+                text: format!("{} -> {} ({} 字节)", input.display(), out.display(), len),
+                json: json!({ "command": "secret decrypt", "input": input, "out": out, "bytes": len }),
+            })
+        }
+    }
+}
+
+fn prove_batch(input: &Path, out: &Path, threads: usize, max_k: u32, key_file: Option<&Path>) -> Result<Output, String> {
+    let key = key_file.map(read_key).transpose()?;
+    let records = read_statements(input, key.as_ref())?;
+    fs::create_dir_all(out).map_err(|e| format!("创建{}失败: {}", out.display(), e))?;
+
+    // 电路形状只和n有关, 每个n生成一次参数和密钥
+    let start = Instant::now();
+    let mut keys = Keys::new();
+    for record in &records {
+        if keys.contains_key(&record.n) {
+            continue;
+        }
+        let key = check_n(record.n, max_k).and_then(|()| {
+            let witness = FibWitness::new(Fp::one(), Fp::one());
+            let statement = FibStatement::from_witness(record.n, &witness).map_err(|e| e.to_string())?;
+            keygen_with_retry(FibCircuit::min_k(record.n), max_k, &FibCircuit::new(&statement, &witness)).map_err(|e| format!("n = {}时生成密钥失败: {:?}", record.n, e))
+        });
+        keys.insert(record.n, key);
+    }
+    let keygen_time = start.elapsed();
+
+    let start = Instant::now();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(records.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= records.len() {
+                    break;
+                }
+                let result = prove_one(i, &records[i], &keys, out);
+                results.lock().expect("结果锁被污染").push((i, result));
+            });
+        }
+    });
+    let prove_time = start.elapsed();
+
+    let mut results = results.into_inner().expect("结果锁被污染");
+    results.sort_by_key(|(i, _)| *i);
+    let mut summary = String::new();
+    writeln!(summary, "序号\tn\tk\ttarget\t耗时(ms)\t字节\t结果").expect("写入摘要失败");
+    for (i, result) in &results {
+        let n = records[*i].n;
+        let line = match result {
+            Ok(p) => writeln!(summary, "{}\t{}\t{}\t{:?}\t{:.1}\t{}\t成功", i, n, p.k, p.target, millis(p.time), p.size),
+            Err(e) => writeln!(summary, "{}\t{}\t-\t-\t-\t-\t{}", i, n, e),
+        };
+        line.expect("写入摘要失败");
+    }
+    fs::write(out.join("summary.tsv"), &summary).map_err(|e| format!("写入摘要失败: {}", e))?;
+
+    let ok = results.iter().filter(|(_, r)| r.is_ok()).count();
+    writeln!(summary, "成功 {}/{}, 密钥 {} 组, 线程 {}", ok, results.len(), keys.values().filter(|key| key.is_ok()).count(), threads).expect("写入摘要失败");
+    write!(summary, "密钥生成 {:.1} ms, 证明 {:.1} ms", millis(keygen_time), millis(prove_time)).expect("写入摘要失败");
+    let json_results: Vec<_> = results.iter().map(|(i, result)| match result {
+        Ok(p) => json!({ "index": i, "n": records[*i].n, "k": p.k, "target": instance_to_hex(&p.target), "proof_path": out.join(format!("{}.proof", i)), "proof_len": p.size, "prove_ms": millis(p.time) }),
+        Err(e) => json!({ "index": i, "n": records[*i].n, "error": e }),
+    }).collect();
+    Ok(Output {
+        ok: ok == results.len(),
+        text: summary,
+        json: json!({
+            "command": "prove-batch", "ok": ok, "total": results.len(), "threads": threads, "results": json_results,
+            "timings_ms": { "keygen": millis(keygen_time), "prove": millis(prove_time) },
+        }),
+    })
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Some(path) = &cli.audit_log {
+        match AuditLog::open(path) {
+            Ok(log) => {
+                let _ = AUDIT_LOG.set(log);
+            }
+            Err(e) => {
+                match cli.format {
+                    Format::Text => eprintln!("{}", e),
+                    Format::Json => println!("{}", json!({ "command": "audit", "error": e.to_string() })),
+                }
+                std::process::exit(1);
+            }
+        }
+    }
+    let (name, result) = match cli.command {
+        Command::Prove { statement, out, k, no_store } => ("prove", prove_cmd(&statement, &out, k, no_store)),
+        Command::Verify { proof, statement, k } => ("verify", verify_cmd(&proof, &statement, k)),
+        Command::Mock { statement, k } => ("mock", mock_cmd(&statement, k)),
+        Command::Profile { statement, folded } => ("profile", profile_cmd(&statement, folded.as_deref())),
+        Command::Circuits => ("circuits", circuits_cmd()),
+        Command::Cache { action } => ("cache", cache_cmd(action)),
+        Command::Params { action } => ("params", params_cmd(action)),
+        Command::Report { n, k } => ("report", report_cmd(n, k)),
+        Command::Trace { n, a, b, out, k } => ("trace", trace_cmd(n, a, b, &out, k)),
+        #[cfg(feature = "tui")]
+        Command::Explore { n, a, b, target, k } => ("explore", explore_cmd(n, a, b, target.as_deref(), k)),
+        Command::MigrateProof { from, to, input, out, n, k } => ("migrate-proof", migrate_proof_cmd(from, to, &input, &out, n, k)),
+        Command::ProveBatch { input, out, threads, max_k, key_file } => {
+            let threads = threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+            ("prove-batch", prove_batch(&input, &out, threads, max_k, key_file.as_deref()))
+        }
+        Command::Secret { action } => ("secret", secret_cmd(action)),
+        Command::Audit { log } => ("audit", audit_cmd(&log)),
+    };
+    match result {
+        Ok(output) => {
+            match cli.format {
+                Format::Text => println!("{}", output.text),
+                Format::Json => println!("{}", output.json),
+            }
+            if !output.ok {
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            match cli.format {
+                Format::Text => eprintln!("{}", e),
+                Format::Json => println!("{}", json!({ "command": name, "error": e })),
+            }
+            std::process::exit(2);
+        }
+    }
+}
+
+#[test]
+fn test_parse_csv() {
+    let records = parse_csv("n,a,b\n10\n20,2,3\n\n30,,5\n").unwrap();
+    let fields: Vec<_> = records.iter().map(|r| (r.n, r.a, r.b)).collect();
+    assert_eq!(fields, vec![(10, 1, 1), (20, 2, 3), (30, 1, 5)]);
+    assert!(parse_csv("10\nx,1,1\n").is_err());
+}
+
+#[test]
+fn test_parse_field() {
+    assert_eq!(parse_field("55").unwrap(), Fp::from(55));
+    // 超过u64: 2^64 = 18446744073709551616
+    assert_eq!(parse_field("18446744073709551616").unwrap(), Fp::from(u64::MAX) + Fp::one());
+    assert!(parse_field("0x10").is_err());
+    assert!(parse_field("").is_err());
+}
+
+#[test]
+fn test_check_batch_args() {
+    assert_eq!(parse_threads("4"), Ok(4));
+    assert!(parse_threads("0").is_err());
+    assert!(parse_threads("-1").is_err());
+    assert!(check_n(10, 20).is_ok());
+    assert!(check_n(2, 20).is_err());
+    // 过大的n在生成见证之前拒绝
+    assert!(check_n(1 << 40, 20).is_err());
+    assert!(check_n(usize::MAX, 64).is_err());
+    assert!(check_n(100, 4).is_err());
+}
@@ -0,0 +1,90 @@
+//! 生成验证器互通测试用的证明语料: 每个用例一个目录, 包含验证密钥文件、证明和公开输入
+//!
+//! 用法: cargo run --release --features cli --bin testdata -- --out testdata/ --sizes 10,100,1000 --seeds 0,1,2
+//!
+//! 目录结构:
+//! - `manifest.json`: 所有用例的列表
+//! - `n{n}_seed{seed}/vk.bin`: [`halo2_fib::vk_file`]格式的验证密钥文件
+//! - `n{n}_seed{seed}/vk.manifest.json`: 实例清单, 说明每行公开输入的名字、类型和编码
+//! - `n{n}_seed{seed}/proof.bin`: 证明
+//! - `n{n}_seed{seed}/instances.json`: 正确的公开输入, 验证应当通过
+//! - `n{n}_seed{seed}/wrong_instances.json`: target加一后的公开输入, 验证应当失败
+//! - `n{n}_seed{seed}/instances.bin`、`wrong_instances.bin`: 同上, 按[`halo2_fib::instance::encode_instances`]编码
+//! - `n{n}_seed{seed}/params.hash`: 验证密钥文件中公共参数的blake2b-256, 十六进制
+//!
+//! 种子固定时输出是确定的. `testdata/golden`下的固定用例由
+//! `cargo run --release --features cli --bin testdata -- --out testdata/golden --sizes 10,100 --seeds 0`生成,
+//! `vk_file`中的测试检查当前代码仍能验证它们, 电路形状或参数生成变了会导致测试失败
+//!
+//! 域元素统一写成[`halo2_fib::instance::INSTANCE_ENCODING`]: 小端字节序的十六进制字符串
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use halo2_fib::instance::{encode_instances, instance_to_hex};
+use halo2_fib::params_file::{params_bytes, params_hash, to_hex};
+use halo2_fib::prover::{keygen_with_retry, prove_with_rng, verify};
+use halo2_fib::vk_file::{export_vk_file_with_manifest, CURVE_NAME};
+use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+use halo2_proofs::pasta::Fp;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use serde_json::json;
+
+#[derive(Parser)]
+#[command(name = "testdata", about = "生成(验证密钥, 证明, 公开输入)语料")]
+struct Args {
+    /// 输出目录
+    #[arg(long)]
+    out: PathBuf,
+    /// 数列长度n
+    #[arg(long, value_delimiter = ',', default_value = "10,100,1000")]
+    sizes: Vec<usize>,
+    /// 随机种子, 决定见证和证明中的随机数
+    #[arg(long, value_delimiter = ',', default_value = "0,1,2")]
+    seeds: Vec<u64>,
+}
+
+fn write_json(path: &Path, value: &serde_json::Value) {
+    let text = serde_json::to_string_pretty(value).expect("序列化JSON失败");
+    fs::write(path, text).unwrap_or_else(|e| panic!("写入{}失败: {}", path.display(), e));
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut cases = vec![];
+    for &n in &args.sizes {
+        // 电路形状只和n有关, 同一个n的用例共用参数和密钥
+        let mut key = None;
+        for &seed in &args.seeds {
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            let witness = FibWitness::new(Fp::from(rng.next_u64()), Fp::from(rng.next_u64()));
+            let statement = FibStatement::from_witness(n, &witness).expect("n至少为3");
+            let circuit = FibCircuit::new(&statement, &witness);
+            let (k, params, pk) = key.get_or_insert_with(|| keygen_with_retry(FibCircuit::min_k(n), 24, &circuit).expect("生成密钥失败"));
+
+            let public_inputs = statement.public_inputs();
+            let proof = prove_with_rng(params, pk, &circuit, &public_inputs, &mut rng).expect("生成证明失败");
+            verify(params, pk.get_vk(), &public_inputs, &proof).into_result().expect("验证证明失败");
+            let wrong_inputs: Vec<Fp> = public_inputs.iter().map(|v| *v + Fp::one()).collect();
+            assert!(!verify(params, pk.get_vk(), &wrong_inputs, &proof).is_valid(), "错误的公开输入通过了验证");
+
+            let name = format!("n{}_seed{}", n, seed);
+            let dir = args.out.join(&name);
+            fs::create_dir_all(&dir).unwrap_or_else(|e| panic!("创建{}失败: {}", dir.display(), e));
+            export_vk_file_with_manifest(&dir.join("vk.bin"), "fib", &format!("n={}", n), *k, params, pk.get_vk(), FibCircuit::<Fp>::instance_layout().slots()).expect("导出验证密钥失败");
+            fs::write(dir.join("proof.bin"), &proof).expect("写入证明失败");
+            let instances = |inputs: &[Fp]| json!({ "n": n, "k": k, "instances": inputs.iter().map(instance_to_hex).collect::<Vec<_>>() });
+            write_json(&dir.join("instances.json"), &instances(&public_inputs));
+            write_json(&dir.join("wrong_instances.json"), &instances(&wrong_inputs));
+            fs::write(dir.join("instances.bin"), encode_instances(&public_inputs)).expect("写入公开输入失败");
+            fs::write(dir.join("wrong_instances.bin"), encode_instances(&wrong_inputs)).expect("写入公开输入失败");
+            fs::write(dir.join("params.hash"), to_hex(&params_hash(&params_bytes(params)))).expect("写入参数哈希失败");
+
+            println!("{}: k = {}, 证明 {} 字节", name, k, proof.len());
+            cases.push(json!({ "name": name, "n": n, "seed": seed, "k": k, "proof_len": proof.len() }));
+        }
+    }
+    write_json(&args.out.join("manifest.json"), &json!({ "curve": CURVE_NAME, "cases": cases }));
+}
@@ -0,0 +1,198 @@
+//! 参数、密钥和证明的缓存目录
+//!
+//! 目录按以下顺序确定:
+//! 1. 环境变量`HALO2_FIB_CACHE_DIR`
+//! 2. 配置文件中的`cache_dir`, 配置文件为`HALO2_FIB_CONFIG`, 默认`$XDG_CONFIG_HOME/halo2-fib/config`
+//! 3. `$XDG_CACHE_HOME/halo2-fib`, 未设置时为`~/.cache/halo2-fib`
+//!
+//! 配置文件每行一个`键 = 值`, `#`开头的行为注释
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const CACHE_DIR_VAR: &str = "HALO2_FIB_CACHE_DIR";
+pub const CONFIG_VAR: &str = "HALO2_FIB_CONFIG";
+
+const APP_NAME: &str = "halo2-fib";
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    /// 配置文件某一行不合法
+    BadConfig { line: usize, reason: String },
+    /// 既没有配置缓存目录, 也找不到主目录
+    NoHome,
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(e) => write!(f, "读写缓存失败: {}", e),
+            CacheError::BadConfig { line, reason } => write!(f, "配置文件第{}行不合法: {}", line, reason),
+            CacheError::NoHome => write!(f, "找不到主目录, 请设置{}", CACHE_DIR_VAR),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<io::Error> for CacheError {
+    fn from(e: io::Error) -> Self {
+        CacheError::Io(e)
+    }
+}
+
+/// 配置文件中的设置, 没有出现的键为None
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, CacheError> {
+        let mut config = Config::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad = |reason: &str| CacheError::BadConfig { line: i + 1, reason: reason.to_string() };
+            let (key, value) = line.split_once('=').ok_or_else(|| bad("缺少="))?;
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "cache_dir" => config.cache_dir = Some(expand_home(value)),
+                other => return Err(bad(&format!("未知的键{}", other))),
+            }
+        }
+        Ok(config)
+    }
+
+    /// 读取配置文件, 文件不存在时返回默认配置
+    pub fn load() -> Result<Self, CacheError> {
+        let Some(path) = config_path() else {
+            return Ok(Config::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => Config::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME").filter(|home| !home.is_empty()).map(PathBuf::from)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// XDG目录变量, 未设置或不是绝对路径时回退到主目录下的默认位置
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var).map(PathBuf::from).filter(|dir| dir.is_absolute()).or_else(|| home_dir().map(|home| home.join(fallback)))
+}
+
+/// 配置文件路径
+pub fn config_path() -> Option<PathBuf> {
+    env::var_os(CONFIG_VAR).map(PathBuf::from).or_else(|| xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join(APP_NAME).join("config")))
+}
+
+/// 缓存目录, 下分params、keys、proofs三个子目录
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheDirs {
+    pub root: PathBuf,
+}
+
+impl CacheDirs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 按环境变量、配置文件、XDG默认位置的顺序确定缓存目录
+    pub fn resolve() -> Result<Self, CacheError> {
+        if let Some(dir) = env::var_os(CACHE_DIR_VAR).filter(|dir| !dir.is_empty()) {
+            return Ok(Self::new(dir));
+        }
+        if let Some(dir) = Config::load()?.cache_dir {
+            return Ok(Self::new(dir));
+        }
+        xdg_dir("XDG_CACHE_HOME", ".cache").map(|dir| Self::new(dir.join(APP_NAME))).ok_or(CacheError::NoHome)
+    }
+
+    pub fn params(&self) -> PathBuf {
+        self.root.join("params")
+    }
+
+    pub fn keys(&self) -> PathBuf {
+        self.root.join("keys")
+    }
+
+    pub fn proofs(&self) -> PathBuf {
+        self.root.join("proofs")
+    }
+
+    /// 参数k对应的缓存文件
+    pub fn params_file(&self, k: u32) -> PathBuf {
+        self.params().join(format!("k{}.bin", k))
+    }
+
+    /// 创建三个子目录
+    pub fn create(&self) -> Result<(), CacheError> {
+        for dir in [self.params(), self.keys(), self.proofs()] {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// 缓存占用的字节数, 目录不存在时为0
+    pub fn size(&self) -> Result<u64, CacheError> {
+        Ok(dir_size(&self.root)?)
+    }
+
+    /// 删除整个缓存目录, 返回释放的字节数
+    pub fn clean(&self) -> Result<u64, CacheError> {
+        let size = self.size()?;
+        match fs::remove_dir_all(&self.root) {
+            Ok(()) => Ok(size),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    fs::read_dir(path)?.try_fold(0, |total, entry| Ok(total + dir_size(&entry?.path())?))
+}
+
+#[test]
+fn test_cache_dirs() {
+    let config = Config::parse("# 注释\n\ncache_dir = \"/tmp/fib-cache\"\n").unwrap();
+    assert_eq!(config.cache_dir, Some(PathBuf::from("/tmp/fib-cache")));
+    assert!(matches!(Config::parse("cache_dir /tmp"), Err(CacheError::BadConfig { line: 1, .. })));
+    assert!(matches!(Config::parse("\nkeys = /tmp"), Err(CacheError::BadConfig { line: 2, .. })));
+
+    let dirs = CacheDirs::new(env::temp_dir().join(format!("halo2-fib-cache-test-{}", std::process::id())));
+    dirs.create().unwrap();
+    fs::write(dirs.params_file(4), [0u8; 100]).unwrap();
+    fs::write(dirs.proofs().join("0.proof"), [0u8; 28]).unwrap();
+    assert_eq!(dirs.size().unwrap(), 128);
+    assert_eq!(dirs.clean().unwrap(), 128);
+    assert!(!dirs.root.exists());
+    // 再次清理不报错
+    assert_eq!(dirs.clean().unwrap(), 0);
+}
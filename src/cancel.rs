@@ -0,0 +1,166 @@
+//! 可取消、可限时的证明: 证明服务可以给每个请求设时限, 超时后干净地放弃而不必杀掉进程
+//!
+//! halo2的`create_proof`不提供中途退出的钩子, 这里包装transcript, 在每次写入承诺或求值前检查标记.
+//! 写入贯穿各个阶段(实例、见证合成后的advice承诺、查找、置换、商多项式、求值和打开),
+//! 所以取消会在当前阶段结束时生效, 已经开始的一次多标量乘法或FFT不会被打断
+
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{create_proof, Circuit, Error, ProvingKey};
+use halo2_proofs::poly::commitment::Params;
+use halo2_proofs::transcript::{Blake2bWrite, Challenge255, Transcript, TranscriptWrite};
+use rand_core::OsRng;
+
+/// 取消标记, 克隆后交给别的线程调用[`cancel`](CancelToken::cancel); 也可以带一个截止时间
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 到deadline时自动取消
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self { cancelled: Arc::default(), deadline: Some(deadline) }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.is_cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "证明已取消"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ProveError {
+    /// 被取消或超过截止时间
+    Cancelled,
+    Plonk(Error),
+}
+
+impl fmt::Display for ProveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProveError::Cancelled => write!(f, "证明已取消"),
+            ProveError::Plonk(e) => write!(f, "生成证明失败: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProveError {}
+
+impl From<Error> for ProveError {
+    fn from(e: Error) -> Self {
+        ProveError::Plonk(e)
+    }
+}
+
+/// 每次写入前检查取消标记的transcript
+struct CancellableTranscript<'a, T> {
+    inner: T,
+    token: &'a CancelToken,
+}
+
+impl<T: Transcript<EqAffine, Challenge255<EqAffine>>> Transcript<EqAffine, Challenge255<EqAffine>> for CancellableTranscript<'_, T> {
+    fn squeeze_challenge(&mut self) -> Challenge255<EqAffine> {
+        self.inner.squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.token.check()?;
+        self.inner.common_point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.token.check()?;
+        self.inner.common_scalar(scalar)
+    }
+}
+
+impl<T: TranscriptWrite<EqAffine, Challenge255<EqAffine>>> TranscriptWrite<EqAffine, Challenge255<EqAffine>> for CancellableTranscript<'_, T> {
+    fn write_point(&mut self, point: EqAffine) -> io::Result<()> {
+        self.token.check()?;
+        self.inner.write_point(point)
+    }
+
+    fn write_scalar(&mut self, scalar: Fp) -> io::Result<()> {
+        self.token.check()?;
+        self.inner.write_scalar(scalar)
+    }
+}
+
+/// 生成证明, token被取消时在下一次写入transcript前放弃并返回[`ProveError::Cancelled`]
+///
+/// ```
+/// use halo2_fib::cancel::{prove_with_cancel, CancelToken, ProveError};
+/// use halo2_fib::prover::{keygen, setup, verify};
+/// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+/// use halo2_proofs::pasta::Fp;
+///
+/// let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one()));
+/// let params = setup(4);
+/// let pk = keygen(&params, &circuit).unwrap();
+/// let token = CancelToken::new();
+/// let proof = prove_with_cancel(&params, &pk, &circuit, &[Fp::from(55)], &token).unwrap();
+/// assert!(verify(&params, pk.get_vk(), &[Fp::from(55)], &proof).is_valid());
+/// token.cancel();
+/// assert!(matches!(prove_with_cancel(&params, &pk, &circuit, &[Fp::from(55)], &token), Err(ProveError::Cancelled)));
+/// ```
+pub fn prove_with_cancel<C: Circuit<Fp>>(params: &Params<EqAffine>, pk: &ProvingKey<EqAffine>, circuit: &C, public_inputs: &[Fp], token: &CancelToken) -> Result<Vec<u8>, ProveError> {
+    if token.is_cancelled() {
+        return Err(ProveError::Cancelled);
+    }
+    let mut transcript = CancellableTranscript { inner: Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]), token };
+    match create_proof(params, pk, std::slice::from_ref(circuit), &[&[public_inputs]], OsRng, &mut transcript) {
+        Ok(()) => Ok(transcript.inner.finalize()),
+        // 取消表现为transcript的io错误
+        Err(_) if token.is_cancelled() => Err(ProveError::Cancelled),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 限时生成证明, 超过timeout后在下一个阶段开始前放弃
+pub fn prove_with_deadline<C: Circuit<Fp>>(params: &Params<EqAffine>, pk: &ProvingKey<EqAffine>, circuit: &C, public_inputs: &[Fp], timeout: Duration) -> Result<Vec<u8>, ProveError> {
+    prove_with_cancel(params, pk, circuit, public_inputs, &CancelToken::with_deadline(Instant::now() + timeout))
+}
+
+#[test]
+fn test_cancel() {
+    use crate::prover::{keygen, setup, verify};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(10, &witness).unwrap();
+    let circuit = FibCircuit::new(&statement, &witness);
+    let params = setup(4);
+    let pk = keygen(&params, &circuit).unwrap();
+    let public_inputs = statement.public_inputs();
+
+    let proof = prove_with_deadline(&params, &pk, &circuit, &public_inputs, Duration::from_secs(600)).unwrap();
+    verify(&params, pk.get_vk(), &public_inputs, &proof).into_result().unwrap();
+    assert!(matches!(prove_with_deadline(&params, &pk, &circuit, &public_inputs, Duration::ZERO), Err(ProveError::Cancelled)));
+
+    // 另一个线程持有的克隆取消后, 原标记也被取消
+    let token = CancelToken::new();
+    let handle = token.clone();
+    std::thread::spawn(move || handle.cancel()).join().unwrap();
+    assert!(matches!(prove_with_cancel(&params, &pk, &circuit, &public_inputs, &token), Err(ProveError::Cancelled)));
+}
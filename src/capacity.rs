@@ -0,0 +1,71 @@
+//! 行容量计算: 2^k行中最后若干行被盲化因子占用, 电路只能使用其余的行
+//!
+//! zcash版halo2_proofs的盲化因子个数由约束系统中单列的最大查询次数决定, 不能单独配置,
+//! 这里按电路配置读出盲化行数, 让min_k等计算自动把它算进去
+
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+
+/// 电路在某个k下的行数分配
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RowBudget {
+    pub k: u32,
+    /// 总行数2^k
+    pub total_rows: usize,
+    /// 盲化因子占用的行, 再加上最后一行l_last
+    pub blinding_rows: usize,
+    /// 电路可用的行数
+    pub usable_rows: usize,
+}
+
+/// 电路C盲化占用的行数, 与k无关
+pub fn blinding_rows<C: Circuit<Fp>>() -> usize {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+    cs.blinding_factors() + 1
+}
+
+/// 电路C在k下的行数分配
+///
+/// ```
+/// use halo2_fib::capacity::row_budget;
+/// use halo2_fib::FibCircuit;
+/// use halo2_proofs::pasta::Fp;
+///
+/// let budget = row_budget::<FibCircuit<Fp>>(4);
+/// assert_eq!(budget.total_rows, 16);
+/// assert_eq!(budget.usable_rows + budget.blinding_rows, 16);
+/// ```
+pub fn row_budget<C: Circuit<Fp>>(k: u32) -> RowBudget {
+    let total_rows = 1 << k;
+    let blinding_rows = blinding_rows::<C>();
+    RowBudget { k, total_rows, blinding_rows, usable_rows: total_rows.saturating_sub(blinding_rows) }
+}
+
+/// 能放下rows行(包括查找表和实例行)的最小k
+pub fn min_k<C: Circuit<Fp>>(rows: usize) -> u32 {
+    // 生成密钥时还要求至少留出l_0和一行可用行
+    let blinding_rows = blinding_rows::<C>();
+    let needed = (rows + blinding_rows).max(blinding_rows + 2);
+    needed.next_power_of_two().trailing_zeros()
+}
+
+#[test]
+fn test_row_budget() {
+    use crate::prover::{keygen, setup};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let blinding = blinding_rows::<FibCircuit<Fp>>();
+    assert_eq!(row_budget::<FibCircuit<Fp>>(5).usable_rows, 32 - blinding);
+
+    // min_k算出的k刚好够用, 小一号就不够
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    for n in [3, 10, 14, 30, 100] {
+        let circuit = FibCircuit::new(&FibStatement::from_witness(n, &witness).unwrap(), &witness);
+        let k = FibCircuit::<Fp>::min_k(n);
+        assert!(keygen(&setup(k), &circuit).is_ok(), "n = {}时k = {}不够", n, k);
+        if k > 1 {
+            assert!(keygen(&setup(k - 1), &circuit).is_err(), "n = {}时k = {}不是最小的", n, k);
+        }
+    }
+}
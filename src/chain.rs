@@ -0,0 +1,147 @@
+//! 链式命题: 把很长的数列拆成多段分别证明, 每段公开起点和终点两项, 前一段的终点等于后一段的起点即可首尾相接
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::*;
+
+use crate::fib::{FibChip, FibConfig};
+use crate::instance::InstanceAllocator;
+use crate::shared::SharedColumns;
+
+/// 一段数列的命题: 从(F(m-1), F(m))出发走steps步到达(F(n-1), F(n)), n = m + steps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainedStatement<F: Field> {
+    pub start: (F, F),
+    pub end: (F, F),
+    pub steps: usize,
+}
+
+impl<F: Field> ChainedStatement<F> {
+    /// 由起点计算终点得到的命题, steps至少为1
+    pub fn from_start(start: (F, F), steps: usize) -> Self {
+        assert!(steps >= 1, "steps至少为1");
+        let (mut a, mut b) = start;
+        for _i in 0..steps {
+            let c = a + b;
+            a = b;
+            b = c;
+        }
+        Self { start, end: (a, b), steps }
+    }
+
+    /// 把从start出发的total_steps步按每段window步拆开, 最后一段可能较短
+    ///
+    /// ```
+    /// use halo2_fib::chain::ChainedStatement;
+    /// use halo2_proofs::pasta::Fp;
+    ///
+    /// let segments = ChainedStatement::split((Fp::one(), Fp::one()), 8, 3);
+    /// assert_eq!(segments.iter().map(|s| s.steps).collect::<Vec<_>>(), vec![3, 3, 2]);
+    /// assert!(segments.windows(2).all(|w| w[0].links_to(&w[1])));
+    /// // 1, 1出发走8步到达F(10) = 55
+    /// assert_eq!(segments[2].end.1, Fp::from(55));
+    /// ```
+    pub fn split(start: (F, F), total_steps: usize, window: usize) -> Vec<Self> {
+        assert!(window >= 1, "window至少为1");
+        let mut segments = vec![];
+        let mut start = start;
+        let mut remaining = total_steps;
+        while remaining > 0 {
+            let segment = Self::from_start(start, remaining.min(window));
+            start = segment.end;
+            remaining -= segment.steps;
+            segments.push(segment);
+        }
+        segments
+    }
+
+    /// 本段终点是否就是下一段的起点
+    pub fn links_to(&self, next: &Self) -> bool {
+        self.end == next.start
+    }
+
+    pub fn public_inputs(&self) -> Vec<F> {
+        let mut layout = ChainedFibCircuit::<F>::instance_layout();
+        layout.set("start_prev", self.start.0).expect("缺少start_prev实例行");
+        layout.set("start", self.start.1).expect("缺少start实例行");
+        layout.set("end_prev", self.end.0).expect("缺少end_prev实例行");
+        layout.set("end", self.end.1).expect("缺少end实例行");
+        layout.public_inputs().expect("公开输入不完整")
+    }
+}
+
+/// 证明一段数列的电路, 起点和终点都是公开输入
+///
+/// 电路形状只和steps有关, 各段窗口相同时可以共用同一个验证密钥
+pub struct ChainedFibCircuit<F: Field> {
+    start: Value<(F, F)>,
+    steps: usize,
+}
+
+impl<F: Field> ChainedFibCircuit<F> {
+    pub fn new(statement: &ChainedStatement<F>) -> Self {
+        Self { start: Value::known(statement.start), steps: statement.steps }
+    }
+
+    /// 实例列布局: 起点两项和终点两项
+    pub fn instance_layout() -> InstanceAllocator<F> {
+        let mut layout = InstanceAllocator::new();
+        for label in ["start_prev", "start", "end_prev", "end"] {
+            layout.alloc(label);
+        }
+        layout
+    }
+}
+
+impl<F: Field> Circuit<F> for ChainedFibCircuit<F> {
+    type Config = FibConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { start: Value::unknown(), steps: self.steps }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        FibChip::configure(meta, &shared)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let fib = FibChip::construct(config);
+        let a = self.start.map(|(a, _)| a);
+        let b = self.start.map(|(_, b)| b);
+        // 起点两项加上steps步, 共steps + 2项
+        let terms = fib.assign_sequence(layouter.namespace(|| "填写数列"), a, b, self.steps + 2)?;
+        let layout = Self::instance_layout();
+        let len = terms.len();
+        for (label, term) in [("start_prev", &terms[0]), ("start", &terms[1]), ("end_prev", &terms[len - 2]), ("end", &terms[len - 1])] {
+            let row = layout.row(label).expect("缺少实例行");
+            fib.expose_public(layouter.namespace(|| "暴露端点"), term, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_chained() {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+    use crate::recurrence::recurrence_terms;
+
+    // F(1..=30)拆成每段10步
+    let segments = ChainedStatement::split((Fp::one(), Fp::one()), 28, 10);
+    assert_eq!(segments.len(), 3);
+    for segment in &segments {
+        let prover = MockProver::run(5, &ChainedFibCircuit::new(segment), vec![segment.public_inputs()]).unwrap();
+        prover.assert_satisfied();
+    }
+    assert!(segments.windows(2).all(|w| w[0].links_to(&w[1])));
+    let terms = recurrence_terms(1, 1, Fp::one(), Fp::one(), 30);
+    assert_eq!(segments[2].end, (terms[28], terms[29]));
+
+    // 终点不对时不能通过
+    let mut wrong = segments[0];
+    wrong.end.1 += Fp::one();
+    let prover = MockProver::run(5, &ChainedFibCircuit::new(&wrong), vec![wrong.public_inputs()]).unwrap();
+    assert!(prover.verify().is_err());
+}
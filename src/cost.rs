@@ -0,0 +1,233 @@
+//! 证明耗时估算: 按电路配置数出证明过程中MSM和FFT的规模, 再乘以在本机校准出的单位耗时
+//!
+//! 只是粗略模型, 用来在开始证明之前判断k = 20是要几秒还是几小时, 与实际耗时相差一两倍是正常的
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use ff::{Field, PrimeField};
+use halo2_proofs::arithmetic::{best_fft, best_multiexp};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+use rand_core::OsRng;
+
+use crate::cache::{CacheDirs, CacheError};
+use crate::prover::setup;
+
+/// 校准时MSM和FFT的规模
+pub const CALIBRATION_K: u32 = 12;
+
+/// 估算用到的电路配置, 与k无关
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub advice_columns: usize,
+    pub instance_columns: usize,
+    /// 包括生成密钥时由选择器转成的fixed列(按合并前计, 偏保守)
+    pub fixed_columns: usize,
+    pub lookups: usize,
+    /// 参与置换的列数
+    pub permutation_columns: usize,
+    pub degree: usize,
+}
+
+impl CircuitStats {
+    pub fn of<C: Circuit<Fp>>() -> Self {
+        let mut cs = ConstraintSystem::<Fp>::default();
+        C::configure(&mut cs);
+        Self {
+            advice_columns: cs.num_advice_columns(),
+            instance_columns: cs.num_instance_columns(),
+            fixed_columns: cs.num_fixed_columns() + cs.num_selectors(),
+            lookups: cs.lookups().len(),
+            permutation_columns: cs.permutation().get_columns().len(),
+            degree: cs.degree(),
+        }
+    }
+
+    /// 证明需要承诺的多项式个数, 每个承诺是一次规模2^k的MSM
+    pub fn commitments(&self) -> usize {
+        // 每个查找参数承诺置换后的输入、表和累乘多项式; 商多项式拆成degree - 1段; 另有随机多项式和多点打开各一次
+        self.advice_columns + 3 * self.lookups + self.permutation_products() + (self.degree - 1) + 2
+    }
+
+    /// 置换累乘多项式的个数, 每个最多覆盖degree - 2列
+    pub fn permutation_products(&self) -> usize {
+        self.permutation_columns.div_ceil(self.degree.saturating_sub(2).max(1))
+    }
+
+    /// 扩展域比原域大的倍数的对数
+    pub fn extension_log(&self) -> u32 {
+        (self.degree.max(2) - 1).next_power_of_two().trailing_zeros()
+    }
+
+    /// k下MSM的总点数和FFT的总工作量(元素数乘以对数)
+    pub fn work(&self, k: u32) -> (f64, f64) {
+        let n = (1u64 << k) as f64;
+        let ext_k = k + self.extension_log();
+        let ext_n = (1u64 << ext_k) as f64;
+        let msm = self.commitments() as f64 * n;
+        // 每个多项式做一次逆FFT回到系数形式, 再做一次扩展域上的陪集FFT; 商多项式在扩展域上做一次逆FFT
+        let polys = self.advice_columns + self.instance_columns + self.fixed_columns + 3 * self.lookups + self.permutation_products() + self.permutation_columns;
+        let fft = polys as f64 * (n * k as f64 + ext_n * ext_k as f64) + ext_n * ext_k as f64;
+        (msm, fft)
+    }
+}
+
+/// 本机的单位耗时, 由[`MachineProfile::calibrate`]测出
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MachineProfile {
+    /// MSM中每个点的纳秒数
+    pub msm_ns_per_point: f64,
+    /// FFT中每个元素每层的纳秒数
+    pub fft_ns_per_unit: f64,
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    Cache(CacheError),
+    /// 校准文件某一行不合法
+    BadProfile { line: usize, reason: String },
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Cache(e) => write!(f, "{}", e),
+            ProfileError::BadProfile { line, reason } => write!(f, "校准文件第{}行不合法: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<CacheError> for ProfileError {
+    fn from(e: CacheError) -> Self {
+        ProfileError::Cache(e)
+    }
+}
+
+impl From<io::Error> for ProfileError {
+    fn from(e: io::Error) -> Self {
+        ProfileError::Cache(CacheError::Io(e))
+    }
+}
+
+impl MachineProfile {
+    /// 在本机跑一次规模2^k的MSM和FFT, 多线程与证明时一致
+    pub fn calibrate(k: u32) -> Self {
+        let n = 1usize << k;
+        let bases = setup(k).get_g();
+        let scalars: Vec<Fp> = (0..n).map(|_| Fp::random(OsRng)).collect();
+        let start = Instant::now();
+        let _ = best_multiexp(&scalars, &bases);
+        let msm = start.elapsed();
+
+        let mut values = scalars;
+        let omega = Fp::ROOT_OF_UNITY.pow_vartime([1u64 << (Fp::S - k)]);
+        let start = Instant::now();
+        best_fft(&mut values, omega, k);
+        let fft = start.elapsed();
+
+        Self {
+            msm_ns_per_point: msm.as_nanos() as f64 / n as f64,
+            fft_ns_per_unit: fft.as_nanos() as f64 / (n as f64 * k as f64),
+        }
+    }
+
+    /// 校准文件在缓存目录中的位置
+    pub fn path(dirs: &CacheDirs) -> PathBuf {
+        dirs.root.join("machine_profile")
+    }
+
+    /// 读取缓存的校准结果, 没有时校准一次并写入缓存
+    pub fn load_or_calibrate(dirs: &CacheDirs) -> Result<Self, ProfileError> {
+        let path = Self::path(dirs);
+        match fs::read_to_string(&path) {
+            Ok(text) => return Self::parse(&text),
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            Err(_) => {}
+        }
+        let profile = Self::calibrate(CALIBRATION_K);
+        profile.save(&path)?;
+        Ok(profile)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ProfileError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// 解析`键 = 值`格式的校准文件
+    pub fn parse(text: &str) -> Result<Self, ProfileError> {
+        let (mut msm, mut fft) = (None, None);
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad = |reason: String| ProfileError::BadProfile { line: i + 1, reason };
+            let (key, value) = line.split_once('=').ok_or_else(|| bad("缺少=".to_string()))?;
+            let value: f64 = value.trim().parse().map_err(|_| bad(format!("{}不是数字", value.trim())))?;
+            match key.trim() {
+                "msm_ns_per_point" => msm = Some(value),
+                "fft_ns_per_unit" => fft = Some(value),
+                other => return Err(bad(format!("未知的键{}", other))),
+            }
+        }
+        match (msm, fft) {
+            (Some(msm_ns_per_point), Some(fft_ns_per_unit)) => Ok(Self { msm_ns_per_point, fft_ns_per_unit }),
+            _ => Err(ProfileError::BadProfile { line: 0, reason: "缺少msm_ns_per_point或fft_ns_per_unit".to_string() }),
+        }
+    }
+}
+
+impl fmt::Display for MachineProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "msm_ns_per_point = {}", self.msm_ns_per_point)?;
+        writeln!(f, "fft_ns_per_unit = {}", self.fft_ns_per_unit)
+    }
+}
+
+/// 估算在k下为配置为stats的电路生成一个证明的耗时
+///
+/// ```
+/// use halo2_fib::cost::{estimate_prove_time, CircuitStats, MachineProfile};
+/// use halo2_fib::FibCircuit;
+/// use halo2_proofs::pasta::Fp;
+///
+/// let stats = CircuitStats::of::<FibCircuit<Fp>>();
+/// let profile = MachineProfile { msm_ns_per_point: 20_000.0, fft_ns_per_unit: 50.0 };
+/// let small = estimate_prove_time(10, &stats, &profile);
+/// let large = estimate_prove_time(20, &stats, &profile);
+/// assert!(large > small * 1000);
+/// ```
+pub fn estimate_prove_time(k: u32, stats: &CircuitStats, profile: &MachineProfile) -> Duration {
+    let (msm, fft) = stats.work(k);
+    Duration::from_nanos((msm * profile.msm_ns_per_point + fft * profile.fft_ns_per_unit) as u64)
+}
+
+#[test]
+fn test_estimate_prove_time() {
+    use crate::FibCircuit;
+
+    let profile = MachineProfile { msm_ns_per_point: 1000.0, fft_ns_per_unit: 10.0 };
+    assert_eq!(MachineProfile::parse(&profile.to_string()).unwrap(), profile);
+    assert!(matches!(MachineProfile::parse("msm_ns_per_point = 1"), Err(ProfileError::BadProfile { line: 0, .. })));
+    assert!(matches!(MachineProfile::parse("fft_ns_per_unit = x"), Err(ProfileError::BadProfile { line: 1, .. })));
+
+    let stats = CircuitStats::of::<FibCircuit<Fp>>();
+    assert!(stats.commitments() > stats.advice_columns);
+
+    // 每加一位k, 工作量至少翻倍
+    let profile = MachineProfile::calibrate(8);
+    assert!(profile.msm_ns_per_point > 0.0 && profile.fft_ns_per_unit > 0.0);
+    let estimate = estimate_prove_time(10, &stats, &profile);
+    assert!(estimate > Duration::ZERO);
+    assert!(estimate_prove_time(11, &stats, &profile) >= estimate * 2);
+}
@@ -0,0 +1,956 @@
+//! 调试和分析电路用的工具
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::dev::{FailureLocation, MockProver, VerifyFailure};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{keygen_vk, Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed, FloorPlanner, Gate, Instance, Selector};
+
+use crate::capacity::row_budget;
+use crate::names::{gate_display, Locale};
+use crate::prover::setup;
+use crate::vk_file::shape_hash;
+
+/// 曲线点(压缩后)和标量在证明中各占32字节
+const POINT_SIZE: usize = 32;
+const SCALAR_SIZE: usize = 32;
+
+/// 证明中的一个组成部分
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofComponent {
+    pub name: &'static str,
+    /// 承诺(曲线点)个数
+    pub points: usize,
+    /// 求值(标量)个数
+    pub scalars: usize,
+}
+
+impl ProofComponent {
+    pub fn size(&self) -> usize {
+        self.points * POINT_SIZE + self.scalars * SCALAR_SIZE
+    }
+}
+
+/// 证明按组成部分拆分后的大小
+#[derive(Clone, Debug)]
+pub struct ProofBreakdown {
+    pub components: Vec<ProofComponent>,
+    pub proof_len: usize,
+}
+
+impl ProofBreakdown {
+    pub fn commitments(&self) -> usize {
+        self.components.iter().map(|c| c.points).sum()
+    }
+
+    pub fn evaluations(&self) -> usize {
+        self.components.iter().map(|c| c.scalars).sum()
+    }
+
+    pub fn expected_len(&self) -> usize {
+        self.components.iter().map(|c| c.size()).sum()
+    }
+}
+
+impl fmt::Display for ProofBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<24}{:>8}{:>8}{:>10}", "部分", "承诺", "求值", "字节")?;
+        for c in &self.components {
+            writeln!(f, "{:<24}{:>8}{:>8}{:>10}", c.name, c.points, c.scalars, c.size())?;
+        }
+        writeln!(f, "{:<24}{:>8}{:>8}{:>10}", "合计", self.commitments(), self.evaluations(), self.proof_len)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InspectError {
+    /// 除选择器列以外至少需要的字节数
+    pub expected_at_least: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for InspectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "证明长度{}与电路结构不符(至少需要{}字节)", self.actual, self.expected_at_least)
+    }
+}
+
+impl std::error::Error for InspectError {}
+
+/// 按电路C在k下的结构拆分证明的transcript, 统计每部分的承诺和求值个数
+///
+/// 选择器在生成密钥时会被合并成fixed列, 合并后的列数由证明长度反推
+pub fn inspect_proof<C: Circuit<Fp>>(k: u32, proof: &[u8]) -> Result<ProofBreakdown, InspectError> {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    C::configure(&mut cs);
+
+    let degree = cs.degree();
+    let perm_columns = cs.permutation().get_columns().len();
+    let perm_sets = if perm_columns == 0 { 0 } else { (perm_columns + degree - 3) / (degree - 2) };
+    let lookups = cs.lookups().len();
+
+    // 多点打开时, 按每个多项式被查询的旋转集合分组, 每组一个q求值
+    let last = -((cs.blinding_factors() + 1) as i32);
+    let mut point_sets: BTreeSet<Vec<i32>> = BTreeSet::new();
+    let mut column_sets = |queries: Vec<(usize, i32)>| {
+        let mut by_column: std::collections::BTreeMap<usize, BTreeSet<i32>> = Default::default();
+        for (column, rotation) in queries {
+            by_column.entry(column).or_default().insert(rotation);
+        }
+        for rotations in by_column.into_values() {
+            point_sets.insert(rotations.into_iter().collect());
+        }
+    };
+    column_sets(cs.advice_queries().iter().map(|(c, r)| (c.index(), r.0)).collect());
+    column_sets(cs.instance_queries().iter().map(|(c, r)| (c.index(), r.0)).collect());
+    column_sets(cs.fixed_queries().iter().map(|(c, r)| (c.index(), r.0)).collect());
+    // 选择器列、置换sigma、h和随机多项式都只在x处打开
+    point_sets.insert(vec![0]);
+    if perm_sets > 1 {
+        point_sets.insert(vec![last, 0, 1]);
+    }
+    if perm_sets > 0 {
+        point_sets.insert(vec![0, 1]);
+    }
+    if lookups > 0 {
+        point_sets.insert(vec![0, 1]);
+        point_sets.insert(vec![-1, 0]);
+    }
+
+    let mut components = vec![
+        ProofComponent { name: "advice承诺", points: cs.num_advice_columns(), scalars: 0 },
+        ProofComponent { name: "查找置换承诺", points: 2 * lookups, scalars: 0 },
+        ProofComponent { name: "置换乘积承诺", points: perm_sets, scalars: 0 },
+        ProofComponent { name: "查找乘积承诺", points: lookups, scalars: 0 },
+        ProofComponent { name: "商多项式承诺", points: 1 + (degree - 1), scalars: 0 },
+        ProofComponent { name: "instance求值", points: 0, scalars: cs.instance_queries().len() },
+        ProofComponent { name: "advice求值", points: 0, scalars: cs.advice_queries().len() },
+        ProofComponent { name: "fixed求值", points: 0, scalars: cs.fixed_queries().len() },
+        ProofComponent { name: "随机多项式求值", points: 0, scalars: 1 },
+        ProofComponent { name: "置换求值", points: 0, scalars: perm_columns + (3 * perm_sets).saturating_sub(1) },
+        ProofComponent { name: "查找求值", points: 0, scalars: 5 * lookups },
+        ProofComponent { name: "多点打开", points: 1, scalars: point_sets.len() },
+        ProofComponent { name: "IPA最终打开", points: 1 + 2 * k as usize, scalars: 2 },
+    ];
+
+    let known: usize = components.iter().map(|c| c.size()).sum();
+    let residual = proof.len().checked_sub(known).filter(|r| r % SCALAR_SIZE == 0);
+    let selector_columns = residual.map(|r| r / SCALAR_SIZE).filter(|&n| n <= cs.num_selectors());
+    match selector_columns {
+        Some(n) => {
+            components.insert(8, ProofComponent { name: "选择器列求值", points: 0, scalars: n });
+            Ok(ProofBreakdown { components, proof_len: proof.len() })
+        }
+        None => Err(InspectError { expected_at_least: known, actual: proof.len() }),
+    }
+}
+
+/// 检查电路结构与见证无关: 用`without_witnesses()`(所有值都是`Value::unknown()`)生成验证密钥必须成功,
+/// 且与带见证时生成的验证密钥形状一致, 否则说明合成过程依赖了见证的取值
+pub fn assert_shape_independent<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<(), Error> {
+    let params = setup(k);
+    let unknown_vk = keygen_vk(&params, &circuit.without_witnesses())?;
+    let known_vk = keygen_vk(&params, circuit)?;
+    assert_eq!(shape_hash(&unknown_vk), shape_hash(&known_vk), "电路形状依赖于见证");
+    Ok(())
+}
+
+/// 一个约束在合并选择器前后的次数
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintDegree {
+    pub gate: &'static str,
+    pub constraint: &'static str,
+    pub degree: usize,
+    pub compressed_degree: usize,
+}
+
+/// 约束次数报告
+///
+/// 选择器在生成密钥时会合并成fixed列, 合并后的选择器表达式次数可能大于1, 但不会让整体次数超过合并前
+#[derive(Clone, Debug)]
+pub struct DegreeReport {
+    pub constraints: Vec<ConstraintDegree>,
+    /// 约束系统的整体次数, 包括置换和查找参数
+    pub degree: usize,
+    pub compressed_degree: usize,
+    pub selectors: usize,
+    /// 选择器合并后占用的fixed列数
+    pub selector_columns: usize,
+}
+
+impl DegreeReport {
+    /// 次数最高的约束, 整体次数来自置换或查找参数时为None
+    pub fn max_constraint(&self) -> Option<&ConstraintDegree> {
+        self.constraints.iter().filter(|c| c.compressed_degree == self.compressed_degree).max_by_key(|c| c.degree)
+    }
+}
+
+impl fmt::Display for DegreeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<24}{:<24}{:>8}{:>8}", "门", "约束", "次数", "合并后")?;
+        for c in &self.constraints {
+            writeln!(f, "{:<24}{:<24}{:>8}{:>8}", c.gate, c.constraint, c.degree, c.compressed_degree)?;
+        }
+        writeln!(f, "整体次数: {} -> {}, 商多项式分为{}段", self.degree, self.compressed_degree, self.compressed_degree - 1)?;
+        writeln!(f, "选择器: {}个合并为{}列fixed", self.selectors, self.selector_columns)?;
+        match self.max_constraint() {
+            Some(c) => writeln!(f, "最高次数来自: {} / {}", c.gate, c.constraint),
+            None => writeln!(f, "最高次数来自: 置换或查找参数"),
+        }
+    }
+}
+
+/// 合成过程中记录下来的一个区域
+#[derive(Clone, Debug, Default)]
+struct RecordedRegion {
+    name: String,
+    rows: Option<(usize, usize)>,
+    columns: BTreeSet<String>,
+    cells: BTreeSet<(String, usize)>,
+}
+
+/// 记录选择器启用位置以及每个区域用到的行和列的`Assignment`, 不计算任何值
+struct Recorder {
+    k: u32,
+    selectors: Vec<Vec<bool>>,
+    regions: Vec<RecordedRegion>,
+    current: Option<usize>,
+}
+
+impl Recorder {
+    fn new(k: u32, cs: &ConstraintSystem<Fp>) -> Self {
+        Self { k, selectors: vec![vec![false; 1 << k]; cs.num_selectors()], regions: vec![], current: None }
+    }
+
+    fn record(&mut self, column: String, row: usize) -> Result<(), Error> {
+        if row >= 1 << self.k {
+            return Err(Error::NotEnoughRowsAvailable { current_k: self.k });
+        }
+        if let Some(region) = self.current.map(|i| &mut self.regions[i]) {
+            region.rows = Some(match region.rows {
+                Some((start, end)) => (start.min(row), end.max(row)),
+                None => (row, row),
+            });
+            region.columns.insert(column.clone());
+            region.cells.insert((column, row));
+        }
+        Ok(())
+    }
+}
+
+fn column_name(column: Column<Any>) -> String {
+    let kind = match column.column_type() {
+        Any::Advice => "advice",
+        Any::Fixed => "fixed",
+        Any::Instance => "instance",
+    };
+    format!("{}[{}]", kind, column.index())
+}
+
+impl Assignment<Fp> for Recorder {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.regions.push(RecordedRegion { name: name().into(), ..Default::default() });
+        self.current = Some(self.regions.len() - 1);
+    }
+
+    fn exit_region(&mut self) {
+        self.current = None;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record(format!("selector[{}]", selector.index()), row)?;
+        self.selectors[selector.index()][row] = true;
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<Fp>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(&mut self, _: A, column: Column<Advice>, row: usize, _: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record(column_name(column.into()), row)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(&mut self, _: A, column: Column<Fixed>, row: usize, _: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.record(column_name(column.into()), row)
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(&mut self, _: Column<Fixed>, _: usize, _: Value<Assigned<Fp>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+/// 用[`Recorder`]合成一遍电路, 返回约束系统和记录结果
+fn record<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<(ConstraintSystem<Fp>, Recorder), Error> {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    let config = C::configure(&mut cs);
+    let mut recorder = Recorder::new(k, &cs);
+    C::FloorPlanner::synthesize(&mut recorder, circuit, config, cs.constants().clone())?;
+    Ok((cs, recorder))
+}
+
+/// 统计每个约束的次数以及选择器合并对次数的影响, 新增门时用来控制次数(也就是扩展域的大小)
+pub fn degree_report<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<DegreeReport, Error> {
+    let (cs, recorder) = record(k, circuit)?;
+    let degree = cs.degree();
+    let selectors = cs.num_selectors();
+    let (compressed, selector_columns) = cs.clone().compress_selectors(recorder.selectors);
+    let constraints = cs.gates().iter().zip(compressed.gates().iter())
+        .flat_map(|(gate, compressed_gate)| {
+            gate.polynomials().iter().zip(compressed_gate.polynomials().iter()).enumerate().map(move |(i, (poly, compressed_poly))| ConstraintDegree {
+                gate: gate.name(),
+                constraint: gate.constraint_name(i),
+                degree: poly.degree(),
+                compressed_degree: compressed_poly.degree(),
+            })
+        })
+        .collect();
+    Ok(DegreeReport { constraints, degree, compressed_degree: compressed.degree(), selectors, selector_columns: selector_columns.len() })
+}
+
+/// 一个选择器在合并中的去向
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectorUsage {
+    pub index: usize,
+    /// 简单选择器可以和别的选择器合并; 只在查找里用到、门里查不到时为None
+    pub simple: Option<bool>,
+    /// 用到它的门
+    pub gates: Vec<&'static str>,
+    /// 启用的行数
+    pub rows: usize,
+    /// 合并进的fixed列, 从新增列的第0列算起; 从未启用的选择器为None
+    pub column: Option<usize>,
+    /// 在该列中代表它的取值
+    pub tag: Option<u64>,
+}
+
+/// 选择器合并报告
+///
+/// 生成密钥时halo2按选择器的实际启用行把互斥的简单选择器合并到同一个fixed列, 每个选择器取列中不同的值,
+/// 复杂选择器(用在查找里的)各占一列. 这里用同样的启用行重做一遍合并, 得到的列数就是验证密钥里固定的列数
+#[derive(Clone, Debug)]
+pub struct SelectorReport {
+    /// 合并前电路自己的fixed列数
+    pub fixed_columns: usize,
+    /// 选择器合并后新增的fixed列数
+    pub selector_columns: usize,
+    pub selectors: Vec<SelectorUsage>,
+}
+
+impl SelectorReport {
+    /// 每个新增fixed列里合并了哪些选择器
+    pub fn columns(&self) -> Vec<Vec<usize>> {
+        let mut columns = vec![vec![]; self.selector_columns];
+        for selector in &self.selectors {
+            if let Some(column) = selector.column {
+                columns[column].push(selector.index);
+            }
+        }
+        columns
+    }
+}
+
+impl fmt::Display for SelectorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<10}{:<8}{:>8}{:>12}{:>6}  门", "选择器", "类型", "启用行", "fixed列", "取值")?;
+        for s in &self.selectors {
+            let kind = match s.simple {
+                Some(true) => "简单",
+                Some(false) => "复杂",
+                None => "查找",
+            };
+            let column = s.column.map_or("-".to_string(), |column| format!("fixed[{}]", self.fixed_columns + column));
+            let tag = s.tag.map_or("-".to_string(), |tag| tag.to_string());
+            writeln!(f, "{:<10}{:<8}{:>8}{:>12}{:>6}  {}", format!("s[{}]", s.index), kind, s.rows, column, tag, s.gates.join(","))?;
+        }
+        writeln!(f, "{}个选择器合并为{}列fixed, fixed列共{}列", self.selectors.len(), self.selector_columns, self.fixed_columns + self.selector_columns)
+    }
+}
+
+/// 门里查询到的选择器
+fn gate_selectors(gate: &Gate<Fp>) -> BTreeSet<(usize, bool)> {
+    gate.polynomials()
+        .iter()
+        .flat_map(|poly| {
+            poly.evaluate(
+                &|_| BTreeSet::new(),
+                &|selector| BTreeSet::from([(selector.index(), selector.is_simple())]),
+                &|_| BTreeSet::new(),
+                &|_| BTreeSet::new(),
+                &|_| BTreeSet::new(),
+                &|a| a,
+                &|mut a, b| { a.extend(b); a },
+                &|mut a, b| { a.extend(b); a },
+                &|a, _| a,
+            )
+        })
+        .collect()
+}
+
+/// 列出每个选择器合并进了哪个fixed列, 加了很多小芯片的电路用来看fixed列为什么变多
+pub fn selector_report<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<SelectorReport, Error> {
+    let (cs, recorder) = record(k, circuit)?;
+    let (compressed, polys) = cs.clone().compress_selectors(recorder.selectors.clone());
+    debug_assert_eq!(compressed.num_fixed_columns(), cs.num_fixed_columns() + polys.len());
+    let used: Vec<(&'static str, BTreeSet<(usize, bool)>)> = cs.gates().iter().map(|gate| (gate.name(), gate_selectors(gate))).collect();
+    let selectors = recorder.selectors.iter().enumerate().map(|(index, enabled)| {
+        let rows: Vec<usize> = enabled.iter().enumerate().filter(|(_, on)| **on).map(|(row, _)| row).collect();
+        // 代表它的取值正好出现在它启用的那些行上
+        let found = rows.first().and_then(|&first| {
+            polys.iter().enumerate().find_map(|(column, values)| {
+                let tag = values[first];
+                let matches = tag != Fp::zero() && values.iter().filter(|v| **v == tag).count() == rows.len() && rows.iter().all(|&row| values[row] == tag);
+                matches.then_some((column, tag))
+            })
+        });
+        let simple = used.iter().flat_map(|(_, selectors)| selectors).find(|(i, _)| *i == index).map(|(_, simple)| *simple);
+        SelectorUsage {
+            index,
+            simple,
+            gates: used.iter().filter(|(_, selectors)| selectors.iter().any(|(i, _)| *i == index)).map(|(name, _)| *name).collect(),
+            rows: rows.len(),
+            column: found.map(|(column, _)| column),
+            tag: found.map(|(_, tag)| u64::from_le_bytes(tag.to_repr()[..8].try_into().expect("repr不足8字节"))),
+        }
+    }).collect();
+    Ok(SelectorReport { fixed_columns: cs.num_fixed_columns(), selector_columns: polys.len(), selectors })
+}
+
+/// 一个区域占用的行和列
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionUsage {
+    pub name: String,
+    /// 起止行(都包含在内), 没有填写任何单元格的区域为None
+    pub rows: Option<(usize, usize)>,
+    /// 用到的列, 选择器也算一列
+    pub columns: Vec<String>,
+    /// 实际填写的单元格数
+    pub cells: usize,
+}
+
+impl RegionUsage {
+    pub fn height(&self) -> usize {
+        self.rows.map_or(0, |(start, end)| end - start + 1)
+    }
+
+    /// 区域矩形(行数乘列数)中没有填写的单元格数
+    pub fn wasted_cells(&self) -> usize {
+        self.height() * self.columns.len() - self.cells
+    }
+}
+
+/// 每个区域的行使用情况
+#[derive(Clone, Debug)]
+pub struct RegionReport {
+    pub k: u32,
+    pub usable_rows: usize,
+    pub regions: Vec<RegionUsage>,
+}
+
+impl RegionReport {
+    /// 区域用到的最大行号加一
+    pub fn used_rows(&self) -> usize {
+        self.regions.iter().filter_map(|r| r.rows).map(|(_, end)| end + 1).max().unwrap_or(0)
+    }
+
+    /// 0..used_rows中没有任何区域覆盖的行
+    pub fn gap_rows(&self) -> Vec<usize> {
+        (0..self.used_rows()).filter(|&row| !self.regions.iter().filter_map(|r| r.rows).any(|(start, end)| start <= row && row <= end)).collect()
+    }
+
+    /// 末尾还没用上的可用行
+    pub fn spare_rows(&self) -> usize {
+        self.usable_rows.saturating_sub(self.used_rows())
+    }
+}
+
+impl fmt::Display for RegionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<24}{:>12}{:>8}{:>10}  列", "区域", "行", "行数", "空单元格")?;
+        for r in &self.regions {
+            let rows = r.rows.map_or("-".to_string(), |(start, end)| format!("{}..={}", start, end));
+            // 有空单元格的区域用*标出
+            let mark = if r.wasted_cells() > 0 { "*" } else { " " };
+            writeln!(f, "{:<24}{:>12}{:>8}{:>9}{}  {}", r.name, rows, r.height(), r.wasted_cells(), mark, r.columns.join(","))?;
+        }
+        writeln!(f, "k = {}, 可用 {} 行, 已用 {} 行, 中间空行 {} 行, 末尾空闲 {} 行", self.k, self.usable_rows, self.used_rows(), self.gap_rows().len(), self.spare_rows())
+    }
+}
+
+/// 列出每个区域的名字、起止行和用到的列, 标出浪费的单元格和行
+pub fn region_report<C: Circuit<Fp>>(k: u32, circuit: &C) -> Result<RegionReport, Error> {
+    let (_, recorder) = record(k, circuit)?;
+    let regions = recorder.regions.into_iter()
+        .map(|r| RegionUsage { name: r.name, rows: r.rows, columns: r.columns.into_iter().collect(), cells: r.cells.len() })
+        .collect();
+    Ok(RegionReport { k, usable_rows: row_budget::<C>(k).usable_rows, regions })
+}
+
+/// 读取`MAX_K`环境变量作为测试允许的最大k, 未设置时为default
+///
+/// CI上可以设小一点限制测试规模, 本地可以设成18以上做压力测试
+pub fn max_k(default: u32) -> u32 {
+    match std::env::var("MAX_K") {
+        Ok(v) => v.parse().unwrap_or_else(|_| panic!("MAX_K必须是整数: {}", v)),
+        Err(_) => default,
+    }
+}
+
+/// 把可用行分块, 在多个线程上分别检查门和查找, 合并去重后返回所有失败
+///
+/// 每一块都会检查一遍置换, 所以同一个置换失败只保留一次
+pub fn verify_par<C: Circuit<Fp>>(k: u32, prover: &MockProver<Fp>) -> Result<(), Vec<VerifyFailure>> {
+    let usable_rows = row_budget::<C>(k).usable_rows;
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = usable_rows.div_ceil(threads);
+    let results: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..usable_rows).step_by(chunk.max(1))
+            .map(|start| {
+                let rows = start..(start + chunk).min(usable_rows);
+                scope.spawn(move || prover.verify_at_rows(rows.clone(), rows))
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().expect("检查线程崩溃")).collect()
+    });
+
+    let mut failures: Vec<VerifyFailure> = vec![];
+    for failure in results.into_iter().filter_map(Result::err).flatten() {
+        if !failures.contains(&failure) {
+            failures.push(failure);
+        }
+    }
+    if failures.is_empty() { Ok(()) } else { Err(failures) }
+}
+
+/// 按门(或失败种类)和区域分组的约束失败
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailureGroup {
+    /// 门名, 或者"查找#i"、"拷贝约束"等
+    pub kind: String,
+    /// 门失败时为门的标识, 见[`crate::names`]
+    pub gate: Option<String>,
+    /// 区域名, 区域外为空
+    pub region: String,
+    /// 每处失败的位置和相关单元格的值
+    pub details: Vec<String>,
+}
+
+fn split_location(location: &FailureLocation) -> (String, String) {
+    match location {
+        FailureLocation::InRegion { region, offset } => (region.to_string(), format!("偏移{}", offset)),
+        FailureLocation::OutsideRegion { row } => (String::new(), format!("第{}行", row)),
+    }
+}
+
+/// 把MockProver的失败按(门, 区域)分组, 组的顺序为第一次出现的顺序
+pub fn group_failures(failures: &[VerifyFailure]) -> Vec<FailureGroup> {
+    let mut groups: Vec<FailureGroup> = vec![];
+    for failure in failures {
+        let (kind, region, detail) = match failure {
+            VerifyFailure::ConstraintNotSatisfied { constraint, location, cell_values } => {
+                // 约束的Display为"Constraint i ('名') in gate j ('门名')"
+                let text = constraint.to_string();
+                let (constraint, gate) = text.split_once(" in gate ").unwrap_or((&text, ""));
+                let (region, at) = split_location(location);
+                let values: Vec<String> = cell_values.iter().map(|(cell, value)| format!("{} = {}", cell, value)).collect();
+                (format!("gate {}", gate), region, format!("{} {}: {}", at, constraint, values.join(", ")))
+            }
+            VerifyFailure::CellNotAssigned { gate, region, column, offset, .. } => {
+                (gate.to_string(), region.to_string(), format!("偏移{} {:?}未填写", offset, column))
+            }
+            VerifyFailure::Lookup { lookup_index, location } => {
+                let (region, at) = split_location(location);
+                (format!("查找#{}", lookup_index), region, at)
+            }
+            VerifyFailure::Permutation { column, location } => {
+                let (region, at) = split_location(location);
+                ("拷贝约束".to_string(), region, format!("{} {}", at, column))
+            }
+            other => ("其他".to_string(), String::new(), other.to_string()),
+        };
+        match groups.iter_mut().find(|g| g.kind == kind && g.region == region) {
+            Some(group) => group.details.push(detail),
+            None => {
+                // 门失败的kind为"gate j ('标识')"
+                let gate = kind.strip_prefix("gate ").and_then(|gate| gate.rsplit_once("('")).and_then(|(_, id)| id.strip_suffix("')")).map(str::to_string);
+                groups.push(FailureGroup { kind, gate, region, details: vec![detail] })
+            }
+        }
+    }
+    groups
+}
+
+/// 每组最多列出的失败数
+const MAX_DETAILS: usize = 5;
+
+/// 把分组后的失败排成便于浏览的文本, color为true时加ANSI颜色, 门的显示名按`LANG`选择语言
+pub fn render_failures(groups: &[FailureGroup], color: bool) -> String {
+    render_failures_in(groups, color, Locale::from_env())
+}
+
+/// 同[`render_failures`], 指定门显示名的语言
+pub fn render_failures_in(groups: &[FailureGroup], color: bool, locale: Locale) -> String {
+    let paint = |code: &str, text: &str| if color { format!("\x1b[{}m{}\x1b[0m", code, text) } else { text.to_string() };
+    let total: usize = groups.iter().map(|g| g.details.len()).sum();
+    let mut out = format!("{}条失败, 分为{}组\n", total, groups.len());
+    for group in groups {
+        let region = if group.region.is_empty() { "区域外".to_string() } else { group.region.clone() };
+        let kind = match &group.gate {
+            Some(id) => format!("{} {}", group.kind, gate_display(id, locale)),
+            None => group.kind.clone(),
+        };
+        out += &format!("{} {} ({}处)\n", paint("1;31", &kind), paint("33", &region), group.details.len());
+        for detail in group.details.iter().take(MAX_DETAILS) {
+            out += &format!("  {}\n", detail);
+        }
+        if group.details.len() > MAX_DETAILS {
+            out += &paint("2", &format!("  ...还有{}处\n", group.details.len() - MAX_DETAILS));
+        }
+    }
+    out
+}
+
+/// 并行版的`MockProver::assert_satisfied`, 失败时按组打印后panic
+pub fn assert_satisfied_par<C: Circuit<Fp>>(k: u32, circuit: &C, instances: Vec<Vec<Fp>>) {
+    let prover = MockProver::run(k, circuit, instances).expect("运行MockProver失败");
+    if let Err(failures) = verify_par::<C>(k, &prover) {
+        eprint!("{}", render_failures(&group_failures(&failures), false));
+        panic!("电路约束不满足, 共{}条失败", failures.len());
+    }
+}
+
+#[test]
+fn test_shape_independent() {
+    use crate::fib_range::FibRangeCircuit;
+    use crate::fib_word::FibWordCircuit;
+    use crate::{FibCircuit, FibStatement, FibWitness, JacobsthalCircuit, PellCircuit};
+
+    assert_shape_independent(4, &FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one()))).unwrap();
+    assert_shape_independent(5, &PellCircuit::<Fp>::pell(12)).unwrap();
+    assert_shape_independent(4, &JacobsthalCircuit::<Fp>::jacobsthal(10)).unwrap();
+    assert_shape_independent(8, &FibWordCircuit::<Fp>::new(&[0, 4, 100])).unwrap();
+    assert_shape_independent(9, &FibRangeCircuit::new(Fp::one(), Fp::one(), 93)).unwrap();
+}
+
+#[test]
+fn test_inspect_proof() {
+    use crate::prover::{keygen, prove};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one()));
+    let params = setup(4);
+    let pk = keygen(&params, &circuit).unwrap();
+    let proof = prove(&params, &pk, &circuit, &[Fp::from(55)]).unwrap();
+
+    // 按FibCircuit的结构手算: 3个advice列; instance列和常量fixed列开启相等约束各带一个查询;
+    // 置换共5列, degree为3所以每组1列共5组; 商多项式拆成2段; 1个选择器合并成1个fixed列; k = 4轮IPA
+    let breakdown = inspect_proof::<FibCircuit<Fp>>(4, &proof).unwrap();
+    let sizes: Vec<(&str, usize, usize)> = breakdown.components.iter().map(|c| (c.name, c.points, c.scalars)).collect();
+    assert_eq!(sizes, vec![
+        ("advice承诺", 3, 0),
+        ("查找置换承诺", 0, 0),
+        ("置换乘积承诺", 5, 0),
+        ("查找乘积承诺", 0, 0),
+        ("商多项式承诺", 3, 0),
+        ("instance求值", 0, 1),
+        ("advice求值", 0, 3),
+        ("fixed求值", 0, 1),
+        ("选择器列求值", 0, 1),
+        ("随机多项式求值", 0, 1),
+        // 5个sigma求值, 每组乘积在x和ωx处的求值, 除最后一组外还有在ω^last x处的求值
+        ("置换求值", 0, 5 + 5 * 3 - 1),
+        ("查找求值", 0, 0),
+        // 旋转集合{0}、{0, 1}和{last, 0, 1}
+        ("多点打开", 1, 3),
+        ("IPA最终打开", 1 + 2 * 4, 2),
+    ]);
+    assert_eq!(proof.len(), (3 + 5 + 3 + 1 + 9) * 32 + (1 + 3 + 1 + 1 + 1 + 19 + 3 + 2) * 32);
+
+    assert!(inspect_proof::<FibCircuit<Fp>>(4, &proof[..64]).is_err());
+}
+
+#[test]
+fn test_layout_variants_equivalent() {
+    use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner};
+    use halo2_proofs::poly::Rotation;
+    use crate::fib_range::FibRangeCircuit;
+    use crate::prover::{keygen, prove, verify};
+    use crate::recurrence::{recurrence_terms, LinearRecurrenceCircuit};
+    use crate::{ConstFibCircuit, FibCircuit, FibStatement, FibWitness};
+
+    // 两列布局: 第i行是(F(i), F(i+1)), 门约束 a' = b, b' = a + b
+    #[derive(Clone, Debug)]
+    struct TwoColumnConfig {
+        selector: Selector,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        instance: Column<Instance>,
+    }
+
+    struct TwoColumnCircuit<const N: usize> {
+        a: Value<Fp>,
+        b: Value<Fp>,
+    }
+
+    impl<const N: usize> Circuit<Fp> for TwoColumnCircuit<N> {
+        type Config = TwoColumnConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { a: Value::unknown(), b: Value::unknown() } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let selector = meta.selector();
+            let (a, b, instance) = (meta.advice_column(), meta.advice_column(), meta.instance_column());
+            meta.enable_equality(b);
+            meta.enable_equality(instance);
+            meta.create_gate("two_column_step", |meta| {
+                let selector = meta.query_selector(selector);
+                let (cur_a, cur_b) = (meta.query_advice(a, Rotation::cur()), meta.query_advice(b, Rotation::cur()));
+                let (next_a, next_b) = (meta.query_advice(a, Rotation::next()), meta.query_advice(b, Rotation::next()));
+                vec![
+                    ("a' = b", selector.clone() * (next_a - cur_b.clone())),
+                    ("b' = a + b", selector * (cur_a + cur_b - next_b)),
+                ]
+            });
+            TwoColumnConfig { selector, a, b, instance }
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let terms = self.a.zip(self.b).map(|(a, b)| recurrence_terms(1, 1, a, b, N));
+            let last = layouter.assign_region(|| "两列数列", |mut region| {
+                let mut last = None;
+                for row in 0..N - 1 {
+                    if row + 2 < N {
+                        config.selector.enable(&mut region, row)?;
+                    }
+                    region.assign_advice(|| "a", config.a, row, || terms.as_ref().map(|t| t[row]))?;
+                    last = Some(region.assign_advice(|| "b", config.b, row, || terms.as_ref().map(|t| t[row + 1]))?);
+                }
+                Ok(last.expect("至少有一行"))
+            })?;
+            layouter.constrain_instance(last.cell(), config.instance, 0)
+        }
+    }
+
+    // 单列布局: 数列竖着填在一列里, 门约束 x(i) + x(i+1) = x(i+2)
+    #[derive(Clone, Debug)]
+    struct SingleColumnConfig {
+        selector: Selector,
+        x: Column<Advice>,
+        instance: Column<Instance>,
+    }
+
+    struct SingleColumnCircuit<const N: usize> {
+        a: Value<Fp>,
+        b: Value<Fp>,
+    }
+
+    impl<const N: usize> Circuit<Fp> for SingleColumnCircuit<N> {
+        type Config = SingleColumnConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { a: Value::unknown(), b: Value::unknown() } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let selector = meta.selector();
+            let (x, instance) = (meta.advice_column(), meta.instance_column());
+            meta.enable_equality(x);
+            meta.enable_equality(instance);
+            meta.create_gate("single_column_add", |meta| {
+                let selector = meta.query_selector(selector);
+                let x0 = meta.query_advice(x, Rotation::cur());
+                let x1 = meta.query_advice(x, Rotation::next());
+                let x2 = meta.query_advice(x, Rotation(2));
+                vec![
+                    ("x + x' = x''", selector * (x0 + x1 - x2)),
+                ]
+            });
+            SingleColumnConfig { selector, x, instance }
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let terms = self.a.zip(self.b).map(|(a, b)| recurrence_terms(1, 1, a, b, N));
+            let last = layouter.assign_region(|| "单列数列", |mut region| {
+                let mut last = None;
+                for row in 0..N {
+                    if row + 2 < N {
+                        config.selector.enable(&mut region, row)?;
+                    }
+                    last = Some(region.assign_advice(|| "x", config.x, row, || terms.as_ref().map(|t| t[row]))?);
+                }
+                Ok(last.expect("至少有一项"))
+            })?;
+            layouter.constrain_instance(last.cell(), config.instance, 0)
+        }
+    }
+
+    // 各布局变体都证明同一个命题: 以1, 1开头的数列第n项等于target
+    fn prove_and_verify<C: Circuit<Fp>>(name: &str, k: u32, circuit: &C, public_inputs: &[Fp]) -> bool {
+        let params = setup(k);
+        let pk = keygen(&params, circuit).unwrap_or_else(|e| panic!("{}生成密钥失败: {:?}", name, e));
+        let proof = prove(&params, &pk, circuit, public_inputs).unwrap_or_else(|e| panic!("{}生成证明失败: {:?}", name, e));
+        verify(&params, pk.get_vk(), public_inputs, &proof).is_valid()
+    }
+
+    fn check_all<const N: usize>() {
+        let target = recurrence_terms(1, 1, Fp::one(), Fp::one(), N)[N - 1];
+        let one = Value::known(Fp::one());
+        for public_inputs in [vec![target], vec![target + Fp::one()]] {
+            let expected = public_inputs[0] == target;
+            let results = [
+                // 三列(宽)布局: 每行一次加法
+                prove_and_verify("FibCircuit", 5, &FibCircuit::new(&FibStatement::new(N, target).unwrap(), &FibWitness::new(Fp::one(), Fp::one())), &public_inputs),
+                prove_and_verify("ConstFibCircuit", 5, &ConstFibCircuit::<Fp, N>::new(Fp::one(), Fp::one()), &public_inputs),
+                prove_and_verify("LinearRecurrenceCircuit", 5, &LinearRecurrenceCircuit::<Fp, 1, 1>::new(Fp::one(), Fp::one(), N), &public_inputs),
+                prove_and_verify("FibRangeCircuit", 9, &FibRangeCircuit::new(Fp::one(), Fp::one(), N), &public_inputs),
+                prove_and_verify("TwoColumnCircuit", 5, &TwoColumnCircuit::<N> { a: one, b: one }, &public_inputs),
+                prove_and_verify("SingleColumnCircuit", 5, &SingleColumnCircuit::<N> { a: one, b: one }, &public_inputs),
+            ];
+            assert!(results.iter().all(|&ok| ok == expected), "n = {}时各变体结果不一致: {:?}", N, results);
+        }
+    }
+
+    check_all::<3>();
+    check_all::<10>();
+    check_all::<20>();
+}
+
+#[test]
+fn test_degree_report() {
+    use crate::fib_range::FibRangeCircuit;
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let circuit = FibCircuit::new(&FibStatement::from_witness(10, &witness).unwrap(), &witness);
+    let report = degree_report(4, &circuit).unwrap();
+    assert_eq!(report.constraints.len(), 1);
+    assert_eq!(report.constraints[0].degree, 2);
+    assert_eq!(report.selectors, 1);
+
+    // 组合电路包含复合选择器和查找参数
+    let report = degree_report(9, &FibRangeCircuit::new(Fp::one(), Fp::one(), 93)).unwrap();
+    assert!(report.compressed_degree <= report.degree);
+}
+
+#[test]
+fn test_assert_satisfied_par() {
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    // 默认只跑k = 8, 设置MAX_K可以放大
+    let k = max_k(8);
+    let n = row_budget::<FibCircuit<Fp>>(k).usable_rows + 2;
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(n, &witness).unwrap();
+    let circuit = FibCircuit::new(&statement, &witness);
+    assert_satisfied_par(k, &circuit, vec![statement.public_inputs()]);
+
+    let prover = MockProver::run(k, &circuit, vec![vec![statement.target + Fp::one()]]).unwrap();
+    assert!(verify_par::<FibCircuit<Fp>>(k, &prover).is_err());
+}
+
+#[test]
+fn test_region_report() {
+    use crate::fib_range::FibRangeCircuit;
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    // n = 10时每行一个区域, 共8行, 每个区域的a、b、c和选择器都填满
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let circuit = FibCircuit::new(&FibStatement::from_witness(10, &witness).unwrap(), &witness);
+    let report = region_report(4, &circuit).unwrap();
+    assert_eq!(report.regions.len(), 8);
+    assert_eq!(report.used_rows(), 8);
+    assert!(report.gap_rows().is_empty());
+    assert!(report.regions.iter().all(|r| r.height() == 1 && r.wasted_cells() == 0));
+
+    // 范围检查区域只用了一列advice, 最后一行没有启用查找选择器
+    let report = region_report(9, &FibRangeCircuit::new(Fp::one(), Fp::one(), 93)).unwrap();
+    assert!(report.regions.iter().any(|r| r.wasted_cells() > 0));
+}
+
+#[test]
+fn test_group_failures() {
+    use crate::segment::{SegmentFlags, SegmentedFibCircuit};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    // target不对时只有拷贝约束失败
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(56)).unwrap(), &witness);
+    let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(56)]]).unwrap();
+    let groups = group_failures(&prover.verify().unwrap_err());
+    assert!(!groups.is_empty());
+    assert!(groups.iter().all(|g| g.kind == "拷贝约束"));
+
+    // 开关为2时布尔门失败, 带上单元格的值
+    let circuit = SegmentedFibCircuit::new(Fp::one(), Fp::one(), 5, 3);
+    let mut public_inputs = circuit.public_inputs(Fp::one(), Fp::one(), SegmentFlags { extend: false, hash: false });
+    public_inputs[0] = Fp::from(2);
+    let prover = MockProver::run(10, &circuit, vec![public_inputs]).unwrap();
+    let failures = prover.verify().unwrap_err();
+    let groups = group_failures(&failures);
+    assert_eq!(groups.iter().map(|g| g.details.len()).sum::<usize>(), failures.len());
+    assert!(groups.iter().any(|g| g.gate.as_deref() == Some("segment_bool") && g.details[0].contains(" = ")));
+
+    let text = render_failures_in(&groups, true, Locale::Zh);
+    assert!(text.contains("开关(布尔)"));
+    assert!(render_failures_in(&groups, false, Locale::En).contains("flag (boolean)"));
+    assert!(text.contains("\x1b[1;31m"));
+    assert!(!render_failures(&groups, false).contains('\x1b'));
+}
+
+#[test]
+fn test_selector_report() {
+    use crate::fib_range::FibRangeCircuit;
+
+    let circuit = FibRangeCircuit::new(Fp::one(), Fp::one(), 93);
+    let report = selector_report(9, &circuit).unwrap();
+    assert_eq!(report.selector_columns, degree_report(9, &circuit).unwrap().selector_columns);
+    assert!(report.selectors.iter().all(|s| s.rows > 0 && s.column.is_some()));
+    let fib = report.selectors.iter().find(|s| s.gates == ["fib_add"]).unwrap();
+    assert_eq!(fib.simple, Some(true));
+    // 范围检查的查找选择器是复杂选择器, 单独占一列
+    let range = report.selectors.iter().find(|s| s.simple.is_none()).unwrap();
+    assert_eq!(report.columns()[range.column.unwrap()], [range.index]);
+    // 同一列里的选择器取值各不相同
+    for column in report.columns() {
+        let mut tags: Vec<u64> = column.iter().map(|&i| report.selectors[i].tag.unwrap()).collect();
+        tags.sort();
+        tags.dedup();
+        assert_eq!(tags.len(), column.len());
+    }
+}
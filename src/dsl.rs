@@ -0,0 +1,270 @@
+//! 命令行用的命题文本格式, 如`fib(n=30, a=1, b=1) == 832040`
+//!
+//! ```text
+//! 命题 := 电路名 '(' [参数 (',' 参数)*] ')' ['==' 十进制数]
+//! 参数 := 名字 '=' 十进制数
+//! ```
+//!
+//! 空白可以随意出现. 解析只得到电路名、参数和目标值, 由各电路自己检查参数并构造命题和见证,
+//! 以后的电路接入同一种写法时只需要加一个转换函数
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ff::PrimeField;
+use halo2_proofs::pasta::Fp;
+
+use crate::{FibStatement, FibWitness};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DslError {
+    /// 第pos个字节处语法不对
+    Syntax { pos: usize, reason: String },
+    /// 命题不是这个电路的
+    WrongCircuit { expected: &'static str, got: String },
+    MissingArg(&'static str),
+    UnknownArg(String),
+    DuplicateArg(String),
+    /// 参数的值超出范围或不合法
+    BadValue { arg: String, value: String },
+}
+
+impl fmt::Display for DslError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DslError::Syntax { pos, reason } => write!(f, "命题第{}字节处{}", pos, reason),
+            DslError::WrongCircuit { expected, got } => write!(f, "应为{}电路的命题, 实际为{}", expected, got),
+            DslError::MissingArg(arg) => write!(f, "缺少参数{}", arg),
+            DslError::UnknownArg(arg) => write!(f, "未知的参数{}", arg),
+            DslError::DuplicateArg(arg) => write!(f, "参数{}重复出现", arg),
+            DslError::BadValue { arg, value } => write!(f, "参数{}的值{}不合法", arg, value),
+        }
+    }
+}
+
+impl std::error::Error for DslError {}
+
+/// 解析出的命题, 参数值保留原始的十进制文本
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Statement {
+    pub circuit: String,
+    pub args: BTreeMap<String, String>,
+    pub target: Option<String>,
+}
+
+/// 十进制字符串转为域元素, 可以超过u64; 不小于模数的值不是规范表示, 返回None而不是取模
+pub fn parse_decimal(s: &str) -> Option<Fp> {
+    if s.is_empty() {
+        return None;
+    }
+    // 小端的四个64位limb, 逐位乘10加上该位, 超过256位时返回None
+    let mut limbs = [0u64; 4];
+    for c in s.chars() {
+        let mut carry = c.to_digit(10)? as u128;
+        for limb in limbs.iter_mut() {
+            let cur = *limb as u128 * 10 + carry;
+            *limb = cur as u64;
+            carry = cur >> 64;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    let mut repr = [0u8; 32];
+    for (chunk, limb) in repr.chunks_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    Fp::from_repr(repr).into()
+}
+
+/// 域元素的十进制表示, 与[`parse_decimal`]互逆
+pub fn format_decimal(value: &Fp) -> String {
+    // 小端的四个64位limb, 反复除以10取余
+    let repr = value.to_repr();
+    let mut limbs: Vec<u64> = repr.chunks(8).map(|chunk| u64::from_le_bytes(chunk.try_into().expect("8字节"))).collect();
+    let mut digits = Vec::new();
+    loop {
+        let mut rem = 0u128;
+        for limb in limbs.iter_mut().rev() {
+            let cur = (rem << 64) | *limb as u128;
+            *limb = (cur / 10) as u64;
+            rem = cur % 10;
+        }
+        digits.push(b'0' + rem as u8);
+        if limbs.iter().all(|&limb| limb == 0) {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("数字都是ASCII")
+}
+
+/// 逐字节读取的游标
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn skip_space(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, reason: &str) -> DslError {
+        DslError::Syntax { pos: self.pos, reason: reason.to_string() }
+    }
+
+    /// 跳过空白后读到token则前进并返回true
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        let found = self.text[self.pos..].starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), DslError> {
+        if self.eat(token) { Ok(()) } else { Err(self.error(&format!("应为{}", token))) }
+    }
+
+    /// 读取满足pred的一段非空文本
+    fn take(&mut self, pred: impl Fn(char) -> bool, what: &str) -> Result<&'a str, DslError> {
+        self.skip_space();
+        let rest = &self.text[self.pos..];
+        let len = rest.find(|c: char| !pred(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(&format!("应为{}", what)));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn ident(&mut self) -> Result<&'a str, DslError> {
+        self.take(|c| c.is_ascii_alphanumeric() || c == '_', "名字")
+    }
+
+    fn number(&mut self) -> Result<&'a str, DslError> {
+        self.take(|c| c.is_ascii_digit(), "十进制数")
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args: Vec<String> = self.args.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        write!(f, "{}({})", self.circuit, args.join(", "))?;
+        match &self.target {
+            Some(target) => write!(f, " == {}", target),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Statement {
+    pub fn parse(text: &str) -> Result<Self, DslError> {
+        let mut cursor = Cursor { text, pos: 0 };
+        let circuit = cursor.ident()?.to_string();
+        cursor.expect("(")?;
+        let mut args = BTreeMap::new();
+        if !cursor.eat(")") {
+            loop {
+                let name = cursor.ident()?;
+                cursor.expect("=")?;
+                let value = cursor.number()?;
+                if args.insert(name.to_string(), value.to_string()).is_some() {
+                    return Err(DslError::DuplicateArg(name.to_string()));
+                }
+                if cursor.eat(")") {
+                    break;
+                }
+                cursor.expect(",")?;
+            }
+        }
+        let target = if cursor.eat("==") { Some(cursor.number()?.to_string()) } else { None };
+        if let Some(target) = target.as_ref().filter(|target| parse_decimal(target).is_none()) {
+            return Err(DslError::BadValue { arg: "target".to_string(), value: target.clone() });
+        }
+        cursor.skip_space();
+        if cursor.pos < text.len() {
+            return Err(cursor.error("有多余的字符"));
+        }
+        Ok(Self { circuit, args, target })
+    }
+
+    /// 检查电路名, 并且参数都在allowed之内
+    pub fn expect_circuit(&self, circuit: &'static str, allowed: &[&str]) -> Result<(), DslError> {
+        if self.circuit != circuit {
+            return Err(DslError::WrongCircuit { expected: circuit, got: self.circuit.clone() });
+        }
+        match self.args.keys().find(|arg| !allowed.contains(&arg.as_str())) {
+            Some(arg) => Err(DslError::UnknownArg(arg.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// u64参数, 没有给出时为default, default为None时必须给出
+    pub fn u64_arg(&self, arg: &'static str, default: Option<u64>) -> Result<u64, DslError> {
+        match self.args.get(arg) {
+            Some(value) => value.parse().map_err(|_| DslError::BadValue { arg: arg.to_string(), value: value.clone() }),
+            None => default.ok_or(DslError::MissingArg(arg)),
+        }
+    }
+
+    /// 目标值, 可以超过u64
+    pub fn target_field(&self) -> Option<Fp> {
+        self.target.as_deref().and_then(parse_decimal)
+    }
+
+    /// 转为斐波那契电路的命题和见证: 参数n必需, a、b默认为1; 没有目标值时由a、b算出
+    pub fn fib(&self) -> Result<(FibStatement<Fp>, FibWitness<Fp>), DslError> {
+        self.expect_circuit("fib", &["n", "a", "b"])?;
+        let n = self.u64_arg("n", None)?;
+        let bad_n = || DslError::BadValue { arg: "n".to_string(), value: n.to_string() };
+        let n = usize::try_from(n).map_err(|_| bad_n())?;
+        let witness = FibWitness::new(Fp::from(self.u64_arg("a", Some(1))?), Fp::from(self.u64_arg("b", Some(1))?));
+        let statement = match self.target_field() {
+            Some(target) => FibStatement::new(n, target),
+            None => FibStatement::from_witness(n, &witness),
+        };
+        Ok((statement.map_err(|_| bad_n())?, witness))
+    }
+}
+
+#[test]
+fn test_statement_dsl() {
+    let statement = Statement::parse(" fib( n = 30, a=1 ,b=1 )==832040 ").unwrap();
+    assert_eq!(statement.circuit, "fib");
+    assert_eq!(statement.target.as_deref(), Some("832040"));
+    let (fib, witness) = statement.fib().unwrap();
+    assert_eq!((fib.n, fib.target), (30, Fp::from(832040)));
+    assert!(witness.satisfies(&fib));
+
+    // 省略a、b和目标值
+    let (fib, _) = Statement::parse("fib(n=10)").unwrap().fib().unwrap();
+    assert_eq!(fib.target, Fp::from(55));
+    assert_eq!(Statement::parse("factorial()").unwrap().args.len(), 0);
+
+    assert!(matches!(Statement::parse("fib(n=30"), Err(DslError::Syntax { pos: 8, .. })));
+    assert!(matches!(Statement::parse("fib(n=-1)"), Err(DslError::Syntax { pos: 6, .. })));
+    assert!(matches!(Statement::parse("fib(n=3) == 2 x"), Err(DslError::Syntax { .. })));
+    assert_eq!(Statement::parse("fib(n=3, n=4)"), Err(DslError::DuplicateArg("n".to_string())));
+    assert_eq!(Statement::parse("fib(a=1)").unwrap().fib().unwrap_err(), DslError::MissingArg("n"));
+    assert_eq!(Statement::parse("fib(n=10, c=1)").unwrap().fib().unwrap_err(), DslError::UnknownArg("c".to_string()));
+    assert_eq!(Statement::parse(" fib( n = 30, a=1 ,b=1 )==832040 ").unwrap().to_string(), "fib(a=1, b=1, n=30) == 832040");
+    assert_eq!(Statement::parse("preimage()").unwrap().to_string(), "preimage()");
+    let big = -Fp::one();
+    assert_eq!(parse_decimal(&format_decimal(&big)), Some(big));
+    assert_eq!(format_decimal(&Fp::zero()), "0");
+    assert_eq!(format_decimal(&Fp::from(832040)), "832040");
+    // 模数p和p+1不是规范表示, 不取模
+    let p = "28948022309329048855892746252171976963363056481941560715954676764349967630337";
+    assert_eq!(format_decimal(&big), "28948022309329048855892746252171976963363056481941560715954676764349967630336");
+    assert_eq!(parse_decimal(p), None);
+    assert_eq!(parse_decimal("28948022309329048855892746252171976963363056481941560715954676764349967630338"), None);
+    assert_eq!(parse_decimal(&"9".repeat(80)), None);
+    assert_eq!(parse_decimal("0012"), Some(Fp::from(12)));
+    assert_eq!(Statement::parse(&format!("fib(n=10) == {}", p)), Err(DslError::BadValue { arg: "target".to_string(), value: p.to_string() }));
+    assert!(matches!(Statement::parse("merkle(n=10)").unwrap().fib(), Err(DslError::WrongCircuit { .. })));
+    assert!(matches!(Statement::parse("fib(n=2)").unwrap().fib(), Err(DslError::BadValue { .. })));
+}
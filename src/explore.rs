@@ -0,0 +1,217 @@
+//! 终端里浏览电路填写情况的交互界面, 由`fib explore`启动, 需要`tui` feature
+//!
+//! 方向键或hjkl移动, PageUp/PageDown翻页, g/G到首尾行, n/N跳到下一个/上一个约束失败, q退出
+
+use std::collections::BTreeMap;
+use std::io;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::ExecutableCommand;
+use halo2_proofs::dev::{FailureLocation, MockProver, VerifyFailure};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Circuit, Error};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
+use ratatui::{Frame, Terminal};
+
+use crate::trace::{format_value, trace, TraceColumn, TraceEvent};
+
+/// 一个失败及其所在的绝对行
+#[derive(Clone, Debug)]
+pub struct LocatedFailure {
+    pub row: Option<usize>,
+    pub message: String,
+}
+
+/// 失败所在的绝对行, region_starts为各区域的起始行
+///
+/// `metadata::Region`的编号不公开, 从它的Display("Region i ('name')")里取
+fn failure_row(failure: &VerifyFailure, region_starts: &BTreeMap<usize, usize>) -> Option<usize> {
+    let in_region = |region: &dyn std::fmt::Display, offset: usize| {
+        let text = region.to_string();
+        let index: usize = text.strip_prefix("Region ")?.split_whitespace().next()?.parse().ok()?;
+        Some(region_starts.get(&index)? + offset)
+    };
+    let location = match failure {
+        VerifyFailure::ConstraintNotSatisfied { location, .. } | VerifyFailure::Lookup { location, .. } | VerifyFailure::Permutation { location, .. } => location,
+        VerifyFailure::CellNotAssigned { region, offset, .. } => return in_region(region, *offset),
+        _ => return None,
+    };
+    match location {
+        FailureLocation::InRegion { region, offset } => in_region(region, *offset),
+        FailureLocation::OutsideRegion { row } => Some(*row),
+    }
+}
+
+/// 浏览器的状态: 填写矩阵、失败列表和光标
+pub struct Explorer {
+    columns: Vec<TraceColumn>,
+    rows: usize,
+    cells: BTreeMap<(usize, TraceColumn), TraceEvent>,
+    failures: Vec<LocatedFailure>,
+    row: usize,
+    column: usize,
+    failure: Option<usize>,
+}
+
+impl Explorer {
+    /// 合成一遍记录填写, 再用MockProver收集失败
+    pub fn new<C: Circuit<Fp>>(k: u32, circuit: &C, instances: Vec<Vec<Fp>>) -> Result<Self, Error> {
+        let mut events: Vec<TraceEvent> = vec![];
+        trace(k, circuit, instances.clone(), &mut events)?;
+        let mut region_starts = BTreeMap::new();
+        for event in &events {
+            if let Some(index) = event.region_index {
+                let start = region_starts.entry(index).or_insert(event.row);
+                *start = (*start).min(event.row);
+            }
+        }
+
+        let prover = MockProver::run(k, circuit, instances)?;
+        let failures = prover.verify().err().unwrap_or_default().iter()
+            .map(|f| LocatedFailure { row: failure_row(f, &region_starts), message: f.to_string() })
+            .collect();
+
+        let mut columns: Vec<_> = events.iter().map(|e| e.column).collect();
+        columns.sort();
+        columns.dedup();
+        let rows = events.iter().map(|e| e.row + 1).max().unwrap_or(0);
+        let cells = events.into_iter().map(|e| ((e.row, e.column), e)).collect();
+        Ok(Self { columns, rows, cells, failures, row: 0, column: 0, failure: None })
+    }
+
+    pub fn failures(&self) -> &[LocatedFailure] {
+        &self.failures
+    }
+
+    /// 跳到下一个(forward)或上一个有行号的失败
+    fn jump_failure(&mut self, forward: bool) {
+        let located: Vec<usize> = (0..self.failures.len()).filter(|&i| self.failures[i].row.is_some()).collect();
+        if located.is_empty() {
+            return;
+        }
+        let pos = self.failure.and_then(|f| located.iter().position(|&i| i == f));
+        let next = match (pos, forward) {
+            (None, _) => 0,
+            (Some(p), true) => (p + 1) % located.len(),
+            (Some(p), false) => (p + located.len() - 1) % located.len(),
+        };
+        self.failure = Some(located[next]);
+        self.row = self.failures[located[next]].row.expect("只在有行号的失败间跳转");
+    }
+
+    fn move_by(&mut self, rows: isize, columns: isize) {
+        let clamp = |v: usize, delta: isize, len: usize| v.saturating_add_signed(delta).min(len.saturating_sub(1));
+        self.row = clamp(self.row, rows, self.rows);
+        self.column = clamp(self.column, columns, self.columns.len());
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(7)])
+            .split(frame.size());
+
+        // 光标所在行居中
+        let height = areas[0].height.saturating_sub(3) as usize;
+        let first = self.row.saturating_sub(height / 2).min(self.rows.saturating_sub(height));
+        let failed_rows: Vec<usize> = self.failures.iter().filter_map(|f| f.row).collect();
+        let header = Row::new(std::iter::once("行".to_string()).chain(self.columns.iter().map(|c| c.to_string())))
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = (first..(first + height).min(self.rows)).map(|row| {
+            let mut cells = vec![Cell::from(row.to_string()).style(if failed_rows.contains(&row) { Style::default().fg(Color::Red) } else { Style::default() })];
+            for (i, column) in self.columns.iter().enumerate() {
+                let (text, mut style) = match self.cells.get(&(row, *column)) {
+                    Some(event) => {
+                        let text = event.value.as_ref().map_or("?".to_string(), format_value);
+                        let color = event.region_index.map_or(Color::DarkGray, |r| [Color::Blue, Color::Green, Color::Magenta, Color::Cyan][r % 4]);
+                        (text, Style::default().fg(color))
+                    }
+                    None => (String::new(), Style::default()),
+                };
+                if row == self.row && i == self.column {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                cells.push(Cell::from(text).style(style));
+            }
+            Row::new(cells)
+        });
+        let widths: Vec<_> = std::iter::once(Constraint::Length(6)).chain(self.columns.iter().map(|_| Constraint::Length(12))).collect();
+        let title = format!("第{}行 / 共{}行, {}个失败", self.row, self.rows, self.failures.len());
+        frame.render_widget(Table::new(rows, widths).header(header).block(Block::default().borders(Borders::ALL).title(title)), areas[0]);
+
+        let mut detail = vec![];
+        if let Some(column) = self.columns.get(self.column) {
+            match self.cells.get(&(self.row, *column)) {
+                Some(event) => detail.push(format!("{} 第{}行 = {:?}  区域: {}", column, self.row, event.value, event.region)),
+                None => detail.push(format!("{} 第{}行 未填写", column, self.row)),
+            }
+        }
+        let selectors: Vec<String> = self.columns.iter().filter(|c| matches!(c, TraceColumn::Selector(_)) && self.cells.contains_key(&(self.row, **c))).map(|c| c.to_string()).collect();
+        detail.push(format!("启用的选择器: {}", if selectors.is_empty() { "无".to_string() } else { selectors.join(", ") }));
+        for failure in self.failures.iter().filter(|f| f.row == Some(self.row)) {
+            detail.push(failure.message.lines().next().unwrap_or_default().to_string());
+        }
+        frame.render_widget(Paragraph::new(detail.join("\n")).block(Block::default().borders(Borders::ALL).title("详情")), areas[1]);
+    }
+
+    /// 进入备用屏幕运行界面, 按q退出
+    pub fn run(&mut self) -> io::Result<()> {
+        enable_raw_mode()?;
+        io::stdout().execute(EnterAlternateScreen)?;
+        let result = self.event_loop();
+        disable_raw_mode()?;
+        io::stdout().execute(LeaveAlternateScreen)?;
+        result
+    }
+
+    fn event_loop(&mut self) -> io::Result<()> {
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let page = terminal.size()?.height.saturating_sub(10).max(1) as isize;
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.move_by(-1, 0),
+                KeyCode::Down | KeyCode::Char('j') => self.move_by(1, 0),
+                KeyCode::Left | KeyCode::Char('h') => self.move_by(0, -1),
+                KeyCode::Right | KeyCode::Char('l') => self.move_by(0, 1),
+                KeyCode::PageUp => self.move_by(-page, 0),
+                KeyCode::PageDown => self.move_by(page, 0),
+                KeyCode::Char('g') => self.row = 0,
+                KeyCode::Char('G') => self.row = self.rows.saturating_sub(1),
+                KeyCode::Char('n') => self.jump_failure(true),
+                KeyCode::Char('N') => self.jump_failure(false),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn test_explorer_failures() {
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    // target错误时拷贝约束失败落在c列的最后一行(第7行)
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(56)).unwrap(), &witness);
+    let mut explorer = Explorer::new(4, &circuit, vec![vec![Fp::from(56)]]).unwrap();
+    assert!(!explorer.failures().is_empty());
+    let rows: Vec<_> = explorer.failures().iter().filter_map(|f| f.row).collect();
+    assert!(rows.contains(&7));
+
+    // 在有行号的失败之间循环
+    for _ in 0..=rows.len() {
+        explorer.jump_failure(true);
+        assert!(rows.contains(&explorer.row));
+    }
+    explorer.jump_failure(false);
+    assert!(rows.contains(&explorer.row));
+}
@@ -4,35 +4,76 @@ use halo2_proofs::{plonk::*};
 use halo2_proofs::arithmetic::Field;
 use halo2_proofs::dev::MockProver;
 use halo2_proofs::pasta::Fp;
-use plotters::prelude::WHITE;
 
+use std::fmt;
+
+use crate::instance::InstanceAllocator;
+use crate::prover::{zeroize_field, zeroize_value, ZeroizeWitness};
+use crate::shared::SharedColumns;
+
+/// 斐波那契电路的列配置: 每行 a + b = c, 结果通过target实例列公开
 #[derive(Clone, Debug, Copy)]
-struct FibConfig {
-    selector: Selector,
-    a: Column<Advice>,
-    b: Column<Advice>,
-    c: Column<Advice>,
-    target: Column<Instance>,
+pub struct FibConfig {
+    pub selector: Selector,
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub target: Column<Instance>,
+    pub constant: Column<Fixed>,
 }
 
-struct FibChip {
+/// 斐波那契芯片, 每行计算一项, 下一行通过拷贝约束接上一行的b和c
+///
+/// ```
+/// use halo2_fib::{FibChip, FibConfig, SharedColumns};
+/// use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+/// use halo2_proofs::dev::MockProver;
+/// use halo2_proofs::pasta::Fp;
+/// use halo2_proofs::plonk::{Circuit, ConstraintSystem, Error};
+///
+/// // 只计算两行: 1, 1, 2, 3, 结果3公开到第0行
+/// struct TwoRows;
+///
+/// impl Circuit<Fp> for TwoRows {
+///     type Config = FibConfig;
+///     type FloorPlanner = SimpleFloorPlanner;
+///
+///     fn without_witnesses(&self) -> Self { TwoRows }
+///
+///     fn configure(meta: &mut ConstraintSystem<Fp>) -> FibConfig {
+///         let shared = SharedColumns::configure(meta);
+///         FibChip::configure(meta, &shared)
+///     }
+///
+///     fn synthesize(&self, config: FibConfig, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+///         let chip = FibChip::construct(config);
+///         let one = Value::known(Fp::one());
+///         let (b, c) = chip.assign_first_row(layouter.namespace(|| "第一行"), one, one)?;
+///         let (_, c) = chip.assign_next_row(layouter.namespace(|| "下一行"), &b, &c)?;
+///         chip.expose_public(layouter.namespace(|| "公开结果"), &c, 0)
+///     }
+/// }
+///
+/// let prover = MockProver::run(4, &TwoRows, vec![vec![Fp::from(3)]]).unwrap();
+/// prover.assert_satisfied();
+/// ```
+pub struct FibChip {
     config: FibConfig
 }
 
 impl FibChip {
-    fn configure<F: Field>(meta: &mut ConstraintSystem<F>) -> FibConfig {
-        let selector = meta.selector();
-        let a = meta.advice_column();
-        let b = meta.advice_column();
-        let c = meta.advice_column();
-        let target = meta.instance_column();
+    pub fn construct(config: FibConfig) -> Self {
+        Self { config }
+    }
 
-        meta.enable_equality(a);
-        meta.enable_equality(b);
-        meta.enable_equality(c);
-        meta.enable_equality(target);
+    /// 使用共享列中的三个advice列作为a、b、c
+    pub fn configure<F: Field>(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> FibConfig {
+        let selector = meta.selector();
+        let [a, b, c] = shared.advice;
+        let target = shared.instance;
+        let constant = shared.constant;
 
-        meta.create_gate("斐波那契(相加)", |meta| {
+        meta.create_gate("fib_add", |meta| {
             let selector = meta.query_selector(selector);
             let num_a = meta.query_advice(a, Rotation::cur());
             let num_b = meta.query_advice(b, Rotation::cur());
@@ -41,93 +82,486 @@ impl FibChip {
                 ("a + b = c", selector * (num_a + num_b - num_c)),
             ]
         });
-        FibConfig { selector, a, b, c, target }
+        FibConfig { selector, a, b, c, target, constant }
     }
 
-    fn assign_first_row<F: Field>(&self, mut layouter: impl Layouter<F>, a: Value<F>, b: Value<F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+    pub fn assign_first_row<F: Field>(&self, layouter: impl Layouter<F>, a: Value<F>, b: Value<F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let (_, cur_b, cur_c) = self.assign_first_row_cells(layouter, a, b)?;
+        Ok((cur_b, cur_c))
+    }
+
+    fn assign_first_row_cells<F: Field>(&self, mut layouter: impl Layouter<F>, a: Value<F>, b: Value<F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>, AssignedCell<F, F>), Error> {
         layouter.assign_region(|| "填写第一行", |mut region| {
             self.config.selector.enable(&mut region, 0)?;
-            region.assign_advice(|| "加载a", self.config.a,  0, || a).expect("加载a失败");
-            let cur_b = region.assign_advice(|| "加载b", self.config.b,  0, || b).expect("加载b失败");
-            let cur_c = region.assign_advice(|| "计算当前c", self.config.c,  0, || a+b).expect("填写c失败");
-            Ok((cur_b, cur_c))
+            let cur_a = region.assign_advice(|| "加载a", self.config.a,  0, || a)?;
+            let cur_b = region.assign_advice(|| "加载b", self.config.b,  0, || b)?;
+            let cur_c = region.assign_advice(|| "计算当前c", self.config.c,  0, || a+b)?;
+            Ok((cur_a, cur_b, cur_c))
         })
     }
 
-    fn assign_next_row<F: Field>(&self, mut layouter: impl Layouter<F>, pre_b: &AssignedCell<F,F>, pre_c: &AssignedCell<F, F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+    pub fn assign_next_row<F: Field>(&self, mut layouter: impl Layouter<F>, pre_b: &AssignedCell<F,F>, pre_c: &AssignedCell<F, F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
         layouter.assign_region(|| "填写下一行", |mut region| {
             self.config.selector.enable(&mut region, 0)?;
-            let cur_a = pre_b.copy_advice(|| "拷贝上一行b到当前a", &mut region, self.config.a, 0).expect("拷贝到a失败");
-            let cur_b = pre_c.copy_advice(|| "拷贝上一行c到当前b", &mut region, self.config.b, 0).expect("拷贝到b失败");
-            let value_c = cur_a.value_field().evaluate() + cur_b.value_field().evaluate();
-            let cur_c = region.assign_advice(|| "计算当前c", self.config.c, 0, || value_c).expect("填写c失败");
+            let cur_a = pre_b.copy_advice(|| "拷贝上一行b到当前a", &mut region, self.config.a, 0)?;
+            let cur_b = pre_c.copy_advice(|| "拷贝上一行c到当前b", &mut region, self.config.b, 0)?;
+            // 用Value组合子计算, 生成密钥时值未知也走同一条路径
+            let value_c = cur_a.value().zip(cur_b.value()).map(|(a, b)| *a + *b);
+            let cur_c = region.assign_advice(|| "计算当前c", self.config.c, 0, || value_c)?;
             Ok((cur_b, cur_c))
         })
     }
 
-    fn expose_public<F:Field>( &self,  mut layouter: impl Layouter<F>, cell: &AssignedCell<F,F>, row: usize ) -> Result<(), Error> {
+    /// 填写数列的第1..=n项(n >= 3), 返回每一项对应的单元格, 供其他芯片继续使用
+    pub fn assign_sequence<F: Field>(&self, mut layouter: impl Layouter<F>, a: Value<F>, b: Value<F>, n: usize) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let (cur_a, mut b, mut c) = self.assign_first_row_cells(layouter.namespace(||"填写第一行"), a, b)?;
+        let mut terms = Vec::with_capacity(n.max(3));
+        terms.extend([cur_a, b.clone(), c.clone()]);
+        // 循环填写下一行
+        for _i in 3..n {
+            let (next_b, next_c) = self.assign_next_row(layouter.namespace(||"填写下一行"), &b, &c)?;
+            terms.push(next_c.clone());
+            b = next_b;
+            c = next_c;
+        }
+        Ok(terms)
+    }
+
+    /// 与[`assign_sequence`](FibChip::assign_sequence)填写相同的行, 但只返回第n项, 不保留中间各项的单元格
+    ///
+    /// 只需要公开最后一项时用它, n很大时省去每行克隆单元格和数组扩容
+    pub fn assign_last<F: Field>(&self, mut layouter: impl Layouter<F>, a: Value<F>, b: Value<F>, n: usize) -> Result<AssignedCell<F, F>, Error> {
+        let (mut b, mut c) = self.assign_first_row(layouter.namespace(||"填写第一行"), a, b)?;
+        for _i in 3..n {
+            (b, c) = self.assign_next_row(layouter.namespace(||"填写下一行"), &b, &c)?;
+        }
+        Ok(c)
+    }
+
+    pub fn expose_public<F:Field>( &self,  mut layouter: impl Layouter<F>, cell: &AssignedCell<F,F>, row: usize ) -> Result<(), Error> {
         layouter.constrain_instance(cell.cell(), self.config.target, row)
     }
+
+    /// 通过拷贝约束要求两个单元格相等
+    pub fn assert_equal<F: Field>(&self, mut layouter: impl Layouter<F>, cell_a: &AssignedCell<F, F>, cell_b: &AssignedCell<F, F>) -> Result<(), Error> {
+        layouter.assign_region(|| "约束相等", |mut region| {
+            region.constrain_equal(cell_a.cell(), cell_b.cell())
+        })
+    }
+
+    /// 要求单元格等于常量, 常量放在配置的fixed列中
+    pub fn assert_equal_const<F: Field>(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, constant: F) -> Result<(), Error> {
+        layouter.assign_region(|| "约束等于常量", |mut region| {
+            region.constrain_constant(cell.cell(), constant)
+        })
+    }
+
+    /// 用 a + b = c 门计算两个已有单元格的和, 可以当作累加和使用
+    pub fn add<F: Field>(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "相加", |mut region| {
+            self.config.selector.enable(&mut region, 0)?;
+            let a = a.copy_advice(|| "拷贝a", &mut region, self.config.a, 0)?;
+            let b = b.copy_advice(|| "拷贝b", &mut region, self.config.b, 0)?;
+            region.assign_advice(|| "a + b", self.config.c, 0, || a.value().copied() + b.value().copied())
+        })
+    }
+}
+
+
+/// 斐波那契命题(公开): 数列第n项等于target
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FibStatement<F: Field> {
+    pub n: usize,
+    pub target: F,
 }
 
+/// 命题不合法的原因
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StatementError {
+    /// n小于3, 数列至少要有一行加法
+    TooShort(usize),
+}
 
-#[derive(Default)]
-struct FibCircuit<F: Field> {
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatementError::TooShort(n) => write!(f, "n至少为3, 实际为{}", n),
+        }
+    }
+}
+
+impl std::error::Error for StatementError {}
+
+impl<F: Field> FibStatement<F> {
+    pub fn new(n: usize, target: F) -> Result<Self, StatementError> {
+        if n < 3 {
+            return Err(StatementError::TooShort(n));
+        }
+        Ok(Self { n, target })
+    }
+
+    /// 由见证计算第n项得到的命题
+    pub fn from_witness(n: usize, witness: &FibWitness<F>) -> Result<Self, StatementError> {
+        Self::new(n, witness.nth(n))
+    }
+
+    /// 按[`FibCircuit::instance_layout`]排好的公开输入
+    pub fn public_inputs(&self) -> Vec<F> {
+        let mut layout = FibCircuit::<F>::instance_layout();
+        layout.set("target", self.target).expect("缺少target实例行");
+        layout.public_inputs().expect("公开输入不完整")
+    }
+}
+
+/// 斐波那契见证(私有): 数列的第1、2项
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FibWitness<F: Field> {
+    pub a: F,
+    pub b: F,
+}
+
+impl<F: Field> FibWitness<F> {
+    pub fn new(a: F, b: F) -> Self {
+        Self { a, b }
+    }
+
+    /// 电路外计算的第n项(n >= 1)
+    pub fn nth(&self, n: usize) -> F {
+        match n {
+            0 => panic!("n至少为1"),
+            1 => self.a,
+            _ => {
+                let (mut a, mut b) = (self.a, self.b);
+                for _i in 3..=n {
+                    let c = a + b;
+                    a = b;
+                    b = c;
+                }
+                b
+            }
+        }
+    }
+
+    /// 见证是否满足命题
+    pub fn satisfies(&self, statement: &FibStatement<F>) -> bool {
+        self.nth(statement.n) == statement.target
+    }
+}
+
+impl ZeroizeWitness for FibWitness<Fp> {
+    fn zeroize_witness(&mut self) {
+        zeroize_field(&mut self.a);
+        zeroize_field(&mut self.b);
+    }
+}
+
+/// 证明斐波那契数列的第n项, 前两项a、b为私有输入
+pub struct FibCircuit<F: Field> {
     a: Value<F>, // 初始a=1
     b: Value<F>, // 初始b=1
+    n: usize, // 公开第n项
+}
+
+impl<F: Field> FibCircuit<F> {
+    /// 用见证证明命题, 电路只用到命题的n, target作为公开输入在证明和验证时传入
+    ///
+    /// ```
+    /// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+    /// use halo2_proofs::dev::MockProver;
+    /// use halo2_proofs::pasta::Fp;
+    ///
+    /// let witness = FibWitness::new(Fp::one(), Fp::one());
+    /// let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+    /// assert!(witness.satisfies(&statement));
+    /// let circuit = FibCircuit::new(&statement, &witness);
+    /// let prover = MockProver::run(4, &circuit, vec![statement.public_inputs()]).unwrap();
+    /// prover.assert_satisfied();
+    /// ```
+    pub fn new(statement: &FibStatement<F>, witness: &FibWitness<F>) -> Self {
+        Self { a: Value::known(witness.a), b: Value::known(witness.b), n: statement.n }
+    }
+
+    /// 实例列布局: 只有第n项target一行
+    pub fn instance_layout() -> InstanceAllocator<F> {
+        let mut layout = InstanceAllocator::new();
+        layout.alloc("target");
+        layout
+    }
+
+    /// 在启动证明之前, 用电路外重新计算的数列检查公开输入, 指出哪一行不一致
+    ///
+    /// ```
+    /// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+    /// use halo2_proofs::pasta::Fp;
+    ///
+    /// let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+    /// let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+    /// assert!(circuit.check_witness(&[Fp::from(55)]).is_ok());
+    /// assert!(circuit.check_witness(&[Fp::from(56)]).is_err());
+    /// ```
+    pub fn check_witness(&self, public_inputs: &[F]) -> Result<(), WitnessError> {
+        let expected = self.expected_public_inputs().ok_or(WitnessError::Unknown)?;
+        if public_inputs.len() != expected.len() {
+            return Err(WitnessError::LengthMismatch { expected: expected.len(), got: public_inputs.len() });
+        }
+        let layout = Self::instance_layout();
+        for slot in layout.slots() {
+            if public_inputs[slot.row] != expected[slot.row] {
+                return Err(WitnessError::Mismatch {
+                    row: slot.row,
+                    label: slot.label.clone(),
+                    expected: format!("{:?}", expected[slot.row]),
+                    got: format!("{:?}", public_inputs[slot.row]),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// 由见证在电路外算出的公开输入, 见证未知时返回None
+    pub fn expected_public_inputs(&self) -> Option<Vec<F>> {
+        let (mut a, mut b) = (None, None);
+        self.a.map(|v| a = Some(v));
+        self.b.map(|v| b = Some(v));
+        let (a, b) = a.zip(b)?;
+        let statement = FibStatement::from_witness(self.n, &FibWitness::new(a, b)).expect("n至少为3");
+        Some(statement.public_inputs())
+    }
+}
+
+impl FibCircuit<Fp> {
+    /// 证明第n项所需的最小k: 数列占n-2行, 盲化行另算
+    pub fn min_k(n: usize) -> u32 {
+        crate::capacity::min_k::<Self>(n.saturating_sub(2))
+    }
+}
+
+/// 见证与公开输入不一致的原因
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WitnessError {
+    /// 见证未知(例如`without_witnesses()`得到的电路)
+    Unknown,
+    /// 公开输入行数不对
+    LengthMismatch { expected: usize, got: usize },
+    /// 某一实例行的值与重新计算的结果不同
+    Mismatch { row: usize, label: String, expected: String, got: String },
+}
+
+impl fmt::Display for WitnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WitnessError::Unknown => write!(f, "见证未知"),
+            WitnessError::LengthMismatch { expected, got } => write!(f, "公开输入应有{}行, 实际{}行", expected, got),
+            WitnessError::Mismatch { row, label, expected, got } => write!(f, "实例第{}行({})应为{}, 实际为{}", row, label, expected, got),
+        }
+    }
+}
+
+impl std::error::Error for WitnessError {}
+
+impl ZeroizeWitness for FibCircuit<Fp> {
+    fn zeroize_witness(&mut self) {
+        zeroize_value(&mut self.a);
+        zeroize_value(&mut self.b);
+    }
 }
 
 impl<F: Field> Circuit<F> for FibCircuit<F> {
     type Config = FibConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
-    fn without_witnesses(&self) -> Self {Self::default()}
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown(), n: self.n }
+    }
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {FibChip::configure(meta) }
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        FibChip::configure(meta, &shared)
+    }
 
     fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
-        let fib = FibChip { config };
-        // 初始化第一行
-        let (mut b, mut c) = fib.assign_first_row(layouter.namespace(||"填写第一行"), self.a, self.b).expect("填写第一行失败");
-        // 循环填写下一行
-        for _i in 3..10 {
-            let (next_b, next_c) = fib.assign_next_row(layouter.namespace(||"填写下一行"), &b, &c).expect("填写下一行失败");
-            b = next_b;
-            c = next_c;
-        }
+        let fib = FibChip::construct(config);
+        let last = fib.assign_last(layouter.namespace(||"填写数列"), self.a, self.b, self.n)?;
         // 暴露结果
-        fib.expose_public(layouter, &c, 0)?;
+        let target_row = Self::instance_layout().row("target").expect("缺少target实例行");
+        fib.expose_public(layouter, &last, target_row)?;
         Ok(())
     }
 }
 
+/// 步数在编译期确定的斐波那契电路, 证明第N项(N >= 3), 布局与[`FibCircuit`]相同
+pub struct ConstFibCircuit<F: Field, const N: usize> {
+    a: Value<F>,
+    b: Value<F>,
+}
+
+impl<F: Field, const N: usize> ConstFibCircuit<F, N> {
+    pub fn new(a: F, b: F) -> Self {
+        assert!(N >= 3, "N至少为3");
+        Self { a: Value::known(a), b: Value::known(b) }
+    }
+
+    /// 电路外计算的完整数列, 第i个元素为第i+1项
+    pub fn trace(a: F, b: F) -> [F; N] {
+        let mut trace = [F::ZERO; N];
+        trace[0] = a;
+        trace[1] = b;
+        for i in 2..N {
+            trace[i] = trace[i - 1] + trace[i - 2];
+        }
+        trace
+    }
+}
+
+impl<F: Field, const N: usize> Circuit<F> for ConstFibCircuit<F, N> {
+    type Config = FibConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        FibChip::configure(meta, &shared)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let fib = FibChip::construct(config);
+        let terms: [AssignedCell<F, F>; N] = match fib.assign_sequence(layouter.namespace(||"填写数列"), self.a, self.b, N)?.try_into() {
+            Ok(terms) => terms,
+            Err(_) => panic!("数列长度不是N"),
+        };
+        // 暴露结果
+        fib.expose_public(layouter, &terms[N - 1], 0)
+    }
+}
+
 #[test]
 fn test_fib() {
-    let circuit = FibCircuit {a: Value::known(Fp::one()),b: Value::known(Fp::one())};
-    let target = Fp::from(55);
-    let public_input = vec![target];
-    let prover = MockProver::run(4, &circuit, vec![public_input]).unwrap();
+    let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+    let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+    let prover = MockProver::run(4, &circuit, vec![statement.public_inputs()]).unwrap();
     prover.assert_satisfied();
 }
 
-#[cfg(feature = "dev")]
 #[test]
-fn print_fib() {
-    use plotters::prelude::*;
-    use halo2_proofs::pasta::Fp;
-
-    let root = BitMapBackend::new("fib-layout.png", (1024, 3096)).into_drawing_area();
-    root.fill(&WHITE).unwrap();
-    let root = root.titled("Fib Layout", ("sans-serif", 60)).unwrap();
-
-    let circuit = FibCircuit {
-        a: Value::known(Fp::one()),
-        b: Value::known(Fp::one()),
-    };
-    halo2_proofs::dev::CircuitLayout::default()
-        .render(4, &circuit, &root)
-        .unwrap();
-
-    let dot_string = halo2_proofs::dev::circuit_dot_graph(&circuit);
-    print!("{}", dot_string);
-}
\ No newline at end of file
+fn test_statement() {
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    assert_eq!(FibStatement::new(2, Fp::one()), Err(StatementError::TooShort(2)));
+    let statement = FibStatement::from_witness(10, &witness).unwrap();
+    assert_eq!(statement.target, Fp::from(55));
+    assert_eq!(statement.public_inputs(), vec![Fp::from(55)]);
+    assert!(witness.satisfies(&statement));
+    assert!(!FibWitness::new(Fp::one(), Fp::from(2)).satisfies(&statement));
+}
+
+#[test]
+fn test_check_witness() {
+    let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+    let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+    assert_eq!(circuit.check_witness(&[Fp::from(55)]), Ok(()));
+    assert_eq!(circuit.check_witness(&[]), Err(WitnessError::LengthMismatch { expected: 1, got: 0 }));
+    assert!(matches!(circuit.check_witness(&[Fp::from(54)]), Err(WitnessError::Mismatch { row: 0, .. })));
+    assert_eq!(circuit.without_witnesses().check_witness(&[Fp::from(55)]), Err(WitnessError::Unknown));
+}
+
+#[test]
+fn test_const_fib() {
+    let trace = ConstFibCircuit::<Fp, 10>::trace(Fp::one(), Fp::one());
+    assert_eq!(trace[9], Fp::from(55));
+
+    let circuit = ConstFibCircuit::<Fp, 10>::new(Fp::one(), Fp::one());
+    let prover = MockProver::run(4, &circuit, vec![vec![trace[9]]]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_assert_equal() {
+    // 1, 1, 2, 3: 分两段计算后用assert_equal连接, 再约束结果等于常量3
+    struct AssertCircuit {
+        expected: Fp,
+    }
+
+    impl Circuit<Fp> for AssertCircuit {
+        type Config = FibConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { expected: self.expected } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            FibChip::configure(meta, &shared)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let fib = FibChip::construct(config);
+            let one = Value::known(Fp::one());
+            let (b, c) = fib.assign_first_row(layouter.namespace(|| "第一段"), one, one)?;
+            let (_, other_c) = fib.assign_first_row(layouter.namespace(|| "第二段"), one, one)?;
+            fib.assert_equal(layouter.namespace(|| "两段结果相等"), &c, &other_c)?;
+            let (_, c) = fib.assign_next_row(layouter.namespace(|| "下一行"), &b, &c)?;
+            fib.assert_equal_const(layouter.namespace(|| "结果等于常量"), &c, self.expected)
+        }
+    }
+
+    let prover = MockProver::run(4, &AssertCircuit { expected: Fp::from(3) }, vec![vec![]]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(4, &AssertCircuit { expected: Fp::from(4) }, vec![vec![]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn test_assign_sequence() {
+    // 把数列每一项都暴露出来, 检查assign_sequence返回的单元格
+    struct AllTerms;
+
+    impl Circuit<Fp> for AllTerms {
+        type Config = FibConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { AllTerms }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            FibChip::configure(meta, &shared)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let fib = FibChip::construct(config);
+            let one = Value::known(Fp::one());
+            let terms = fib.assign_sequence(layouter.namespace(|| "填写数列"), one, one, 8)?;
+            assert_eq!(terms.len(), 8);
+            for (row, term) in terms.iter().enumerate() {
+                fib.expose_public(layouter.namespace(|| "暴露每一项"), term, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    let sequence: Vec<Fp> = [1u64, 1, 2, 3, 5, 8, 13, 21].iter().map(|&x| Fp::from(x)).collect();
+    let prover = MockProver::run(4, &AllTerms, vec![sequence]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_fib_prove_with_retry() {
+    use crate::prover::{keygen, keygen_with_retry, prove_with_retry, prove_with_retry_zeroizing, setup, verify};
+
+    let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+    let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+    let public_input = vec![Fp::from(55)];
+    // 行数不够时返回错误而不是panic, 重试才能接着进行
+    assert!(matches!(keygen(&setup(3), &circuit), Err(Error::NotEnoughRowsAvailable { .. })));
+    // k=3时行数不够, 应自动扩大到k=4
+    let res = prove_with_retry(3, 6, &circuit, &public_input).expect("生成证明失败");
+    assert_eq!(res.k, 4);
+    verify(&res.params, res.pk.get_vk(), &public_input, &res.proof).into_result().expect("验证证明失败");
+    // 起点超过上限时不生成参数, 直接返回错误; 上限内放不下时也不会越过上限
+    assert!(matches!(keygen_with_retry(40, 20, &circuit), Err(Error::NotEnoughRowsAvailable { current_k: 20 })));
+    assert!(matches!(keygen_with_retry(2, 3, &circuit), Err(Error::NotEnoughRowsAvailable { current_k: 3 })));
+    // 抹掉见证的版本同样会重试
+    let res = prove_with_retry_zeroizing(3, 6, circuit, &public_input).expect("生成证明失败");
+    assert_eq!(res.k, 4);
+    verify(&res.params, res.pk.get_vk(), &public_input, &res.proof).into_result().expect("验证证明失败");
+}
@@ -0,0 +1,147 @@
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::*;
+
+use crate::fib::{FibChip, FibConfig};
+use crate::gadgets::poseidon::{hash2, PoseidonChip, PoseidonConfig};
+use crate::prover::{zeroize_value, ZeroizeWitness};
+use crate::shared::SharedColumns;
+
+/// 斐波那契芯片和Poseidon芯片组合后的配置
+#[derive(Clone, Debug)]
+pub struct FibMerkleConfig {
+    pub fib: FibConfig,
+    pub poseidon: PoseidonConfig,
+    pub shared: SharedColumns,
+}
+
+/// 叶子补零到2的幂后逐层两两哈希, 第0层为叶子, 最后一层只有根
+pub fn merkle_layers(leaves: &[Fp]) -> Vec<Vec<Fp>> {
+    let mut layer = leaves.to_vec();
+    layer.resize(leaves.len().next_power_of_two().max(2), Fp::zero());
+    let mut layers = vec![layer];
+    while layers[layers.len() - 1].len() > 1 {
+        let next = layers[layers.len() - 1].chunks(2).map(|pair| hash2(pair[0], pair[1])).collect();
+        layers.push(next);
+    }
+    layers
+}
+
+pub fn merkle_root(leaves: &[Fp]) -> Fp {
+    let layers = merkle_layers(leaves);
+    layers[layers.len() - 1][0]
+}
+
+/// 某一项的包含证明: 从叶子到根每层的兄弟节点
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub index: usize,
+    pub siblings: Vec<Fp>,
+}
+
+impl InclusionProof {
+    pub fn new(leaves: &[Fp], index: usize) -> Self {
+        let layers = merkle_layers(leaves);
+        let siblings = layers[..layers.len() - 1].iter().enumerate().map(|(depth, layer)| layer[(index >> depth) ^ 1]).collect();
+        Self { index, siblings }
+    }
+
+    /// 检查leaf是第index个叶子且树根为root
+    pub fn verify(&self, root: Fp, leaf: Fp) -> bool {
+        let mut node = leaf;
+        for (depth, sibling) in self.siblings.iter().enumerate() {
+            node = if (self.index >> depth) & 1 == 0 { hash2(node, *sibling) } else { hash2(*sibling, node) };
+        }
+        node == root
+    }
+}
+
+/// 在电路内对数列第1..=n项建Poseidon默克尔树, 只公开树根
+///
+/// 验证者之后可以用[`InclusionProof`]抽查任意一项, 不需要知道其余各项
+pub struct FibMerkleCircuit {
+    a: Value<Fp>,
+    b: Value<Fp>,
+    n: usize,
+}
+
+impl FibMerkleCircuit {
+    /// 以a、b作为第1、2项, 对第1..=n项(n >= 3)建树
+    pub fn new(a: Fp, b: Fp, n: usize) -> Self {
+        assert!(n >= 3, "n至少为3");
+        Self { a: Value::known(a), b: Value::known(b), n }
+    }
+}
+
+/// 一次Poseidon哈希至少占用的行数: 8个全轮和56个部分轮(两轮一行)共36行, 加上初始状态和吸收各一行
+const HASH_ROWS: usize = 38;
+
+impl FibMerkleCircuit {
+    /// 生成密钥时k的起点: 数列占n-2行, 补零一行, 补到2的幂后共有叶子数减一次哈希
+    pub fn min_k(n: usize) -> u32 {
+        crate::capacity::min_k::<Self>(n.saturating_sub(2) + 1 + (n.next_power_of_two() - 1) * HASH_ROWS)
+    }
+}
+
+impl ZeroizeWitness for FibMerkleCircuit {
+    fn zeroize_witness(&mut self) {
+        zeroize_value(&mut self.a);
+        zeroize_value(&mut self.b);
+    }
+}
+
+impl Circuit<Fp> for FibMerkleCircuit {
+    type Config = FibMerkleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown(), n: self.n }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let fib = FibChip::configure(meta, &shared);
+        let poseidon = PoseidonChip::configure(meta, &shared);
+        FibMerkleConfig { fib, poseidon, shared }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fib = FibChip::construct(config.fib);
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+        let mut layer = fib.assign_sequence(layouter.namespace(|| "填写数列"), self.a, self.b, self.n)?;
+
+        // 补零到2的幂, 零由常量约束固定
+        let zero = layouter.assign_region(|| "补零", |mut region| {
+            region.assign_advice_from_constant(|| "零", config.shared.advice[0], 0, Fp::zero())
+        })?;
+        layer.resize(self.n.next_power_of_two(), zero);
+
+        while layer.len() > 1 {
+            layer = layer.chunks(2)
+                .map(|pair| poseidon.hash2(layouter.namespace(|| "默克尔节点"), &pair[0], &pair[1]))
+                .collect::<Result<Vec<_>, Error>>()?;
+        }
+        fib.expose_public(layouter.namespace(|| "暴露树根"), &layer[0], 0)
+    }
+}
+
+#[test]
+fn test_fib_merkle() {
+    use halo2_proofs::dev::MockProver;
+    use crate::recurrence::recurrence_terms;
+
+    let terms = recurrence_terms(1, 1, Fp::one(), Fp::one(), 5);
+    let root = merkle_root(&terms);
+
+    let circuit = FibMerkleCircuit::new(Fp::one(), Fp::one(), 5);
+    let prover = MockProver::run(10, &circuit, vec![vec![root]]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(10, &circuit, vec![vec![root + Fp::one()]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 抽查第4项F(4) = 3
+    let proof = InclusionProof::new(&terms, 3);
+    assert_eq!(proof.siblings.len(), 3);
+    assert!(proof.verify(root, Fp::from(3)));
+    assert!(!proof.verify(root, Fp::from(4)));
+}
@@ -0,0 +1,79 @@
+use ff::PrimeField;
+use halo2_proofs::circuit::{Value, Layouter, SimpleFloorPlanner};
+use halo2_proofs::plonk::*;
+
+use crate::fib::{FibChip, FibConfig};
+use crate::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::shared::SharedColumns;
+
+/// 斐波那契芯片和范围检查芯片组合后的配置
+#[derive(Clone, Debug, Copy)]
+pub struct FibRangeConfig {
+    pub fib: FibConfig,
+    pub range: RangeCheckConfig,
+}
+
+/// 证明数列第n项F(n) < 2^64, 并公开F(n)
+///
+/// F(n)所在单元格通过拷贝约束传给范围检查芯片, 演示跨芯片组合
+pub struct FibRangeCircuit<F: PrimeField> {
+    a: Value<F>,
+    b: Value<F>,
+    n: usize,
+}
+
+impl<F: PrimeField> FibRangeCircuit<F> {
+    /// 以a、b作为第1、2项, 证明第n项(n >= 3)小于2^64
+    pub fn new(a: F, b: F, n: usize) -> Self {
+        assert!(n >= 3, "n至少为3");
+        Self { a: Value::known(a), b: Value::known(b), n }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for FibRangeCircuit<F> {
+    type Config = FibRangeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown(), n: self.n }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        // 两个芯片共用同一组advice列
+        let shared = SharedColumns::configure(meta);
+        FibRangeConfig {
+            fib: FibChip::configure(meta, &shared),
+            range: RangeCheckChip::configure(meta, &shared),
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let fib = FibChip::construct(config.fib);
+        let range = RangeCheckChip::construct(config.range);
+        range.load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let result = fib.assign_last(layouter.namespace(|| "填写数列"), self.a, self.b, self.n)?;
+        // 8个8位limb即64位
+        range.copy_check(layouter.namespace(|| "检查结果小于2^64"), &result, 8)?;
+        fib.expose_public(layouter.namespace(|| "暴露结果"), &result, 0)
+    }
+}
+
+#[test]
+fn test_fib_range() {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+    use crate::recurrence::recurrence_terms;
+
+    // F(93) < 2^64 <= F(94)
+    let terms = recurrence_terms(1, 1, Fp::one(), Fp::one(), 94);
+    assert_eq!(terms[92], Fp::from(12200160415121876738));
+
+    let circuit = FibRangeCircuit::new(Fp::one(), Fp::one(), 93);
+    let prover = MockProver::run(9, &circuit, vec![vec![terms[92]]]).unwrap();
+    prover.assert_satisfied();
+
+    let circuit = FibRangeCircuit::new(Fp::one(), Fp::one(), 94);
+    let prover = MockProver::run(9, &circuit, vec![vec![terms[93]]]).unwrap();
+    assert!(prover.verify().is_err());
+}
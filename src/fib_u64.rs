@@ -0,0 +1,179 @@
+//! 按u64语义计算的斐波那契数列: 每一项都模2^64回绕, 与普通程序里`wrapping_add`的结果一致, 而不是域上的加法
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::shared::SharedColumns;
+
+/// 64位即8个8位limb
+const U64_LIMBS: usize = 8;
+
+/// 电路外按u64回绕计算第n项, 第1、2项为a、b
+pub fn fib_u64(a: u64, b: u64, n: usize) -> u64 {
+    let (mut a, mut b) = (a, b);
+    for _ in 2..n {
+        (a, b) = (b, a.wrapping_add(b));
+    }
+    if n == 1 { a } else { b }
+}
+
+fn two_pow_64<F: PrimeField>() -> F {
+    F::from(u64::MAX) + F::ONE
+}
+
+/// 每行 a + b = c + carry * 2^64, carry为0或1, c经范围检查小于2^64
+#[derive(Clone, Debug, Copy)]
+pub struct FibU64Config {
+    pub selector: Selector,
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub carry: Column<Advice>,
+    pub range: RangeCheckConfig,
+    pub instance: Column<Instance>,
+}
+
+/// 模2^64的斐波那契芯片, 每一项都先经过范围检查再参与下一次加法
+pub struct FibU64Chip<F: PrimeField> {
+    config: FibU64Config,
+    range: RangeCheckChip<F>,
+}
+
+impl<F: PrimeField> FibU64Chip<F> {
+    pub fn construct(config: FibU64Config) -> Self {
+        Self { config, range: RangeCheckChip::construct(config.range) }
+    }
+
+    /// a、b、c使用共享的三个advice列, 另外分配一列放进位
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> FibU64Config {
+        let selector = meta.selector();
+        let [a, b, c] = shared.advice;
+        let carry = meta.advice_column();
+        let range = RangeCheckChip::configure(meta, shared);
+
+        meta.create_gate("fib_u64_add", |meta| {
+            let selector = meta.query_selector(selector);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let carry = meta.query_advice(carry, Rotation::cur());
+            vec![
+                ("a + b = c + carry * 2^64", selector.clone() * (a + b - c - carry.clone() * Expression::Constant(two_pow_64::<F>()))),
+                ("carry为0或1", selector * carry.clone() * (Expression::Constant(F::ONE) - carry)),
+            ]
+        });
+        FibU64Config { selector, a, b, c, carry, range, instance: shared.instance }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_table(layouter)
+    }
+
+    /// 填写一个u64并做范围检查
+    pub fn witness_u64(&self, layouter: impl Layouter<F>, value: Value<u64>) -> Result<AssignedCell<F, F>, Error> {
+        self.range.witness_check(layouter, value.map(F::from), U64_LIMBS)
+    }
+
+    /// 计算 c = (a + b) mod 2^64, a、b须已经过范围检查
+    pub fn add(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, sum: Value<u64>, carry: Value<bool>) -> Result<AssignedCell<F, F>, Error> {
+        let c = layouter.assign_region(|| "回绕相加", |mut region| {
+            self.config.selector.enable(&mut region, 0)?;
+            a.copy_advice(|| "拷贝a", &mut region, self.config.a, 0)?;
+            b.copy_advice(|| "拷贝b", &mut region, self.config.b, 0)?;
+            region.assign_advice(|| "填写进位", self.config.carry, 0, || carry.map(|carry| F::from(carry as u64)))?;
+            region.assign_advice(|| "填写c", self.config.c, 0, || sum.map(F::from))
+        })?;
+        self.range.copy_check(layouter.namespace(|| "检查c小于2^64"), &c, U64_LIMBS)?;
+        Ok(c)
+    }
+
+    /// 填写数列的第1..=n项(n >= 3), 返回每一项的单元格
+    pub fn assign_sequence(&self, mut layouter: impl Layouter<F>, a: Value<u64>, b: Value<u64>, n: usize) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let mut terms = vec![
+            self.witness_u64(layouter.namespace(|| "第1项"), a)?,
+            self.witness_u64(layouter.namespace(|| "第2项"), b)?,
+        ];
+        let (mut prev, mut cur) = (a, b);
+        for _ in 2..n {
+            let sum = prev.zip(cur).map(|(a, b)| a.overflowing_add(b));
+            let cell = self.add(layouter.namespace(|| "下一项"), &terms[terms.len() - 2], &terms[terms.len() - 1], sum.map(|(s, _)| s), sum.map(|(_, carry)| carry))?;
+            terms.push(cell);
+            prev = cur;
+            cur = sum.map(|(s, _)| s);
+        }
+        Ok(terms)
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// 证明以a、b开头、按u64回绕计算的数列第n项, 公开该项
+///
+/// 每项占1行加法和9行范围检查, 另有256行查找表, n = 100时k = 11
+pub struct FibU64Circuit {
+    a: Value<u64>,
+    b: Value<u64>,
+    n: usize,
+}
+
+impl FibU64Circuit {
+    pub fn new(a: u64, b: u64, n: usize) -> Self {
+        assert!(n >= 3, "n至少为3");
+        Self { a: Value::known(a), b: Value::known(b), n }
+    }
+
+    /// 实例列内容
+    pub fn public_inputs<F: PrimeField>(a: u64, b: u64, n: usize) -> Vec<F> {
+        vec![F::from(fib_u64(a, b, n))]
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for FibU64Circuit {
+    type Config = FibU64Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown(), n: self.n }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        FibU64Chip::configure(meta, &shared)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FibU64Chip::construct(config);
+        chip.load_table(layouter.namespace(|| "加载查找表"))?;
+        let terms = chip.assign_sequence(layouter.namespace(|| "填写数列"), self.a, self.b, self.n)?;
+        chip.expose_public(layouter.namespace(|| "暴露结果"), &terms[self.n - 1], 0)
+    }
+}
+
+#[test]
+fn test_fib_u64() {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+    use crate::recurrence::recurrence_terms;
+
+    assert_eq!(fib_u64(1, 1, 10), 55);
+    // F(94)超过2^64, 之后开始回绕, 与域上的结果不同
+    let field_terms = recurrence_terms(1, 1, Fp::one(), Fp::one(), 100);
+    assert_eq!(Fp::from(fib_u64(1, 1, 93)), field_terms[92]);
+    assert_ne!(Fp::from(fib_u64(1, 1, 100)), field_terms[99]);
+
+    let circuit = FibU64Circuit::new(1, 1, 100);
+    let prover = MockProver::run(11, &circuit, vec![FibU64Circuit::public_inputs(1, 1, 100)]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(11, &circuit, vec![vec![field_terms[99]]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 起始值本身也要是u64
+    let circuit = FibU64Circuit::new(u64::MAX, u64::MAX, 3);
+    let prover = MockProver::run(11, &circuit, vec![vec![Fp::from(u64::MAX - 1)]]).unwrap();
+    prover.assert_satisfied();
+}
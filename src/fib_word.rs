@@ -0,0 +1,196 @@
+use ff::PrimeField;
+use halo2_proofs::circuit::{Value, Layouter, AssignedCell, SimpleFloorPlanner};
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::plonk::*;
+
+use crate::instance::InstanceAllocator;
+use crate::shared::SharedColumns;
+
+/// 查找表中存放的斐波那契词前缀长度(F(12) = 144)
+pub const WORD_LEN: usize = 144;
+
+/// 斐波那契词查询的列配置: 每行一个(下标, 字符)对, 通过查找表约束
+#[derive(Clone, Debug, Copy)]
+pub struct FibWordConfig {
+    pub q_lookup: Selector,
+    pub index: Column<Advice>,
+    pub bit: Column<Advice>,
+    pub table_index: TableColumn,
+    pub table_bit: TableColumn,
+    pub instance: Column<Instance>,
+}
+
+/// 斐波那契词芯片: 固定表存放词的前缀, 每次查询约束 (下标, 字符) 在表中
+pub struct FibWordChip {
+    config: FibWordConfig
+}
+
+impl FibWordChip {
+    pub fn construct(config: FibWordConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> FibWordConfig {
+        let q_lookup = meta.complex_selector();
+        let [index, bit, _] = shared.advice;
+        let table_index = meta.lookup_table_column();
+        let table_bit = meta.lookup_table_column();
+        let instance = shared.instance;
+
+        // 未启用的行查到(0, 0), 恰好是词的第0位
+        meta.lookup(|meta| {
+            let q_lookup = meta.query_selector(q_lookup);
+            let index = meta.query_advice(index, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            vec![
+                (q_lookup.clone() * index, table_index),
+                (q_lookup * bit, table_bit),
+            ]
+        });
+        FibWordConfig { q_lookup, index, bit, table_index, table_bit, instance }
+    }
+
+    pub fn load_table<F: PrimeField>(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let word = fib_word(WORD_LEN);
+        layouter.assign_table(|| "斐波那契词表", |mut table| {
+            for (i, &bit) in word.iter().enumerate() {
+                table.assign_cell(|| "下标", self.config.table_index, i, || Value::known(F::from(i as u64)))?;
+                table.assign_cell(|| "字符", self.config.table_bit, i, || Value::known(F::from(bit as u64)))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// 从实例列的index_row行拷贝下标, 填写对应字符
+    pub fn assign_query<F: PrimeField>(&self, mut layouter: impl Layouter<F>, index_row: usize, bit: Value<F>) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "查询字符", |mut region| {
+            self.config.q_lookup.enable(&mut region, 0)?;
+            region.assign_advice_from_instance(|| "拷贝下标", self.config.instance, index_row, self.config.index, 0)?;
+            let bit = region.assign_advice(|| "填写字符", self.config.bit, 0, || bit)?;
+            Ok(bit)
+        })
+    }
+
+    pub fn expose_public<F: PrimeField>(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// 证明斐波那契词若干位置上的字符, 实例列布局见[`FibWordCircuit::instance_layout`]
+pub struct FibWordCircuit<F: PrimeField> {
+    bits: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> FibWordCircuit<F> {
+    /// 查询的下标需小于[`WORD_LEN`]
+    pub fn new(indices: &[usize]) -> Self {
+        assert!(indices.iter().all(|&i| i < WORD_LEN), "下标超出查找表范围");
+        let bits = indices.iter().map(|&i| Value::known(F::from(fib_word_char(i as u64) as u64))).collect();
+        Self { bits }
+    }
+
+    /// 实例列布局: 每个查询依次占 index[i]、char[i] 两行
+    pub fn instance_layout(count: usize) -> InstanceAllocator<F> {
+        let mut layout = InstanceAllocator::new();
+        for i in 0..count {
+            layout.alloc(&format!("index[{}]", i));
+            layout.alloc(&format!("char[{}]", i));
+        }
+        layout
+    }
+
+    /// 与电路对应的公开输入
+    pub fn public_inputs(indices: &[usize]) -> Vec<F> {
+        let mut layout = Self::instance_layout(indices.len());
+        for (i, &index) in indices.iter().enumerate() {
+            layout.set(&format!("index[{}]", i), F::from(index as u64)).expect("填写下标失败");
+            layout.set(&format!("char[{}]", i), F::from(fib_word_char(index as u64) as u64)).expect("填写字符失败");
+        }
+        layout.public_inputs().expect("公开输入不完整")
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for FibWordCircuit<F> {
+    type Config = FibWordConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { bits: vec![Value::unknown(); self.bits.len()] }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        FibWordChip::configure(meta, &shared)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FibWordChip::construct(config);
+        chip.load_table(layouter.namespace(|| "加载查找表"))?;
+        let layout = Self::instance_layout(self.bits.len());
+        for (i, &bit) in self.bits.iter().enumerate() {
+            let index_row = layout.row(&format!("index[{}]", i)).expect("缺少下标实例行");
+            let char_row = layout.row(&format!("char[{}]", i)).expect("缺少字符实例行");
+            let cell = chip.assign_query(layouter.namespace(|| "查询字符"), index_row, bit)?;
+            chip.expose_public(layouter.namespace(|| "暴露字符"), &cell, char_row)?;
+        }
+        Ok(())
+    }
+}
+
+/// 电路外生成斐波那契词 0100101001001... 的前len位
+pub fn fib_word(len: usize) -> Vec<u8> {
+    let (mut prev, mut cur) = (vec![0u8], vec![0u8, 1]);
+    while cur.len() < len {
+        let next = [cur.as_slice(), prev.as_slice()].concat();
+        prev = cur;
+        cur = next;
+    }
+    cur.truncate(len);
+    cur
+}
+
+/// 电路外计算斐波那契词的第k位: 等于k的齐肯多夫表示的最低位
+pub fn fib_word_char(k: u64) -> u8 {
+    let mut fibs = vec![1u64, 2];
+    while let Some(next) = fibs[fibs.len() - 1].checked_add(fibs[fibs.len() - 2]) {
+        if fibs[fibs.len() - 1] > k {
+            break;
+        }
+        fibs.push(next);
+    }
+    let (mut rest, mut last) = (k, 0);
+    for &f in fibs.iter().rev() {
+        if f <= rest {
+            rest -= f;
+            last = (f == 1) as u8;
+        }
+    }
+    last
+}
+
+#[test]
+fn test_fib_word_reference() {
+    let word = fib_word(WORD_LEN);
+    assert_eq!(&word[..13], &[0, 1, 0, 0, 1, 0, 1, 0, 0, 1, 0, 0, 1]);
+    for (k, &bit) in word.iter().enumerate() {
+        assert_eq!(fib_word_char(k as u64), bit, "第{}位不一致", k);
+    }
+}
+
+#[test]
+fn test_fib_word_circuit() {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    let indices = [0, 1, 4, 6, 100, 143];
+    let circuit = FibWordCircuit::<Fp>::new(&indices);
+    let public_inputs = FibWordCircuit::<Fp>::public_inputs(&indices);
+    let prover = MockProver::run(8, &circuit, vec![public_inputs.clone()]).unwrap();
+    prover.assert_satisfied();
+
+    // 改掉第4位的字符
+    let mut bad_inputs = public_inputs;
+    bad_inputs[5] = Fp::one() - bad_inputs[5];
+    let prover = MockProver::run(8, &circuit, vec![bad_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+}
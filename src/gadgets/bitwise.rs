@@ -0,0 +1,205 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::shared::SharedColumns;
+
+/// 查找表按4位分块, 三种运算共3 * 256行, 因此k至少为10
+pub const NIBBLE_BITS: usize = 4;
+
+/// 按位运算, 标签即查找表中op列的值
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOp {
+    Xor,
+    And,
+    Or,
+}
+
+impl BitOp {
+    pub const ALL: [BitOp; 3] = [BitOp::Xor, BitOp::And, BitOp::Or];
+
+    /// 异或的标签为0, 未启用的行查到(0, 0, 0, 0)正好是表中的一行
+    pub fn tag(self) -> u64 {
+        match self {
+            BitOp::Xor => 0,
+            BitOp::And => 1,
+            BitOp::Or => 2,
+        }
+    }
+
+    pub fn apply(self, x: u8, y: u8) -> u8 {
+        match self {
+            BitOp::Xor => x ^ y,
+            BitOp::And => x & y,
+            BitOp::Or => x | y,
+        }
+    }
+}
+
+/// 域元素的最低字节, pasta域元素的repr为小端字节序
+fn low_byte<F: PrimeField>(v: &F) -> u8 {
+    v.to_repr().as_ref()[0]
+}
+
+/// 按位运算的列配置
+///
+/// 每个字节运算占三行: 第0行为x、y、z三个字节, 第1、2行为它们的低4位和高4位, 两行分别查表(op, x, y, z)
+#[derive(Clone, Debug, Copy)]
+pub struct BitwiseConfig {
+    pub q_compose: Selector,
+    pub q_lookup: Selector,
+    pub x: Column<Advice>,
+    pub y: Column<Advice>,
+    pub z: Column<Advice>,
+    pub op: Column<Fixed>,
+    pub table: [TableColumn; 4],
+}
+
+/// 8位按位运算芯片, 同时保证x、y、z都是字节
+pub struct BitwiseChip<F: PrimeField> {
+    config: BitwiseConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> BitwiseChip<F> {
+    pub fn construct(config: BitwiseConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 使用共享的三个advice列作为x、y、z, 另外分配一个fixed列放运算标签
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> BitwiseConfig {
+        let q_compose = meta.selector();
+        let q_lookup = meta.complex_selector();
+        let [x, y, z] = shared.advice;
+        let op = meta.fixed_column();
+        let table = [(); 4].map(|_| meta.lookup_table_column());
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let op = meta.query_fixed(op, Rotation::cur());
+            let x = meta.query_advice(x, Rotation::cur());
+            let y = meta.query_advice(y, Rotation::cur());
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![
+                (q.clone() * op, table[0]),
+                (q.clone() * x, table[1]),
+                (q.clone() * y, table[2]),
+                (q * z, table[3]),
+            ]
+        });
+
+        meta.create_gate("bitwise_nibble", |meta| {
+            let q = meta.query_selector(q_compose);
+            let shift = Expression::Constant(F::from(1 << NIBBLE_BITS));
+            [("x = x_lo + 16 * x_hi", x), ("y = y_lo + 16 * y_hi", y), ("z = z_lo + 16 * z_hi", z)].map(|(name, column)| {
+                let byte = meta.query_advice(column, Rotation::cur());
+                let lo = meta.query_advice(column, Rotation::next());
+                let hi = meta.query_advice(column, Rotation(2));
+                (name, q.clone() * (byte - lo - hi * shift.clone()))
+            })
+        });
+        BitwiseConfig { q_compose, q_lookup, x, y, z, op, table }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(|| "按位运算表", |mut table| {
+            let mut row = 0;
+            for op in BitOp::ALL {
+                for x in 0..1u8 << NIBBLE_BITS {
+                    for y in 0..1u8 << NIBBLE_BITS {
+                        let values = [op.tag(), x as u64, y as u64, op.apply(x, y) as u64];
+                        for (column, value) in self.config.table.iter().zip(values) {
+                            table.assign_cell(|| "按位运算", *column, row, || Value::known(F::from(value)))?;
+                        }
+                        row += 1;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// 计算 z = x op y, x、y不是字节时约束不满足
+    pub fn op_byte(&self, mut layouter: impl Layouter<F>, op: BitOp, x: &AssignedCell<F, F>, y: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "按位运算", |mut region| {
+            self.config.q_compose.enable(&mut region, 0)?;
+            let x = x.copy_advice(|| "拷贝x", &mut region, self.config.x, 0)?;
+            let y = y.copy_advice(|| "拷贝y", &mut region, self.config.y, 0)?;
+            let bytes = x.value().zip(y.value()).map(|(x, y)| (low_byte(x), low_byte(y)));
+            let z_byte = bytes.map(|(x, y)| op.apply(x, y));
+            let z = region.assign_advice(|| "填写z", self.config.z, 0, || z_byte.map(|z| F::from(z as u64)))?;
+
+            for (offset, shift) in [(1, 0), (2, NIBBLE_BITS)] {
+                self.config.q_lookup.enable(&mut region, offset)?;
+                region.assign_fixed(|| "运算标签", self.config.op, offset, || Value::known(F::from(op.tag())))?;
+                let nibble = |byte: u8| F::from(((byte >> shift) & 0xf) as u64);
+                region.assign_advice(|| "x半字节", self.config.x, offset, || bytes.map(|(x, _)| nibble(x)))?;
+                region.assign_advice(|| "y半字节", self.config.y, offset, || bytes.map(|(_, y)| nibble(y)))?;
+                region.assign_advice(|| "z半字节", self.config.z, offset, || z_byte.map(nibble))?;
+            }
+            Ok(z)
+        })
+    }
+
+    /// 逐字节运算, xs和ys长度须相同
+    pub fn op_bytes(&self, mut layouter: impl Layouter<F>, op: BitOp, xs: &[AssignedCell<F, F>], ys: &[AssignedCell<F, F>]) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert_eq!(xs.len(), ys.len(), "字节数不一致");
+        xs.iter().zip(ys).map(|(x, y)| self.op_byte(layouter.namespace(|| "字节"), op, x, y)).collect()
+    }
+}
+
+#[test]
+fn test_bitwise() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    #[derive(Clone)]
+    struct BitwiseCircuit {
+        x: Value<Fp>,
+        y: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for BitwiseCircuit {
+        type Config = (BitwiseConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { x: Value::unknown(), y: Value::unknown() } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (BitwiseChip::configure(meta, &shared), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = BitwiseChip::construct(config);
+            chip.load_table(layouter.namespace(|| "加载查找表"))?;
+            let (x, y) = layouter.assign_region(|| "输入", |mut region| {
+                let x = region.assign_advice(|| "x", shared.advice[0], 0, || self.x)?;
+                let y = region.assign_advice(|| "y", shared.advice[1], 0, || self.y)?;
+                Ok((x, y))
+            })?;
+            for (row, op) in BitOp::ALL.into_iter().enumerate() {
+                let z = chip.op_byte(layouter.namespace(|| "运算"), op, &x, &y)?;
+                layouter.constrain_instance(z.cell(), shared.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    let run = |x: u64, y: u64, outputs: Vec<Fp>| {
+        let circuit = BitwiseCircuit { x: Value::known(Fp::from(x)), y: Value::known(Fp::from(y)) };
+        MockProver::run(10, &circuit, vec![outputs]).unwrap().verify()
+    };
+    for (x, y) in [(0u8, 0u8), (0xa5, 0x3c), (0xff, 0x01)] {
+        let outputs = BitOp::ALL.map(|op| Fp::from(op.apply(x, y) as u64)).to_vec();
+        assert!(run(x as u64, y as u64, outputs).is_ok());
+    }
+    // 结果错误
+    assert!(run(0xa5, 0x3c, vec![Fp::from(0x99), Fp::from(0x24), Fp::from(0xbe)]).is_err());
+    // 输入不是字节时, 按最低字节算出的结果也通不过分解约束
+    assert!(run(0x1a5, 0x3c, vec![Fp::from(0x99), Fp::from(0x24), Fp::from(0xbd)]).is_err());
+}
@@ -0,0 +1,260 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::shared::SharedColumns;
+
+/// 字节串的最大长度, 31个字节按小端打包后小于2^248, 不会在域上回绕
+pub const MAX_LEN: usize = 31;
+
+/// 电路外按小端把字节打包成域元素
+pub fn pack<F: PrimeField>(bytes: &[u8]) -> F {
+    assert!(bytes.len() <= MAX_LEN, "字节串超过{}字节", MAX_LEN);
+    bytes.iter().rev().fold(F::ZERO, |acc, &byte| acc * F::from(256) + F::from(byte as u64))
+}
+
+/// 电路里的字节串: 打包值和长度
+///
+/// 打包值按小端计算, 长度以外的字节为0, 所以打包值小于256^len, 两个字节串相等当且仅当打包值和长度都相等
+#[derive(Clone, Debug)]
+pub struct AssignedBytes<F: PrimeField> {
+    pub packed: AssignedCell<F, F>,
+    pub len: AssignedCell<F, F>,
+}
+
+/// 字节串的列配置
+///
+/// 打包时从最后一个字节往前, 每行一个字节: acc_next = acc * 256 + byte, len_next = len + active,
+/// active为0或1且往前不减, 不活跃的字节为0, 字节查范围检查的表. 拼接占两行:
+/// out = a + b * 256^len_a, len_out = len_a + len_b, (len_a, 256^len_a)查幂表, MAX_LEN - len_out再做范围检查
+#[derive(Clone, Debug, Copy)]
+pub struct BytesConfig {
+    pub q_pack: Selector,
+    pub q_order: Selector,
+    pub q_concat: Selector,
+    pub byte: Column<Advice>,
+    pub active: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub len: Column<Advice>,
+    pub len_table: TableColumn,
+    pub pow_table: TableColumn,
+    pub range: RangeCheckConfig,
+}
+
+/// 定长字节串的相等和拼接芯片, 容量n的字节串打包占n + 1行, 拼接占两行加一次范围检查
+pub struct BytesChip<F: PrimeField> {
+    config: BytesConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> BytesChip<F> {
+    pub fn construct(config: BytesConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 使用共享的三个advice列放字节、active和累加值, 另外分配一个advice列放长度, 字节复用范围检查的查找表
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns, range: RangeCheckConfig) -> BytesConfig {
+        let q_pack = meta.complex_selector();
+        let q_order = meta.selector();
+        let q_concat = meta.complex_selector();
+        let [byte, active, acc] = shared.advice;
+        let len = meta.advice_column();
+        meta.enable_equality(len);
+        let len_table = meta.lookup_table_column();
+        let pow_table = meta.lookup_table_column();
+
+        meta.create_gate("bytes_pack", |meta| {
+            let q = meta.query_selector(q_pack);
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let active = meta.query_advice(active, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let len_cur = meta.query_advice(len, Rotation::cur());
+            let len_next = meta.query_advice(len, Rotation::next());
+            let one = Expression::Constant(F::ONE);
+            vec![
+                ("active * (1 - active) = 0", q.clone() * active.clone() * (one.clone() - active.clone())),
+                ("byte * (1 - active) = 0", q.clone() * byte.clone() * (one - active.clone())),
+                ("acc_next = acc * 256 + byte", q.clone() * (acc_next - acc_cur * Expression::Constant(F::from(256)) - byte)),
+                ("len_next = len + active", q * (len_next - len_cur - active)),
+            ]
+        });
+
+        meta.create_gate("bytes_active_prefix", |meta| {
+            let q = meta.query_selector(q_order);
+            let active_cur = meta.query_advice(active, Rotation::cur());
+            let active_next = meta.query_advice(active, Rotation::next());
+            vec![("active_next >= active", q * active_cur * (Expression::Constant(F::ONE) - active_next))]
+        });
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_pack);
+            let byte = meta.query_advice(byte, Rotation::cur());
+            vec![(q * byte, range.table)]
+        });
+
+        meta.create_gate("bytes_concat", |meta| {
+            let q = meta.query_selector(q_concat);
+            let a = meta.query_advice(byte, Rotation::cur());
+            let b = meta.query_advice(active, Rotation::cur());
+            let out = meta.query_advice(acc, Rotation::cur());
+            let pow = meta.query_advice(len, Rotation::cur());
+            let len_a = meta.query_advice(byte, Rotation::next());
+            let len_b = meta.query_advice(active, Rotation::next());
+            let len_out = meta.query_advice(acc, Rotation::next());
+            let slack = meta.query_advice(len, Rotation::next());
+            vec![
+                ("out = a + b * 256^len_a", q.clone() * (out - a - b * pow)),
+                ("len_out = len_a + len_b", q.clone() * (len_out.clone() - len_a - len_b)),
+                ("slack = MAX_LEN - len_out", q * (slack - Expression::Constant(F::from(MAX_LEN as u64)) + len_out)),
+            ]
+        });
+
+        // 未启用的行查(0, 1), 在表中
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_concat);
+            let len_a = meta.query_advice(byte, Rotation::next());
+            let pow = meta.query_advice(len, Rotation::cur());
+            vec![(q.clone() * len_a, len_table), (q.clone() * pow + Expression::Constant(F::ONE) - q, pow_table)]
+        });
+        BytesConfig { q_pack, q_order, q_concat, byte, active, acc, len, len_table, pow_table, range }
+    }
+
+    /// 加载(len, 256^len)幂表, 字节的范围检查表由范围检查芯片加载
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(|| "256的幂表", |mut table| {
+            let mut pow = F::ONE;
+            for i in 0..=MAX_LEN {
+                table.assign_cell(|| "len", self.config.len_table, i, || Value::known(F::from(i as u64)))?;
+                table.assign_cell(|| "256^len", self.config.pow_table, i, || Value::known(pow))?;
+                pow *= F::from(256);
+            }
+            Ok(())
+        })
+    }
+
+    /// 填写容量为capacity的私有字节串, 返回字节串和capacity个字节单元格(长度以外为0); 字节串超过容量时返回`Error::Synthesis`
+    pub fn witness(&self, mut layouter: impl Layouter<F>, bytes: Value<Vec<u8>>, capacity: usize) -> Result<(AssignedBytes<F>, Vec<AssignedCell<F, F>>), Error> {
+        assert!(capacity > 0 && capacity <= MAX_LEN, "容量须在1到{}之间", MAX_LEN);
+        let mut too_long = false;
+        bytes.as_ref().map(|bytes| too_long = bytes.len() > capacity);
+        if too_long {
+            return Err(Error::Synthesis);
+        }
+        layouter.assign_region(|| "字节串", |mut region| {
+            let mut acc = region.assign_advice_from_constant(|| "acc初值", self.config.acc, 0, F::ZERO)?;
+            let mut len = region.assign_advice_from_constant(|| "len初值", self.config.len, 0, F::ZERO)?;
+            let mut cells = vec![];
+            // 第row行是第capacity - 1 - row个字节
+            for row in 0..capacity {
+                let index = capacity - 1 - row;
+                self.config.q_pack.enable(&mut region, row)?;
+                if row + 1 < capacity {
+                    self.config.q_order.enable(&mut region, row)?;
+                }
+                let byte = bytes.as_ref().map(|bytes| F::from(bytes.get(index).copied().unwrap_or(0) as u64));
+                let active = bytes.as_ref().map(|bytes| if index < bytes.len() { F::ONE } else { F::ZERO });
+                cells.push(region.assign_advice(|| "字节", self.config.byte, row, || byte)?);
+                region.assign_advice(|| "active", self.config.active, row, || active)?;
+                acc = region.assign_advice(|| "acc", self.config.acc, row + 1, || acc.value().copied() * Value::known(F::from(256)) + byte)?;
+                len = region.assign_advice(|| "len", self.config.len, row + 1, || len.value().copied() + active)?;
+            }
+            cells.reverse();
+            Ok((AssignedBytes { packed: acc, len }, cells))
+        })
+    }
+
+    /// 拼接a和b, 总长度超过MAX_LEN时约束不满足
+    pub fn concat(&self, mut layouter: impl Layouter<F>, a: &AssignedBytes<F>, b: &AssignedBytes<F>) -> Result<AssignedBytes<F>, Error> {
+        let (out, slack) = layouter.assign_region(|| "字节串拼接", |mut region| {
+            self.config.q_concat.enable(&mut region, 0)?;
+            let a_packed = a.packed.copy_advice(|| "拷贝a", &mut region, self.config.byte, 0)?;
+            let b_packed = b.packed.copy_advice(|| "拷贝b", &mut region, self.config.active, 0)?;
+            let len_a = a.len.copy_advice(|| "拷贝len_a", &mut region, self.config.byte, 1)?;
+            let len_b = b.len.copy_advice(|| "拷贝len_b", &mut region, self.config.active, 1)?;
+            // 长度不超过MAX_LEN, 取低8位足够
+            let pow = len_a.value().map(|len| F::from(256).pow([len.to_repr().as_ref()[0] as u64]));
+            region.assign_advice(|| "256^len_a", self.config.len, 0, || pow)?;
+            let packed = region.assign_advice(|| "out", self.config.acc, 0, || a_packed.value().copied() + b_packed.value().copied() * pow)?;
+            let len = region.assign_advice(|| "len_out", self.config.acc, 1, || len_a.value().copied() + len_b.value().copied())?;
+            let slack = region.assign_advice(|| "MAX_LEN - len_out", self.config.len, 1, || Value::known(F::from(MAX_LEN as u64)) - len.value().copied())?;
+            Ok((AssignedBytes { packed, len }, slack))
+        })?;
+        RangeCheckChip::construct(self.config.range).copy_check(layouter.namespace(|| "长度不超过MAX_LEN"), &slack, 1)?;
+        Ok(out)
+    }
+
+    /// 约束两个字节串相等
+    pub fn assert_equal(&self, mut layouter: impl Layouter<F>, a: &AssignedBytes<F>, b: &AssignedBytes<F>) -> Result<(), Error> {
+        layouter.assign_region(|| "字节串相等", |mut region| {
+            region.constrain_equal(a.packed.cell(), b.packed.cell())?;
+            region.constrain_equal(a.len.cell(), b.len.cell())
+        })
+    }
+
+    /// 约束字节串等于常量
+    pub fn assert_equal_const(&self, mut layouter: impl Layouter<F>, a: &AssignedBytes<F>, bytes: &[u8]) -> Result<(), Error> {
+        layouter.assign_region(|| "字节串等于常量", |mut region| {
+            region.constrain_constant(a.packed.cell(), pack::<F>(bytes))?;
+            region.constrain_constant(a.len.cell(), F::from(bytes.len() as u64))
+        })
+    }
+}
+
+#[test]
+fn test_bytes() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// 证明 a || b = expected, 三者都是私有的
+    struct ConcatCircuit {
+        a: Vec<u8>,
+        b: Vec<u8>,
+        expected: &'static [u8],
+    }
+
+    impl Circuit<Fp> for ConcatCircuit {
+        type Config = BytesConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { a: vec![], b: vec![], expected: self.expected } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            let range = RangeCheckChip::configure(meta, &shared);
+            BytesChip::configure(meta, &shared, range)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            RangeCheckChip::<Fp>::construct(config.range).load_table(layouter.namespace(|| "加载查找表"))?;
+            let chip = BytesChip::construct(config);
+            chip.load_table(layouter.namespace(|| "加载幂表"))?;
+            let (a, _) = chip.witness(layouter.namespace(|| "a"), Value::known(self.a.clone()), 20)?;
+            let (b, _) = chip.witness(layouter.namespace(|| "b"), Value::known(self.b.clone()), 20)?;
+            let out = chip.concat(layouter.namespace(|| "a || b"), &a, &b)?;
+            chip.assert_equal_const(layouter.namespace(|| "等于期望值"), &out, self.expected)
+        }
+    }
+
+    let run = |a: &[u8], b: &[u8], expected: &'static [u8]| {
+        let circuit = ConcatCircuit { a: a.to_vec(), b: b.to_vec(), expected };
+        MockProver::run(9, &circuit, vec![vec![]]).unwrap().verify()
+    };
+    assert!(run(b"hello, ", b"world", b"hello, world").is_ok());
+    assert!(run(b"", b"abc", b"abc").is_ok());
+    assert!(run(b"abc", b"", b"abc").is_ok());
+    assert!(run(b"hello", b"world", b"hello, world").is_err());
+    // 末尾的0字节也计入长度
+    assert!(run(b"abc\0", b"", b"abc").is_err());
+    // 超过31字节
+    assert!(run(&[b'x'; 16], &[b'y'; 16], b"").is_err());
+    // 超过容量的输入返回错误, 不会panic
+    let circuit = ConcatCircuit { a: vec![b'x'; 21], b: vec![], expected: b"" };
+    assert!(matches!(MockProver::run(9, &circuit, vec![vec![]]), Err(Error::Synthesis)));
+    assert_eq!(pack::<Fp>(b"ab"), Fp::from(0x6261));
+}
@@ -0,0 +1,102 @@
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::shared::SharedColumns;
+
+/// 比较的列配置: 一行 a - b = d, 再对d做范围检查
+#[derive(Clone, Debug, Copy)]
+pub struct ComparisonConfig {
+    pub q_diff: Selector,
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub diff: Column<Advice>,
+    pub range: RangeCheckConfig,
+}
+
+/// 比较芯片, 把有符号整数的大小比较归结为差的范围检查
+///
+/// 约束 0 <= a - b < 2^(8 * num_limbs), 只有当a、b作为整数之差的绝对值小于2^(8 * num_limbs)时才等价于a >= b
+pub struct ComparisonChip<F: PrimeField> {
+    config: ComparisonConfig,
+    range: RangeCheckChip<F>,
+}
+
+impl<F: PrimeField> ComparisonChip<F> {
+    pub fn construct(config: ComparisonConfig) -> Self {
+        Self { config, range: RangeCheckChip::construct(config.range) }
+    }
+
+    /// 使用共享的三个advice列, 范围检查复用已配置的查找表
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns, range: RangeCheckConfig) -> ComparisonConfig {
+        let q_diff = meta.selector();
+        let [a, b, diff] = shared.advice;
+
+        meta.create_gate("compare_diff", |meta| {
+            let q = meta.query_selector(q_diff);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            vec![("a - b = d", q * (a - b - diff))]
+        });
+        ComparisonConfig { q_diff, a, b, diff, range }
+    }
+
+    /// 约束a >= b, 见芯片说明中的前提
+    pub fn assert_ge(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, num_limbs: usize) -> Result<(), Error> {
+        let diff = layouter.assign_region(|| "求差", |mut region| {
+            self.config.q_diff.enable(&mut region, 0)?;
+            let a = a.copy_advice(|| "拷贝a", &mut region, self.config.a, 0)?;
+            let b = b.copy_advice(|| "拷贝b", &mut region, self.config.b, 0)?;
+            region.assign_advice(|| "a - b", self.config.diff, 0, || a.value().copied() - b.value().copied())
+        })?;
+        self.range.copy_check(layouter.namespace(|| "差不为负"), &diff, num_limbs)
+    }
+}
+
+#[test]
+fn test_comparison() {
+    use halo2_proofs::circuit::{SimpleFloorPlanner, Value};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// 约束a >= b, 差在16位以内
+    struct GeCircuit {
+        a: Value<Fp>,
+        b: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for GeCircuit {
+        type Config = (ComparisonConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { a: Value::unknown(), b: Value::unknown() } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            let range = RangeCheckChip::configure(meta, &shared);
+            (ComparisonChip::configure(meta, &shared, range), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            RangeCheckChip::<Fp>::construct(config.range).load_table(layouter.namespace(|| "加载查找表"))?;
+            let chip = ComparisonChip::construct(config);
+            let (a, b) = layouter.assign_region(|| "输入", |mut region| {
+                let a = region.assign_advice(|| "a", shared.advice[0], 0, || self.a)?;
+                let b = region.assign_advice(|| "b", shared.advice[1], 0, || self.b)?;
+                Ok((a, b))
+            })?;
+            chip.assert_ge(layouter.namespace(|| "a >= b"), &a, &b, 2)
+        }
+    }
+
+    let run = |a: Fp, b: Fp| MockProver::run(9, &GeCircuit { a: Value::known(a), b: Value::known(b) }, vec![vec![]]).unwrap().verify();
+    assert!(run(Fp::from(10), Fp::from(3)).is_ok());
+    assert!(run(Fp::from(10), Fp::from(10)).is_ok());
+    // 负数也可以比较
+    assert!(run(-Fp::from(3), -Fp::from(10)).is_ok());
+    assert!(run(Fp::from(3), Fp::from(10)).is_err());
+    assert!(run(-Fp::from(1), Fp::zero()).is_err());
+}
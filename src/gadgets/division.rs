@@ -0,0 +1,135 @@
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::shared::SharedColumns;
+
+/// 域元素的低128位, pasta域元素的repr为小端字节序
+pub(crate) fn low_u128<F: PrimeField>(v: &F) -> u128 {
+    let repr = v.to_repr();
+    u128::from_le_bytes(repr.as_ref()[..16].try_into().expect("repr不足16字节"))
+}
+
+/// 带余除法的列配置
+///
+/// 第0行为a、b、q, 第1行为r和d, 门约束 a = q * b + r 和 b - r - 1 = d, 再对q、r、d做范围检查,
+/// d不为负即r < b
+#[derive(Clone, Debug, Copy)]
+pub struct DivisionConfig {
+    pub q_div: Selector,
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub q: Column<Advice>,
+    pub range: RangeCheckConfig,
+}
+
+/// 整数带余除法芯片, 把乘法关系当作见证来检查, 而不是在电路里做除法
+///
+/// 调用者要保证b < 2^(8 * num_limbs), 而q、r由芯片检查小于2^(8 * num_limbs), 所以num_limbs不超过8时
+/// q * b + r < 2^128, 不会在域上回绕, 域上的等式即整数等式
+pub struct DivisionChip<F: PrimeField> {
+    config: DivisionConfig,
+    range: RangeCheckChip<F>,
+}
+
+impl<F: PrimeField> DivisionChip<F> {
+    pub fn construct(config: DivisionConfig) -> Self {
+        Self { config, range: RangeCheckChip::construct(config.range) }
+    }
+
+    /// 使用共享的三个advice列, 范围检查复用已配置的查找表
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns, range: RangeCheckConfig) -> DivisionConfig {
+        let q_div = meta.selector();
+        let [a, b, q] = shared.advice;
+
+        meta.create_gate("div_rem", |meta| {
+            let s = meta.query_selector(q_div);
+            let a_cur = meta.query_advice(a, Rotation::cur());
+            let b_cur = meta.query_advice(b, Rotation::cur());
+            let q_cur = meta.query_advice(q, Rotation::cur());
+            let r = meta.query_advice(a, Rotation::next());
+            let d = meta.query_advice(b, Rotation::next());
+            vec![
+                ("a = q * b + r", s.clone() * (a_cur - q_cur * b_cur.clone() - r.clone())),
+                ("b - r - 1 = d", s * (b_cur - r - Expression::Constant(F::ONE) - d)),
+            ]
+        });
+        DivisionConfig { q_div, a, b, q, range }
+    }
+
+    /// 计算a除以b的商和余数, b为0时约束不满足
+    pub fn div_rem(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, num_limbs: usize) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        assert!(num_limbs <= 8, "num_limbs至多为8");
+        let (q, r, d) = layouter.assign_region(|| "带余除法", |mut region| {
+            self.config.q_div.enable(&mut region, 0)?;
+            let a = a.copy_advice(|| "拷贝a", &mut region, self.config.a, 0)?;
+            let b = b.copy_advice(|| "拷贝b", &mut region, self.config.b, 0)?;
+            // b为0时随便填, 约束不会满足
+            let qr = a.value().zip(b.value()).map(|(a, b)| {
+                let (a, b) = (low_u128(a), low_u128(b));
+                if b == 0 { (0, a) } else { (a / b, a % b) }
+            });
+            let q = region.assign_advice(|| "商", self.config.q, 0, || qr.map(|(q, _)| F::from_u128(q)))?;
+            let r = region.assign_advice(|| "余数", self.config.a, 1, || qr.map(|(_, r)| F::from_u128(r)))?;
+            let d = region.assign_advice(|| "b - r - 1", self.config.b, 1, || b.value().copied() - r.value().copied() - Value::known(F::ONE))?;
+            Ok((q, r, d))
+        })?;
+        self.range.copy_check(layouter.namespace(|| "商的范围"), &q, num_limbs)?;
+        self.range.copy_check(layouter.namespace(|| "余数的范围"), &r, num_limbs)?;
+        self.range.copy_check(layouter.namespace(|| "余数小于除数"), &d, num_limbs)?;
+        Ok((q, r))
+    }
+
+    /// 约束b整除a, 返回商
+    pub fn assert_divides(&self, mut layouter: impl Layouter<F>, b: &AssignedCell<F, F>, a: &AssignedCell<F, F>, num_limbs: usize) -> Result<AssignedCell<F, F>, Error> {
+        let (q, r) = self.div_rem(layouter.namespace(|| "除法"), a, b, num_limbs)?;
+        layouter.assign_region(|| "余数为0", |mut region| region.constrain_constant(r.cell(), F::ZERO))?;
+        Ok(q)
+    }
+}
+
+#[test]
+fn test_division() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// 实例列为a、b、q、r, 都在16位以内
+    struct DivCircuit;
+
+    impl Circuit<Fp> for DivCircuit {
+        type Config = (DivisionConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { DivCircuit }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            let range = RangeCheckChip::configure(meta, &shared);
+            (DivisionChip::configure(meta, &shared, range), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            RangeCheckChip::<Fp>::construct(config.range).load_table(layouter.namespace(|| "加载查找表"))?;
+            let chip = DivisionChip::construct(config);
+            let (a, b) = layouter.assign_region(|| "输入", |mut region| {
+                let a = region.assign_advice_from_instance(|| "a", shared.instance, 0, shared.advice[0], 0)?;
+                let b = region.assign_advice_from_instance(|| "b", shared.instance, 1, shared.advice[1], 0)?;
+                Ok((a, b))
+            })?;
+            let (q, r) = chip.div_rem(layouter.namespace(|| "a / b"), &a, &b, 2)?;
+            layouter.constrain_instance(q.cell(), shared.instance, 2)?;
+            layouter.constrain_instance(r.cell(), shared.instance, 3)
+        }
+    }
+
+    let run = |values: [u64; 4]| MockProver::run(9, &DivCircuit, vec![values.map(Fp::from).to_vec()]).unwrap().verify();
+    assert!(run([100, 7, 14, 2]).is_ok());
+    assert!(run([144, 8, 18, 0]).is_ok());
+    assert!(run([5, 9, 0, 5]).is_ok());
+    assert!(run([100, 7, 13, 9]).is_err());
+    assert!(run([100, 7, 14, 3]).is_err());
+    assert!(run([100, 0, 0, 100]).is_err());
+}
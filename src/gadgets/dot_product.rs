@@ -0,0 +1,121 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::shared::SharedColumns;
+
+/// 电路外计算内积
+pub fn dot<F: PrimeField>(a: &[F], b: &[F]) -> F {
+    assert_eq!(a.len(), b.len(), "向量长度不一致");
+    a.iter().zip(b).map(|(a, b)| *a * b).sum()
+}
+
+/// 内积的列配置, 每行 acc_next = acc + a * b
+#[derive(Clone, Debug, Copy)]
+pub struct DotProductConfig {
+    pub q_mac: Selector,
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub acc: Column<Advice>,
+}
+
+/// 证明 <a, b> = c 的芯片, 长度为n的向量占n + 1行, 长度在调用时决定
+pub struct DotProductChip<F: PrimeField> {
+    config: DotProductConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> DotProductChip<F> {
+    pub fn construct(config: DotProductConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 使用共享的三个advice列作为a、b和累加值
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> DotProductConfig {
+        let q_mac = meta.selector();
+        let [a, b, acc] = shared.advice;
+
+        meta.create_gate("dot_product", |meta| {
+            let q = meta.query_selector(q_mac);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            vec![("acc_next = acc + a * b", q * (acc_next - acc_cur - a * b))]
+        });
+        DotProductConfig { q_mac, a, b, acc }
+    }
+
+    /// 填写一个私有向量
+    pub fn witness_vector(&self, mut layouter: impl Layouter<F>, values: &[Value<F>]) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(|| "填写向量", |mut region| {
+            values.iter().enumerate().map(|(i, v)| region.assign_advice(|| "分量", self.config.a, i, || *v)).collect()
+        })
+    }
+
+    /// 计算<a, b>, 两个向量长度须相同
+    pub fn dot(&self, mut layouter: impl Layouter<F>, a: &[AssignedCell<F, F>], b: &[AssignedCell<F, F>]) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(a.len(), b.len(), "向量长度不一致");
+        layouter.assign_region(|| "内积", |mut region| {
+            let mut acc = region.assign_advice_from_constant(|| "acc初值", self.config.acc, 0, F::ZERO)?;
+            for (row, (a, b)) in a.iter().zip(b).enumerate() {
+                self.config.q_mac.enable(&mut region, row)?;
+                let a = a.copy_advice(|| "拷贝a", &mut region, self.config.a, row)?;
+                let b = b.copy_advice(|| "拷贝b", &mut region, self.config.b, row)?;
+                let value = acc.value().zip(a.value()).zip(b.value()).map(|((acc, a), b)| *acc + *a * b);
+                acc = region.assign_advice(|| "acc", self.config.acc, row + 1, || value)?;
+            }
+            Ok(acc)
+        })
+    }
+}
+
+#[test]
+fn test_dot_product() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// 两个私有向量, 实例列为内积
+    struct DotCircuit {
+        a: Vec<Value<Fp>>,
+        b: Vec<Value<Fp>>,
+    }
+
+    impl Circuit<Fp> for DotCircuit {
+        type Config = (DotProductConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { a: vec![Value::unknown(); self.a.len()], b: vec![Value::unknown(); self.b.len()] }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (DotProductChip::configure(meta, &shared), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = DotProductChip::construct(config);
+            let a = chip.witness_vector(layouter.namespace(|| "a"), &self.a)?;
+            let b = chip.witness_vector(layouter.namespace(|| "b"), &self.b)?;
+            let c = chip.dot(layouter.namespace(|| "内积"), &a, &b)?;
+            layouter.constrain_instance(c.cell(), shared.instance, 0)
+        }
+    }
+
+    let known = |v: &[Fp]| v.iter().map(|&x| Value::known(x)).collect::<Vec<_>>();
+    for len in [1u64, 5, 20] {
+        let a: Vec<Fp> = (1..=len).map(Fp::from).collect();
+        let b: Vec<Fp> = (1..=len).map(|i| -Fp::from(2 * i)).collect();
+        let c = dot(&a, &b);
+        let circuit = DotCircuit { a: known(&a), b: known(&b) };
+        let prover = MockProver::run(7, &circuit, vec![vec![c]]).unwrap();
+        prover.assert_satisfied();
+        let prover = MockProver::run(7, &circuit, vec![vec![c + Fp::one()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
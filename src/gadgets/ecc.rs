@@ -0,0 +1,348 @@
+//! 电路域上的椭圆曲线 y^2 = x^3 + b 的点加和倍点
+//!
+//! 坐标就是电路的域元素, 例如电路域为pasta的Fp时取b = 5即为Pallas曲线. 点用射影坐标(X : Y : Z)表示,
+//! 对应仿射点(X/Z, Y/Z), 无穷远点为(0 : 1 : 0). 点加用Renes-Costello-Batina的完备公式(a = 0的情形):
+//!
+//! ```text
+//! X3 = (X1Y2 + X2Y1)(Y1Y2 - 3bZ1Z2) - 3b(Y1Z2 + Y2Z1)(X1Z2 + X2Z1)
+//! Y3 = (Y1Y2 + 3bZ1Z2)(Y1Y2 - 3bZ1Z2) + 9bX1X2(X1Z2 + X2Z1)
+//! Z3 = (Y1Z2 + Y2Z1)(Y1Y2 + 3bZ1Z2) + 3X1X2(X1Y2 + X2Y1)
+//! ```
+//!
+//! 对任意两点(包括相等、互为相反数和无穷远点)都成立, 所以电路里不需要分情况, 也不需要求逆.
+//! 公式里每项都是两个二次式之积, 门的次数为4(加上选择器为5). 倍点可以直接用点加公式, 单独的倍点门更省:
+//!
+//! ```text
+//! X3 = 2XY(Y^2 - 9bZ^2)
+//! Y3 = (Y^2 - 9bZ^2)(Y^2 + 3bZ^2) + 24bY^2Z^2
+//! Z3 = 8Y^3Z
+//! ```
+//!
+//! 公式的完备性要求曲线没有2阶点(即 x^3 + b 在域上没有根), Pallas和Vesta的阶都是素数, 满足这一点
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Region, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::shared::SharedColumns;
+
+/// 射影坐标下的点, 同一个点有多种表示, 比较时用[`ProjectivePoint::same_point`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProjectivePoint<F> {
+    pub x: F,
+    pub y: F,
+    pub z: F,
+}
+
+impl<F: PrimeField> ProjectivePoint<F> {
+    pub fn identity() -> Self {
+        Self { x: F::ZERO, y: F::ONE, z: F::ZERO }
+    }
+
+    pub fn from_affine(x: F, y: F) -> Self {
+        Self { x, y, z: F::ONE }
+    }
+
+    /// 无穷远点为None
+    pub fn to_affine(&self) -> Option<(F, F)> {
+        Option::<F>::from(self.z.invert()).map(|inv| (self.x * inv, self.y * inv))
+    }
+
+    pub fn is_identity(&self) -> bool {
+        bool::from(self.z.is_zero())
+    }
+
+    pub fn neg(&self) -> Self {
+        Self { x: self.x, y: -self.y, z: self.z }
+    }
+
+    /// 两个表示是否为同一个点
+    pub fn same_point(&self, other: &Self) -> bool {
+        self.x * other.z == other.x * self.z && self.y * other.z == other.y * self.z
+    }
+
+    /// 射影形式的曲线方程 Y^2 Z = X^3 + b Z^3
+    pub fn is_on_curve(&self, b: F) -> bool {
+        self.y.square() * self.z == self.x.square() * self.x + b * self.z.square() * self.z
+    }
+
+    /// 与[`EccChip::add`]的公式一致
+    pub fn add(&self, other: &Self, b: F) -> Self {
+        let b3 = b * F::from(3);
+        let (x1, y1, z1) = (self.x, self.y, self.z);
+        let (x2, y2, z2) = (other.x, other.y, other.z);
+        let (xy, yz, xz) = (x1 * y2 + x2 * y1, y1 * z2 + y2 * z1, x1 * z2 + x2 * z1);
+        let (yy_minus, yy_plus) = (y1 * y2 - b3 * z1 * z2, y1 * y2 + b3 * z1 * z2);
+        let xx3 = x1 * x2 * F::from(3);
+        Self { x: xy * yy_minus - b3 * yz * xz, y: yy_plus * yy_minus + b3 * xx3 * xz, z: yz * yy_plus + xx3 * xy }
+    }
+
+    /// 与[`EccChip::double`]的公式一致
+    pub fn double(&self, b: F) -> Self {
+        let (x, y, z) = (self.x, self.y, self.z);
+        let (yy, zz) = (y.square(), z.square());
+        let t = yy - b * F::from(9) * zz;
+        Self { x: x * y * t.double(), y: t * (yy + b * F::from(3) * zz) + b * F::from(24) * yy * zz, z: yy * y * z * F::from(8) }
+    }
+}
+
+/// 电路里的点, 三个坐标单元格
+#[derive(Clone, Debug)]
+pub struct AssignedPoint<F: PrimeField> {
+    pub x: AssignedCell<F, F>,
+    pub y: AssignedCell<F, F>,
+    pub z: AssignedCell<F, F>,
+}
+
+impl<F: PrimeField> AssignedPoint<F> {
+    pub fn value(&self) -> Value<ProjectivePoint<F>> {
+        self.x.value().zip(self.y.value()).zip(self.z.value()).map(|((x, y), z)| ProjectivePoint { x: *x, y: *y, z: *z })
+    }
+}
+
+/// 点运算的列配置, 共享的三个advice列依次放X、Y、Z, 每个点占一行
+#[derive(Clone, Debug, Copy)]
+pub struct EccConfig<F: PrimeField> {
+    pub q_add: Selector,
+    pub q_double: Selector,
+    pub q_on_curve: Selector,
+    pub q_affine: Selector,
+    pub advice: [Column<Advice>; 3],
+    /// 曲线参数
+    pub b: F,
+}
+
+/// 查询第rotation行的(X, Y, Z)
+fn query_point<F: PrimeField>(meta: &mut VirtualCells<'_, F>, advice: [Column<Advice>; 3], rotation: i32) -> [Expression<F>; 3] {
+    advice.map(|column| meta.query_advice(column, Rotation(rotation)))
+}
+
+/// 曲线 y^2 = x^3 + b 上的点加和倍点芯片
+pub struct EccChip<F: PrimeField> {
+    config: EccConfig<F>,
+}
+
+impl<F: PrimeField> EccChip<F> {
+    pub fn construct(config: EccConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns, b: F) -> EccConfig<F> {
+        let (q_add, q_double, q_on_curve, q_affine) = (meta.selector(), meta.selector(), meta.selector(), meta.selector());
+        let advice = shared.advice;
+        let constant = |v: F| Expression::Constant(v);
+
+        // 第0、1行为两个加数, 第2行为和
+        meta.create_gate("ecc_add", |meta| {
+            let q = meta.query_selector(q_add);
+            let [x1, y1, z1] = query_point(meta, advice, 0);
+            let [x2, y2, z2] = query_point(meta, advice, 1);
+            let [x3, y3, z3] = query_point(meta, advice, 2);
+            let b3 = constant(b * F::from(3));
+            let xy = x1.clone() * y2.clone() + x2.clone() * y1.clone();
+            let yz = y1.clone() * z2.clone() + y2.clone() * z1.clone();
+            let xz = x1.clone() * z2.clone() + x2.clone() * z1.clone();
+            let yy_minus = y1.clone() * y2.clone() - b3.clone() * z1.clone() * z2.clone();
+            let yy_plus = y1 * y2 + b3.clone() * z1 * z2;
+            let xx3 = constant(F::from(3)) * x1 * x2;
+            vec![
+                ("X3 = (X1Y2 + X2Y1)(Y1Y2 - 3bZ1Z2) - 3b(Y1Z2 + Y2Z1)(X1Z2 + X2Z1)", q.clone() * (x3 - (xy.clone() * yy_minus.clone() - b3.clone() * yz.clone() * xz.clone()))),
+                ("Y3 = (Y1Y2 + 3bZ1Z2)(Y1Y2 - 3bZ1Z2) + 9bX1X2(X1Z2 + X2Z1)", q.clone() * (y3 - (yy_plus.clone() * yy_minus + b3 * xx3.clone() * xz))),
+                ("Z3 = (Y1Z2 + Y2Z1)(Y1Y2 + 3bZ1Z2) + 3X1X2(X1Y2 + X2Y1)", q * (z3 - (yz * yy_plus + xx3 * xy))),
+            ]
+        });
+
+        // 第0行为输入, 第1行为倍点
+        meta.create_gate("ecc_double", |meta| {
+            let q = meta.query_selector(q_double);
+            let [x, y, z] = query_point(meta, advice, 0);
+            let [x3, y3, z3] = query_point(meta, advice, 1);
+            let yy = y.clone() * y.clone();
+            let zz = z.clone() * z.clone();
+            let t = yy.clone() - constant(b * F::from(9)) * zz.clone();
+            let y3_expected = t.clone() * (yy.clone() + constant(b * F::from(3)) * zz.clone()) + constant(b * F::from(24)) * yy.clone() * zz;
+            vec![
+                ("X3 = 2XY(Y^2 - 9bZ^2)", q.clone() * (x3 - constant(F::from(2)) * x * y.clone() * t)),
+                ("Y3 = (Y^2 - 9bZ^2)(Y^2 + 3bZ^2) + 24bY^2Z^2", q.clone() * (y3 - y3_expected)),
+                ("Z3 = 8Y^3Z", q * (z3 - constant(F::from(8)) * yy * y * z)),
+            ]
+        });
+
+        meta.create_gate("ecc_on_curve", |meta| {
+            let q = meta.query_selector(q_on_curve);
+            let [x, y, z] = query_point(meta, advice, 0);
+            let zzz = z.clone() * z.clone() * z.clone();
+            vec![("Y^2 Z = X^3 + bZ^3", q * (y.clone() * y * z - x.clone() * x.clone() * x - constant(b) * zzz))]
+        });
+
+        // 第0行为(X, Y, Z), 第1行为(x, y, Z的逆)
+        meta.create_gate("ecc_to_affine", |meta| {
+            let q = meta.query_selector(q_affine);
+            let [x, y, z] = query_point(meta, advice, 0);
+            let [affine_x, affine_y, z_inv] = query_point(meta, advice, 1);
+            vec![
+                ("Z * z_inv = 1", q.clone() * (z * z_inv.clone() - constant(F::ONE))),
+                ("x = X * z_inv", q.clone() * (affine_x - x * z_inv.clone())),
+                ("y = Y * z_inv", q * (affine_y - y * z_inv)),
+            ]
+        });
+        EccConfig { q_add, q_double, q_on_curve, q_affine, advice, b }
+    }
+
+    /// 把点拷贝到区域的第row行
+    pub fn copy_point(&self, region: &mut Region<'_, F>, point: &AssignedPoint<F>, row: usize) -> Result<AssignedPoint<F>, Error> {
+        let [x, y, z] = self.config.advice;
+        Ok(AssignedPoint {
+            x: point.x.copy_advice(|| "拷贝X", region, x, row)?,
+            y: point.y.copy_advice(|| "拷贝Y", region, y, row)?,
+            z: point.z.copy_advice(|| "拷贝Z", region, z, row)?,
+        })
+    }
+
+    /// 在区域的第row行填写点, 不检查是否在曲线上
+    pub fn assign_point(&self, region: &mut Region<'_, F>, point: Value<ProjectivePoint<F>>, row: usize) -> Result<AssignedPoint<F>, Error> {
+        let [x, y, z] = self.config.advice;
+        Ok(AssignedPoint {
+            x: region.assign_advice(|| "X", x, row, || point.map(|p| p.x))?,
+            y: region.assign_advice(|| "Y", y, row, || point.map(|p| p.y))?,
+            z: region.assign_advice(|| "Z", z, row, || point.map(|p| p.z))?,
+        })
+    }
+
+    /// 填写仿射点(x, y)并检查在曲线上; Z由常量约束固定为1, 所以不会是无穷远点
+    pub fn witness_point(&self, mut layouter: impl Layouter<F>, point: Value<(F, F)>) -> Result<AssignedPoint<F>, Error> {
+        layouter.assign_region(|| "填写点", |mut region| {
+            self.config.q_on_curve.enable(&mut region, 0)?;
+            let [x, y, z] = self.config.advice;
+            Ok(AssignedPoint {
+                x: region.assign_advice(|| "x", x, 0, || point.map(|(x, _)| x))?,
+                y: region.assign_advice(|| "y", y, 0, || point.map(|(_, y)| y))?,
+                z: region.assign_advice_from_constant(|| "Z = 1", z, 0, F::ONE)?,
+            })
+        })
+    }
+
+    /// 由常量约束固定的无穷远点(0 : 1 : 0)
+    pub fn identity(&self, mut layouter: impl Layouter<F>) -> Result<AssignedPoint<F>, Error> {
+        layouter.assign_region(|| "无穷远点", |mut region| {
+            let [x, y, z] = self.config.advice;
+            Ok(AssignedPoint {
+                x: region.assign_advice_from_constant(|| "X = 0", x, 0, F::ZERO)?,
+                y: region.assign_advice_from_constant(|| "Y = 1", y, 0, F::ONE)?,
+                z: region.assign_advice_from_constant(|| "Z = 0", z, 0, F::ZERO)?,
+            })
+        })
+    }
+
+    /// 完备点加, 对任意两点成立
+    pub fn add(&self, mut layouter: impl Layouter<F>, p: &AssignedPoint<F>, q: &AssignedPoint<F>) -> Result<AssignedPoint<F>, Error> {
+        layouter.assign_region(|| "点加", |mut region| {
+            self.config.q_add.enable(&mut region, 0)?;
+            let p = self.copy_point(&mut region, p, 0)?;
+            let q = self.copy_point(&mut region, q, 1)?;
+            let sum = p.value().zip(q.value()).map(|(p, q)| p.add(&q, self.config.b));
+            self.assign_point(&mut region, sum, 2)
+        })
+    }
+
+    pub fn double(&self, mut layouter: impl Layouter<F>, p: &AssignedPoint<F>) -> Result<AssignedPoint<F>, Error> {
+        layouter.assign_region(|| "倍点", |mut region| {
+            self.config.q_double.enable(&mut region, 0)?;
+            let p = self.copy_point(&mut region, p, 0)?;
+            let doubled = p.value().map(|p| p.double(self.config.b));
+            self.assign_point(&mut region, doubled, 1)
+        })
+    }
+
+    /// 转为仿射坐标(x, y), 输入为无穷远点时电路无解
+    pub fn to_affine(&self, mut layouter: impl Layouter<F>, p: &AssignedPoint<F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(|| "转为仿射坐标", |mut region| {
+            self.config.q_affine.enable(&mut region, 0)?;
+            let p = self.copy_point(&mut region, p, 0)?;
+            let [x, y, z] = self.config.advice;
+            let z_inv = p.z.value().map(|z| Option::<F>::from(z.invert()).unwrap_or(F::ZERO));
+            let affine_x = region.assign_advice(|| "x", x, 1, || p.x.value().copied() * z_inv)?;
+            let affine_y = region.assign_advice(|| "y", y, 1, || p.y.value().copied() * z_inv)?;
+            region.assign_advice(|| "Z的逆", z, 1, || z_inv)?;
+            Ok((affine_x, affine_y))
+        })
+    }
+}
+
+#[test]
+fn test_ecc() {
+    use group::Curve;
+    use halo2_proofs::arithmetic::CurveAffine;
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::{pallas, Fp};
+
+    // Pallas: y^2 = x^3 + 5, 生成元为(-1, 2)
+    let b = Fp::from(5);
+    let g = (-Fp::one(), Fp::from(2));
+    let to_pasta = |(x, y): (Fp, Fp)| pallas::Point::from(pallas::Affine::from_xy(x, y).unwrap());
+    let from_pasta = |p: pallas::Point| {
+        let coordinates = p.to_affine().coordinates().unwrap();
+        (*coordinates.x(), *coordinates.y())
+    };
+    let two = from_pasta(to_pasta(g) + to_pasta(g));
+    let three = from_pasta(to_pasta(g) + to_pasta(g) + to_pasta(g));
+
+    let p = ProjectivePoint::from_affine(g.0, g.1);
+    assert!(p.is_on_curve(b));
+    assert_eq!(p.double(b).to_affine(), Some(two));
+    assert_eq!(p.add(&p, b).to_affine(), Some(two));
+    assert_eq!(p.double(b).add(&p, b).to_affine(), Some(three));
+    assert!(p.double(b).is_on_curve(b));
+    // 无穷远点和相反数
+    let identity = ProjectivePoint::identity();
+    assert!(identity.add(&p, b).same_point(&p));
+    assert!(p.add(&p.neg(), b).is_identity());
+    assert!(identity.double(b).is_identity());
+    assert_eq!(identity.to_affine(), None);
+
+    /// 实例列依次为2P、2P、3P、P的仿射坐标, 分别由倍点、点加、倍点再加和无穷远点加P得到
+    struct EccCircuit {
+        p: Value<(Fp, Fp)>,
+    }
+
+    impl Circuit<Fp> for EccCircuit {
+        type Config = (EccConfig<Fp>, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { p: Value::unknown() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (EccChip::configure(meta, &shared, Fp::from(5)), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = EccChip::construct(config);
+            let p = chip.witness_point(layouter.namespace(|| "P"), self.p)?;
+            let doubled = chip.double(layouter.namespace(|| "2P"), &p)?;
+            let sum = chip.add(layouter.namespace(|| "P + P"), &p, &p)?;
+            let tripled = chip.add(layouter.namespace(|| "2P + P"), &doubled, &p)?;
+            let identity = chip.identity(layouter.namespace(|| "O"))?;
+            let same = chip.add(layouter.namespace(|| "O + P"), &identity, &p)?;
+            for (i, point) in [doubled, sum, tripled, same].iter().enumerate() {
+                let (x, y) = chip.to_affine(layouter.namespace(|| "仿射坐标"), point)?;
+                layouter.constrain_instance(x.cell(), shared.instance, 2 * i)?;
+                layouter.constrain_instance(y.cell(), shared.instance, 2 * i + 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    let instances: Vec<Fp> = [two, two, three, g].iter().flat_map(|&(x, y)| [x, y]).collect();
+    let prover = MockProver::run(6, &EccCircuit { p: Value::known(g) }, vec![instances.clone()]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(6, &EccCircuit { p: Value::known(g) }, vec![[instances[..7].to_vec(), vec![g.1 + Fp::one()]].concat()]).unwrap();
+    assert!(prover.verify().is_err());
+    // 不在曲线上的点
+    let prover = MockProver::run(6, &EccCircuit { p: Value::known((g.0, g.1 + Fp::one())) }, vec![instances]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,118 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::shared::SharedColumns;
+
+/// 电路外用秦九韶算法求p(x), coeffs按升幂排列
+pub fn horner<F: PrimeField>(coeffs: &[F], x: F) -> F {
+    coeffs.iter().rev().fold(F::ZERO, |acc, c| acc * x + c)
+}
+
+/// 多项式求值的列配置
+///
+/// 系数按降幂逐行放入, 每行 acc_next = acc * x + coeff, 和斐波那契芯片一样靠上一行的结果推出下一行
+#[derive(Clone, Debug, Copy)]
+pub struct HornerConfig {
+    pub q_horner: Selector,
+    pub coeff: Column<Advice>,
+    pub x: Column<Advice>,
+    pub acc: Column<Advice>,
+}
+
+/// 证明 p(x) = y 的芯片, n个系数占n + 1行
+pub struct HornerChip<F: PrimeField> {
+    config: HornerConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> HornerChip<F> {
+    pub fn construct(config: HornerConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 使用共享的三个advice列作为系数、x和累加值
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> HornerConfig {
+        let q_horner = meta.selector();
+        let [coeff, x, acc] = shared.advice;
+
+        meta.create_gate("horner", |meta| {
+            let q = meta.query_selector(q_horner);
+            let coeff = meta.query_advice(coeff, Rotation::cur());
+            let x = meta.query_advice(x, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            vec![("acc_next = acc * x + coeff", q * (acc_next - acc_cur * x - coeff))]
+        });
+        HornerConfig { q_horner, coeff, x, acc }
+    }
+
+    /// 填写按升幂排列的私有系数
+    pub fn witness_coeffs(&self, mut layouter: impl Layouter<F>, coeffs: &[Value<F>]) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(|| "填写系数", |mut region| {
+            coeffs.iter().enumerate().map(|(i, c)| region.assign_advice(|| "系数", self.config.coeff, i, || *c)).collect()
+        })
+    }
+
+    /// 求p(x), coeffs按升幂排列且至少有一个
+    pub fn evaluate(&self, mut layouter: impl Layouter<F>, coeffs: &[AssignedCell<F, F>], x: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!coeffs.is_empty(), "至少需要一个系数");
+        layouter.assign_region(|| "秦九韶求值", |mut region| {
+            let mut acc = region.assign_advice_from_constant(|| "acc初值", self.config.acc, 0, F::ZERO)?;
+            for (row, coeff) in coeffs.iter().rev().enumerate() {
+                self.config.q_horner.enable(&mut region, row)?;
+                let coeff = coeff.copy_advice(|| "拷贝系数", &mut region, self.config.coeff, row)?;
+                let x = x.copy_advice(|| "拷贝x", &mut region, self.config.x, row)?;
+                let value = acc.value().zip(x.value()).zip(coeff.value()).map(|((acc, x), c)| *acc * x + c);
+                acc = region.assign_advice(|| "acc", self.config.acc, row + 1, || value)?;
+            }
+            Ok(acc)
+        })
+    }
+}
+
+#[test]
+fn test_horner() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// 私有系数, 实例列为x和y
+    struct PolyCircuit {
+        coeffs: Vec<Value<Fp>>,
+    }
+
+    impl Circuit<Fp> for PolyCircuit {
+        type Config = (HornerConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { coeffs: vec![Value::unknown(); self.coeffs.len()] } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (HornerChip::configure(meta, &shared), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = HornerChip::construct(config);
+            let coeffs = chip.witness_coeffs(layouter.namespace(|| "系数"), &self.coeffs)?;
+            let x = layouter.assign_region(|| "x", |mut region| region.assign_advice_from_instance(|| "x", shared.instance, 0, shared.advice[1], 0))?;
+            let y = chip.evaluate(layouter.namespace(|| "求值"), &coeffs, &x)?;
+            layouter.constrain_instance(y.cell(), shared.instance, 1)
+        }
+    }
+
+    // p(x) = 3 + 2x + x^3, p(5) = 138
+    let coeffs = [3u64, 2, 0, 1].map(Fp::from);
+    assert_eq!(horner(&coeffs, Fp::from(5)), Fp::from(138));
+    let circuit = PolyCircuit { coeffs: coeffs.iter().map(|&c| Value::known(c)).collect() };
+    let prover = MockProver::run(5, &circuit, vec![vec![Fp::from(5), Fp::from(138)]]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(5, &circuit, vec![vec![Fp::from(5), Fp::from(139)]]).unwrap();
+    assert!(prover.verify().is_err());
+    let prover = MockProver::run(5, &circuit, vec![vec![Fp::from(4), Fp::from(138)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
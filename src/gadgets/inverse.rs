@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::shared::SharedColumns;
+
+/// 求逆的列配置, 一行内放x、x_inv和is_zero
+///
+/// 门约束 x * x_inv = 1 - is_zero 和 x * is_zero = 0: x不为0时is_zero只能为0, x_inv就是x的逆;
+/// x为0时is_zero只能为1, x_inv可以随便填, 芯片填0
+#[derive(Clone, Debug, Copy)]
+pub struct InverseConfig {
+    pub q_inv: Selector,
+    pub x: Column<Advice>,
+    pub x_inv: Column<Advice>,
+    pub is_zero: Column<Advice>,
+}
+
+/// 域上求逆芯片, 带is_zero出口, 0也能填写而不会让电路无解
+pub struct InverseChip<F: PrimeField> {
+    config: InverseConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> InverseChip<F> {
+    pub fn construct(config: InverseConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 使用共享的三个advice列作为x、x_inv和is_zero
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> InverseConfig {
+        let q_inv = meta.selector();
+        let [x, x_inv, is_zero] = shared.advice;
+
+        meta.create_gate("inverse", |meta| {
+            let q = meta.query_selector(q_inv);
+            let x_cur = meta.query_advice(x, Rotation::cur());
+            let inv_cur = meta.query_advice(x_inv, Rotation::cur());
+            let zero_cur = meta.query_advice(is_zero, Rotation::cur());
+            vec![
+                ("x * x_inv = 1 - is_zero", q.clone() * (x_cur.clone() * inv_cur - Expression::Constant(F::ONE) + zero_cur.clone())),
+                ("x * is_zero = 0", q * x_cur * zero_cur),
+            ]
+        });
+        InverseConfig { q_inv, x, x_inv, is_zero }
+    }
+
+    /// 返回(x_inv, is_zero), x为0时x_inv为0、is_zero为1
+    pub fn invert(&self, mut layouter: impl Layouter<F>, x: &AssignedCell<F, F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(|| "求逆", |mut region| {
+            self.config.q_inv.enable(&mut region, 0)?;
+            let x = x.copy_advice(|| "拷贝x", &mut region, self.config.x, 0)?;
+            let inv = x.value().map(|x| Option::<F>::from(x.invert()).unwrap_or(F::ZERO));
+            let is_zero = x.value().map(|x| if bool::from(x.is_zero()) { F::ONE } else { F::ZERO });
+            let inv = region.assign_advice(|| "x_inv", self.config.x_inv, 0, || inv)?;
+            let is_zero = region.assign_advice(|| "is_zero", self.config.is_zero, 0, || is_zero)?;
+            Ok((inv, is_zero))
+        })
+    }
+
+    /// 约束x不为0, 返回x的逆
+    pub fn invert_nonzero(&self, mut layouter: impl Layouter<F>, x: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let (inv, is_zero) = self.invert(layouter.namespace(|| "求逆"), x)?;
+        layouter.assign_region(|| "x不为0", |mut region| region.constrain_constant(is_zero.cell(), F::ZERO))?;
+        Ok(inv)
+    }
+}
+
+#[test]
+fn test_inverse() {
+    use ff::Field;
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// 实例列为x、x_inv、is_zero
+    struct InvCircuit;
+
+    impl Circuit<Fp> for InvCircuit {
+        type Config = (InverseConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { InvCircuit }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (InverseChip::configure(meta, &shared), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = InverseChip::construct(config);
+            let x = layouter.assign_region(|| "输入", |mut region| region.assign_advice_from_instance(|| "x", shared.instance, 0, shared.advice[0], 0))?;
+            let (inv, is_zero) = chip.invert(layouter.namespace(|| "求逆"), &x)?;
+            layouter.constrain_instance(inv.cell(), shared.instance, 1)?;
+            layouter.constrain_instance(is_zero.cell(), shared.instance, 2)
+        }
+    }
+
+    let run = |values: [Fp; 3]| MockProver::run(4, &InvCircuit, vec![values.to_vec()]).unwrap().verify();
+    let seven = Fp::from(7);
+    assert!(run([seven, seven.invert().unwrap(), Fp::zero()]).is_ok());
+    assert!(run([Fp::zero(), Fp::zero(), Fp::one()]).is_ok());
+    assert!(run([seven, Fp::from(3), Fp::zero()]).is_err());
+    // 非零的x不能走出口
+    assert!(run([seven, Fp::zero(), Fp::one()]).is_err());
+}
@@ -0,0 +1,207 @@
+//! 小矩阵乘法 C = A × B
+//!
+//! 每个C[i][j]是A第i行和B第j列的内积. 一行放lanes组(a, b), 门约束 acc_next = acc + Σ a_l * b_l,
+//! 所以长度为n的内积占ceil(n / lanes) + 1行. lanes越大行数越少, 但advice列(2 * lanes + 1)越多,
+//! 每列都要一次承诺和若干次打开, 见[`MatMulCost`]
+
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::shared::SharedColumns;
+
+/// 电路外的矩阵乘法
+pub fn matmul<F: PrimeField>(a: &[Vec<F>], b: &[Vec<F>]) -> Vec<Vec<F>> {
+    let p = b.first().map_or(0, |row| row.len());
+    a.iter().map(|row| (0..p).map(|j| row.iter().zip(b).map(|(x, b_row)| *x * b_row[j]).sum()).collect()).collect()
+}
+
+/// m×n乘n×p在给定lanes下的开销
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatMulCost {
+    pub lanes: usize,
+    /// 乘法部分占用的行数, 不含输入矩阵本身
+    pub rows: usize,
+    /// 乘法芯片自己的advice列数
+    pub advice_columns: usize,
+    /// 行数乘列数, 即需要填写的单元格数(含补零)
+    pub cells: usize,
+}
+
+impl MatMulCost {
+    pub fn new(m: usize, n: usize, p: usize, lanes: usize) -> Self {
+        let rows = m * p * (n.div_ceil(lanes) + 1);
+        let advice_columns = 2 * lanes + 1;
+        Self { lanes, rows, advice_columns, cells: rows * advice_columns }
+    }
+}
+
+/// 分块乘加的列配置
+#[derive(Clone, Debug)]
+pub struct MatMulConfig {
+    pub q_mac: Selector,
+    pub a: Vec<Column<Advice>>,
+    pub b: Vec<Column<Advice>>,
+    pub acc: Column<Advice>,
+}
+
+impl MatMulConfig {
+    pub fn lanes(&self) -> usize {
+        self.a.len()
+    }
+}
+
+/// 矩阵乘法芯片
+pub struct MatMulChip<F: PrimeField> {
+    config: MatMulConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> MatMulChip<F> {
+    pub fn construct(config: MatMulConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 另外分配2 * lanes + 1个advice列, 补零和acc初值用共享fixed列中的常量
+    pub fn configure(meta: &mut ConstraintSystem<F>, _shared: &SharedColumns, lanes: usize) -> MatMulConfig {
+        assert!(lanes > 0, "lanes至少为1");
+        let q_mac = meta.selector();
+        let a: Vec<_> = (0..lanes).map(|_| meta.advice_column()).collect();
+        let b: Vec<_> = (0..lanes).map(|_| meta.advice_column()).collect();
+        let acc = meta.advice_column();
+        for column in a.iter().chain(&b).chain([&acc]) {
+            meta.enable_equality(*column);
+        }
+
+        meta.create_gate("matmul_block", |meta| {
+            let q = meta.query_selector(q_mac);
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            let products = a.iter().zip(&b).fold(Expression::Constant(F::ZERO), |sum, (a, b)| {
+                sum + meta.query_advice(*a, Rotation::cur()) * meta.query_advice(*b, Rotation::cur())
+            });
+            vec![("acc_next = acc + sum(a * b)", q * (acc_next - acc_cur - products))]
+        });
+        MatMulConfig { q_mac, a, b, acc }
+    }
+
+    /// 分块计算内积, 最后一行不足lanes组时补常量0
+    fn dot(&self, mut layouter: impl Layouter<F>, a: &[&AssignedCell<F, F>], b: &[&AssignedCell<F, F>]) -> Result<AssignedCell<F, F>, Error> {
+        let lanes = self.config.lanes();
+        layouter.assign_region(|| "分块内积", |mut region| {
+            let mut acc = region.assign_advice_from_constant(|| "acc初值", self.config.acc, 0, F::ZERO)?;
+            for (row, (a_chunk, b_chunk)) in a.chunks(lanes).zip(b.chunks(lanes)).enumerate() {
+                self.config.q_mac.enable(&mut region, row)?;
+                let mut sum = acc.value().copied();
+                for lane in 0..lanes {
+                    let (a_col, b_col) = (self.config.a[lane], self.config.b[lane]);
+                    let (x, y) = match (a_chunk.get(lane), b_chunk.get(lane)) {
+                        (Some(x), Some(y)) => (x.copy_advice(|| "拷贝a", &mut region, a_col, row)?, y.copy_advice(|| "拷贝b", &mut region, b_col, row)?),
+                        _ => (region.assign_advice_from_constant(|| "补零", a_col, row, F::ZERO)?, region.assign_advice_from_constant(|| "补零", b_col, row, F::ZERO)?),
+                    };
+                    sum = sum + x.value().copied() * y.value().copied();
+                }
+                acc = region.assign_advice(|| "acc", self.config.acc, row + 1, || sum)?;
+            }
+            Ok(acc)
+        })
+    }
+
+    /// 填写一个私有矩阵, 每个元素单独一行
+    pub fn witness_matrix(&self, mut layouter: impl Layouter<F>, values: &[Vec<Value<F>>]) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        layouter.assign_region(|| "填写矩阵", |mut region| {
+            let mut matrix = Vec::with_capacity(values.len());
+            let mut offset = 0;
+            for row in values {
+                let mut cells = Vec::with_capacity(row.len());
+                for value in row {
+                    cells.push(region.assign_advice(|| "元素", self.config.acc, offset, || *value)?);
+                    offset += 1;
+                }
+                matrix.push(cells);
+            }
+            Ok(matrix)
+        })
+    }
+
+    /// 计算C = A × B, A为m×n、B为n×p
+    pub fn matmul(&self, mut layouter: impl Layouter<F>, a: &[Vec<AssignedCell<F, F>>], b: &[Vec<AssignedCell<F, F>>]) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+        let n = b.len();
+        let p = b.first().map_or(0, |row| row.len());
+        assert!(a.iter().all(|row| row.len() == n), "A的列数与B的行数不一致");
+        let mut c = Vec::with_capacity(a.len());
+        for a_row in a {
+            let a_row: Vec<_> = a_row.iter().collect();
+            let mut c_row = Vec::with_capacity(p);
+            for j in 0..p {
+                let b_col: Vec<_> = b.iter().map(|b_row| &b_row[j]).collect();
+                c_row.push(self.dot(layouter.namespace(|| "C元素"), &a_row, &b_col)?);
+            }
+            c.push(c_row);
+        }
+        Ok(c)
+    }
+}
+
+#[test]
+fn test_matmul() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// A、B私有, C按行展开公开
+    struct MatCircuit<const LANES: usize> {
+        a: Vec<Vec<Value<Fp>>>,
+        b: Vec<Vec<Value<Fp>>>,
+    }
+
+    impl<const LANES: usize> Circuit<Fp> for MatCircuit<LANES> {
+        type Config = (MatMulConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            let unknown = |m: &[Vec<Value<Fp>>]| -> Vec<Vec<Value<Fp>>> { m.iter().map(|row| vec![Value::unknown(); row.len()]).collect() };
+            Self { a: unknown(&self.a), b: unknown(&self.b) }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (MatMulChip::configure(meta, &shared, LANES), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = MatMulChip::construct(config);
+            let a = chip.witness_matrix(layouter.namespace(|| "A"), &self.a)?;
+            let b = chip.witness_matrix(layouter.namespace(|| "B"), &self.b)?;
+            let c = chip.matmul(layouter.namespace(|| "A × B"), &a, &b)?;
+            for (row, cell) in c.iter().flatten().enumerate() {
+                layouter.constrain_instance(cell.cell(), shared.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    let to_fp = |m: &[[u64; 3]]| m.iter().map(|row| row.iter().map(|&x| Fp::from(x)).collect::<Vec<_>>()).collect::<Vec<_>>();
+    let known = |m: &[Vec<Fp>]| m.iter().map(|row| row.iter().map(|&x| Value::known(x)).collect::<Vec<_>>()).collect::<Vec<_>>();
+    let a = to_fp(&[[1, 2, 3], [4, 5, 6]]);
+    let b = to_fp(&[[7, 8, 9], [10, 11, 12], [13, 14, 15]]);
+    let c: Vec<Fp> = matmul(&a, &b).into_iter().flatten().collect();
+    assert_eq!(c[0], Fp::from(66));
+
+    let prover = MockProver::run(6, &MatCircuit::<1> { a: known(&a), b: known(&b) }, vec![c.clone()]).unwrap();
+    prover.assert_satisfied();
+    // 3不是2的倍数, 最后一行补零
+    let prover = MockProver::run(6, &MatCircuit::<2> { a: known(&a), b: known(&b) }, vec![c.clone()]).unwrap();
+    prover.assert_satisfied();
+    let mut wrong = c;
+    wrong[5] += Fp::one();
+    let prover = MockProver::run(6, &MatCircuit::<3> { a: known(&a), b: known(&b) }, vec![wrong]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 2×3乘3×3: 一组一行时24行3列, 三组一行时12行7列
+    assert_eq!(MatMulCost::new(2, 3, 3, 1), MatMulCost { lanes: 1, rows: 24, advice_columns: 3, cells: 72 });
+    assert_eq!(MatMulCost::new(2, 3, 3, 3), MatMulCost { lanes: 3, rows: 12, advice_columns: 7, cells: 84 });
+}
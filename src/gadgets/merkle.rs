@@ -0,0 +1,93 @@
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::gadgets::poseidon::{PoseidonChip, PoseidonConfig};
+use crate::shared::SharedColumns;
+
+/// 默克尔路径的列配置
+///
+/// 每层占两行: 第0行为当前节点、兄弟节点和方向位, 第1行为按方向位交换后的左右子节点
+#[derive(Clone, Debug)]
+pub struct MerklePathConfig {
+    pub q_swap: Selector,
+    pub advice: [Column<Advice>; 3],
+    pub poseidon: PoseidonConfig,
+}
+
+/// 已填入电路的路径, 从叶子到根每层一个方向位和一个兄弟节点, 同一条路径可以算出更新前后的两个根
+#[derive(Clone, Debug)]
+pub struct MerklePath {
+    pub bits: Vec<AssignedCell<Fp, Fp>>,
+    pub siblings: Vec<AssignedCell<Fp, Fp>>,
+}
+
+/// 沿路径用Poseidon算出树根的芯片, 与[`crate::fib_merkle::merkle_root`]的树一致
+pub struct MerklePathChip {
+    config: MerklePathConfig,
+}
+
+impl MerklePathChip {
+    pub fn construct(config: MerklePathConfig) -> Self {
+        Self { config }
+    }
+
+    /// 使用共享的三个advice列, 另外配置一个Poseidon芯片
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, shared: &SharedColumns) -> MerklePathConfig {
+        let q_swap = meta.selector();
+        let [cur, sib, bit] = shared.advice;
+
+        meta.create_gate("merkle_swap", |meta| {
+            let q = meta.query_selector(q_swap);
+            let cur_v = meta.query_advice(cur, Rotation::cur());
+            let sib_v = meta.query_advice(sib, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let left = meta.query_advice(cur, Rotation::next());
+            let right = meta.query_advice(sib, Rotation::next());
+            vec![
+                ("bit * (1 - bit) = 0", q.clone() * bit.clone() * (Expression::Constant(Fp::one()) - bit.clone())),
+                ("left = cur + bit * (sib - cur)", q.clone() * (left - cur_v.clone() - bit.clone() * (sib_v.clone() - cur_v.clone()))),
+                ("right = sib + bit * (cur - sib)", q * (right - sib_v.clone() - bit * (cur_v - sib_v))),
+            ]
+        });
+        let poseidon = PoseidonChip::configure(meta, shared);
+        MerklePathConfig { q_swap, advice: shared.advice, poseidon }
+    }
+
+    /// 填写第index个叶子的路径, siblings为从叶子到根的兄弟节点
+    pub fn witness_path(&self, mut layouter: impl Layouter<Fp>, index: Value<usize>, siblings: Value<Vec<Fp>>, depth: usize) -> Result<MerklePath, Error> {
+        layouter.assign_region(|| "填写路径", |mut region| {
+            let mut path = MerklePath { bits: Vec::with_capacity(depth), siblings: Vec::with_capacity(depth) };
+            for level in 0..depth {
+                let bit = index.map(|index| Fp::from(((index >> level) & 1) as u64));
+                let sibling = siblings.as_ref().map(|siblings| siblings[level]);
+                path.bits.push(region.assign_advice(|| "方向位", self.config.advice[0], level, || bit)?);
+                path.siblings.push(region.assign_advice(|| "兄弟节点", self.config.advice[1], level, || sibling)?);
+            }
+            Ok(path)
+        })
+    }
+
+    /// 从叶子沿路径算出树根
+    pub fn root(&self, mut layouter: impl Layouter<Fp>, leaf: &AssignedCell<Fp, Fp>, path: &MerklePath) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let poseidon = PoseidonChip::construct(self.config.poseidon.clone());
+        let [cur_col, sib_col, bit_col] = self.config.advice;
+        let mut node = leaf.clone();
+        for (bit, sibling) in path.bits.iter().zip(&path.siblings) {
+            let (left, right) = layouter.assign_region(|| "交换", |mut region| {
+                self.config.q_swap.enable(&mut region, 0)?;
+                let cur = node.copy_advice(|| "当前节点", &mut region, cur_col, 0)?;
+                let sib = sibling.copy_advice(|| "兄弟节点", &mut region, sib_col, 0)?;
+                let bit = bit.copy_advice(|| "方向位", &mut region, bit_col, 0)?;
+                let swap = bit.value().map(|bit| *bit == Fp::one());
+                let values = cur.value().zip(sib.value()).zip(swap).map(|((cur, sib), swap)| if swap { (*sib, *cur) } else { (*cur, *sib) });
+                let left = region.assign_advice(|| "左", cur_col, 1, || values.map(|(left, _)| left))?;
+                let right = region.assign_advice(|| "右", sib_col, 1, || values.map(|(_, right)| right))?;
+                Ok((left, right))
+            })?;
+            node = poseidon.hash2(layouter.namespace(|| "父节点"), &left, &right)?;
+        }
+        Ok(node)
+    }
+}
@@ -0,0 +1,20 @@
+//! 可与斐波那契芯片组合使用的通用小工具芯片
+
+pub mod bitwise;
+pub mod bytes;
+pub mod comparison;
+pub mod division;
+pub mod dot_product;
+pub mod ecc;
+pub mod horner;
+pub mod inverse;
+pub mod matmul;
+pub mod merkle;
+pub mod pack;
+pub mod poseidon;
+pub mod public_gate;
+pub mod range_check;
+pub mod scalar_mul;
+pub mod set_membership;
+pub mod state_machine;
+pub mod word32;
@@ -0,0 +1,221 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::shared::SharedColumns;
+
+/// 一个打包值最多占的字节数, 248位小于pasta域的模数, 打包不会回绕
+pub const MAX_PACKED_BYTES: usize = 31;
+
+/// 2^(8 * bytes)
+fn byte_shift<F: PrimeField>(bytes: usize) -> F {
+    F::from(2).pow_vartime([8 * bytes as u64])
+}
+
+/// 电路外打包: 每个(值, 字节数)按顺序从低字节往高字节排, 值放不下或总字节数超过[`MAX_PACKED_BYTES`]时为None
+pub fn pack<F: PrimeField>(fields: &[(F, usize)]) -> Option<F> {
+    let mut repr = F::Repr::default();
+    let mut offset = 0;
+    for (value, bytes) in fields {
+        if offset + bytes > MAX_PACKED_BYTES {
+            return None;
+        }
+        // pasta域元素的repr为小端字节序
+        let value = value.to_repr();
+        if value.as_ref()[*bytes..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        repr.as_mut()[offset..offset + bytes].copy_from_slice(&value.as_ref()[..*bytes]);
+        offset += bytes;
+    }
+    F::from_repr(repr).into()
+}
+
+/// 电路外拆包, widths为各字段的字节数
+pub fn unpack<F: PrimeField>(packed: F, widths: &[usize]) -> Vec<F> {
+    let packed = packed.to_repr();
+    let mut offset = 0;
+    widths
+        .iter()
+        .map(|bytes| {
+            let mut repr = F::Repr::default();
+            repr.as_mut()[..*bytes].copy_from_slice(&packed.as_ref()[offset..offset + bytes]);
+            offset += bytes;
+            F::from_repr(repr).expect("低字节总是合法的域元素")
+        })
+        .collect()
+}
+
+/// 电路外取低bytes个字节, 与[`PackChip::truncate`]一致
+pub fn truncate<F: PrimeField>(value: F, bytes: usize) -> F {
+    let mut repr = value.to_repr();
+    repr.as_mut()[bytes..].iter_mut().for_each(|b| *b = 0);
+    F::from_repr(repr).expect("低字节总是合法的域元素")
+}
+
+/// 打包的列配置
+///
+/// 每行 acc_next = acc + value * coeff, coeff为fixed列, 打包时coeff依次为2^(8 * offset).
+/// 每个字段先用范围检查约束在自己的字节数内, 总字节数不超过[`MAX_PACKED_BYTES`], 所以打包值唯一确定各字段
+#[derive(Clone, Debug, Copy)]
+pub struct PackConfig {
+    pub q_pack: Selector,
+    pub value: Column<Advice>,
+    pub coeff: Column<Fixed>,
+    pub acc: Column<Advice>,
+    pub range: RangeCheckConfig,
+}
+
+/// 把多个小的公开值打包成一个域元素的芯片
+///
+/// 电路里的值都是单元格, 打包值由它们算出后公开, 验证方只需要一个实例值, 链上验证时每个实例值都要做一次标量乘法.
+/// 范围检查用到查找表, k至少为9
+pub struct PackChip<F: PrimeField> {
+    config: PackConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> PackChip<F> {
+    pub fn construct(config: PackConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 共享的第二、三个advice列作为value和acc, 另外分配一个fixed列放系数; 范围检查用第一个advice列
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> PackConfig {
+        let q_pack = meta.selector();
+        let [_, value, acc] = shared.advice;
+        let coeff = meta.fixed_column();
+
+        meta.create_gate("pack_accumulate", |meta| {
+            let q = meta.query_selector(q_pack);
+            let value = meta.query_advice(value, Rotation::cur());
+            let coeff = meta.query_fixed(coeff, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_next = meta.query_advice(acc, Rotation::next());
+            vec![("acc_next = acc + value * coeff", q * (acc_next - acc_cur - value * coeff))]
+        });
+        PackConfig { q_pack, value, coeff, acc, range: RangeCheckChip::configure(meta, shared) }
+    }
+
+    pub fn load_table(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+        RangeCheckChip::<F>::construct(self.config.range).load_table(layouter)
+    }
+
+    /// 约束并返回 Σ value_i * coeff_i
+    pub fn combine(&self, mut layouter: impl Layouter<F>, terms: &[(AssignedCell<F, F>, F)]) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "线性组合", |mut region| {
+            let mut acc = region.assign_advice_from_constant(|| "acc_0", self.config.acc, 0, F::ZERO)?;
+            for (row, (cell, coeff)) in terms.iter().enumerate() {
+                self.config.q_pack.enable(&mut region, row)?;
+                let value = cell.copy_advice(|| "拷贝value", &mut region, self.config.value, row)?;
+                region.assign_fixed(|| "coeff", self.config.coeff, row, || Value::known(*coeff))?;
+                let next = acc.value().copied() + value.value().map(|v| *v * coeff);
+                acc = region.assign_advice(|| "acc", self.config.acc, row + 1, || next)?;
+            }
+            Ok(acc)
+        })
+    }
+
+    /// 约束每个字段小于2^(8 * 字节数)后按[`pack`]的顺序打包
+    pub fn pack(&self, mut layouter: impl Layouter<F>, fields: &[(AssignedCell<F, F>, usize)]) -> Result<AssignedCell<F, F>, Error> {
+        assert!(fields.iter().map(|(_, bytes)| bytes).sum::<usize>() <= MAX_PACKED_BYTES, "打包字段总共超过{}字节", MAX_PACKED_BYTES);
+        let range = RangeCheckChip::construct(self.config.range);
+        let mut terms = Vec::with_capacity(fields.len());
+        let mut offset = 0;
+        for (cell, bytes) in fields {
+            range.copy_check(layouter.namespace(|| "检查字段宽度"), cell, *bytes)?;
+            terms.push((cell.clone(), byte_shift(offset)));
+            offset += bytes;
+        }
+        self.combine(layouter.namespace(|| "打包"), &terms)
+    }
+
+    /// 取value的低bytes个字节, 与[`truncate`]一致
+    ///
+    /// 拆成 value = low + high * 2^(8 * bytes), 除了low、high各自的范围外还约束 4 * high < 2^(8 * (32 - bytes)),
+    /// 于是low + high * 2^(8 * bytes) < 2^254, 小于模数, 拆法唯一. 代价是不支持大于等于2^254的值, 随机域元素落在这里的概率约为2^-126
+    pub fn truncate(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, bytes: usize) -> Result<AssignedCell<F, F>, Error> {
+        assert!(bytes > 0 && bytes < 32, "截取的字节数应在1到31之间");
+        let range = RangeCheckChip::construct(self.config.range);
+        let shift = byte_shift::<F>(bytes);
+        let low_value = cell.value().map(|v| truncate(*v, bytes));
+        let high_value = (cell.value().copied() - low_value) * Value::known(shift.invert().unwrap());
+
+        let low = range.witness_check(layouter.namespace(|| "低字节"), low_value, bytes)?;
+        let high = range.witness_check(layouter.namespace(|| "高字节"), high_value, 32 - bytes)?;
+        let quadruple = self.combine(layouter.namespace(|| "4 * high"), &[(high.clone(), F::from(4))])?;
+        range.copy_check(layouter.namespace(|| "检查高字节小于2^(254 - 8 * bytes)"), &quadruple, 32 - bytes)?;
+        let sum = self.combine(layouter.namespace(|| "low + high * 2^(8 * bytes)"), &[(low.clone(), F::ONE), (high, shift)])?;
+        layouter.assign_region(|| "拆分相等", |mut region| region.constrain_equal(sum.cell(), cell.cell()))?;
+        Ok(low)
+    }
+}
+
+#[test]
+fn test_pack() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    // 公开值为 pack(n: 4字节, flags: 1字节, truncate(target, 16): 16字节)
+    const WIDTHS: [usize; 3] = [4, 1, 16];
+
+    struct PackCircuit {
+        n: Value<Fp>,
+        flags: Value<Fp>,
+        target: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for PackCircuit {
+        type Config = (SharedColumns, PackConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { n: Value::unknown(), flags: Value::unknown(), target: Value::unknown() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (shared, PackChip::configure(meta, &shared))
+        }
+
+        fn synthesize(&self, (shared, config): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = PackChip::construct(config);
+            chip.load_table(layouter.namespace(|| "加载查找表"))?;
+            let [n, flags, target] = layouter.assign_region(|| "填写字段", |mut region| {
+                let mut row = 0;
+                Ok([self.n, self.flags, self.target].map(|v| {
+                    row += 1;
+                    region.assign_advice(|| "字段", shared.advice[0], row - 1, || v).expect("填写字段失败")
+                }))
+            })?;
+            let target = chip.truncate(layouter.namespace(|| "截取目标值"), &target, WIDTHS[2])?;
+            let packed = chip.pack(layouter.namespace(|| "打包"), &[(n, WIDTHS[0]), (flags, WIDTHS[1]), (target, WIDTHS[2])])?;
+            layouter.constrain_instance(packed.cell(), shared.instance, 0)
+        }
+    }
+
+    // 超过16字节的目标值
+    let target = Fp::from(u64::MAX) * Fp::from(u64::MAX) * Fp::from(12345);
+    let fields = [Fp::from(1000), Fp::from(3), truncate(target, 16)];
+    let packed = pack(&[(fields[0], 4), (fields[1], 1), (fields[2], 16)]).unwrap();
+    assert_eq!(unpack(packed, &WIDTHS), fields);
+    assert_ne!(fields[2], target);
+    assert_eq!(truncate(Fp::from(0x1234), 1), Fp::from(0x34));
+    assert_eq!(pack(&[(Fp::from(256), 1)]), None);
+    assert_eq!(pack(&[(Fp::one(), 16), (Fp::one(), 16)]), None);
+
+    let circuit = |flags: u64| PackCircuit { n: Value::known(Fp::from(1000)), flags: Value::known(Fp::from(flags)), target: Value::known(target) };
+    let prover = MockProver::run(10, &circuit(3), vec![vec![packed]]).unwrap();
+    prover.assert_satisfied();
+    // 打包值与字段不符
+    let prover = MockProver::run(10, &circuit(3), vec![vec![packed + Fp::one()]]).unwrap();
+    assert!(prover.verify().is_err());
+    // flags超过一个字节时, 即使按整数相加后打包值一致也不能通过
+    let prover = MockProver::run(10, &circuit(3 + 256), vec![vec![packed + Fp::from(1 << 40)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
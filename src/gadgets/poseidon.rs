@@ -0,0 +1,51 @@
+use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+use halo2_gadgets::poseidon::{Hash, Pow5Chip, Pow5Config};
+use halo2_proofs::circuit::{AssignedCell, Layouter};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::*;
+
+use crate::shared::SharedColumns;
+
+/// 宽度3、速率2的Poseidon配置
+pub type PoseidonConfig = Pow5Config<Fp, 3, 2>;
+
+/// 电路外计算Poseidon(left, right), 与[`PoseidonChip::hash2`]一致
+pub fn hash2(left: Fp, right: Fp) -> Fp {
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([left, right])
+}
+
+/// 电路外计算单输入的Poseidon(x), 与[`PoseidonChip::hash1`]一致; 输入长度不同, 结果与`hash2(x, 0)`不同
+pub fn hash1(x: Fp) -> Fp {
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<1>, 3, 2>::init().hash([x])
+}
+
+/// 对一个或两个单元格做Poseidon哈希的芯片, 状态列复用共享的三个advice列
+pub struct PoseidonChip {
+    config: PoseidonConfig,
+}
+
+impl PoseidonChip {
+    pub fn construct(config: PoseidonConfig) -> Self {
+        Self { config }
+    }
+
+    /// 另外分配一个advice列和六个fixed列放轮常数, 哈希初始化用到的常量放在共享的fixed列中
+    pub fn configure(meta: &mut ConstraintSystem<Fp>, shared: &SharedColumns) -> PoseidonConfig {
+        let partial_sbox = meta.advice_column();
+        let rc_a = [(); 3].map(|_| meta.fixed_column());
+        let rc_b = [(); 3].map(|_| meta.fixed_column());
+        Pow5Chip::configure::<P128Pow5T3>(meta, shared.advice, partial_sbox, rc_a, rc_b)
+    }
+
+    pub fn hash2(&self, mut layouter: impl Layouter<Fp>, left: &AssignedCell<Fp, Fp>, right: &AssignedCell<Fp, Fp>) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let chip = Pow5Chip::construct(self.config.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<2>, 3, 2>::init(chip, layouter.namespace(|| "初始化哈希"))?;
+        hasher.hash(layouter.namespace(|| "哈希"), [left.clone(), right.clone()])
+    }
+
+    pub fn hash1(&self, mut layouter: impl Layouter<Fp>, x: &AssignedCell<Fp, Fp>) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let chip = Pow5Chip::construct(self.config.clone());
+        let hasher = Hash::<_, _, P128Pow5T3, ConstantLength<1>, 3, 2>::init(chip, layouter.namespace(|| "初始化哈希"))?;
+        hasher.hash(layouter.namespace(|| "哈希"), [x.clone()])
+    }
+}
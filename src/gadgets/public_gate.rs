@@ -0,0 +1,119 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Region};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+/// 用门公开的列配置: q_pub * (value - instance) = 0, 两列都按当前行查询
+#[derive(Clone, Debug, Copy)]
+pub struct PublicGateConfig {
+    pub q_pub: Selector,
+    pub value: Column<Advice>,
+    pub instance: Column<Instance>,
+}
+
+/// 用门代替`constrain_instance`公开单元格
+///
+/// `constrain_instance`靠拷贝约束, 实例列要开启相等约束, 每个公开值在置换里多一条边.
+/// 用门公开时实例列不进置换, 但advice值必须和实例值在同一绝对行. 两种做法的取舍:
+/// - 公开值本来就按行排在某一列里(例如整条执行轨迹), 用[`PublicGateChip::expose_in_place`]原地打开选择器,
+///   不需要任何拷贝约束, 可以少一个置换列, 公开值很多时证明更快
+/// - 公开值散落在各处时仍要先拷贝到value列, 只是把置换中的实例列换成了value列, 没有好处,
+///   还要自己保证区域从第0行开始, 这时应当用`constrain_instance`
+///
+/// 对比见`examples/exposure.rs`
+pub struct PublicGateChip<F: PrimeField> {
+    config: PublicGateConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> PublicGateChip<F> {
+    pub fn construct(config: PublicGateConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 实例列不需要开启相等约束
+    pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>, instance: Column<Instance>) -> PublicGateConfig {
+        let q_pub = meta.selector();
+        meta.create_gate("public_instance_eq", |meta| {
+            let q = meta.query_selector(q_pub);
+            let value = meta.query_advice(value, Rotation::cur());
+            let instance = meta.query_instance(instance, Rotation::cur());
+            vec![("value = instance", q * (value - instance))]
+        });
+        PublicGateConfig { q_pub, value, instance }
+    }
+
+    /// 约束区域内第offset行的value等于同一绝对行的实例值, 调用者负责区域的位置
+    pub fn expose_in_place(&self, region: &mut Region<'_, F>, offset: usize) -> Result<(), Error> {
+        self.config.q_pub.enable(region, offset)
+    }
+
+    /// 把cells依次拷贝到value列的第0..n行并公开到实例列的第0..n行, value列要开启相等约束
+    ///
+    /// 必须在其他用到value列的区域之前调用, 布局器才会把这个区域放在第0行
+    pub fn expose(&self, mut layouter: impl Layouter<F>, cells: &[AssignedCell<F, F>]) -> Result<(), Error> {
+        layouter.assign_region(|| "用门公开", |mut region| {
+            for (row, cell) in cells.iter().enumerate() {
+                cell.copy_advice(|| "拷贝公开值", &mut region, self.config.value, row)?;
+                self.expose_in_place(&mut region, row)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[test]
+fn test_public_gate() {
+    use halo2_proofs::circuit::{SimpleFloorPlanner, Value};
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// 第0行公开私有值x, 第1行公开x的平方, 都在value列中原地计算
+    struct SquareCircuit {
+        x: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for SquareCircuit {
+        type Config = (PublicGateConfig, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { x: Value::unknown() } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let value = meta.advice_column();
+            let instance = meta.instance_column();
+            let q_square = meta.selector();
+            meta.create_gate("square", |meta| {
+                let q = meta.query_selector(q_square);
+                let x = meta.query_advice(value, Rotation::cur());
+                let y = meta.query_advice(value, Rotation::next());
+                vec![("y = x * x", q * (y - x.clone() * x))]
+            });
+            (PublicGateChip::configure(meta, value, instance), q_square)
+        }
+
+        fn synthesize(&self, (config, q_square): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = PublicGateChip::construct(config);
+            layouter.assign_region(|| "平方", |mut region| {
+                q_square.enable(&mut region, 0)?;
+                region.assign_advice(|| "x", config.value, 0, || self.x)?;
+                region.assign_advice(|| "y", config.value, 1, || self.x.map(|x| x * x))?;
+                chip.expose_in_place(&mut region, 0)?;
+                chip.expose_in_place(&mut region, 1)
+            })
+        }
+    }
+
+    let circuit = SquareCircuit { x: Value::known(Fp::from(7)) };
+    let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(7), Fp::from(49)]]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(7), Fp::from(48)]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 实例列不进置换
+    let mut cs = ConstraintSystem::<Fp>::default();
+    SquareCircuit::configure(&mut cs);
+    assert!(cs.permutation().get_columns().is_empty());
+}
@@ -0,0 +1,139 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{Value, Layouter, AssignedCell, Region};
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::plonk::*;
+
+use crate::shared::SharedColumns;
+
+/// 每个limb的位数, 查找表大小为2^LIMB_BITS, 因此k至少为9
+pub const LIMB_BITS: usize = 8;
+
+/// 范围检查的列配置
+///
+/// 用累加和分解: z_0 = v, z_{i+1} = (z_i - limb_i) / 2^8, 每个limb查表, 最后要求 z_n = 0
+#[derive(Clone, Debug, Copy)]
+pub struct RangeCheckConfig {
+    pub q_range: Selector,
+    pub q_zero: Selector,
+    pub z: Column<Advice>,
+    pub table: TableColumn,
+}
+
+/// 范围检查芯片, 约束 v < 2^(8 * num_limbs)
+pub struct RangeCheckChip<F: PrimeField> {
+    config: RangeCheckConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> RangeCheckChip<F> {
+    pub fn construct(config: RangeCheckConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 使用共享列中的第一个advice列作为z
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> RangeCheckConfig {
+        let q_range = meta.complex_selector();
+        let q_zero = meta.selector();
+        let z = shared.advice[0];
+        let table = meta.lookup_table_column();
+
+        // 未启用的行查到0, 0在表中
+        meta.lookup(|meta| {
+            let q_range = meta.query_selector(q_range);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let limb = z_cur - z_next * Expression::Constant(F::from(1 << LIMB_BITS));
+            vec![(q_range * limb, table)]
+        });
+
+        meta.create_gate("range_check_zero", |meta| {
+            let q_zero = meta.query_selector(q_zero);
+            let z = meta.query_advice(z, Rotation::cur());
+            vec![("z_n = 0", q_zero * z)]
+        });
+        RangeCheckConfig { q_range, q_zero, z, table }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(|| "范围检查表", |mut table| {
+            for i in 0..(1 << LIMB_BITS) {
+                table.assign_cell(|| "limb", self.config.table, i, || Value::known(F::from(i as u64)))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// 把已有单元格拷贝进来并约束其小于2^(8 * num_limbs)
+    pub fn copy_check(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, num_limbs: usize) -> Result<(), Error> {
+        layouter.assign_region(|| "范围检查", |mut region| {
+            let z_0 = cell.copy_advice(|| "拷贝z_0", &mut region, self.config.z, 0)?;
+            self.decompose(&mut region, z_0.value().copied(), num_limbs)
+        })
+    }
+
+    /// 填写一个新值并约束其小于2^(8 * num_limbs), 返回该值的单元格
+    pub fn witness_check(&self, mut layouter: impl Layouter<F>, value: Value<F>, num_limbs: usize) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "范围检查", |mut region| {
+            let z_0 = region.assign_advice(|| "填写z_0", self.config.z, 0, || value)?;
+            self.decompose(&mut region, value, num_limbs)?;
+            Ok(z_0)
+        })
+    }
+
+    fn decompose(&self, region: &mut Region<'_, F>, value: Value<F>, num_limbs: usize) -> Result<(), Error> {
+        let inv = F::from(1 << LIMB_BITS).invert().unwrap();
+        let limbs = value.map(|v| {
+            // pasta域元素的repr为小端字节序
+            let repr = v.to_repr();
+            repr.as_ref().iter().map(|&byte| F::from(byte as u64)).collect::<Vec<_>>()
+        });
+        let mut z = value;
+        for i in 0..num_limbs {
+            self.config.q_range.enable(region, i)?;
+            let limb = limbs.as_ref().map(|limbs| limbs[i]);
+            z = (z - limb).map(|v| v * inv);
+            region.assign_advice(|| "填写z", self.config.z, i + 1, || z)?;
+        }
+        self.config.q_zero.enable(region, num_limbs)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_range_check() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    struct RangeCircuit {
+        value: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for RangeCircuit {
+        type Config = RangeCheckConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { value: Value::unknown() } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            RangeCheckChip::configure(meta, &shared)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = RangeCheckChip::construct(config);
+            chip.load_table(layouter.namespace(|| "加载查找表"))?;
+            chip.witness_check(layouter.namespace(|| "检查16位"), self.value, 2)?;
+            Ok(())
+        }
+    }
+
+    let prover = MockProver::run(9, &RangeCircuit { value: Value::known(Fp::from(65535)) }, vec![vec![]]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(9, &RangeCircuit { value: Value::known(Fp::from(65536)) }, vec![vec![]]).unwrap();
+    assert!(prover.verify().is_err());
+    let prover = MockProver::run(9, &RangeCircuit { value: Value::known(-Fp::one()) }, vec![vec![]]).unwrap();
+    assert!(prover.verify().is_err());
+}
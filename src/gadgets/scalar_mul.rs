@@ -0,0 +1,453 @@
+//! 基于[`ecc`](super::ecc)点加芯片的窗口法标量乘法
+//!
+//! 标量按小端每w位切成一个窗口, 由 z_i = k_i + 2^w * z_(i+1)、z_n = 0 的累减约束分解. 固定基时每个窗口的
+//! 倍数 j * 2^(w * i) * G 预先算好放进查找表, 每个窗口查一次表, 再把各窗口的点加起来; 变量基时先在电路内算出
+//! 0·P到(2^w - 1)·P, 每个窗口用独热位从中选出一个, 从高位窗口起每次倍点w次再加上选出的点.
+//! 窗口越大, 固定基的点加越少而查找表越大, 变量基的倍点数不变、预计算和选择的行数随2^w增长, 见[`cost_report`]
+
+use std::fmt::Write as _;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::gadgets::ecc::{AssignedPoint, EccChip, EccConfig, ProjectivePoint};
+use crate::shared::SharedColumns;
+
+/// 窗口划分: 标量共count个窗口, 每个窗口bits位
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Window {
+    pub bits: usize,
+    pub count: usize,
+}
+
+impl Window {
+    /// scalar_bits须为window_bits的倍数
+    pub fn new(window_bits: usize, scalar_bits: usize) -> Self {
+        assert!((1..=8).contains(&window_bits), "窗口位数应在1到8之间");
+        assert!(scalar_bits > 0 && scalar_bits % window_bits == 0, "标量位数{}不是窗口位数{}的倍数", scalar_bits, window_bits);
+        Self { bits: window_bits, count: scalar_bits / window_bits }
+    }
+
+    /// 一个窗口的取值个数2^w
+    pub fn size(&self) -> usize {
+        1 << self.bits
+    }
+
+    pub fn scalar_bits(&self) -> usize {
+        self.bits * self.count
+    }
+}
+
+/// 小端的各窗口值
+pub fn window_values<F: PrimeField>(scalar: F, window: Window) -> Vec<u64> {
+    // pasta域元素的repr为小端字节序
+    let repr = scalar.to_repr();
+    let bit = |i: usize| ((repr.as_ref()[i / 8] >> (i % 8)) & 1) as u64;
+    (0..window.count).map(|i| (0..window.bits).map(|j| bit(i * window.bits + j) << j).sum()).collect()
+}
+
+/// 电路外的标量乘法, 逐位倍点加
+pub fn scalar_mul<F: PrimeField>(point: &ProjectivePoint<F>, scalar: F, b: F) -> ProjectivePoint<F> {
+    let repr = scalar.to_repr();
+    let mut acc = ProjectivePoint::identity();
+    for byte in repr.as_ref().iter().rev() {
+        for j in (0..8).rev() {
+            acc = acc.double(b);
+            if (byte >> j) & 1 == 1 {
+                acc = acc.add(point, b);
+            }
+        }
+    }
+    acc
+}
+
+/// Z化为1, 无穷远点为(0 : 1 : 0)
+fn normalize<F: PrimeField>(point: &ProjectivePoint<F>) -> ProjectivePoint<F> {
+    point.to_affine().map_or(ProjectivePoint::identity(), |(x, y)| ProjectivePoint::from_affine(x, y))
+}
+
+/// 固定基各窗口的倍数表, 第i个窗口的第j项为 j * 2^(w * i) * G
+pub fn fixed_multiples<F: PrimeField>(base: (F, F), window: Window, b: F) -> Vec<Vec<ProjectivePoint<F>>> {
+    let mut window_base = ProjectivePoint::from_affine(base.0, base.1);
+    (0..window.count)
+        .map(|_| {
+            let mut multiples = vec![ProjectivePoint::identity()];
+            for j in 1..window.size() {
+                multiples.push(multiples[j - 1].add(&window_base, b));
+            }
+            for _ in 0..window.bits {
+                window_base = window_base.double(b);
+            }
+            multiples.iter().map(normalize).collect()
+        })
+        .collect()
+}
+
+/// 标量乘法的列配置
+///
+/// 窗口分解时k列放k_i、z列放z_i, 固定基时同一行的X、Y、Z列放查表得到的点, fixed列index放窗口标签i + 1;
+/// 选择倍数时k列交替放独热位b_j和累计的Σb_j, z列放累计的Σj * b_j, index放j
+#[derive(Clone, Copy, Debug)]
+pub struct ScalarMulConfig<F: PrimeField> {
+    pub ecc: EccConfig<F>,
+    pub q_window: Selector,
+    pub q_lookup: Selector,
+    pub q_select: Selector,
+    pub k: Column<Advice>,
+    pub z: Column<Advice>,
+    pub index: Column<Fixed>,
+    /// 标签、窗口值、X、Y、Z
+    pub table: [TableColumn; 5],
+    pub window: Window,
+    /// 固定基的仿射坐标
+    pub fixed_base: (F, F),
+}
+
+/// 固定基和变量基标量乘法芯片
+pub struct ScalarMulChip<F: PrimeField> {
+    config: ScalarMulConfig<F>,
+}
+
+impl<F: PrimeField> ScalarMulChip<F> {
+    pub fn construct(config: ScalarMulConfig<F>) -> Self {
+        Self { config }
+    }
+
+    /// 点运算用共享的三个advice列, 另外分配k、z两个advice列、一个fixed列和固定基的查找表
+    ///
+    /// 标量位数须小于域的位数, 这样窗口分解唯一
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns, b: F, fixed_base: (F, F), window: Window) -> ScalarMulConfig<F> {
+        assert!(window.scalar_bits() < F::NUM_BITS as usize, "标量位数{}不小于域的位数", window.scalar_bits());
+        let ecc = EccChip::configure(meta, shared, b);
+        let (q_window, q_lookup, q_select) = (meta.selector(), meta.complex_selector(), meta.selector());
+        let (k, z) = (meta.advice_column(), meta.advice_column());
+        meta.enable_equality(k);
+        meta.enable_equality(z);
+        let index = meta.fixed_column();
+        let table = [(); 5].map(|_| meta.lookup_table_column());
+        let [x, y, z_coord] = shared.advice;
+        let shift = Expression::Constant(F::from(window.size() as u64));
+
+        // k_i的范围由查表(固定基)或独热选择(变量基)保证
+        meta.create_gate("scalar_window", |meta| {
+            let q = meta.query_selector(q_window);
+            let k = meta.query_advice(k, Rotation::cur());
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            vec![("z_i = k_i + 2^w * z_(i+1)", q * (z_cur - k - z_next * shift))]
+        });
+
+        // 未启用的行查到全0, 表的第0行为全0
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let inputs = [
+                meta.query_fixed(index, Rotation::cur()),
+                meta.query_advice(k, Rotation::cur()),
+                meta.query_advice(x, Rotation::cur()),
+                meta.query_advice(y, Rotation::cur()),
+                meta.query_advice(z_coord, Rotation::cur()),
+            ];
+            inputs.into_iter().zip(table).map(|(input, column)| (q.clone() * input, column)).collect()
+        });
+
+        // 偶数行为累计值, 奇数行为第j个倍数和它的独热位
+        meta.create_gate("window_select", |meta| {
+            let q = meta.query_selector(q_select);
+            let bit = meta.query_advice(k, Rotation::next());
+            let j = meta.query_fixed(index, Rotation::next());
+            let mut constraints = vec![("b * (1 - b) = 0", q.clone() * bit.clone() * (Expression::Constant(F::ONE) - bit.clone()))];
+            for (name, column) in [("acc_X' = acc_X + b * X_j", x), ("acc_Y' = acc_Y + b * Y_j", y), ("acc_Z' = acc_Z + b * Z_j", z_coord)] {
+                let acc = meta.query_advice(column, Rotation::cur());
+                let multiple = meta.query_advice(column, Rotation::next());
+                let acc_next = meta.query_advice(column, Rotation(2));
+                constraints.push((name, q.clone() * (acc_next - acc - bit.clone() * multiple)));
+            }
+            let s = meta.query_advice(k, Rotation::cur());
+            let s_next = meta.query_advice(k, Rotation(2));
+            let t = meta.query_advice(z, Rotation::cur());
+            let t_next = meta.query_advice(z, Rotation(2));
+            constraints.push(("s' = s + b", q.clone() * (s_next - s - bit.clone())));
+            constraints.push(("t' = t + j * b", q * (t_next - t - j * bit)));
+            constraints
+        });
+        ScalarMulConfig { ecc, q_window, q_lookup, q_select, k, z, index, table, window, fixed_base }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let multiples = fixed_multiples(self.config.fixed_base, self.config.window, self.config.ecc.b);
+        layouter.assign_table(|| "固定基倍数表", |mut table| {
+            let entries = multiples.iter().enumerate().flat_map(|(i, window)| {
+                window.iter().enumerate().map(move |(j, p)| [F::from(i as u64 + 1), F::from(j as u64), p.x, p.y, p.z])
+            });
+            for (row, values) in std::iter::once([F::ZERO; 5]).chain(entries).enumerate() {
+                for (column, value) in self.config.table.iter().zip(values) {
+                    table.assign_cell(|| "固定基倍数", *column, row, || Value::known(value))?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// 窗口分解, 返回各窗口的k_i; lookup时同时查出固定基在该窗口的倍数
+    fn decompose(&self, mut layouter: impl Layouter<F>, scalar: &AssignedCell<F, F>, lookup: bool) -> Result<Vec<(AssignedCell<F, F>, Option<AssignedPoint<F>>)>, Error> {
+        let window = self.config.window;
+        let ecc = EccChip::construct(self.config.ecc);
+        let multiples = lookup.then(|| fixed_multiples(self.config.fixed_base, window, self.config.ecc.b));
+        let shift_inv = F::from(window.size() as u64).invert().expect("2^w不为0");
+        layouter.assign_region(|| "窗口分解", |mut region| {
+            let values = scalar.value().map(|s| window_values(*s, window));
+            let mut z = scalar.copy_advice(|| "z_0", &mut region, self.config.z, 0)?;
+            let mut windows = Vec::with_capacity(window.count);
+            for i in 0..window.count {
+                self.config.q_window.enable(&mut region, i)?;
+                let k_value = values.as_ref().map(|v| F::from(v[i]));
+                let k = region.assign_advice(|| "k_i", self.config.k, i, || k_value)?;
+                let point = match &multiples {
+                    Some(multiples) => {
+                        self.config.q_lookup.enable(&mut region, i)?;
+                        region.assign_fixed(|| "窗口标签", self.config.index, i, || Value::known(F::from(i as u64 + 1)))?;
+                        let entry = values.as_ref().map(|v| multiples[i][v[i] as usize]);
+                        Some(ecc.assign_point(&mut region, entry, i)?)
+                    }
+                    None => None,
+                };
+                let next = (z.value().copied() - k_value) * Value::known(shift_inv);
+                z = region.assign_advice(|| "z", self.config.z, i + 1, || next)?;
+                windows.push((k, point));
+            }
+            region.constrain_constant(z.cell(), F::ZERO)?;
+            Ok(windows)
+        })
+    }
+
+    /// 约束scalar < 2^(w * count)并返回 scalar * G, G为配置中的固定基
+    pub fn fixed_base_mul(&self, mut layouter: impl Layouter<F>, scalar: &AssignedCell<F, F>) -> Result<AssignedPoint<F>, Error> {
+        let ecc = EccChip::construct(self.config.ecc);
+        let windows = self.decompose(layouter.namespace(|| "查表"), scalar, true)?;
+        let mut points = windows.into_iter().map(|(_, point)| point.expect("查表时每个窗口都有点"));
+        let mut acc = points.next().expect("至少有一个窗口");
+        for point in points {
+            acc = ecc.add(layouter.namespace(|| "累加窗口"), &acc, &point)?;
+        }
+        Ok(acc)
+    }
+
+    /// 用独热位从multiples中选出第k个
+    fn select(&self, mut layouter: impl Layouter<F>, k: &AssignedCell<F, F>, multiples: &[AssignedPoint<F>]) -> Result<AssignedPoint<F>, Error> {
+        let ecc = EccChip::construct(self.config.ecc);
+        layouter.assign_region(|| "选择倍数", |mut region| {
+            let [x, y, z] = self.config.ecc.advice;
+            let mut acc = AssignedPoint {
+                x: region.assign_advice_from_constant(|| "acc_X", x, 0, F::ZERO)?,
+                y: region.assign_advice_from_constant(|| "acc_Y", y, 0, F::ZERO)?,
+                z: region.assign_advice_from_constant(|| "acc_Z", z, 0, F::ZERO)?,
+            };
+            let mut s = region.assign_advice_from_constant(|| "s", self.config.k, 0, F::ZERO)?;
+            let mut t = region.assign_advice_from_constant(|| "t", self.config.z, 0, F::ZERO)?;
+            for (j, multiple) in multiples.iter().enumerate() {
+                let row = 2 * j;
+                self.config.q_select.enable(&mut region, row)?;
+                let multiple = ecc.copy_point(&mut region, multiple, row + 1)?;
+                let j = F::from(j as u64);
+                region.assign_fixed(|| "j", self.config.index, row + 1, || Value::known(j))?;
+                let bit = k.value().map(|k| if *k == j { F::ONE } else { F::ZERO });
+                region.assign_advice(|| "b_j", self.config.k, row + 1, || bit)?;
+                let next = acc.value().zip(multiple.value()).zip(bit).map(|((acc, m), bit)| ProjectivePoint { x: acc.x + bit * m.x, y: acc.y + bit * m.y, z: acc.z + bit * m.z });
+                acc = ecc.assign_point(&mut region, next, row + 2)?;
+                s = region.assign_advice(|| "s", self.config.k, row + 2, || s.value().copied() + bit)?;
+                t = region.assign_advice(|| "t", self.config.z, row + 2, || t.value().copied() + bit * Value::known(j))?;
+            }
+            // 恰好一个独热位为1, 且它的序号就是k
+            region.constrain_constant(s.cell(), F::ONE)?;
+            region.constrain_equal(t.cell(), k.cell())?;
+            Ok(acc)
+        })
+    }
+
+    /// 约束scalar < 2^(w * count)并返回 scalar * point
+    pub fn variable_base_mul(&self, mut layouter: impl Layouter<F>, point: &AssignedPoint<F>, scalar: &AssignedCell<F, F>) -> Result<AssignedPoint<F>, Error> {
+        let ecc = EccChip::construct(self.config.ecc);
+        let windows = self.decompose(layouter.namespace(|| "窗口分解"), scalar, false)?;
+        // 0·P, 1·P, ..., (2^w - 1)·P
+        let mut multiples = vec![ecc.identity(layouter.namespace(|| "0·P"))?, point.clone()];
+        while multiples.len() < self.config.window.size() {
+            let next = ecc.add(layouter.namespace(|| "预计算倍数"), &multiples[multiples.len() - 1], point)?;
+            multiples.push(next);
+        }
+        let mut acc: Option<AssignedPoint<F>> = None;
+        for (k, _) in windows.iter().rev() {
+            let selected = self.select(layouter.namespace(|| "选择倍数"), k, &multiples)?;
+            acc = Some(match acc {
+                None => selected,
+                Some(mut acc) => {
+                    for _ in 0..self.config.window.bits {
+                        acc = ecc.double(layouter.namespace(|| "倍点"), &acc)?;
+                    }
+                    ecc.add(layouter.namespace(|| "加上窗口"), &acc, &selected)?
+                }
+            });
+        }
+        Ok(acc.expect("至少有一个窗口"))
+    }
+}
+
+/// 一次标量乘法按各区域行数估计的代价
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MulCost {
+    pub window_bits: usize,
+    /// 各区域的行数之和, 不含查找表
+    pub rows: usize,
+    pub table_rows: usize,
+    pub additions: usize,
+    pub doublings: usize,
+    pub lookups: usize,
+}
+
+impl MulCost {
+    /// 电路至少要有的行数(不含盲化行)
+    pub fn total_rows(&self) -> usize {
+        self.rows.max(self.table_rows)
+    }
+}
+
+/// 固定基: 分解和查表count + 1行, 每次点加3行, 查找表每个窗口2^w行加一行全0
+pub fn fixed_base_cost(window_bits: usize, scalar_bits: usize) -> MulCost {
+    let window = Window::new(window_bits, scalar_bits);
+    let additions = window.count - 1;
+    MulCost {
+        window_bits,
+        rows: window.count + 1 + 3 * additions,
+        table_rows: window.count * window.size() + 1,
+        additions,
+        doublings: 0,
+        lookups: window.count,
+    }
+}
+
+/// 变量基: 分解count + 1行, 无穷远点1行, 预计算2^w - 2次点加, 每个窗口选择2^(w+1) + 1行, 每次倍点2行
+pub fn variable_base_cost(window_bits: usize, scalar_bits: usize) -> MulCost {
+    let window = Window::new(window_bits, scalar_bits);
+    let additions = window.size() - 2 + window.count - 1;
+    let doublings = window.bits * (window.count - 1);
+    MulCost {
+        window_bits,
+        rows: window.count + 1 + 1 + 3 * additions + 2 * doublings + window.count * (2 * window.size() + 1),
+        table_rows: 0,
+        additions,
+        doublings,
+        lookups: 0,
+    }
+}
+
+/// 各窗口位数下两种标量乘法的代价表
+pub fn cost_report(scalar_bits: usize, window_bits: &[usize]) -> String {
+    let mut out = format!("{:>8} {:>10} {:>10} {:>10} {:>10} {:>10}\n", "窗口位数", "固定基行数", "查找表行数", "变量基行数", "变量基点加", "变量基倍点");
+    for &bits in window_bits {
+        let (fixed, variable) = (fixed_base_cost(bits, scalar_bits), variable_base_cost(bits, scalar_bits));
+        writeln!(out, "{:>8} {:>10} {:>10} {:>10} {:>10} {:>10}", bits, fixed.rows, fixed.table_rows, variable.rows, variable.additions, variable.doublings).expect("写入字符串失败");
+    }
+    out
+}
+
+#[test]
+fn test_scalar_mul() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    // Pallas: y^2 = x^3 + 5, 生成元为(-1, 2); 16位标量, 4位窗口
+    const WINDOW_BITS: usize = 4;
+    const SCALAR_BITS: usize = 16;
+    let b = Fp::from(5);
+    let g = (-Fp::one(), Fp::from(2));
+
+    let window = Window::new(WINDOW_BITS, SCALAR_BITS);
+    assert_eq!(window_values(Fp::from(0x1234), window), [4, 3, 2, 1]);
+    let generator = ProjectivePoint::from_affine(g.0, g.1);
+    let p = scalar_mul(&generator, Fp::from(7), b);
+    assert!(p.same_point(&generator.double(b).double(b).double(b).add(&generator.neg(), b)));
+    let table = fixed_multiples(g, window, b);
+    assert!(table[1][3].same_point(&scalar_mul(&generator, Fp::from(3 << 4), b)));
+    assert!(table[0][0].is_identity());
+
+    /// 实例列依次为 s * G 和 s * P 的仿射坐标
+    struct MulCircuit {
+        scalar: Value<Fp>,
+        point: Value<(Fp, Fp)>,
+    }
+
+    impl Circuit<Fp> for MulCircuit {
+        type Config = (ScalarMulConfig<Fp>, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { scalar: Value::unknown(), point: Value::unknown() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            let config = ScalarMulChip::configure(meta, &shared, Fp::from(5), (-Fp::one(), Fp::from(2)), Window::new(WINDOW_BITS, SCALAR_BITS));
+            (config, shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = ScalarMulChip::construct(config);
+            let ecc = EccChip::construct(config.ecc);
+            chip.load_table(layouter.namespace(|| "加载查找表"))?;
+            let scalar = layouter.assign_region(|| "标量", |mut region| region.assign_advice(|| "s", shared.advice[0], 0, || self.scalar))?;
+            let point = ecc.witness_point(layouter.namespace(|| "P"), self.point)?;
+            let fixed = chip.fixed_base_mul(layouter.namespace(|| "s * G"), &scalar)?;
+            let variable = chip.variable_base_mul(layouter.namespace(|| "s * P"), &point, &scalar)?;
+            for (i, result) in [fixed, variable].iter().enumerate() {
+                let (x, y) = ecc.to_affine(layouter.namespace(|| "仿射坐标"), result)?;
+                layouter.constrain_instance(x.cell(), shared.instance, 2 * i)?;
+                layouter.constrain_instance(y.cell(), shared.instance, 2 * i + 1)?;
+            }
+            Ok(())
+        }
+    }
+
+    let point = p.to_affine().unwrap();
+    let circuit = |s: u64| MulCircuit { scalar: Value::known(Fp::from(s)), point: Value::known(point) };
+    let instances = |s: u64| {
+        let (fixed, variable) = (scalar_mul(&generator, Fp::from(s), b), scalar_mul(&p, Fp::from(s), b));
+        let ((fx, fy), (vx, vy)) = (fixed.to_affine().unwrap(), variable.to_affine().unwrap());
+        vec![fx, fy, vx, vy]
+    };
+    for s in [1, 0x1234, 0xffff] {
+        let prover = MockProver::run(9, &circuit(s), vec![instances(s)]).unwrap();
+        prover.assert_satisfied();
+    }
+    let prover = MockProver::run(9, &circuit(0x1234), vec![instances(0x1235)]).unwrap();
+    assert!(prover.verify().is_err());
+    // 超过16位的标量
+    let prover = MockProver::run(9, &circuit(0x10001), vec![instances(0x10001)]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 代价估计与实际布局一致: 两次窗口分解之间是固定基的区域, 第二次到转为仿射坐标之前是变量基的区域
+    let report = crate::dev::region_report(9, &circuit(0x1234)).unwrap();
+    let position = |name: &str, from: usize| from + report.regions[from..].iter().position(|r| r.name == name).unwrap();
+    let fixed_start = position("窗口分解", 0);
+    let variable_start = position("窗口分解", fixed_start + 1);
+    let variable_end = position("转为仿射坐标", variable_start);
+    let (fixed_regions, variable_regions) = (&report.regions[fixed_start..variable_start], &report.regions[variable_start..variable_end]);
+    let count = |regions: &[crate::dev::RegionUsage], name: &str| regions.iter().filter(|r| r.name == name).count();
+    let (fixed_cost, variable_cost) = (fixed_base_cost(WINDOW_BITS, SCALAR_BITS), variable_base_cost(WINDOW_BITS, SCALAR_BITS));
+    assert_eq!(fixed_regions.iter().map(|r| r.height()).sum::<usize>(), fixed_cost.rows);
+    assert_eq!(count(fixed_regions, "点加"), fixed_cost.additions);
+    assert_eq!(variable_regions.iter().map(|r| r.height()).sum::<usize>(), variable_cost.rows);
+    assert_eq!(count(variable_regions, "点加"), variable_cost.additions);
+    assert_eq!(count(variable_regions, "倍点"), variable_cost.doublings);
+    let table = report.regions.iter().find(|r| r.name == "固定基倍数表").unwrap();
+    assert_eq!(table.height(), fixed_cost.table_rows);
+
+    // 252位标量: 固定基窗口越大点加越少、查找表越大; 变量基在w = 2时最省
+    let windows = [1, 2, 3, 4, 6];
+    let fixed: Vec<MulCost> = windows.iter().map(|&w| fixed_base_cost(w, 252)).collect();
+    assert!(fixed.windows(2).all(|pair| pair[1].rows < pair[0].rows && pair[1].table_rows >= pair[0].table_rows));
+    let best = windows.iter().min_by_key(|&&w| variable_base_cost(w, 252).total_rows()).unwrap();
+    assert_eq!(*best, 2);
+    assert_eq!(cost_report(252, &windows).lines().count(), 1 + windows.len());
+}
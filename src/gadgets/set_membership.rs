@@ -0,0 +1,199 @@
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::gadgets::range_check::{RangeCheckChip, RangeCheckConfig, LIMB_BITS};
+use crate::shared::SharedColumns;
+
+/// 域元素的低64位, 高位不为0时返回None, pasta域元素的repr为小端字节序
+fn to_u64<F: PrimeField>(v: &F) -> Option<u64> {
+    let repr = v.to_repr();
+    let (low, high) = repr.as_ref().split_at(8);
+    high.iter().all(|&b| b == 0).then(|| u64::from_le_bytes(low.try_into().expect("repr不足8字节")))
+}
+
+/// 集合成员证明的列配置
+///
+/// 两张表都带一个标签列, 未启用的行查到全0行, 不会误把0当成集合元素:
+/// - 成员表(1, s_i), x ∈ S 时查表(1, x)
+/// - 间隔表(1, lo, hi), 为排序后相邻元素及两端的哨兵 -1 和 2^(8 * num_limbs).
+///   x ∉ S 时查表(1, lo, hi), 下一行为 x - lo - 1 和 hi - x - 1, 两者都经范围检查, 即 lo < x < hi
+#[derive(Clone, Debug, Copy)]
+pub struct SetMembershipConfig {
+    pub q_member: Selector,
+    pub q_gap: Selector,
+    pub x: Column<Advice>,
+    pub lo: Column<Advice>,
+    pub hi: Column<Advice>,
+    pub member_table: [TableColumn; 2],
+    pub gap_table: [TableColumn; 3],
+    pub range: RangeCheckConfig,
+}
+
+/// 证明 x ∈ S 或 x ∉ S 的芯片, S是构造时给定的公开集合, 元素和x都小于2^(8 * num_limbs)
+pub struct SetMembershipChip<F: PrimeField> {
+    config: SetMembershipConfig,
+    range: RangeCheckChip<F>,
+    set: Vec<u64>,
+    num_limbs: usize,
+}
+
+impl<F: PrimeField> SetMembershipChip<F> {
+    /// 集合会被排序去重, num_limbs至多为7, 上端的哨兵要能放进u64
+    pub fn construct(config: SetMembershipConfig, set: &[u64], num_limbs: usize) -> Self {
+        assert!(num_limbs > 0 && num_limbs * LIMB_BITS < 64, "num_limbs须在1..=7之间");
+        let mut set = set.to_vec();
+        set.sort_unstable();
+        set.dedup();
+        assert!(set.iter().all(|&s| s >> (num_limbs * LIMB_BITS) == 0), "集合元素超出范围");
+        Self { config, range: RangeCheckChip::construct(config.range), set, num_limbs }
+    }
+
+    /// 使用共享的三个advice列作为x、lo、hi, 范围检查复用已配置的查找表
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns, range: RangeCheckConfig) -> SetMembershipConfig {
+        let q_member = meta.complex_selector();
+        let q_gap = meta.complex_selector();
+        let [x, lo, hi] = shared.advice;
+        let member_table = [(); 2].map(|_| meta.lookup_table_column());
+        let gap_table = [(); 3].map(|_| meta.lookup_table_column());
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_member);
+            let x = meta.query_advice(x, Rotation::cur());
+            vec![(q.clone(), member_table[0]), (q * x, member_table[1])]
+        });
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_gap);
+            let lo = meta.query_advice(lo, Rotation::cur());
+            let hi = meta.query_advice(hi, Rotation::cur());
+            vec![(q.clone(), gap_table[0]), (q.clone() * lo, gap_table[1]), (q * hi, gap_table[2])]
+        });
+
+        meta.create_gate("non_member_diff", |meta| {
+            let q = meta.query_selector(q_gap);
+            let one = Expression::Constant(F::ONE);
+            let x_cur = meta.query_advice(x, Rotation::cur());
+            let lo_cur = meta.query_advice(lo, Rotation::cur());
+            let hi_cur = meta.query_advice(hi, Rotation::cur());
+            let d_lo = meta.query_advice(x, Rotation::next());
+            let d_hi = meta.query_advice(lo, Rotation::next());
+            vec![
+                ("x - lo - 1 = d_lo", q.clone() * (x_cur.clone() - lo_cur - one.clone() - d_lo)),
+                ("hi - x - 1 = d_hi", q * (hi_cur - x_cur - one - d_hi)),
+            ]
+        });
+        SetMembershipConfig { q_member, q_gap, x, lo, hi, member_table, gap_table, range }
+    }
+
+    /// 上端的哨兵
+    fn upper(&self) -> u64 {
+        1 << (self.num_limbs * LIMB_BITS)
+    }
+
+    /// 加载成员表和间隔表, 范围检查表需另外加载
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(|| "成员表", |mut table| {
+            let members = std::iter::once([F::ZERO, F::ZERO]).chain(self.set.iter().map(|&s| [F::ONE, F::from(s)]));
+            for (row, values) in members.enumerate() {
+                for (column, value) in self.config.member_table.iter().zip(values) {
+                    table.assign_cell(|| "成员", *column, row, || Value::known(value))?;
+                }
+            }
+            Ok(())
+        })?;
+        layouter.assign_table(|| "间隔表", |mut table| {
+            let bounds: Vec<F> = std::iter::once(-F::ONE).chain(self.set.iter().map(|&s| F::from(s))).chain([F::from(self.upper())]).collect();
+            let gaps = std::iter::once([F::ZERO, F::ZERO, F::ZERO]).chain(bounds.windows(2).map(|w| [F::ONE, w[0], w[1]]));
+            for (row, values) in gaps.enumerate() {
+                for (column, value) in self.config.gap_table.iter().zip(values) {
+                    table.assign_cell(|| "间隔", *column, row, || Value::known(value))?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// 约束 x ∈ S
+    pub fn assert_member(&self, mut layouter: impl Layouter<F>, x: &AssignedCell<F, F>) -> Result<(), Error> {
+        layouter.assign_region(|| "成员", |mut region| {
+            self.config.q_member.enable(&mut region, 0)?;
+            x.copy_advice(|| "拷贝x", &mut region, self.config.x, 0)?;
+            Ok(())
+        })
+    }
+
+    /// 约束 x ∉ S 且 x < 2^(8 * num_limbs)
+    pub fn assert_non_member(&self, mut layouter: impl Layouter<F>, x: &AssignedCell<F, F>) -> Result<(), Error> {
+        // 夹住x的相邻两个边界, x超出范围时随便取一个, 约束不会满足
+        let bounds = x.value().map(|x| {
+            let x = to_u64(x).unwrap_or(u64::MAX);
+            let i = self.set.partition_point(|&s| s < x);
+            let lo = if i == 0 { -F::ONE } else { F::from(self.set[i - 1]) };
+            let hi = F::from(self.set.get(i).copied().unwrap_or(self.upper()));
+            (lo, hi)
+        });
+        let (d_lo, d_hi) = layouter.assign_region(|| "非成员", |mut region| {
+            self.config.q_gap.enable(&mut region, 0)?;
+            let x = x.copy_advice(|| "拷贝x", &mut region, self.config.x, 0)?;
+            region.assign_advice(|| "lo", self.config.lo, 0, || bounds.map(|(lo, _)| lo))?;
+            region.assign_advice(|| "hi", self.config.hi, 0, || bounds.map(|(_, hi)| hi))?;
+            let d = x.value().zip(bounds).map(|(x, (lo, hi))| (*x - lo - F::ONE, hi - x - F::ONE));
+            let d_lo = region.assign_advice(|| "x - lo - 1", self.config.x, 1, || d.map(|(d, _)| d))?;
+            let d_hi = region.assign_advice(|| "hi - x - 1", self.config.lo, 1, || d.map(|(_, d)| d))?;
+            Ok((d_lo, d_hi))
+        })?;
+        self.range.copy_check(layouter.namespace(|| "x大于lo"), &d_lo, self.num_limbs)?;
+        self.range.copy_check(layouter.namespace(|| "x小于hi"), &d_hi, self.num_limbs)
+    }
+}
+
+#[test]
+fn test_set_membership() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    const SET: [u64; 4] = [1000, 3, 42, 10];
+
+    /// 实例列为x和y, 约束 x ∈ S, y ∉ S, 元素在16位以内
+    struct SetCircuit;
+
+    impl Circuit<Fp> for SetCircuit {
+        type Config = (SetMembershipConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { SetCircuit }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            let range = RangeCheckChip::configure(meta, &shared);
+            (SetMembershipChip::configure(meta, &shared, range), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            RangeCheckChip::<Fp>::construct(config.range).load_table(layouter.namespace(|| "加载范围检查表"))?;
+            let chip = SetMembershipChip::construct(config, &SET, 2);
+            chip.load_table(layouter.namespace(|| "加载集合"))?;
+            let (x, y) = layouter.assign_region(|| "输入", |mut region| {
+                let x = region.assign_advice_from_instance(|| "x", shared.instance, 0, shared.advice[0], 0)?;
+                let y = region.assign_advice_from_instance(|| "y", shared.instance, 1, shared.advice[1], 0)?;
+                Ok((x, y))
+            })?;
+            chip.assert_member(layouter.namespace(|| "x ∈ S"), &x)?;
+            chip.assert_non_member(layouter.namespace(|| "y ∉ S"), &y)
+        }
+    }
+
+    let run = |x: u64, y: Fp| MockProver::run(9, &SetCircuit, vec![vec![Fp::from(x), y]]).unwrap().verify();
+    assert!(run(10, Fp::from(11)).is_ok());
+    // 两端的间隔
+    assert!(run(3, Fp::zero()).is_ok());
+    assert!(run(1000, Fp::from(65535)).is_ok());
+    assert!(run(11, Fp::from(12)).is_err());
+    assert!(run(0, Fp::from(12)).is_err());
+    assert!(run(42, Fp::from(42)).is_err());
+    assert!(run(42, Fp::from(65536)).is_err());
+    assert!(run(42, -Fp::one()).is_err());
+}
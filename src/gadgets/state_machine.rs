@@ -0,0 +1,140 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::shared::SharedColumns;
+
+/// 一条转移: (状态, 输入, 下一状态)
+pub type Transition = (u64, u64, u64);
+
+/// 状态机的列配置
+///
+/// 第i行放状态s_i和输入x_i, 下一行放s_{i+1}, (1, s_i, x_i, s_{i+1})查转移表.
+/// 表的第一列是标记, 另有一行(0, 0, 0, 0)给未启用的行用, 所以表里的转移不受影响
+#[derive(Clone, Debug, Copy)]
+pub struct StateMachineConfig {
+    pub q_step: Selector,
+    pub state: Column<Advice>,
+    pub input: Column<Advice>,
+    pub table: [TableColumn; 4],
+}
+
+/// 通用状态机芯片, 合法的转移放在固定查找表中, 状态和输入都是小整数
+///
+/// 转移表是电路的一部分, 不同的转移表对应不同的验证密钥. 每步占一行, 加上最后一个状态共n + 1行
+pub struct StateMachineChip<F: PrimeField> {
+    config: StateMachineConfig,
+    transitions: Vec<Transition>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> StateMachineChip<F> {
+    pub fn construct(config: StateMachineConfig, transitions: &[Transition]) -> Self {
+        Self { config, transitions: transitions.to_vec(), _marker: PhantomData }
+    }
+
+    /// 使用共享的前两个advice列放状态和输入
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> StateMachineConfig {
+        let q_step = meta.complex_selector();
+        let [state, input, _] = shared.advice;
+        let table = [meta.lookup_table_column(), meta.lookup_table_column(), meta.lookup_table_column(), meta.lookup_table_column()];
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_step);
+            let state_cur = meta.query_advice(state, Rotation::cur());
+            let input = meta.query_advice(input, Rotation::cur());
+            let state_next = meta.query_advice(state, Rotation::next());
+            vec![(q.clone(), table[0]), (q.clone() * state_cur, table[1]), (q.clone() * input, table[2]), (q * state_next, table[3])]
+        });
+        StateMachineConfig { q_step, state, input, table }
+    }
+
+    pub fn load_table(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(|| "转移表", |mut table| {
+            let rows = std::iter::once((0, 0, 0, 0)).chain(self.transitions.iter().map(|&(s, x, n)| (1, s, x, n)));
+            for (row, (tag, s, x, n)) in rows.enumerate() {
+                for (column, value) in self.config.table.iter().zip([tag, s, x, n]) {
+                    table.assign_cell(|| "转移", *column, row, || Value::known(F::from(value)))?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// 电路外走一步, 没有对应的转移时为None
+    pub fn next(&self, state: u64, input: u64) -> Option<u64> {
+        self.transitions.iter().find(|&&(s, x, _)| s == state && x == input).map(|&(_, _, n)| n)
+    }
+
+    /// 电路外从start依次读入inputs, 返回经过的全部状态
+    pub fn states(&self, start: u64, inputs: &[u64]) -> Option<Vec<u64>> {
+        let mut states = vec![start];
+        for &input in inputs {
+            states.push(self.next(*states.last().expect("至少有初始状态"), input)?);
+        }
+        Some(states)
+    }
+
+    /// 从常量状态start开始读入私有输入, 返回输入和n + 1个状态的单元格
+    ///
+    /// 没有对应的转移时后面的状态填0, 查找不会满足
+    pub fn run(&self, mut layouter: impl Layouter<F>, start: u64, inputs: &[Value<u64>]) -> Result<(Vec<AssignedCell<F, F>>, Vec<AssignedCell<F, F>>), Error> {
+        layouter.assign_region(|| "状态机", |mut region| {
+            let mut state = Value::known(Some(start));
+            let mut states = vec![region.assign_advice_from_constant(|| "初始状态", self.config.state, 0, F::from(start))?];
+            let mut cells = Vec::with_capacity(inputs.len());
+            for (row, input) in inputs.iter().enumerate() {
+                self.config.q_step.enable(&mut region, row)?;
+                cells.push(region.assign_advice(|| "输入", self.config.input, row, || input.map(F::from))?);
+                state = state.zip(*input).map(|(s, x)| s.and_then(|s| self.next(s, x)));
+                states.push(region.assign_advice(|| "状态", self.config.state, row + 1, || state.map(|s| F::from(s.unwrap_or(0))))?);
+            }
+            Ok((cells, states))
+        })
+    }
+}
+
+#[test]
+fn test_state_machine() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    /// 奇偶校验: 状态为读到的1的个数的奇偶性
+    const PARITY: [Transition; 4] = [(0, 0, 0), (0, 1, 1), (1, 0, 1), (1, 1, 0)];
+
+    /// 实例列为最后的状态
+    struct ParityCircuit {
+        bits: Vec<u64>,
+    }
+
+    impl Circuit<Fp> for ParityCircuit {
+        type Config = (StateMachineConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { bits: self.bits.clone() } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (StateMachineChip::configure(meta, &shared), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = StateMachineChip::construct(config, &PARITY);
+            chip.load_table(layouter.namespace(|| "加载转移表"))?;
+            let inputs: Vec<_> = self.bits.iter().map(|&b| Value::known(b)).collect();
+            let (_, states) = chip.run(layouter.namespace(|| "校验"), 0, &inputs)?;
+            layouter.constrain_instance(states.last().unwrap().cell(), shared.instance, 0)
+        }
+    }
+
+    let run = |bits: &[u64], parity: u64| MockProver::run(5, &ParityCircuit { bits: bits.to_vec() }, vec![vec![Fp::from(parity)]]).unwrap().verify();
+    assert!(run(&[1, 0, 1, 1], 1).is_ok());
+    assert!(run(&[1, 1, 0, 0], 0).is_ok());
+    assert!(run(&[1, 0, 1, 1], 0).is_err());
+    // 输入2没有转移
+    assert!(run(&[1, 2, 1, 1], 1).is_err());
+}
@@ -0,0 +1,256 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::shared::SharedColumns;
+
+/// 字的位数
+pub const WORD_BITS: usize = 32;
+
+/// 32位字的移位和循环移位
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WordOp {
+    RotateLeft(u32),
+    RotateRight(u32),
+    ShiftLeft(u32),
+    ShiftRight(u32),
+}
+
+impl WordOp {
+    pub fn apply(self, word: u32) -> u32 {
+        match self {
+            WordOp::RotateLeft(r) => word.rotate_left(r),
+            WordOp::RotateRight(r) => word.rotate_right(r),
+            WordOp::ShiftLeft(r) => word.checked_shl(r).unwrap_or(0),
+            WordOp::ShiftRight(r) => word.checked_shr(r).unwrap_or(0),
+        }
+    }
+
+    /// 输入第i位在结果中的权重, 移出的位权重为0
+    pub fn weight(self, i: usize) -> u64 {
+        self.apply(1 << i) as u64
+    }
+}
+
+/// 域元素的低32位, pasta域元素的repr为小端字节序
+pub(crate) fn low_u32<F: PrimeField>(v: &F) -> u32 {
+    let repr = v.to_repr();
+    u32::from_le_bytes(repr.as_ref()[..4].try_into().expect("repr不足4字节"))
+}
+
+/// 字运算的列配置
+///
+/// 把字拆成32位, 第i行放第i位和两个累加和: acc_in按2^i累加出输入, acc_out按运算后的权重累加出结果,
+/// 权重放在fixed列中, 所以同一个门可以做任意移位和循环移位. 分解本身也保证了输入小于2^32
+///
+/// 另有两个两行的门: q_add约束 a + b = c + carry * 2^32, q_pack约束字等于四个字节按小端拼接
+#[derive(Clone, Debug, Copy)]
+pub struct WordConfig {
+    pub q_bit: Selector,
+    pub q_add: Selector,
+    pub q_pack: Selector,
+    pub bit: Column<Advice>,
+    pub acc_in: Column<Advice>,
+    pub acc_out: Column<Advice>,
+    pub w_in: Column<Fixed>,
+    pub w_out: Column<Fixed>,
+}
+
+/// 32位字芯片: 移位每次占33行, 加法另加一次分解, 与字节互转各占2行
+pub struct WordChip<F: PrimeField> {
+    config: WordConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> WordChip<F> {
+    pub fn construct(config: WordConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 使用共享的三个advice列放位和两个累加和(加法和拼接也用这三列), 另外分配两个fixed列放权重
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> WordConfig {
+        let q_bit = meta.selector();
+        let q_add = meta.selector();
+        let q_pack = meta.selector();
+        let [bit, acc_in, acc_out] = shared.advice;
+        let w_in = meta.fixed_column();
+        let w_out = meta.fixed_column();
+
+        meta.create_gate("word_shift", |meta| {
+            let q = meta.query_selector(q_bit);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let in_cur = meta.query_advice(acc_in, Rotation::cur());
+            let in_next = meta.query_advice(acc_in, Rotation::next());
+            let out_cur = meta.query_advice(acc_out, Rotation::cur());
+            let out_next = meta.query_advice(acc_out, Rotation::next());
+            let w_in = meta.query_fixed(w_in, Rotation::cur());
+            let w_out = meta.query_fixed(w_out, Rotation::cur());
+            vec![
+                ("bit * (1 - bit) = 0", q.clone() * bit.clone() * (Expression::Constant(F::ONE) - bit.clone())),
+                ("in_next = in + bit * w_in", q.clone() * (in_next - in_cur - bit.clone() * w_in)),
+                ("out_next = out + bit * w_out", q * (out_next - out_cur - bit * w_out)),
+            ]
+        });
+
+        meta.create_gate("word_add", |meta| {
+            let q = meta.query_selector(q_add);
+            let a = meta.query_advice(bit, Rotation::cur());
+            let b = meta.query_advice(acc_in, Rotation::cur());
+            let c = meta.query_advice(acc_out, Rotation::cur());
+            let carry = meta.query_advice(bit, Rotation::next());
+            vec![
+                ("a + b = c + carry * 2^32", q.clone() * (a + b - c - carry.clone() * Expression::Constant(F::from(1 << WORD_BITS)))),
+                ("carry * (1 - carry) = 0", q * carry.clone() * (Expression::Constant(F::ONE) - carry)),
+            ]
+        });
+
+        meta.create_gate("word_concat", |meta| {
+            let q = meta.query_selector(q_pack);
+            let bytes = [
+                meta.query_advice(bit, Rotation::cur()),
+                meta.query_advice(acc_in, Rotation::cur()),
+                meta.query_advice(acc_out, Rotation::cur()),
+                meta.query_advice(bit, Rotation::next()),
+            ];
+            let word = meta.query_advice(acc_in, Rotation::next());
+            let packed = bytes.into_iter().enumerate().fold(Expression::Constant(F::ZERO), |acc, (i, byte)| acc + byte * Expression::Constant(F::from(1 << (8 * i))));
+            vec![("word = b0 + 2^8 * b1 + 2^16 * b2 + 2^24 * b3", q * (word - packed))]
+        });
+        WordConfig { q_bit, q_add, q_pack, bit, acc_in, acc_out, w_in, w_out }
+    }
+
+    /// 分解输入并按运算重新累加, input为None时把word作为新值填入, 返回输入和结果的单元格
+    fn decompose(&self, mut layouter: impl Layouter<F>, op: WordOp, word: Value<u32>, input: Option<&AssignedCell<F, F>>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(|| "字移位", |mut region| {
+            region.assign_advice_from_constant(|| "acc_in初值", self.config.acc_in, 0, F::ZERO)?;
+            region.assign_advice_from_constant(|| "acc_out初值", self.config.acc_out, 0, F::ZERO)?;
+            let (mut acc_in, mut acc_out) = (Value::known(0u64), Value::known(0u64));
+            for i in 0..WORD_BITS {
+                self.config.q_bit.enable(&mut region, i)?;
+                region.assign_fixed(|| "输入权重", self.config.w_in, i, || Value::known(F::from(1 << i)))?;
+                region.assign_fixed(|| "结果权重", self.config.w_out, i, || Value::known(F::from(op.weight(i))))?;
+                let bit = word.map(|w| ((w >> i) & 1) as u64);
+                region.assign_advice(|| "位", self.config.bit, i, || bit.map(F::from))?;
+                acc_in = acc_in.zip(bit).map(|(acc, bit)| acc + (bit << i));
+                acc_out = acc_out.zip(bit).map(|(acc, bit)| acc + bit * op.weight(i));
+                if i + 1 < WORD_BITS {
+                    region.assign_advice(|| "acc_in", self.config.acc_in, i + 1, || acc_in.map(F::from))?;
+                    region.assign_advice(|| "acc_out", self.config.acc_out, i + 1, || acc_out.map(F::from))?;
+                }
+            }
+            let input = match input {
+                Some(cell) => cell.copy_advice(|| "拷贝输入", &mut region, self.config.acc_in, WORD_BITS)?,
+                None => region.assign_advice(|| "填写输入", self.config.acc_in, WORD_BITS, || word.map(|w| F::from(w as u64)))?,
+            };
+            let output = region.assign_advice(|| "结果", self.config.acc_out, WORD_BITS, || acc_out.map(F::from))?;
+            Ok((input, output))
+        })
+    }
+
+    /// 填写一个新字, 同时约束其小于2^32
+    pub fn witness(&self, layouter: impl Layouter<F>, word: Value<u32>) -> Result<AssignedCell<F, F>, Error> {
+        self.decompose(layouter, WordOp::RotateLeft(0), word, None).map(|(input, _)| input)
+    }
+
+    /// 对已有的字做移位或循环移位, 输入不小于2^32时约束不满足
+    pub fn apply(&self, layouter: impl Layouter<F>, op: WordOp, word: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let value = word.value().map(low_u32);
+        self.decompose(layouter, op, value, Some(word)).map(|(_, output)| output)
+    }
+
+    /// 计算 (a + b) mod 2^32, 结果经过分解约束小于2^32, a、b须已约束为32位
+    pub fn add(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let sum = a.value().zip(b.value()).map(|(a, b)| low_u32(a).overflowing_add(low_u32(b)));
+        let c = layouter.assign_region(|| "字加法", |mut region| {
+            self.config.q_add.enable(&mut region, 0)?;
+            a.copy_advice(|| "拷贝a", &mut region, self.config.bit, 0)?;
+            b.copy_advice(|| "拷贝b", &mut region, self.config.acc_in, 0)?;
+            region.assign_advice(|| "进位", self.config.bit, 1, || sum.map(|(_, carry)| F::from(carry as u64)))?;
+            region.assign_advice(|| "和", self.config.acc_out, 0, || sum.map(|(c, _)| F::from(c as u64)))
+        })?;
+        self.apply(layouter.namespace(|| "检查和小于2^32"), WordOp::RotateLeft(0), &c)
+    }
+
+    /// 拆成小端的四个字节, 本身不检查字节范围, 须交给会检查范围的芯片(如按位运算芯片)使用
+    pub fn to_bytes(&self, mut layouter: impl Layouter<F>, word: &AssignedCell<F, F>) -> Result<[AssignedCell<F, F>; 4], Error> {
+        let bytes = word.value().map(|w| low_u32(w).to_le_bytes());
+        let byte = |i: usize| bytes.map(|bytes| F::from(bytes[i] as u64));
+        layouter.assign_region(|| "拆分字节", |mut region| {
+            self.config.q_pack.enable(&mut region, 0)?;
+            word.copy_advice(|| "拷贝字", &mut region, self.config.acc_in, 1)?;
+            Ok([
+                region.assign_advice(|| "字节0", self.config.bit, 0, || byte(0))?,
+                region.assign_advice(|| "字节1", self.config.acc_in, 0, || byte(1))?,
+                region.assign_advice(|| "字节2", self.config.acc_out, 0, || byte(2))?,
+                region.assign_advice(|| "字节3", self.config.bit, 1, || byte(3))?,
+            ])
+        })
+    }
+
+    /// 由小端的四个字节拼成字, 字节须已约束为8位
+    pub fn from_bytes(&self, mut layouter: impl Layouter<F>, bytes: &[AssignedCell<F, F>; 4]) -> Result<AssignedCell<F, F>, Error> {
+        let word = bytes.iter().rev().fold(Value::known(F::ZERO), |acc, byte| acc.zip(byte.value()).map(|(acc, byte)| acc * F::from(256) + *byte));
+        layouter.assign_region(|| "拼接字节", |mut region| {
+            self.config.q_pack.enable(&mut region, 0)?;
+            bytes[0].copy_advice(|| "字节0", &mut region, self.config.bit, 0)?;
+            bytes[1].copy_advice(|| "字节1", &mut region, self.config.acc_in, 0)?;
+            bytes[2].copy_advice(|| "字节2", &mut region, self.config.acc_out, 0)?;
+            bytes[3].copy_advice(|| "字节3", &mut region, self.config.bit, 1)?;
+            region.assign_advice(|| "字", self.config.acc_in, 1, || word)
+        })
+    }
+}
+
+#[test]
+fn test_word_ops() {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    const OPS: [WordOp; 5] = [WordOp::RotateLeft(7), WordOp::RotateRight(13), WordOp::ShiftLeft(3), WordOp::ShiftRight(10), WordOp::RotateLeft(16)];
+
+    struct WordCircuit {
+        word: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for WordCircuit {
+        type Config = (WordConfig, SharedColumns);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self { Self { word: Value::unknown() } }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let shared = SharedColumns::configure(meta);
+            (WordChip::configure(meta, &shared), shared)
+        }
+
+        fn synthesize(&self, (config, shared): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = WordChip::construct(config);
+            let word = layouter.assign_region(|| "输入", |mut region| region.assign_advice(|| "字", shared.advice[0], 0, || self.word))?;
+            for (row, op) in OPS.into_iter().enumerate() {
+                let output = chip.apply(layouter.namespace(|| "运算"), op, &word)?;
+                layouter.constrain_instance(output.cell(), shared.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    let run = |word: Fp, outputs: Vec<Fp>| MockProver::run(8, &WordCircuit { word: Value::known(word) }, vec![outputs]).unwrap().verify();
+    for word in [0u32, 1, 0x8000_0001, 0xdead_beef, u32::MAX] {
+        let outputs = OPS.iter().map(|op| Fp::from(op.apply(word) as u64)).collect();
+        assert!(run(Fp::from(word as u64), outputs).is_ok());
+    }
+    assert_eq!(WordOp::RotateLeft(4).apply(0x1234_5678), 0x2345_6781);
+    assert_eq!(WordOp::ShiftRight(32).apply(u32::MAX), 0);
+
+    // 结果错误
+    let mut outputs: Vec<Fp> = OPS.iter().map(|op| Fp::from(op.apply(0xdead_beef) as u64)).collect();
+    outputs[1] += Fp::one();
+    assert!(run(Fp::from(0xdead_beefu64), outputs).is_err());
+    // 输入超过32位
+    let outputs = OPS.iter().map(|op| Fp::from(op.apply(0xdead_beef) as u64)).collect();
+    assert!(run(Fp::from(0x1_dead_beefu64), outputs).is_err());
+}
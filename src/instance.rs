@@ -0,0 +1,287 @@
+use std::fmt;
+
+use ff::PrimeField;
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::pasta::Fp;
+
+/// 清单中每行的编码: 域元素规范表示(32字节小端)的十六进制
+///
+/// 这是公开输入唯一的字节编码, 证明文件、审计日志和证明缓存的哈希、JSON输出和测试语料都经过
+/// [`encode_instance`]和[`instance_to_hex`], 不要在别处直接用`to_repr`或`{:?}`(后者是大端)
+pub const INSTANCE_ENCODING: &str = "le_hex32";
+
+/// 一个公开输入编码后的字节数
+pub const INSTANCE_BYTES: usize = 32;
+
+/// 规范编码: 小于模数的整数的32字节小端表示
+pub fn encode_instance(value: &Fp) -> [u8; INSTANCE_BYTES] {
+    value.to_repr()
+}
+
+/// 解码一个公开输入, 不小于模数的字节串不是规范编码, 拒绝而不是取模
+pub fn decode_instance(bytes: &[u8]) -> Result<Fp, InstanceError> {
+    let repr: [u8; INSTANCE_BYTES] = bytes.try_into().map_err(|_| InstanceError::BadLength(bytes.len()))?;
+    Option::from(Fp::from_repr(repr)).ok_or(InstanceError::NonCanonical)
+}
+
+/// 多个公开输入按行号顺序拼接, 没有长度前缀, 用作调用数据
+///
+/// 每个字都是小端; 要求大端uint256的合约验证器需要逐字翻转
+pub fn encode_instances(values: &[Fp]) -> Vec<u8> {
+    values.iter().flat_map(encode_instance).collect()
+}
+
+pub fn decode_instances(bytes: &[u8]) -> Result<Vec<Fp>, InstanceError> {
+    if bytes.len() % INSTANCE_BYTES != 0 {
+        return Err(InstanceError::BadLength(bytes.len()));
+    }
+    bytes.chunks(INSTANCE_BYTES).map(decode_instance).collect()
+}
+
+/// [`INSTANCE_ENCODING`]的文本形式: 64个小写十六进制字符, 不带0x
+pub fn instance_to_hex(value: &Fp) -> String {
+    encode_instance(value).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 解析[`instance_to_hex`]的输出, 大小写都接受
+pub fn instance_from_hex(hex: &str) -> Result<Fp, InstanceError> {
+    if hex.len() != 2 * INSTANCE_BYTES || !hex.is_ascii() {
+        return Err(InstanceError::BadHex);
+    }
+    let mut bytes = [0u8; INSTANCE_BYTES];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| InstanceError::BadHex)?;
+    }
+    decode_instance(&bytes)
+}
+
+/// 实例行的值类型, 写进清单供外部验证者构造公开输入
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InstanceType {
+    /// 任意域元素
+    #[default]
+    Field,
+    /// 无符号64位整数
+    U64,
+    /// 0或1
+    Bool,
+    /// 哈希或承诺
+    Digest,
+}
+
+impl InstanceType {
+    pub fn name(self) -> &'static str {
+        match self {
+            InstanceType::Field => "field",
+            InstanceType::U64 => "u64",
+            InstanceType::Bool => "bool",
+            InstanceType::Digest => "digest",
+        }
+    }
+}
+
+/// 实例列中一行的用途
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstanceSlot {
+    pub row: usize,
+    pub label: String,
+    pub ty: InstanceType,
+}
+
+impl InstanceSlot {
+    /// 清单中的一行: 行号、名字、类型和编码
+    pub fn to_json(&self) -> String {
+        format!(r#"{{"row": {}, "name": {}, "type": "{}", "encoding": "{}"}}"#, self.row, json_string(&self.label), self.ty.name(), INSTANCE_ENCODING)
+    }
+}
+
+/// JSON字符串字面量
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InstanceError {
+    /// 没有分配过该标签
+    UnknownLabel(String),
+    /// 该标签对应的行还没有填值
+    Unset(String),
+    /// 编码后的字节数不是32的倍数
+    BadLength(usize),
+    /// 字节串不小于模数
+    NonCanonical,
+    /// 不是64个十六进制字符
+    BadHex,
+}
+
+impl fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstanceError::UnknownLabel(label) => write!(f, "未分配的实例标签: {}", label),
+            InstanceError::Unset(label) => write!(f, "实例标签{}还没有填值", label),
+            InstanceError::BadLength(len) => write!(f, "公开输入应为{}字节的整数倍, 实际为{}字节", INSTANCE_BYTES, len),
+            InstanceError::NonCanonical => write!(f, "公开输入不是规范编码: 不小于模数"),
+            InstanceError::BadHex => write!(f, "公开输入应为{}个十六进制字符", 2 * INSTANCE_BYTES),
+        }
+    }
+}
+
+impl std::error::Error for InstanceError {}
+
+/// 实例行分配器: 按标签依次分配实例列的行, 电路和证明者用同一份布局, 避免行号错位
+///
+/// ```
+/// use halo2_fib::instance::InstanceAllocator;
+/// use halo2_proofs::pasta::Fp;
+///
+/// let mut layout = InstanceAllocator::<Fp>::new();
+/// assert_eq!(layout.alloc("n"), 0);
+/// assert_eq!(layout.alloc("target"), 1);
+/// layout.set("n", Fp::from(10)).unwrap();
+/// layout.set("target", Fp::from(55)).unwrap();
+/// assert_eq!(layout.public_inputs().unwrap(), vec![Fp::from(10), Fp::from(55)]);
+/// assert_eq!(layout.manifest(), "0\tn\n1\ttarget\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct InstanceAllocator<F: Field> {
+    slots: Vec<InstanceSlot>,
+    values: Vec<Option<F>>,
+}
+
+impl<F: Field> Default for InstanceAllocator<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Field> InstanceAllocator<F> {
+    pub fn new() -> Self {
+        Self { slots: vec![], values: vec![] }
+    }
+
+    /// 为标签分配下一行, 类型为任意域元素, 同一标签不能重复分配
+    pub fn alloc(&mut self, label: &str) -> usize {
+        self.alloc_typed(label, InstanceType::Field)
+    }
+
+    /// 为标签分配下一行并注明值类型
+    pub fn alloc_typed(&mut self, label: &str, ty: InstanceType) -> usize {
+        assert!(self.row(label).is_none(), "实例标签{}重复分配", label);
+        let row = self.slots.len();
+        self.slots.push(InstanceSlot { row, label: label.to_string(), ty });
+        self.values.push(None);
+        row
+    }
+
+    pub fn row(&self, label: &str) -> Option<usize> {
+        self.slots.iter().find(|slot| slot.label == label).map(|slot| slot.row)
+    }
+
+    pub fn slots(&self) -> &[InstanceSlot] {
+        &self.slots
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// 为标签对应的行填值
+    pub fn set(&mut self, label: &str, value: F) -> Result<(), InstanceError> {
+        let row = self.row(label).ok_or_else(|| InstanceError::UnknownLabel(label.to_string()))?;
+        self.values[row] = Some(value);
+        Ok(())
+    }
+
+    /// 按行号生成公开输入, 每一行都必须已经填值
+    pub fn public_inputs(&self) -> Result<Vec<F>, InstanceError> {
+        self.slots.iter().zip(self.values.iter())
+            .map(|(slot, value)| value.ok_or_else(|| InstanceError::Unset(slot.label.clone())))
+            .collect()
+    }
+
+    /// 每行一条"行号\t标签"的清单
+    pub fn manifest(&self) -> String {
+        self.slots.iter().map(|slot| format!("{}\t{}\n", slot.row, slot.label)).collect()
+    }
+
+    /// JSON数组形式的清单, 每行给出行号、名字、类型和编码
+    pub fn manifest_json(&self) -> String {
+        let rows: Vec<String> = self.slots.iter().map(InstanceSlot::to_json).collect();
+        format!("[{}]", rows.join(", "))
+    }
+}
+
+#[test]
+fn test_instance_allocator() {
+    use halo2_proofs::pasta::Fp;
+
+    let mut layout = InstanceAllocator::<Fp>::new();
+    assert_eq!(layout.alloc("a"), 0);
+    assert_eq!(layout.alloc("b"), 1);
+    assert_eq!(layout.row("b"), Some(1));
+    assert_eq!(layout.row("c"), None);
+
+    layout.set("a", Fp::one()).unwrap();
+    assert_eq!(layout.public_inputs(), Err(InstanceError::Unset("b".to_string())));
+    assert_eq!(layout.set("c", Fp::one()), Err(InstanceError::UnknownLabel("c".to_string())));
+    layout.set("b", Fp::from(2)).unwrap();
+    assert_eq!(layout.public_inputs().unwrap(), vec![Fp::one(), Fp::from(2)]);
+
+    layout.alloc_typed("flag\"", InstanceType::Bool);
+    assert_eq!(
+        layout.manifest_json(),
+        r#"[{"row": 0, "name": "a", "type": "field", "encoding": "le_hex32"}, {"row": 1, "name": "b", "type": "field", "encoding": "le_hex32"}, {"row": 2, "name": "flag\"", "type": "bool", "encoding": "le_hex32"}]"#
+    );
+}
+
+#[test]
+fn test_instance_encoding() {
+    let values = [Fp::zero(), Fp::one(), Fp::from(0x0102), -Fp::one()];
+    for value in &values {
+        let bytes = encode_instance(value);
+        assert_eq!(decode_instance(&bytes), Ok(*value));
+        assert_eq!(instance_from_hex(&instance_to_hex(value)), Ok(*value));
+        assert_eq!(instance_from_hex(&instance_to_hex(value).to_uppercase()), Ok(*value));
+        // Debug输出是同一个整数的大端十六进制
+        let mut big_endian = bytes;
+        big_endian.reverse();
+        let debug: String = big_endian.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(format!("{:?}", value), format!("0x{}", debug));
+    }
+    assert_eq!(instance_to_hex(&Fp::from(0x0102)), format!("0201{}", "0".repeat(60)));
+
+    let calldata = encode_instances(&values);
+    assert_eq!(calldata.len(), values.len() * INSTANCE_BYTES);
+    assert_eq!(decode_instances(&calldata).unwrap(), values);
+    assert_eq!(decode_instances(&calldata[1..]), Err(InstanceError::BadLength(calldata.len() - 1)));
+
+    // 模数本身和全1都不是规范编码
+    let mut modulus = encode_instance(&-Fp::one());
+    modulus[0] += 1;
+    assert_eq!(decode_instance(&modulus), Err(InstanceError::NonCanonical));
+    assert_eq!(decode_instance(&[0xff; 32]), Err(InstanceError::NonCanonical));
+    assert_eq!(instance_from_hex("0x01"), Err(InstanceError::BadHex));
+
+    // 哈希公开输入的地方都按这个编码
+    #[cfg(feature = "prover")]
+    {
+        let mut state = blake2b_simd::Params::new().hash_length(32).personal(b"halo2-fib-inst").to_state();
+        state.update(&(values.len() as u64).to_le_bytes());
+        state.update(&calldata);
+        assert_eq!(crate::audit::instance_hash(&values).as_slice(), state.finalize().as_bytes());
+    }
+}
@@ -0,0 +1,174 @@
+//! 按电路形状缓存的参数、证明密钥和验证密钥, 同一形状的多个命题只做一次密钥生成
+//!
+//! halo2的`ProvingKey`里已经存着fixed列和选择器的多项式和陪集求值, 验证密钥里存着它们的承诺, 证明时不会重算,
+//! 所以不改halo2的前提下, 证明密钥之外没有别的fixed列或选择器承诺可以缓存. 每次证明真正多余的是重新生成
+//! 参数和密钥: 对斐波那契电路这一步和证明本身耗时相当. 验证一侧多余的是重新生成参数和`keygen_vk`,
+//! 后者要对每个fixed列和选择器做一次承诺, 所以验证密钥按"形状和k"另外缓存.
+//! 缓存的键由调用方给出, 通常为"电路名/布局变体", 必须能唯一确定电路形状和k
+//!
+//! 两种缓存的收益用`cargo bench --bench key_reuse -- [n]`测量, 它分别对比每次重新生成和经注册表缓存时的
+//! 单次证明和单次验证耗时
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use halo2_proofs::pasta::EqAffine;
+use halo2_proofs::plonk::{ProvingKey, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+
+/// 一个形状的参数和证明密钥
+pub struct ProvingSetup {
+    pub k: u32,
+    pub params: Params<EqAffine>,
+    pub pk: ProvingKey<EqAffine>,
+}
+
+/// 一个形状在某个k下的参数和验证密钥, 验证密钥里存着fixed列和选择器的承诺
+pub struct VerifyingSetup {
+    pub params: Params<EqAffine>,
+    pub vk: VerifyingKey<EqAffine>,
+}
+
+/// 一种缓存的条目和命中、未命中次数
+struct Entries<T> {
+    entries: Mutex<HashMap<String, Arc<T>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl<T> Default for Entries<T> {
+    fn default() -> Self {
+        Self { entries: Mutex::default(), hits: AtomicUsize::new(0), misses: AtomicUsize::new(0) }
+    }
+}
+
+impl<T> Entries<T> {
+    /// 生成时不持有锁, 两个线程同时请求同一个新键时都会生成一次, 后插入的被丢弃
+    fn get_or_insert<E>(&self, key: &str, generate: impl FnOnce() -> Result<T, E>) -> Result<Arc<T>, E> {
+        if let Some(value) = self.entries.lock().expect("密钥缓存锁被污染").get(key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value.clone());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = Arc::new(generate()?);
+        Ok(self.entries.lock().expect("密钥缓存锁被污染").entry(key.to_string()).or_insert(value).clone())
+    }
+
+    fn len(&self) -> usize {
+        self.entries.lock().expect("密钥缓存锁被污染").len()
+    }
+
+    fn stats(&self) -> (usize, usize) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+
+    fn clear(&self) {
+        self.entries.lock().expect("密钥缓存锁被污染").clear();
+    }
+}
+
+/// 线程安全的密钥缓存
+#[derive(Default)]
+pub struct KeyCache {
+    proving: Entries<ProvingSetup>,
+    verifying: Entries<VerifyingSetup>,
+}
+
+impl KeyCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 已缓存时直接返回, 否则调用keygen生成并缓存
+    ///
+    /// 生成密钥时不持有锁, 两个线程同时请求同一个新形状时都会生成一次, 后插入的被丢弃
+    pub fn get_or_keygen<E>(&self, key: &str, keygen: impl FnOnce() -> Result<ProvingSetup, E>) -> Result<Arc<ProvingSetup>, E> {
+        self.proving.get_or_insert(key, keygen)
+    }
+
+    /// 同[`get_or_keygen`](KeyCache::get_or_keygen), 缓存验证用的参数和验证密钥; key还要包含k
+    pub fn get_or_keygen_vk<E>(&self, key: &str, keygen: impl FnOnce() -> Result<VerifyingSetup, E>) -> Result<Arc<VerifyingSetup>, E> {
+        self.verifying.get_or_insert(key, keygen)
+    }
+
+    /// 缓存的证明密钥数
+    pub fn len(&self) -> usize {
+        self.proving.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 证明密钥命中和未命中的次数
+    pub fn stats(&self) -> (usize, usize) {
+        self.proving.stats()
+    }
+
+    /// 验证密钥命中和未命中的次数
+    pub fn vk_stats(&self) -> (usize, usize) {
+        self.verifying.stats()
+    }
+
+    pub fn clear(&self) {
+        self.proving.clear();
+        self.verifying.clear();
+    }
+}
+
+#[test]
+fn test_key_cache() {
+    use crate::prover::{keygen, prove, setup, verify};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+    use halo2_proofs::pasta::Fp;
+    use halo2_proofs::plonk::{keygen_vk, Circuit, Error};
+
+    let cache = KeyCache::new();
+    let mut generated = 0;
+    // 见证不同、n相同的两个命题共用密钥
+    for (a, b) in [(1, 1), (2, 3)] {
+        let witness = FibWitness::new(Fp::from(a), Fp::from(b));
+        let statement = FibStatement::from_witness(10, &witness).unwrap();
+        let circuit = FibCircuit::new(&statement, &witness);
+        let cached = cache
+            .get_or_keygen("fib/n=10", || {
+                generated += 1;
+                let params = setup(4);
+                let pk = keygen(&params, &circuit.without_witnesses())?;
+                Ok::<_, Error>(ProvingSetup { k: 4, params, pk })
+            })
+            .unwrap();
+        let proof = prove(&cached.params, &cached.pk, &circuit, &statement.public_inputs()).unwrap();
+        verify(&cached.params, cached.pk.get_vk(), &statement.public_inputs(), &proof).into_result().unwrap();
+    }
+    assert_eq!(generated, 1);
+    assert_eq!(cache.stats(), (1, 1));
+    assert_eq!(cache.len(), 1);
+
+    // 验证密钥另外缓存, 第二次验证不再对fixed列和选择器做承诺
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(10, &witness).unwrap();
+    let circuit = FibCircuit::new(&statement, &witness);
+    let proof = {
+        let cached = cache.get_or_keygen("fib/n=10", || Err(Error::Synthesis)).unwrap();
+        prove(&cached.params, &cached.pk, &circuit, &statement.public_inputs()).unwrap()
+    };
+    for _ in 0..2 {
+        let cached = cache
+            .get_or_keygen_vk("fib/n=10/k=4", || {
+                let params = setup(4);
+                let vk = keygen_vk(&params, &circuit.without_witnesses())?;
+                Ok::<_, Error>(VerifyingSetup { params, vk })
+            })
+            .unwrap();
+        assert!(verify(&cached.params, &cached.vk, &statement.public_inputs(), &proof).is_valid());
+    }
+    assert_eq!(cache.vk_stats(), (1, 1));
+
+    // 生成失败时不缓存
+    assert!(cache.get_or_keygen("fib/n=11", || Err(Error::Synthesis)).is_err());
+    assert_eq!(cache.len(), 1);
+    cache.clear();
+    assert!(cache.is_empty());
+}
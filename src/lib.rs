@@ -1 +1,135 @@
-mod fib;
\ No newline at end of file
+//! 基于halo2的斐波那契数列电路
+//!
+//! - [`FibChip`]: 每行约束 a + b = c 的芯片
+//! - [`FibCircuit`]: 证明数列第n项的电路, [`ConstFibCircuit`]为编译期确定步数的版本
+//! - [`FibStatement`]、[`FibWitness`]: 斐波那契电路的公开命题和私有见证
+//! - [`recurrence`]: 二阶线性递推芯片, 以及佩尔、雅各布斯塔尔数列电路
+//! - [`chain`]: 把长数列拆成首尾相接的多段分别证明
+//! - [`step`]: IVC风格的单步电路接口及斐波那契单步实现
+//! - [`audit`]: 证明和验证操作的哈希链审计日志
+//! - [`cache`]: 参数、密钥和证明的缓存目录, 可由环境变量或配置文件指定
+//! - [`cancel`]: 可取消、可限时的证明
+//! - [`capacity`]: 扣除盲化行后的行容量和最小k
+//! - [`cost`]: 按电路配置和本机校准结果估算证明耗时
+//! - [`dev`]: 证明结构分析、约束次数和选择器合并报告等调试工具
+//! - [`dsl`]: 命令行用的命题文本格式, 如`fib(n=30, a=1, b=1) == 832040`
+//! - [`trace`]: 逐格记录合成过程, 可画成HTML表格的教学工具
+//! - `explore`: 在终端里浏览填写矩阵并跳转到约束失败(需要`tui` feature)
+//! - [`fib_merkle`]: 在电路内对数列各项建Poseidon默克尔树, 只公开树根
+//! - [`fib_range`]: 组合斐波那契芯片与范围检查芯片, 证明 F(n) < 2^64
+//! - [`fib_u64`]: 按u64回绕语义计算的斐波那契数列, 每项经范围检查
+//! - [`fib_word`]: 用查找表证明斐波那契词某一位的字符
+//! - [`gadgets`]: 范围检查、比较、带余除法、集合成员、求逆、字节串相等与拼接、查表状态机、按位运算、32位字移位、多项式求值、内积、矩阵乘法、椭圆曲线点加与倍点、窗口法标量乘法、Poseidon哈希、默克尔路径、用门公开、公开值打包等通用芯片
+//! - [`preimage`]: 知道x使得 Poseidon(x) = h 的原像知识证明, 最小的隐私命题, 也是自定义命题的模板
+//! - [`profile`]: 按命名空间和区域统计合成耗时, 可输出火焰图用的folded格式
+//! - `render`: 不依赖系统字体和显示设备的SVG电路布局图(需要`dev` feature)
+//! - [`registry`]: 电路注册表, 按命题里的电路名分派证明和验证
+//! - [`rollup`]: 在默克尔余额树上批量执行转账, 公开前后树根的rollup演示
+//! - [`sequence`]: 由递推式生成芯片和电路的[`sequence_circuit!`]宏
+//! - `service`: 在阻塞线程池中证明和验证的异步接口, 以及有界、可写审计日志的证明队列(需要`server` feature)
+//! - `metrics`: 证明队列的请求数、按k的耗时直方图、队列深度、密钥缓存命中和进程内存, 以Prometheus文本格式输出(需要`server` feature)
+//! - `sealed`: 命题和见证文件的ChaCha20-Poly1305静态加密(需要`encrypt` feature)
+//! - [`segment`]: 由公开开关包含或跳过分段计算, 一套密钥覆盖多种命题, 也可以把n、开关和输出打包成一个公开输入
+//! - [`SharedColumns`]: 组合电路时各芯片共用的列
+//! - [`testing`]: 为新电路生成MockProver和真实证明测试的[`circuit_test!`]宏, 以及两者的差分测试
+//! - [`names`]: 门的ASCII标识和按语言查的显示名
+//! - [`instance`]: 按标签分配实例行的工具, 可导出说明每行含义的清单; 公开输入唯一的字节编码(32字节小端)
+//! - [`key_cache`]: 按电路形状缓存的参数和证明密钥, 同一形状反复证明时只生成一次
+//! - [`preset`]: 按k分Small/Medium/Large的斐波那契电路预设, 一行得到参数和密钥
+//! - [`prover`]: 参数生成、密钥生成、证明和验证的辅助函数, 验证结论[`VerifyOutcome`](prover::VerifyOutcome)区分失败原因, [`prove_zeroizing`](prover::prove_zeroizing)证明后抹掉见证
+//! - [`params_file`]: 公共参数文件的完整性和哈希检查
+//! - [`vk_file`]: 验证密钥的导出与导入, 导出时可附带实例清单JSON
+//! - [`proof_file`]: 带电路标识、k、曲线、版本和验证密钥指纹文件头的证明格式
+//! - [`proof_store`]: 按验证密钥指纹和公开输入寻址的证明仓库, 相同的命题不重复证明
+//!
+//! 默认开启的`prover` feature包含证明侧的工具、注册表、芯片和斐波那契以外的电路.
+//! 关掉默认feature后只编译验证、斐波那契电路、参数文件、验证密钥文件头和证明文件, 供只做验证的wasm构建使用;
+//! 按注册表重建验证密钥的[`verify_with_vk_file`](vk_file::verify_with_vk_file)也需要`prover`
+//!
+//! ```
+//! use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+//! use halo2_fib::prover::{keygen, prove, setup, verify};
+//! use halo2_proofs::pasta::Fp;
+//!
+//! let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+//! let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+//! let params = setup(4);
+//! let pk = keygen(&params, &circuit).unwrap();
+//! let proof = prove(&params, &pk, &circuit, &statement.public_inputs()).unwrap();
+//! assert!(verify(&params, pk.get_vk(), &statement.public_inputs(), &proof).is_valid());
+//! ```
+
+#[cfg(feature = "prover")]
+pub mod audit;
+#[cfg(feature = "prover")]
+pub mod cache;
+#[cfg(feature = "prover")]
+pub mod cancel;
+pub mod capacity;
+#[cfg(feature = "prover")]
+pub mod chain;
+#[cfg(feature = "prover")]
+pub mod cost;
+#[cfg(feature = "prover")]
+pub mod dev;
+pub mod dsl;
+#[cfg(feature = "tui")]
+pub mod explore;
+pub mod fib;
+#[cfg(feature = "prover")]
+pub mod fib_merkle;
+#[cfg(feature = "prover")]
+pub mod fib_range;
+#[cfg(feature = "prover")]
+pub mod fib_u64;
+#[cfg(feature = "prover")]
+pub mod fib_word;
+#[cfg(feature = "prover")]
+pub mod gadgets;
+pub mod instance;
+#[cfg(feature = "prover")]
+pub mod key_cache;
+#[cfg(feature = "server")]
+pub mod metrics;
+#[cfg(feature = "prover")]
+pub mod names;
+pub mod params_file;
+#[cfg(feature = "prover")]
+pub mod preimage;
+#[cfg(feature = "prover")]
+pub mod profile;
+pub mod proof_file;
+#[cfg(feature = "prover")]
+pub mod proof_store;
+#[cfg(feature = "prover")]
+pub mod preset;
+pub mod prover;
+#[cfg(feature = "prover")]
+pub mod recurrence;
+#[cfg(feature = "prover")]
+pub mod registry;
+#[cfg(feature = "dev")]
+pub mod render;
+#[cfg(feature = "prover")]
+pub mod rollup;
+#[cfg(feature = "encrypt")]
+pub mod sealed;
+#[cfg(feature = "prover")]
+pub mod segment;
+#[cfg(feature = "server")]
+pub mod service;
+#[cfg(feature = "prover")]
+pub mod sequence;
+pub mod shared;
+#[cfg(feature = "prover")]
+pub mod step;
+#[cfg(feature = "prover")]
+pub mod testing;
+#[cfg(feature = "prover")]
+pub mod trace;
+pub mod vk_file;
+
+pub use fib::{ConstFibCircuit, FibChip, FibCircuit, FibConfig, FibStatement, FibWitness};
+#[cfg(feature = "prover")]
+pub use recurrence::{JacobsthalCircuit, LinearRecurrenceCircuit, PellCircuit};
+pub use shared::SharedColumns;
@@ -0,0 +1,140 @@
+//! Prometheus文本格式的服务指标(需要`server` feature)
+//!
+//! [`ProveQueue`](crate::service::ProveQueue)按操作和结果记录请求数, 按操作和k记录证明、验证耗时的直方图.
+//! [`ProveQueue::render_metrics`](crate::service::ProveQueue::render_metrics)另外写出队列深度、密钥缓存命中和进程内存,
+//! 输出直接作为`/metrics`的响应体, Content-Type为[`CONTENT_TYPE`]
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// `/metrics`响应的Content-Type
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// 耗时直方图的桶上界(秒), 覆盖小电路的毫秒级验证到大k的分钟级证明
+const LATENCY_BUCKETS: [f64; 12] = [0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 120.0];
+
+/// 累积直方图: 每个桶计数不超过其上界的观测
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    /// (操作, 结果) -> 请求数
+    requests: BTreeMap<(&'static str, &'static str), u64>,
+    /// (操作, k) -> 耗时
+    latency: BTreeMap<(&'static str, u32), Histogram>,
+}
+
+/// 请求计数和耗时直方图, 由证明队列记录
+#[derive(Default)]
+pub struct Metrics(Mutex<Recorded>);
+
+impl Metrics {
+    /// 记录一个请求的结果, 如"ok"、"full"、"rate_limited"
+    pub fn record_request(&self, op: &'static str, result: &'static str) {
+        *self.0.lock().expect("指标锁被污染").requests.entry((op, result)).or_default() += 1;
+    }
+
+    /// 记录一次证明或验证本身的耗时, 不含排队
+    pub fn observe_latency(&self, op: &'static str, k: u32, elapsed: Duration) {
+        self.0.lock().expect("指标锁被污染").latency.entry((op, k)).or_default().observe(elapsed.as_secs_f64());
+    }
+
+    /// 某个操作某种结果的请求数
+    pub fn requests(&self, op: &str, result: &str) -> u64 {
+        let recorded = self.0.lock().expect("指标锁被污染");
+        recorded.requests.iter().filter(|((o, r), _)| *o == op && *r == result).map(|(_, count)| count).sum()
+    }
+
+    /// 写出请求计数和耗时直方图
+    pub fn render(&self, out: &mut String) {
+        let recorded = self.0.lock().expect("指标锁被污染");
+        header(out, "halo2_fib_requests_total", "经证明队列的请求数, 按操作和结果分", "counter");
+        for ((op, result), count) in &recorded.requests {
+            let _ = writeln!(out, "halo2_fib_requests_total{{op=\"{}\",result=\"{}\"}} {}", op, result, count);
+        }
+        header(out, "halo2_fib_latency_seconds", "证明和验证的耗时, 不含排队, 按操作和k分", "histogram");
+        for ((op, k), histogram) in &recorded.latency {
+            for (bound, bucket) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(out, "halo2_fib_latency_seconds_bucket{{op=\"{}\",k=\"{}\",le=\"{}\"}} {}", op, k, bound, bucket);
+            }
+            let _ = writeln!(out, "halo2_fib_latency_seconds_bucket{{op=\"{}\",k=\"{}\",le=\"+Inf\"}} {}", op, k, histogram.count);
+            let _ = writeln!(out, "halo2_fib_latency_seconds_sum{{op=\"{}\",k=\"{}\"}} {}", op, k, histogram.sum);
+            let _ = writeln!(out, "halo2_fib_latency_seconds_count{{op=\"{}\",k=\"{}\"}} {}", op, k, histogram.count);
+        }
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// 写出一个不带标签的gauge
+pub fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    header(out, name, help, "gauge");
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// 写出一组按标签区分的计数, labels为(标签名, 标签值, 计数)
+pub fn counters(out: &mut String, name: &str, help: &str, labels: &[(&str, &str, usize)]) {
+    header(out, name, help, "counter");
+    for (label, value, count) in labels {
+        let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, value, count);
+    }
+}
+
+/// 进程的常驻内存(字节), 从/proc/self/status读取, 其他平台为`None`
+pub fn resident_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.trim_start_matches("VmRSS:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[test]
+fn test_render_metrics() {
+    let metrics = Metrics::default();
+    metrics.record_request("prove", "ok");
+    metrics.record_request("prove", "ok");
+    metrics.record_request("prove", "full");
+    metrics.observe_latency("prove", 4, Duration::from_millis(30));
+    metrics.observe_latency("prove", 4, Duration::from_secs(3));
+    assert_eq!(metrics.requests("prove", "ok"), 2);
+    assert_eq!(metrics.requests("verify", "ok"), 0);
+
+    let mut out = String::new();
+    metrics.render(&mut out);
+    assert!(out.contains("# TYPE halo2_fib_requests_total counter\n"));
+    assert!(out.contains("halo2_fib_requests_total{op=\"prove\",result=\"full\"} 1\n"));
+    // 桶是累积的
+    assert!(out.contains("halo2_fib_latency_seconds_bucket{op=\"prove\",k=\"4\",le=\"0.01\"} 0\n"));
+    assert!(out.contains("halo2_fib_latency_seconds_bucket{op=\"prove\",k=\"4\",le=\"0.05\"} 1\n"));
+    assert!(out.contains("halo2_fib_latency_seconds_bucket{op=\"prove\",k=\"4\",le=\"5\"} 2\n"));
+    assert!(out.contains("halo2_fib_latency_seconds_bucket{op=\"prove\",k=\"4\",le=\"+Inf\"} 2\n"));
+    assert!(out.contains("halo2_fib_latency_seconds_count{op=\"prove\",k=\"4\"} 2\n"));
+
+    gauge(&mut out, "halo2_fib_queue_depth", "正在证明和排队的请求数", 3);
+    assert!(out.ends_with("# TYPE halo2_fib_queue_depth gauge\nhalo2_fib_queue_depth 3\n"));
+    if cfg!(target_os = "linux") {
+        assert!(resident_memory_bytes().unwrap() > 0);
+    }
+}
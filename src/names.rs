@@ -0,0 +1,109 @@
+//! 门的稳定标识和显示名
+//!
+//! `create_gate`只传ASCII标识, 它会出现在MockProver的失败信息、dot图和[`Expected::Gate`](crate::testing::Expected::Gate)里,
+//! 程序按标识匹配, 改显示名不会影响测试和工具. 约束名同样只用ASCII, 一般直接写约束式.
+//! 给人看的名字按语言在[`GATES`]里查
+
+/// 显示名的语言
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Locale {
+    /// 按`LANG`环境变量选择, 以zh开头或未设置时为中文
+    pub fn from_env() -> Self {
+        match std::env::var("LANG") {
+            Ok(lang) if !lang.is_empty() && !lang.starts_with("zh") => Locale::En,
+            _ => Locale::Zh,
+        }
+    }
+}
+
+/// 一个门的标识和各语言的显示名
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GateName {
+    pub id: &'static str,
+    pub zh: &'static str,
+    pub en: &'static str,
+}
+
+impl GateName {
+    pub fn display(&self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::Zh => self.zh,
+            Locale::En => self.en,
+        }
+    }
+}
+
+/// 库里所有芯片的门
+pub const GATES: &[GateName] = &[
+    GateName { id: "fib_add", zh: "斐波那契(相加)", en: "Fibonacci (add)" },
+    GateName { id: "fib_u64_add", zh: "u64斐波那契(回绕相加)", en: "u64 Fibonacci (wrapping add)" },
+    GateName { id: "linear_recurrence", zh: "线性递推", en: "linear recurrence" },
+    GateName { id: "segment_bool", zh: "开关(布尔)", en: "flag (boolean)" },
+    GateName { id: "segment_step", zh: "开关(分段相加)", en: "flag (gated add)" },
+    GateName { id: "segment_select", zh: "开关(选择)", en: "flag (select)" },
+    GateName { id: "bitwise_nibble", zh: "按位运算(组合半字节)", en: "bitwise (combine nibbles)" },
+    GateName { id: "bytes_pack", zh: "字节串打包", en: "byte string packing" },
+    GateName { id: "bytes_active_prefix", zh: "字节串打包(活跃在前)", en: "byte string packing (active prefix)" },
+    GateName { id: "bytes_concat", zh: "字节串拼接", en: "byte string concatenation" },
+    GateName { id: "compare_diff", zh: "比较(求差)", en: "comparison (difference)" },
+    GateName { id: "div_rem", zh: "带余除法", en: "division with remainder" },
+    GateName { id: "dot_product", zh: "内积(乘加)", en: "dot product (multiply-add)" },
+    GateName { id: "ecc_add", zh: "椭圆曲线(完备点加)", en: "elliptic curve (complete addition)" },
+    GateName { id: "ecc_double", zh: "椭圆曲线(倍点)", en: "elliptic curve (doubling)" },
+    GateName { id: "ecc_on_curve", zh: "椭圆曲线(点在曲线上)", en: "elliptic curve (point on curve)" },
+    GateName { id: "ecc_to_affine", zh: "椭圆曲线(转为仿射坐标)", en: "elliptic curve (to affine)" },
+    GateName { id: "horner", zh: "秦九韶求值", en: "Horner evaluation" },
+    GateName { id: "inverse", zh: "求逆", en: "inverse" },
+    GateName { id: "matmul_block", zh: "矩阵乘法(分块乘加)", en: "matrix multiplication (block multiply-add)" },
+    GateName { id: "merkle_swap", zh: "默克尔路径(按方向位交换)", en: "Merkle path (swap by direction bit)" },
+    GateName { id: "pack_accumulate", zh: "打包累加", en: "packing accumulator" },
+    GateName { id: "public_instance_eq", zh: "公开(实例相等)", en: "expose (equal to instance)" },
+    GateName { id: "range_check_zero", zh: "范围检查(剩余为0)", en: "range check (remainder is zero)" },
+    GateName { id: "scalar_window", zh: "标量乘法(窗口分解)", en: "scalar multiplication (window decomposition)" },
+    GateName { id: "window_select", zh: "标量乘法(独热选择倍数)", en: "scalar multiplication (one-hot multiple selection)" },
+    GateName { id: "non_member_diff", zh: "非成员(求差)", en: "non-membership (difference)" },
+    GateName { id: "word_shift", zh: "字移位(逐位累加)", en: "word shift (bitwise accumulate)" },
+    GateName { id: "word_add", zh: "字加法(模2^32)", en: "word add (mod 2^32)" },
+    GateName { id: "word_concat", zh: "字拼接(小端字节)", en: "word concatenation (little-endian bytes)" },
+];
+
+pub fn gate_name(id: &str) -> Option<&'static GateName> {
+    GATES.iter().find(|gate| gate.id == id)
+}
+
+/// 门的显示名, 表中没有的门(例如示例和[`sequence_circuit!`](crate::sequence_circuit)生成的)显示标识本身
+pub fn gate_display(id: &str, locale: Locale) -> &str {
+    gate_name(id).map_or(id, |gate| gate.display(locale))
+}
+
+#[test]
+fn test_gate_names() {
+    use halo2_proofs::pasta::Fp;
+    use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+
+    use crate::fib_range::FibRangeCircuit;
+
+    for (i, gate) in GATES.iter().enumerate() {
+        assert!(gate.id.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_'), "{}", gate.id);
+        assert!(GATES[..i].iter().all(|other| other.id != gate.id), "{}重复", gate.id);
+    }
+    assert_eq!(gate_display("fib_add", Locale::Zh), "斐波那契(相加)");
+    assert_eq!(gate_display("fib_add", Locale::En), "Fibonacci (add)");
+    assert_eq!(gate_display("fib_add_v2", Locale::Zh), "fib_add_v2");
+
+    // 电路里的门名和约束名都是ASCII, 门都在表里
+    let mut cs = ConstraintSystem::<Fp>::default();
+    FibRangeCircuit::<Fp>::configure(&mut cs);
+    for gate in cs.gates() {
+        assert!(gate_name(gate.name()).is_some(), "{}不在表中", gate.name());
+        for i in 0..gate.polynomials().len() {
+            assert!(gate.constraint_name(i).is_ascii(), "{}", gate.constraint_name(i));
+        }
+    }
+}
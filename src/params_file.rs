@@ -0,0 +1,148 @@
+//! 公共参数文件的检查: 使用前确认文件完整、每个点都能解码, 并与公布的哈希一致
+//!
+//! 文件为halo2_proofs `Params::write`的输出: k(u32小端), 2^k个g、2^k个g_lagrange、w和u, 每个点为32字节压缩编码.
+//! 被截断或损坏的文件直接交给`Params::read`时, 错误要到证明或验证时才以难懂的方式出现.
+//! 哈希为整个文件的blake2b-256, 与`b2sum -l 256`的输出相同
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use halo2_proofs::pasta::EqAffine;
+use halo2_proofs::poly::commitment::Params;
+
+/// 每个压缩点的字节数
+const POINT_LEN: u64 = 32;
+
+/// 文件头中k的上限, 更大的k多半是文件损坏
+pub const MAX_PARAMS_K: u32 = 32;
+
+#[derive(Debug)]
+pub enum ParamsFileError {
+    Io(io::Error),
+    /// 文件头中的k不合法
+    BadK(u32),
+    /// 文件长度与k不符, 被截断或有多余的字节
+    Length { k: u32, expected: u64, got: u64 },
+    /// 有点无法解码
+    BadPoint(String),
+    WrongK { expected: u32, got: u32 },
+    HashMismatch { expected: [u8; 32], got: [u8; 32] },
+}
+
+impl fmt::Display for ParamsFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsFileError::Io(e) => write!(f, "读取参数文件失败: {}", e),
+            ParamsFileError::BadK(k) => write!(f, "参数文件头中的k = {}不合法", k),
+            ParamsFileError::Length { k, expected, got } => write!(f, "k = {}的参数文件应为{}字节, 实际为{}字节", k, expected, got),
+            ParamsFileError::BadPoint(reason) => write!(f, "参数文件中有无法解码的点: {}", reason),
+            ParamsFileError::WrongK { expected, got } => write!(f, "参数文件的k应为{}, 实际为{}", expected, got),
+            ParamsFileError::HashMismatch { expected, got } => write!(f, "参数文件哈希不一致: 应为{}, 实际为{}", to_hex(expected), to_hex(got)),
+        }
+    }
+}
+
+impl std::error::Error for ParamsFileError {}
+
+impl From<io::Error> for ParamsFileError {
+    fn from(e: io::Error) -> Self {
+        ParamsFileError::Io(e)
+    }
+}
+
+pub fn to_hex(hash: &[u8; 32]) -> String {
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 由64个十六进制字符解析哈希, 大小写均可
+pub fn parse_hash(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+/// 整个文件的blake2b-256
+pub fn params_hash(bytes: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(blake2b_simd::Params::new().hash_length(32).hash(bytes).as_bytes());
+    hash
+}
+
+/// k对应的文件长度
+pub fn params_len(k: u32) -> u64 {
+    4 + ((2u64 << k) + 2) * POINT_LEN
+}
+
+/// 参数序列化为字节
+pub fn params_bytes(params: &Params<EqAffine>) -> Vec<u8> {
+    let mut bytes = vec![];
+    params.write(&mut bytes).expect("写入内存失败");
+    bytes
+}
+
+/// 检查通过的参数
+pub struct CheckedParams {
+    pub k: u32,
+    pub hash: [u8; 32],
+    pub params: Params<EqAffine>,
+}
+
+/// 依次检查k、长度、各点的编码, 再比对期望的k和哈希, 全部通过才返回参数
+pub fn check_params(bytes: &[u8], expected_k: Option<u32>, expected_hash: Option<&[u8; 32]>) -> Result<CheckedParams, ParamsFileError> {
+    let got = bytes.len() as u64;
+    let k = match bytes.get(..4) {
+        Some(k) => u32::from_le_bytes(k.try_into().expect("k为4字节")),
+        None => return Err(ParamsFileError::Length { k: 0, expected: params_len(0), got }),
+    };
+    if k == 0 || k > MAX_PARAMS_K {
+        return Err(ParamsFileError::BadK(k));
+    }
+    if got != params_len(k) {
+        return Err(ParamsFileError::Length { k, expected: params_len(k), got });
+    }
+    let params = Params::read(&mut &bytes[..]).map_err(|e| ParamsFileError::BadPoint(e.to_string()))?;
+    if let Some(expected) = expected_k.filter(|&expected| expected != k) {
+        return Err(ParamsFileError::WrongK { expected, got: k });
+    }
+    let hash = params_hash(bytes);
+    if let Some(expected) = expected_hash.filter(|&expected| *expected != hash) {
+        return Err(ParamsFileError::HashMismatch { expected: *expected, got: hash });
+    }
+    Ok(CheckedParams { k, hash, params })
+}
+
+/// 读取并检查参数文件
+pub fn read_params_file(path: &Path, expected_k: Option<u32>, expected_hash: Option<&[u8; 32]>) -> Result<CheckedParams, ParamsFileError> {
+    check_params(&fs::read(path)?, expected_k, expected_hash)
+}
+
+#[test]
+fn test_check_params() {
+    use crate::prover::{params_k, setup};
+
+    let bytes = params_bytes(&setup(4));
+    assert_eq!(bytes.len() as u64, params_len(4));
+    let hash = params_hash(&bytes);
+    assert_eq!(parse_hash(&to_hex(&hash)), Some(hash));
+    let checked = check_params(&bytes, Some(4), Some(&hash)).unwrap();
+    assert_eq!((checked.k, checked.hash), (4, hash));
+    assert_eq!(params_k(&checked.params), 4);
+
+    assert!(matches!(check_params(&bytes[..bytes.len() - 1], None, None), Err(ParamsFileError::Length { k: 4, .. })));
+    assert!(matches!(check_params(&bytes[..2], None, None), Err(ParamsFileError::Length { .. })));
+    assert!(matches!(check_params(&[0xff; 4], None, None), Err(ParamsFileError::BadK(u32::MAX))));
+    assert!(matches!(check_params(&bytes, Some(5), None), Err(ParamsFileError::WrongK { expected: 5, got: 4 })));
+    assert!(matches!(check_params(&bytes, None, Some(&[0; 32])), Err(ParamsFileError::HashMismatch { .. })));
+
+    // x坐标超出域的点无法解码
+    let mut corrupted = bytes.clone();
+    corrupted[4..36].fill(0xff);
+    assert!(matches!(check_params(&corrupted, None, None), Err(ParamsFileError::BadPoint(_))));
+}
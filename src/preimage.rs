@@ -0,0 +1,82 @@
+//! 哈希原像的知识证明: 证明方知道x使得 Poseidon(x) = h, 只公开h
+//!
+//! 这是最小的隐私命题, 也可以当作自定义命题的模板: 在[`PreimageCircuit::synthesize`]里把x换成自己的见证,
+//! 哈希换成自己的计算, 最后把结果约束到实例列; 再仿照[`CircuitRegistry::builtin`](crate::registry::CircuitRegistry::builtin)
+//! 登记后即可在命令行用`prove --statement "preimage(x=42)"`证明, 用`verify --statement "preimage() == h"`验证
+
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::*;
+
+use crate::gadgets::poseidon::{hash1, PoseidonChip, PoseidonConfig};
+use crate::prover::{zeroize_value, ZeroizeWitness};
+use crate::shared::SharedColumns;
+
+/// Poseidon芯片需要的行数之外还有盲化行, 2^7行足够
+pub const PREIMAGE_K: u32 = 7;
+
+/// 电路外计算公开的哈希值
+pub fn preimage_hash(x: Fp) -> Fp {
+    hash1(x)
+}
+
+#[derive(Clone, Debug)]
+pub struct PreimageConfig {
+    pub poseidon: PoseidonConfig,
+    pub shared: SharedColumns,
+}
+
+/// 证明知道h的原像, 公开输入只有实例第0行的h
+pub struct PreimageCircuit {
+    x: Value<Fp>,
+}
+
+impl PreimageCircuit {
+    pub fn new(x: Fp) -> Self {
+        Self { x: Value::known(x) }
+    }
+}
+
+impl ZeroizeWitness for PreimageCircuit {
+    fn zeroize_witness(&mut self) {
+        zeroize_value(&mut self.x);
+    }
+}
+
+impl Circuit<Fp> for PreimageCircuit {
+    type Config = PreimageConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { x: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let poseidon = PoseidonChip::configure(meta, &shared);
+        PreimageConfig { poseidon, shared }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        // 1. 填写私有见证
+        let x = layouter.assign_region(|| "原像", |mut region| region.assign_advice(|| "x", config.shared.advice[0], 0, || self.x))?;
+        // 2. 在电路内计算
+        let h = PoseidonChip::construct(config.poseidon.clone()).hash1(layouter.namespace(|| "哈希原像"), &x)?;
+        // 3. 公开结果
+        layouter.constrain_instance(h.cell(), config.shared.instance, 0)
+    }
+}
+
+#[test]
+fn test_preimage() {
+    use halo2_proofs::dev::MockProver;
+
+    let h = preimage_hash(Fp::from(42));
+    assert_ne!(h, preimage_hash(Fp::from(43)));
+
+    let prover = MockProver::run(PREIMAGE_K, &PreimageCircuit::new(Fp::from(42)), vec![vec![h]]).unwrap();
+    prover.assert_satisfied();
+    // 原像不对
+    let prover = MockProver::run(PREIMAGE_K, &PreimageCircuit::new(Fp::from(43)), vec![vec![h]]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,155 @@
+//! 斐波那契电路的预设配置, 一行拿到能用的参数和密钥
+//!
+//! 预设只决定k(都会预先检查见证): 电路固定为[`FibCircuit`]的三列布局, 只公开第n项, transcript固定为Blake2b.
+//! 需要别的k时直接构造[`FibOptions`], 需要别的布局或暴露方式时使用对应的电路和[`crate::prover`]
+
+use std::fmt;
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{Circuit, Error, ProvingKey};
+use halo2_proofs::poly::commitment::Params;
+
+use crate::capacity::row_budget;
+use crate::prover::{keygen, prove, setup, verify, VerifyOutcome};
+use crate::fib::WitnessError;
+use crate::{FibCircuit, FibStatement, FibWitness};
+
+/// 预设规模, 只决定k
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FibPreset {
+    /// k = 8, 适合测试和演示
+    Small,
+    /// k = 14
+    Medium,
+    /// k = 20, 可证明到百万项
+    Large,
+}
+
+/// 手工组合的选项, 预设只是几组默认值
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FibOptions {
+    pub k: u32,
+    /// 证明前先用[`FibCircuit::check_witness`]检查见证
+    pub check_witness: bool,
+}
+
+impl FibPreset {
+    pub fn options(self) -> FibOptions {
+        let k = match self {
+            FibPreset::Small => 8,
+            FibPreset::Medium => 14,
+            FibPreset::Large => 20,
+        };
+        FibOptions { k, check_witness: true }
+    }
+
+    /// 能放下第n项的最小预设, 超出Large时返回None
+    pub fn for_n(n: usize) -> Option<Self> {
+        [FibPreset::Small, FibPreset::Medium, FibPreset::Large].into_iter().find(|preset| n <= preset.options().max_n())
+    }
+}
+
+impl FibOptions {
+    /// 在k下能证明的最大n: 数列占n-2行
+    pub fn max_n(&self) -> usize {
+        row_budget::<FibCircuit<Fp>>(self.k).usable_rows + 2
+    }
+
+    /// 为命题生成参数和密钥, 电路形状只和n有关
+    pub fn keygen(&self, statement: &FibStatement<Fp>) -> Result<FibSetup, Error> {
+        if statement.n > self.max_n() {
+            return Err(Error::NotEnoughRowsAvailable { current_k: self.k });
+        }
+        let params = setup(self.k);
+        let circuit = FibCircuit::new(statement, &FibWitness::new(Fp::zero(), Fp::zero())).without_witnesses();
+        let pk = keygen(&params, &circuit)?;
+        Ok(FibSetup { options: *self, statement: *statement, params, pk })
+    }
+}
+
+/// 用预设证明失败的原因
+#[derive(Debug)]
+pub enum PresetError {
+    /// 见证与命题不符, 没有开始证明
+    Witness(WitnessError),
+    Plonk(Error),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Witness(e) => write!(f, "见证与命题不符: {}", e),
+            PresetError::Plonk(e) => write!(f, "生成证明失败: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl From<WitnessError> for PresetError {
+    fn from(e: WitnessError) -> Self {
+        PresetError::Witness(e)
+    }
+}
+
+impl From<Error> for PresetError {
+    fn from(e: Error) -> Self {
+        PresetError::Plonk(e)
+    }
+}
+
+/// 某个命题对应的参数和密钥
+pub struct FibSetup {
+    pub options: FibOptions,
+    pub statement: FibStatement<Fp>,
+    pub params: Params<EqAffine>,
+    pub pk: ProvingKey<EqAffine>,
+}
+
+impl FibSetup {
+    /// 用见证生成证明, 公开输入由命题决定, 开启check_witness时见证不满足命题返回[`PresetError::Witness`]
+    ///
+    /// ```
+    /// use halo2_fib::preset::FibPreset;
+    /// use halo2_fib::{FibStatement, FibWitness};
+    /// use halo2_proofs::pasta::Fp;
+    ///
+    /// let witness = FibWitness::new(Fp::one(), Fp::one());
+    /// let statement = FibStatement::from_witness(100, &witness).unwrap();
+    /// let setup = FibPreset::Small.options().keygen(&statement).unwrap();
+    /// let proof = setup.prove(&witness).unwrap();
+    /// assert!(setup.verify(&proof).is_valid());
+    /// ```
+    pub fn prove(&self, witness: &FibWitness<Fp>) -> Result<Vec<u8>, PresetError> {
+        let circuit = FibCircuit::new(&self.statement, witness);
+        let public_inputs = self.statement.public_inputs();
+        if self.options.check_witness {
+            circuit.check_witness(&public_inputs)?;
+        }
+        Ok(prove(&self.params, &self.pk, &circuit, &public_inputs)?)
+    }
+
+    pub fn verify(&self, proof: &[u8]) -> VerifyOutcome {
+        verify(&self.params, self.pk.get_vk(), &self.statement.public_inputs(), proof)
+    }
+}
+
+#[test]
+fn test_presets() {
+    assert_eq!(FibPreset::for_n(100), Some(FibPreset::Small));
+    assert_eq!(FibPreset::for_n(1000), Some(FibPreset::Medium));
+    assert_eq!(FibPreset::for_n(1 << 21), None);
+
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(1000, &witness).unwrap();
+    assert!(FibPreset::Small.options().keygen(&statement).is_err());
+
+    let statement = FibStatement::from_witness(50, &witness).unwrap();
+    let setup = FibPreset::Small.options().keygen(&statement).unwrap();
+    let proof = setup.prove(&witness).unwrap();
+    assert!(setup.verify(&proof).is_valid());
+    assert!(matches!(setup.verify(&proof[1..]), VerifyOutcome::BadProof { .. }));
+    // 见证与命题不符时不开始证明
+    let err = setup.prove(&FibWitness::new(Fp::one(), Fp::from(2))).unwrap_err();
+    assert!(matches!(err, PresetError::Witness(WitnessError::Mismatch { row: 0, .. })));
+}
@@ -0,0 +1,248 @@
+//! 合成过程的耗时剖析: 组合大电路时看清时间花在斐波那契行还是哈希芯片上
+//!
+//! [`profile`]用自己的`Assignment`合成一遍电路, 把相邻两次区域进出或命名空间进出之间的时间记到当时所在的调用栈上.
+//! 栈由layouter的命名空间和区域名组成; 区域内的时间包括计算见证值, 区域外的时间主要是floor planner测量区域形状时
+//! 先跑一遍的区域闭包. 结果可以按栈汇总成表格, 也可以输出flamegraph.pl和inferno能读的folded格式
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed, FloorPlanner, Instance, Selector};
+
+/// 栈底的名字
+pub const ROOT: &str = "synthesize";
+
+/// 一个调用栈上的统计, 只算直接记在这个栈上的部分
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub time: Duration,
+    /// 进入次数, 栈顶为区域时是区域被分配的次数
+    pub calls: usize,
+    /// 填写的advice和fixed单元格数
+    pub cells: usize,
+}
+
+/// 剖析结果, 键为从[`ROOT`]开始的调用栈
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub frames: BTreeMap<Vec<String>, Frame>,
+}
+
+impl Profile {
+    pub fn total(&self) -> Duration {
+        self.frames.values().map(|frame| frame.time).sum()
+    }
+
+    /// 栈及其所有子栈的合计
+    pub fn inclusive(&self, stack: &[String]) -> Frame {
+        self.frames.iter().filter(|(s, _)| s.starts_with(stack)).fold(Frame::default(), |acc, (s, frame)| Frame {
+            time: acc.time + frame.time,
+            // 进入次数只算栈本身
+            calls: if s.len() == stack.len() { frame.calls } else { acc.calls },
+            cells: acc.cells + frame.cells,
+        })
+    }
+
+    /// folded格式, 每行"栈;用分号;连接 微秒数"
+    pub fn folded(&self) -> String {
+        let mut out = String::new();
+        for (stack, frame) in &self.frames {
+            let micros = frame.time.as_micros();
+            if micros > 0 {
+                let names: Vec<String> = stack.iter().map(|name| name.replace(';', ",")).collect();
+                writeln!(out, "{} {}", names.join(";"), micros).expect("写入字符串失败");
+            }
+        }
+        out
+    }
+
+    /// 按调用栈缩进的表格, 同层按合计耗时从大到小排列
+    pub fn report(&self) -> String {
+        let total = self.total().as_secs_f64().max(f64::MIN_POSITIVE);
+        let mut out = format!("{:<48} {:>8} {:>10} {:>10} {:>10} {:>7}\n", "调用栈", "次数", "单元格", "自身(ms)", "合计(ms)", "占比");
+        self.report_children(&[ROOT.to_string()], total, &mut out);
+        out
+    }
+
+    fn report_children(&self, stack: &[String], total: f64, out: &mut String) {
+        let own = self.frames.get(stack).copied().unwrap_or_default();
+        let sum = self.inclusive(stack);
+        let name = format!("{}{}", "  ".repeat(stack.len() - 1), stack.last().expect("栈非空"));
+        writeln!(
+            out,
+            "{:<48} {:>8} {:>10} {:>10.2} {:>10.2} {:>6.1}%",
+            name, sum.calls, sum.cells, own.time.as_secs_f64() * 1e3, sum.time.as_secs_f64() * 1e3, sum.time.as_secs_f64() / total * 100.0
+        )
+        .expect("写入字符串失败");
+        let mut children: Vec<Vec<String>> = self.frames.keys().filter(|s| s.len() > stack.len() && s.starts_with(stack)).map(|s| s[..stack.len() + 1].to_vec()).collect();
+        children.dedup();
+        children.sort_by_key(|child| std::cmp::Reverse(self.inclusive(child).time));
+        for child in children {
+            self.report_children(&child, total, out);
+        }
+    }
+}
+
+/// 计时的`Assignment`
+struct Profiler {
+    instances: Vec<Vec<Fp>>,
+    stack: Vec<String>,
+    in_region: bool,
+    last: Instant,
+    profile: Profile,
+}
+
+impl Profiler {
+    /// 把上次记账以来的时间记到当前栈上
+    fn mark(&mut self) {
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.frame().time += elapsed;
+        self.last = now;
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.profile.frames.entry(self.stack.clone()).or_default()
+    }
+
+    fn enter(&mut self, name: String) {
+        self.mark();
+        self.stack.push(name);
+        self.frame().calls += 1;
+    }
+
+    fn exit(&mut self) {
+        self.mark();
+        self.stack.pop();
+    }
+}
+
+impl Assignment<Fp> for Profiler {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.enter(name().into());
+        self.in_region = true;
+    }
+
+    fn exit_region(&mut self) {
+        self.exit();
+        self.in_region = false;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, _: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        Ok(())
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<Fp>, Error> {
+        Ok(self.instances.get(column.index()).and_then(|values| values.get(row)).map_or(Value::unknown(), |v| Value::known(*v)))
+    }
+
+    fn assign_advice<V, VR, A, AR>(&mut self, _: A, _: Column<Advice>, _: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // 见证值在这里才真正计算
+        let _ = to().map(|v| v.into().evaluate());
+        self.frame().cells += 1;
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(&mut self, _: A, _: Column<Fixed>, _: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let _ = to().map(|v| v.into().evaluate());
+        self.frame().cells += 1;
+        Ok(())
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(&mut self, _: Column<Fixed>, _: usize, _: Value<Assigned<Fp>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // 区域内的命名空间只是给单元格起名, 不单独计时
+        if !self.in_region {
+            self.enter(name().into());
+        }
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {
+        if !self.in_region {
+            self.exit();
+        }
+    }
+}
+
+/// 合成一遍电路并计时, 不检查约束也不限制行数
+///
+/// ```
+/// use halo2_fib::profile::profile;
+/// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+/// use halo2_proofs::pasta::Fp;
+///
+/// let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+/// let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+/// let profile = profile(&circuit, vec![statement.public_inputs()]).unwrap();
+/// assert!(profile.frames.values().map(|frame| frame.cells).sum::<usize>() > 0);
+/// println!("{}", profile.report());
+/// ```
+pub fn profile<C: Circuit<Fp>>(circuit: &C, instances: Vec<Vec<Fp>>) -> Result<Profile, Error> {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    let config = C::configure(&mut cs);
+    let mut profiler = Profiler { instances, stack: vec![ROOT.to_string()], in_region: false, last: Instant::now(), profile: Profile::default() };
+    profiler.frame().calls = 1;
+    C::FloorPlanner::synthesize(&mut profiler, circuit, config, cs.constants().clone())?;
+    profiler.mark();
+    Ok(profiler.profile)
+}
+
+#[test]
+fn test_profile() {
+    use crate::fib_merkle::{merkle_root, FibMerkleCircuit};
+    use crate::recurrence::recurrence_terms;
+
+    let (a, b) = (Fp::one(), Fp::one());
+    let root = merkle_root(&recurrence_terms(1, 1, a, b, 8));
+    let profile = profile(&FibMerkleCircuit::new(a, b, 8), vec![vec![root]]).unwrap();
+    let root_stack = [ROOT.to_string()];
+    assert_eq!(profile.inclusive(&root_stack).time, profile.total());
+    assert_eq!(profile.inclusive(&root_stack).calls, 1);
+
+    // 每个栈都从ROOT开始, folded每行一个栈
+    assert!(profile.frames.keys().all(|stack| stack[0] == ROOT));
+    assert!(profile.frames.keys().any(|stack| stack.len() > 1));
+    for line in profile.folded().lines() {
+        let (stack, micros) = line.rsplit_once(' ').unwrap();
+        assert!(stack.starts_with(ROOT));
+        assert!(micros.parse::<u128>().unwrap() > 0);
+    }
+    // 表头之后每个栈一行
+    let report = profile.report();
+    assert!(report.lines().nth(1).unwrap().starts_with(ROOT));
+    assert_eq!(report.lines().count(), 1 + profile.frames.len());
+}
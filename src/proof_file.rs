@@ -0,0 +1,321 @@
+//! 带文件头的证明格式: 魔数和版本, 电路标识、布局变体、k、曲线、crate版本、时间戳和验证密钥指纹.
+//! 验证前先逐项比对, 混用不同电路、参数或版本的部署会明确报错, 而不是莫名其妙地验证失败
+//!
+//! 第2版的布局(整数为小端):
+//!
+//! | 字段 | 长度 |
+//! |------|------|
+//! | 魔数`FIBPF\0\0` | 7 |
+//! | 版本 | 1 |
+//! | 验证密钥指纹 | 32 |
+//! | k | 4 |
+//! | 时间戳(unix秒) | 8 |
+//! | 电路标识、布局变体、曲线、crate版本 | 各1字节长度加UTF-8 |
+//!
+//! 第1版的文件头只有魔数`FIBPF\0\0\x01`和32字节指纹, 可以用[`migrate_v1`]补齐元数据升级到第2版, 证明本体不变
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{Error, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+
+use crate::prover::{verify, VerifyOutcome};
+use crate::vk_file::{shape_hash, CURVE_NAME};
+
+/// 文件头魔数, 后跟一个字节的格式版本
+pub const PROOF_MAGIC: [u8; 7] = *b"FIBPF\0\0";
+
+/// 当前的格式版本
+pub const PROOF_VERSION: u8 = 2;
+
+/// 第1版的文件头长度: 魔数、版本和指纹
+const V1_HEADER_LEN: usize = 7 + 1 + 32;
+
+/// 固定长度部分: 魔数、版本、指纹、k和时间戳
+const FIXED_LEN: usize = 7 + 1 + 32 + 4 + 8;
+
+/// 证明文件头
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofHeader {
+    /// 电路标识, 如"fib"
+    pub circuit: String,
+    /// 同一电路的布局变体, 如"n=10"
+    pub layout: String,
+    pub k: u32,
+    pub curve: String,
+    pub crate_version: String,
+    /// 生成证明的时间, 不参与比对
+    pub timestamp: u64,
+    /// 生成证明时验证密钥的指纹, 即[`shape_hash`]
+    pub fingerprint: [u8; 32],
+}
+
+#[derive(Debug)]
+pub enum ProofFileError {
+    /// 文件头不合法
+    BadHeader(String),
+    /// 文件头的某一项与验证方期望的不一致
+    Mismatch { field: &'static str, expected: String, got: String },
+    /// 证明是用另一个电路的密钥生成的
+    FingerprintMismatch { expected: [u8; 32], got: [u8; 32] },
+    Plonk(Error),
+}
+
+impl fmt::Display for ProofFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofFileError::BadHeader(reason) => write!(f, "证明文件头不合法: {}", reason),
+            ProofFileError::Mismatch { field, expected, got } => write!(f, "证明文件头的{}不一致: 应为{}, 证明中为{}", field, expected, got),
+            ProofFileError::FingerprintMismatch { expected, got } => write!(f, "验证密钥指纹不一致: 应为{}, 证明中为{}", hex(expected), hex(got)),
+            ProofFileError::Plonk(e) => write!(f, "验证失败: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProofFileError {}
+
+impl From<Error> for ProofFileError {
+    fn from(e: Error) -> Self {
+        ProofFileError::Plonk(e)
+    }
+}
+
+/// 文件头的问题归为验证结论: 无法解析的是坏证明, k不一致是参数不匹配, 其余字段或指纹不一致是验证密钥不匹配
+impl From<ProofFileError> for VerifyOutcome {
+    fn from(e: ProofFileError) -> Self {
+        match e {
+            ProofFileError::BadHeader(reason) => VerifyOutcome::BadProof { reason: format!("证明文件头不合法: {}", reason) },
+            ProofFileError::Mismatch { field: "k", expected, got } => match (expected.parse(), got.parse()) {
+                (Ok(expected_k), Ok(got_k)) => VerifyOutcome::ParamsMismatch { expected_k, got_k },
+                _ => VerifyOutcome::BadProof { reason: format!("k不合法: {}", got) },
+            },
+            ProofFileError::Mismatch { field, expected, got } => VerifyOutcome::VkMismatch { expected: format!("{}为{}", field, expected), got: format!("{}为{}", field, got) },
+            ProofFileError::FingerprintMismatch { expected, got } => VerifyOutcome::VkMismatch { expected: format!("指纹{}", hex(&expected)), got: format!("指纹{}", hex(&got)) },
+            ProofFileError::Plonk(e) => VerifyOutcome::BadProof { reason: format!("{:?}", e) },
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 依次读出字段的游标
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProofFileError> {
+        if self.bytes.len() < len {
+            return Err(ProofFileError::BadHeader("文件头被截断".to_string()));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn string(&mut self, field: &str) -> Result<String, ProofFileError> {
+        let len = self.take(1)?[0] as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| ProofFileError::BadHeader(format!("{}不是UTF-8", field)))
+    }
+}
+
+impl ProofHeader {
+    /// 当前crate生成的文件头, 曲线、版本和时间戳自动填写; 电路标识或布局变体超过255字节时报错
+    pub fn new(circuit: &str, layout: &str, k: u32, vk: &VerifyingKey<EqAffine>) -> Result<Self, ProofFileError> {
+        for (field, value) in [("电路标识", circuit), ("布局变体", layout)] {
+            if value.len() > u8::MAX as usize {
+                return Err(ProofFileError::BadHeader(format!("{}有{}字节, 超过255字节", field, value.len())));
+            }
+        }
+        Ok(Self {
+            circuit: circuit.to_string(),
+            layout: layout.to_string(),
+            k,
+            curve: CURVE_NAME.to_string(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            fingerprint: shape_hash(vk),
+        })
+    }
+
+    /// 字段长度已由[`ProofHeader::new`]检查, 直接改写字段使其超过255字节时panic
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(FIXED_LEN + 64);
+        out.extend_from_slice(&PROOF_MAGIC);
+        out.push(PROOF_VERSION);
+        out.extend_from_slice(&self.fingerprint);
+        out.extend_from_slice(&self.k.to_le_bytes());
+        out.extend_from_slice(&self.timestamp.to_le_bytes());
+        for field in [&self.circuit, &self.layout, &self.curve, &self.crate_version] {
+            assert!(field.len() <= u8::MAX as usize, "文件头字段超过255字节");
+            out.push(field.len() as u8);
+            out.extend_from_slice(field.as_bytes());
+        }
+        out
+    }
+
+    /// 除时间戳外逐项与expected比对
+    pub fn check(&self, expected: &ProofHeader) -> Result<(), ProofFileError> {
+        let fields = [
+            ("电路标识", &self.circuit, &expected.circuit),
+            ("布局变体", &self.layout, &expected.layout),
+            ("曲线", &self.curve, &expected.curve),
+            ("crate版本", &self.crate_version, &expected.crate_version),
+        ];
+        for (field, got, expected) in fields {
+            if got != expected {
+                return Err(ProofFileError::Mismatch { field, expected: expected.clone(), got: got.clone() });
+            }
+        }
+        if self.k != expected.k {
+            return Err(ProofFileError::Mismatch { field: "k", expected: expected.k.to_string(), got: self.k.to_string() });
+        }
+        if self.fingerprint != expected.fingerprint {
+            return Err(ProofFileError::FingerprintMismatch { expected: expected.fingerprint, got: self.fingerprint });
+        }
+        Ok(())
+    }
+}
+
+/// 在证明前加上文件头
+pub fn encode_proof(header: &ProofHeader, proof: &[u8]) -> Vec<u8> {
+    let mut out = header.to_bytes();
+    out.extend_from_slice(proof);
+    out
+}
+
+/// 拆出文件头和证明本体
+pub fn decode_proof(bytes: &[u8]) -> Result<(ProofHeader, &[u8]), ProofFileError> {
+    if bytes.len() < FIXED_LEN {
+        return Err(ProofFileError::BadHeader(format!("长度{}小于文件头的固定部分{}", bytes.len(), FIXED_LEN)));
+    }
+    let mut reader = Reader { bytes };
+    if reader.take(7)? != PROOF_MAGIC {
+        return Err(ProofFileError::BadHeader("魔数不匹配".to_string()));
+    }
+    let version = reader.take(1)?[0];
+    if version == 1 {
+        return Err(ProofFileError::BadHeader("第1版格式没有电路标识和k, 请先用migrate-proof升级".to_string()));
+    }
+    if version != PROOF_VERSION {
+        return Err(ProofFileError::BadHeader(format!("不支持第{}版格式, 当前为第{}版", version, PROOF_VERSION)));
+    }
+    let fingerprint = reader.take(32)?.try_into().expect("指纹为32字节");
+    let k = u32::from_le_bytes(reader.take(4)?.try_into().expect("k为4字节"));
+    let timestamp = u64::from_le_bytes(reader.take(8)?.try_into().expect("时间戳为8字节"));
+    let circuit = reader.string("电路标识")?;
+    let layout = reader.string("布局变体")?;
+    let curve = reader.string("曲线")?;
+    let crate_version = reader.string("crate版本")?;
+    Ok((ProofHeader { circuit, layout, k, curve, crate_version, timestamp, fingerprint }, reader.bytes))
+}
+
+/// 拆出第1版证明的指纹和证明本体
+pub fn decode_proof_v1(bytes: &[u8]) -> Result<([u8; 32], &[u8]), ProofFileError> {
+    if bytes.len() < V1_HEADER_LEN {
+        return Err(ProofFileError::BadHeader(format!("长度{}小于第1版文件头{}", bytes.len(), V1_HEADER_LEN)));
+    }
+    if bytes[..7] != PROOF_MAGIC || bytes[7] != 1 {
+        return Err(ProofFileError::BadHeader("不是第1版格式".to_string()));
+    }
+    let fingerprint = bytes[8..V1_HEADER_LEN].try_into().expect("指纹为32字节");
+    Ok((fingerprint, &bytes[V1_HEADER_LEN..]))
+}
+
+/// 把第1版证明升级到第2版, 只改写文件头, 不重新证明
+///
+/// 第1版没有的电路标识、布局变体和k由调用方按验证方的验证密钥填进header, 其指纹必须与证明中记录的一致,
+/// 所以补上的元数据不会指向另一个电路
+pub fn migrate_v1(bytes: &[u8], header: &ProofHeader) -> Result<Vec<u8>, ProofFileError> {
+    let (fingerprint, proof) = decode_proof_v1(bytes)?;
+    if fingerprint != header.fingerprint {
+        return Err(ProofFileError::FingerprintMismatch { expected: header.fingerprint, got: fingerprint });
+    }
+    Ok(encode_proof(header, proof))
+}
+
+/// 验证带文件头的证明, 文件头与验证方按vk构造的expected不一致时直接拒绝, 结论见[`VerifyOutcome`]
+///
+/// ```
+/// use halo2_fib::proof_file::{encode_proof, verify_encoded, ProofHeader};
+/// use halo2_fib::prover::{keygen, prove, setup};
+/// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+/// use halo2_proofs::pasta::Fp;
+///
+/// let witness = FibWitness::new(Fp::one(), Fp::one());
+/// let statement = FibStatement::from_witness(10, &witness).unwrap();
+/// let circuit = FibCircuit::new(&statement, &witness);
+/// let params = setup(4);
+/// let pk = keygen(&params, &circuit).unwrap();
+/// let proof = prove(&params, &pk, &circuit, &statement.public_inputs()).unwrap();
+/// let header = ProofHeader::new("fib", "n=10", 4, pk.get_vk()).unwrap();
+/// let encoded = encode_proof(&header, &proof);
+/// assert!(verify_encoded(&params, pk.get_vk(), &header, &statement.public_inputs(), &encoded).is_valid());
+/// ```
+pub fn verify_encoded(params: &Params<EqAffine>, vk: &VerifyingKey<EqAffine>, expected: &ProofHeader, public_inputs: &[Fp], bytes: &[u8]) -> VerifyOutcome {
+    let (header, proof) = match decode_proof(bytes) {
+        Ok(decoded) => decoded,
+        Err(e) => return e.into(),
+    };
+    if let Err(e) = header.check(expected) {
+        return e.into();
+    }
+    let fingerprint = shape_hash(vk);
+    if header.fingerprint != fingerprint {
+        return ProofFileError::FingerprintMismatch { expected: fingerprint, got: header.fingerprint }.into();
+    }
+    verify(params, vk, public_inputs, proof)
+}
+
+#[test]
+fn test_header_mismatch() {
+    use crate::prover::{keygen, prove, setup};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(10, &witness).unwrap();
+    let params = setup(4);
+    let pk = keygen(&params, &FibCircuit::new(&statement, &witness)).unwrap();
+    let proof = prove(&params, &pk, &FibCircuit::new(&statement, &witness), &statement.public_inputs()).unwrap();
+    let header = ProofHeader::new("fib", "n=10", 4, pk.get_vk()).unwrap();
+    let encoded = encode_proof(&header, &proof);
+    let (decoded, body) = decode_proof(&encoded).unwrap();
+    assert_eq!(decoded, header);
+    assert_eq!(body, &proof[..]);
+
+    // 布局变体不同
+    let result = verify_encoded(&params, pk.get_vk(), &ProofHeader::new("fib", "n=9", 4, pk.get_vk()).unwrap(), &statement.public_inputs(), &encoded);
+    assert!(matches!(result, VerifyOutcome::VkMismatch { .. }));
+    let result = verify_encoded(&params, pk.get_vk(), &ProofHeader::new("fib", "n=10", 5, pk.get_vk()).unwrap(), &statement.public_inputs(), &encoded);
+    assert_eq!(result, VerifyOutcome::ParamsMismatch { expected_k: 5, got_k: 4 });
+
+    // n不同的电路形状不同, 指纹也不同
+    let other = FibStatement::from_witness(9, &witness).unwrap();
+    let other_pk = keygen(&params, &FibCircuit::new(&other, &witness)).unwrap();
+    let result = verify_encoded(&params, other_pk.get_vk(), &header, &statement.public_inputs(), &encoded);
+    assert!(matches!(result, VerifyOutcome::VkMismatch { .. }));
+    assert!(matches!(verify_encoded(&params, pk.get_vk(), &header, &statement.public_inputs(), &encoded[..20]), VerifyOutcome::BadProof { .. }));
+
+    assert!(matches!(decode_proof(&encoded[..FIXED_LEN - 1]), Err(ProofFileError::BadHeader(_))));
+    let mut old = encoded.clone();
+    old[7] = 3;
+    assert!(matches!(decode_proof(&old), Err(ProofFileError::BadHeader(_))));
+
+    // 第1版升级后可以验证, 用别的电路的元数据升级会被拒绝
+    let mut v1 = b"FIBPF\0\0\x01".to_vec();
+    v1.extend_from_slice(&header.fingerprint);
+    v1.extend_from_slice(&proof);
+    assert!(matches!(decode_proof(&v1), Err(ProofFileError::BadHeader(_))));
+    let migrated = migrate_v1(&v1, &header).unwrap();
+    assert!(verify_encoded(&params, pk.get_vk(), &header, &statement.public_inputs(), &migrated).is_valid());
+    assert!(matches!(migrate_v1(&v1, &ProofHeader::new("fib", "n=9", 4, other_pk.get_vk()).unwrap()), Err(ProofFileError::FingerprintMismatch { .. })));
+    assert!(matches!(migrate_v1(&encoded, &header), Err(ProofFileError::BadHeader(_))));
+
+    // 过长的名字在构造时报错, 不会在写出时panic
+    assert!(matches!(ProofHeader::new(&"x".repeat(256), "n=10", 4, pk.get_vk()), Err(ProofFileError::BadHeader(_))));
+    assert!(matches!(ProofHeader::new("fib", &"n=10,".repeat(60), 4, pk.get_vk()), Err(ProofFileError::BadHeader(_))));
+}
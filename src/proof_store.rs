@@ -0,0 +1,160 @@
+//! 按内容寻址的证明仓库: 键为验证密钥指纹和公开输入的哈希, 同一电路上的同一命题只证明一次
+//!
+//! 每个证明存为`<键的十六进制>.proof`, 内容原样保存, 可以是裸证明也可以是带文件头的证明.
+//! 写入先写临时文件再改名, 并发写入同一个键时后写的覆盖先写的, 两者都是有效证明
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::VerifyingKey;
+
+use crate::cache::CacheDirs;
+use crate::instance::encode_instance;
+use crate::vk_file::shape_hash;
+
+const EXTENSION: &str = "proof";
+
+/// 证明的键: blake2b-256(指纹 || 公开输入个数 || 各公开输入)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProofKey(pub [u8; 32]);
+
+impl ProofKey {
+    pub fn new(vk: &VerifyingKey<EqAffine>, public_inputs: &[Fp]) -> Self {
+        let mut state = blake2b_simd::Params::new().hash_length(32).personal(b"halo2-fib-proof").to_state();
+        state.update(&shape_hash(vk));
+        state.update(&(public_inputs.len() as u64).to_le_bytes());
+        for input in public_inputs {
+            state.update(&encode_instance(input));
+        }
+        let mut key = [0u8; 32];
+        key.copy_from_slice(state.finalize().as_bytes());
+        ProofKey(key)
+    }
+
+    /// 由64个十六进制字符解析
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.len() != 64 || !hex.is_ascii() {
+            return None;
+        }
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+        }
+        Some(ProofKey(key))
+    }
+}
+
+impl fmt::Display for ProofKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+/// 磁盘上的证明仓库
+#[derive(Clone, Debug)]
+pub struct ProofStore {
+    dir: PathBuf,
+}
+
+impl ProofStore {
+    /// 打开目录, 不存在时创建
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// 缓存目录下的proofs子目录
+    pub fn in_cache(dirs: &CacheDirs) -> io::Result<Self> {
+        Self::open(dirs.proofs())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self, key: &ProofKey) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, EXTENSION))
+    }
+
+    pub fn get(&self, key: &ProofKey) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)) {
+            Ok(proof) => Ok(Some(proof)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 保存证明, 键已存在时不改动并返回false
+    pub fn put(&self, key: &ProofKey, proof: &[u8]) -> io::Result<bool> {
+        let path = self.path(key);
+        if path.exists() {
+            return Ok(false);
+        }
+        let tmp = self.dir.join(format!("{}.tmp{}", key, std::process::id()));
+        fs::write(&tmp, proof)?;
+        fs::rename(&tmp, &path)?;
+        Ok(true)
+    }
+
+    /// 已存的证明则直接返回, 否则调用prove生成并保存; 第二个返回值表示是否命中
+    pub fn get_or_prove<E: From<io::Error>>(&self, key: &ProofKey, prove: impl FnOnce() -> Result<Vec<u8>, E>) -> Result<(Vec<u8>, bool), E> {
+        if let Some(proof) = self.get(key)? {
+            return Ok((proof, true));
+        }
+        let proof = prove()?;
+        self.put(key, &proof)?;
+        Ok((proof, false))
+    }
+
+    /// 全部键及证明的字节数, 按键排序, 跳过不是证明的文件
+    pub fn list(&self) -> io::Result<Vec<(ProofKey, u64)>> {
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                if let Some(key) = path.file_stem().and_then(|stem| stem.to_str()).and_then(ProofKey::from_hex) {
+                    entries.push((key, fs::metadata(&path)?.len()));
+                }
+            }
+        }
+        entries.sort();
+        Ok(entries)
+    }
+}
+
+#[test]
+fn test_proof_store() {
+    use crate::prover::{keygen, prove, setup};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(10, &witness).unwrap();
+    let circuit = FibCircuit::new(&statement, &witness);
+    let params = setup(4);
+    let pk = keygen(&params, &circuit).unwrap();
+    let key = ProofKey::new(pk.get_vk(), &statement.public_inputs());
+    assert_eq!(ProofKey::from_hex(&key.to_string()), Some(key));
+    assert_ne!(ProofKey::new(pk.get_vk(), &[Fp::from(56)]), key);
+
+    let store = ProofStore::open(std::env::temp_dir().join(format!("halo2-fib-store-test-{}", std::process::id()))).unwrap();
+    let mut proved = 0;
+    let mut get = || {
+        store.get_or_prove(&key, || {
+            proved += 1;
+            prove(&params, &pk, &circuit, &statement.public_inputs()).map_err(|_| io::Error::other("生成证明失败"))
+        })
+    };
+    let (first, hit) = get().unwrap();
+    assert!(!hit);
+    let (second, hit) = get().unwrap();
+    assert!(hit);
+    assert_eq!(first, second);
+    assert_eq!(proved, 1);
+    assert_eq!(store.list().unwrap(), vec![(key, first.len() as u64)]);
+    assert!(!store.put(&key, b"other").unwrap());
+    fs::remove_dir_all(store.dir()).unwrap();
+}
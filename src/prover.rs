@@ -0,0 +1,338 @@
+use std::fmt;
+
+use halo2_proofs::circuit::Value;
+use halo2_proofs::dev::{metadata, FailureLocation, MockProver, VerifyFailure};
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Any, Circuit, Error, ProvingKey, SingleVerifier, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+use halo2_proofs::transcript::{Blake2bRead, Blake2bWrite, Challenge255};
+use rand_core::{OsRng, RngCore};
+
+/// 生成密钥和读取验证密钥文件时k的上限
+pub const MAX_K: u32 = 20;
+
+/// 生成k对应的公共参数
+pub fn setup(k: u32) -> Params<EqAffine> {
+    Params::new(k)
+}
+
+/// 生成证明密钥(包含验证密钥)
+pub fn keygen<C: Circuit<Fp>>(params: &Params<EqAffine>, circuit: &C) -> Result<ProvingKey<EqAffine>, Error> {
+    let vk = keygen_vk(params, circuit)?;
+    keygen_pk(params, vk, circuit)
+}
+
+/// 生成证明
+///
+/// ```
+/// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+/// use halo2_fib::prover::{keygen, prove, setup};
+/// use halo2_proofs::pasta::Fp;
+///
+/// let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one()));
+/// let params = setup(4);
+/// let pk = keygen(&params, &circuit).unwrap();
+/// let proof = prove(&params, &pk, &circuit, &[Fp::from(55)]).unwrap();
+/// assert!(!proof.is_empty());
+/// ```
+pub fn prove<C: Circuit<Fp>>(params: &Params<EqAffine>, pk: &ProvingKey<EqAffine>, circuit: &C, public_inputs: &[Fp]) -> Result<Vec<u8>, Error> {
+    prove_with_rng(params, pk, circuit, public_inputs, OsRng)
+}
+
+/// 用指定的随机数源生成证明, 随机数源固定时证明也是确定的
+pub fn prove_with_rng<C: Circuit<Fp>, R: RngCore>(params: &Params<EqAffine>, pk: &ProvingKey<EqAffine>, circuit: &C, public_inputs: &[Fp], rng: R) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    create_proof(params, pk, std::slice::from_ref(circuit), &[&[public_inputs]], rng, &mut transcript)?;
+    Ok(transcript.finalize())
+}
+
+/// 证明完成后可以抹掉的秘密见证
+///
+/// 只能覆写电路自己持有的值, `create_proof`内部的advice多项式由halo2分配和释放, 这里够不着
+pub trait ZeroizeWitness {
+    /// 把见证覆写为零并置为未知, 之后电路只能用于密钥生成
+    fn zeroize_witness(&mut self);
+}
+
+/// 把域元素覆写为零, 写入不会被编译器当作无用写入优化掉
+pub fn zeroize_field(value: &mut Fp) {
+    // SAFETY: zeroize_flat_type要求类型没有Drop、不含指针且全零字节是合法值.
+    // pasta的Fp是4个u64的数组(蒙哥马利形式), 满足前两条, 全零字节恰好是零元素
+    unsafe { zeroize::zeroize_flat_type(value) }
+}
+
+/// 覆写已知的值, 再置为未知
+pub fn zeroize_value(value: &mut Value<Fp>) {
+    let _ = value.as_mut().map(zeroize_field);
+    *value = Value::unknown();
+}
+
+/// 离开作用域(包括证明出错或panic)时抹掉见证
+pub(crate) struct ZeroizeOnDrop<C: ZeroizeWitness>(pub(crate) C);
+
+impl<C: ZeroizeWitness> Drop for ZeroizeOnDrop<C> {
+    fn drop(&mut self) {
+        self.0.zeroize_witness();
+    }
+}
+
+/// 生成证明后抹掉电路中的见证, 证明成功与否都会抹掉
+///
+/// ```
+/// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+/// use halo2_fib::prover::{keygen, prove_zeroizing, setup, verify};
+/// use halo2_proofs::pasta::Fp;
+///
+/// let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+/// let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+/// let params = setup(4);
+/// let pk = keygen(&params, &circuit).unwrap();
+/// let proof = prove_zeroizing(&params, &pk, circuit, &statement.public_inputs()).unwrap();
+/// assert!(verify(&params, pk.get_vk(), &statement.public_inputs(), &proof).is_valid());
+/// ```
+pub fn prove_zeroizing<C: Circuit<Fp> + ZeroizeWitness>(params: &Params<EqAffine>, pk: &ProvingKey<EqAffine>, circuit: C, public_inputs: &[Fp]) -> Result<Vec<u8>, Error> {
+    let circuit = ZeroizeOnDrop(circuit);
+    prove(params, pk, &circuit.0, public_inputs)
+}
+
+/// 调用halo2的验证器, 错误由[`verify`]归为[`VerifyOutcome`]
+fn verify_raw(params: &Params<EqAffine>, vk: &VerifyingKey<EqAffine>, public_inputs: &[Fp], proof: &[u8]) -> Result<(), Error> {
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(proof);
+    verify_proof(params, vk, strategy, &[&[public_inputs]], &mut transcript)
+}
+
+/// 验证的结论, 失败时区分原因并带上下文
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    Valid,
+    /// 证明无效, 或证明字节无法解析
+    BadProof { reason: String },
+    /// 公开输入的形状与电路不符, 例如超过实例列的可用行
+    BadPublicInputShape { len: usize, reason: String },
+    /// 证明是为另一个电路或验证密钥生成的
+    VkMismatch { expected: String, got: String },
+    /// 公共参数的k与验证密钥或证明的不一致
+    ParamsMismatch { expected_k: u32, got_k: u32 },
+}
+
+impl VerifyOutcome {
+    pub fn is_valid(&self) -> bool {
+        matches!(self, VerifyOutcome::Valid)
+    }
+
+    /// 机器可读的种类名, 用于JSON输出
+    pub fn kind(&self) -> &'static str {
+        match self {
+            VerifyOutcome::Valid => "valid",
+            VerifyOutcome::BadProof { .. } => "bad_proof",
+            VerifyOutcome::BadPublicInputShape { .. } => "bad_public_input_shape",
+            VerifyOutcome::VkMismatch { .. } => "vk_mismatch",
+            VerifyOutcome::ParamsMismatch { .. } => "params_mismatch",
+        }
+    }
+
+    /// 有效时为`Ok`, 方便用`?`传播
+    pub fn into_result(self) -> Result<(), VerifyOutcome> {
+        if self.is_valid() { Ok(()) } else { Err(self) }
+    }
+}
+
+impl fmt::Display for VerifyOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyOutcome::Valid => write!(f, "验证通过"),
+            VerifyOutcome::BadProof { reason } => write!(f, "证明无效: {}", reason),
+            VerifyOutcome::BadPublicInputShape { len, reason } => write!(f, "公开输入的形状不对({}个): {}", len, reason),
+            VerifyOutcome::VkMismatch { expected, got } => write!(f, "验证密钥不匹配: 应为{}, 证明对应{}", expected, got),
+            VerifyOutcome::ParamsMismatch { expected_k, got_k } => write!(f, "公共参数不匹配: 应为k = {}, 实际为k = {}", expected_k, got_k),
+        }
+    }
+}
+
+impl std::error::Error for VerifyOutcome {}
+
+/// 公共参数的k
+pub fn params_k(params: &Params<EqAffine>) -> u32 {
+    params.get_g().len().trailing_zeros()
+}
+
+/// 验证密钥的k, 从`vk.pinned()`的调试输出中取出, 与[`shape_hash`](crate::vk_file::shape_hash)一样依赖这一输出
+pub fn vk_k(vk: &VerifyingKey<EqAffine>) -> Option<u32> {
+    let pinned = format!("{:?}", vk.pinned());
+    let rest = pinned.split_once("PinnedEvaluationDomain { k: ")?.1;
+    rest.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+/// 验证证明并给出结论: 先比对参数与验证密钥的k, 再把验证器的错误分成公开输入形状不对和证明无效
+///
+/// ```
+/// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+/// use halo2_fib::prover::{keygen, prove, setup, verify, VerifyOutcome};
+/// use halo2_proofs::pasta::Fp;
+///
+/// let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one()));
+/// let params = setup(4);
+/// let pk = keygen(&params, &circuit).unwrap();
+/// let proof = prove(&params, &pk, &circuit, &[Fp::from(55)]).unwrap();
+/// assert_eq!(verify(&params, pk.get_vk(), &[Fp::from(55)], &proof), VerifyOutcome::Valid);
+/// assert!(matches!(verify(&params, pk.get_vk(), &[Fp::from(56)], &proof), VerifyOutcome::BadProof { .. }));
+/// assert!(matches!(verify(&setup(5), pk.get_vk(), &[Fp::from(55)], &proof), VerifyOutcome::ParamsMismatch { expected_k: 4, got_k: 5 }));
+/// ```
+pub fn verify(params: &Params<EqAffine>, vk: &VerifyingKey<EqAffine>, public_inputs: &[Fp], proof: &[u8]) -> VerifyOutcome {
+    let got_k = params_k(params);
+    if let Some(expected_k) = vk_k(vk).filter(|&k| k != got_k) {
+        return VerifyOutcome::ParamsMismatch { expected_k, got_k };
+    }
+    let len = public_inputs.len();
+    match verify_raw(params, vk, public_inputs, proof) {
+        Ok(()) => VerifyOutcome::Valid,
+        Err(Error::InstanceTooLarge) => VerifyOutcome::BadPublicInputShape { len, reason: "超过实例列的可用行数".to_string() },
+        Err(Error::InvalidInstances) => VerifyOutcome::BadPublicInputShape { len, reason: "实例列个数与电路不符".to_string() },
+        Err(e) => VerifyOutcome::BadProof { reason: format!("{:?}", e) },
+    }
+}
+
+/// 带重试的证明结果: 实际使用的k以及对应的参数、密钥和证明
+pub struct RetryProof {
+    pub k: u32,
+    pub params: Params<EqAffine>,
+    pub pk: ProvingKey<EqAffine>,
+    pub proof: Vec<u8>,
+}
+
+/// 从k开始生成参数和密钥, 遇到`NotEnoughRowsAvailable`时把k加一重新生成, 直到max_k为止, 返回实际使用的k
+///
+/// 每次生成参数之前都检查k不超过max_k, 起点已经超过max_k时直接返回`NotEnoughRowsAvailable`
+pub fn keygen_with_retry<C: Circuit<Fp>>(k: u32, max_k: u32, circuit: &C) -> Result<(u32, Params<EqAffine>, ProvingKey<EqAffine>), Error> {
+    for k in k..=max_k {
+        let params = setup(k);
+        match keygen(&params, circuit) {
+            Ok(pk) => return Ok((k, params, pk)),
+            Err(Error::NotEnoughRowsAvailable { .. }) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(Error::NotEnoughRowsAvailable { current_k: max_k })
+}
+
+/// 从k开始生成证明, 遇到`NotEnoughRowsAvailable`时把k加一并重新生成参数和密钥, 直到max_k为止
+pub fn prove_with_retry<C: Circuit<Fp>>(k: u32, max_k: u32, circuit: &C, public_inputs: &[Fp]) -> Result<RetryProof, Error> {
+    let (k, params, pk) = keygen_with_retry(k, max_k, circuit)?;
+    let proof = prove(&params, &pk, circuit, public_inputs)?;
+    Ok(RetryProof { k, params, pk, proof })
+}
+
+/// 同[`prove_with_retry`], 证明后抹掉电路中的见证, 证明成功与否都会抹掉
+pub fn prove_with_retry_zeroizing<C: Circuit<Fp> + ZeroizeWitness>(k: u32, max_k: u32, circuit: C, public_inputs: &[Fp]) -> Result<RetryProof, Error> {
+    let circuit = ZeroizeOnDrop(circuit);
+    prove_with_retry(k, max_k, &circuit.0, public_inputs)
+}
+
+/// 能由见证算出公开输入的电路, 调试验证失败时用来给出期望值
+pub trait ExpectedPublicInputs {
+    fn expected_public_inputs(&self) -> Option<Vec<Fp>>;
+}
+
+impl ExpectedPublicInputs for crate::FibCircuit<Fp> {
+    fn expected_public_inputs(&self) -> Option<Vec<Fp>> {
+        crate::FibCircuit::expected_public_inputs(self)
+    }
+}
+
+/// 验证失败的一条诊断
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// 实例第row行与见证算出的值不一致, 见证未知时expected为None
+    InstanceMismatch { row: usize, expected: Option<Fp>, got: Fp },
+    /// MockProver报告的其他失败
+    Failure(String),
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnosis::InstanceMismatch { row, expected: Some(expected), got } => write!(f, "实例第{}行应为{:?}, 实际为{:?}", row, expected, got),
+            Diagnosis::InstanceMismatch { row, expected: None, got } => write!(f, "实例第{}行的值{:?}与见证不一致", row, got),
+            Diagnosis::Failure(failure) => write!(f, "{}", failure),
+        }
+    }
+}
+
+/// 调试验证的结果: 验证的结论以及MockProver给出的诊断
+#[derive(Debug)]
+pub struct DebugVerifyError {
+    pub outcome: VerifyOutcome,
+    pub diagnoses: Vec<Diagnosis>,
+}
+
+impl fmt::Display for DebugVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.outcome)?;
+        for diagnosis in &self.diagnoses {
+            writeln!(f, "  {}", diagnosis)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DebugVerifyError {}
+
+/// 验证证明, 失败时用证明者一侧的见证重跑MockProver, 把失败映射为"实例第R行应为X, 实际为Y"
+pub fn debug_verify<C: Circuit<Fp> + ExpectedPublicInputs>(params: &Params<EqAffine>, vk: &VerifyingKey<EqAffine>, k: u32, circuit: &C, public_inputs: &[Fp], proof: &[u8]) -> Result<(), DebugVerifyError> {
+    let outcome = match verify(params, vk, public_inputs, proof) {
+        VerifyOutcome::Valid => return Ok(()),
+        outcome => outcome,
+    };
+    let expected = circuit.expected_public_inputs();
+    let instance_column = metadata::Column::from((Any::Instance, 0));
+    let mut diagnoses = vec![];
+    match MockProver::run(k, circuit, vec![public_inputs.to_vec()]) {
+        Ok(prover) => {
+            for failure in prover.verify().err().unwrap_or_default() {
+                match &failure {
+                    VerifyFailure::Permutation { column, location: FailureLocation::OutsideRegion { row } } if *column == instance_column => {
+                        diagnoses.push(Diagnosis::InstanceMismatch {
+                            row: *row,
+                            expected: expected.as_ref().and_then(|e| e.get(*row).copied()),
+                            got: public_inputs.get(*row).copied().unwrap_or(Fp::zero()),
+                        });
+                    }
+                    _ => diagnoses.push(Diagnosis::Failure(failure.to_string())),
+                }
+            }
+        }
+        Err(e) => diagnoses.push(Diagnosis::Failure(format!("MockProver运行失败: {:?}", e))),
+    }
+    Err(DebugVerifyError { outcome, diagnoses })
+}
+
+#[test]
+fn test_debug_verify() {
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let circuit = FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one()));
+    let params = setup(4);
+    let pk = keygen(&params, &circuit).unwrap();
+    let proof = prove(&params, &pk, &circuit, &[Fp::from(55)]).unwrap();
+    debug_verify(&params, pk.get_vk(), 4, &circuit, &[Fp::from(55)], &proof).unwrap();
+
+    let err = debug_verify(&params, pk.get_vk(), 4, &circuit, &[Fp::from(56)], &proof).unwrap_err();
+    assert!(err.diagnoses.contains(&Diagnosis::InstanceMismatch { row: 0, expected: Some(Fp::from(55)), got: Fp::from(56) }));
+}
+
+#[test]
+fn test_zeroize_witness() {
+    use crate::FibWitness;
+
+    let mut value = Fp::from(7);
+    zeroize_field(&mut value);
+    assert_eq!(value, Fp::zero());
+    let mut witness = FibWitness::new(Fp::from(3), Fp::from(5));
+    witness.zeroize_witness();
+    assert_eq!(witness, FibWitness::new(Fp::zero(), Fp::zero()));
+    // 抹掉后为未知值, 断言不会被执行
+    let mut value = Value::known(Fp::from(7));
+    zeroize_value(&mut value);
+    value.assert_if_known(|_| false);
+}
@@ -0,0 +1,227 @@
+use ff::PrimeField;
+use halo2_proofs::circuit::{Value, Layouter, AssignedCell, SimpleFloorPlanner};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::poly::Rotation;
+use halo2_proofs::plonk::*;
+
+use crate::prover::{zeroize_value, ZeroizeWitness};
+use crate::shared::SharedColumns;
+
+/// 二阶线性递推 x(n) = p * x(n-1) + q * x(n-2) 的列配置, p = q = 1 时即斐波那契
+#[derive(Clone, Debug, Copy)]
+pub struct RecurrenceConfig {
+    pub selector: Selector,
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub target: Column<Instance>,
+    pub p: u64,
+    pub q: u64,
+}
+
+/// 线性递推芯片, 布局与[`FibChip`](crate::FibChip)相同, 只是门里带上了系数
+pub struct RecurrenceChip {
+    config: RecurrenceConfig
+}
+
+impl RecurrenceChip {
+    pub fn construct(config: RecurrenceConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>, shared: &SharedColumns, p: u64, q: u64) -> RecurrenceConfig {
+        let selector = meta.selector();
+        let [a, b, c] = shared.advice;
+        let target = shared.instance;
+
+        meta.create_gate("linear_recurrence", |meta| {
+            let selector = meta.query_selector(selector);
+            let num_a = meta.query_advice(a, Rotation::cur());
+            let num_b = meta.query_advice(b, Rotation::cur());
+            let num_c = meta.query_advice(c, Rotation::cur());
+            let coeff_p = Expression::Constant(F::from(p));
+            let coeff_q = Expression::Constant(F::from(q));
+            vec![
+                ("p * b + q * a = c", selector * (coeff_p * num_b + coeff_q * num_a - num_c)),
+            ]
+        });
+        RecurrenceConfig { selector, a, b, c, target, p, q }
+    }
+
+    fn next_value<F: PrimeField>(&self, a: Value<F>, b: Value<F>) -> Value<F> {
+        let p = F::from(self.config.p);
+        let q = F::from(self.config.q);
+        a.zip(b).map(|(a, b)| p * b + q * a)
+    }
+
+    pub fn assign_first_row<F: PrimeField>(&self, mut layouter: impl Layouter<F>, a: Value<F>, b: Value<F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(|| "填写第一行", |mut region| {
+            self.config.selector.enable(&mut region, 0)?;
+            region.assign_advice(|| "加载a", self.config.a, 0, || a)?;
+            let cur_b = region.assign_advice(|| "加载b", self.config.b, 0, || b)?;
+            let cur_c = region.assign_advice(|| "计算当前c", self.config.c, 0, || self.next_value(a, b))?;
+            Ok((cur_b, cur_c))
+        })
+    }
+
+    pub fn assign_next_row<F: PrimeField>(&self, mut layouter: impl Layouter<F>, pre_b: &AssignedCell<F, F>, pre_c: &AssignedCell<F, F>) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        layouter.assign_region(|| "填写下一行", |mut region| {
+            self.config.selector.enable(&mut region, 0)?;
+            let cur_a = pre_b.copy_advice(|| "拷贝上一行b到当前a", &mut region, self.config.a, 0)?;
+            let cur_b = pre_c.copy_advice(|| "拷贝上一行c到当前b", &mut region, self.config.b, 0)?;
+            let value_c = self.next_value(cur_a.value().copied(), cur_b.value().copied());
+            let cur_c = region.assign_advice(|| "计算当前c", self.config.c, 0, || value_c)?;
+            Ok((cur_b, cur_c))
+        })
+    }
+
+    pub fn expose_public<F: PrimeField>(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.target, row)
+    }
+}
+
+/// 证明线性递推 x(n) = P * x(n-1) + Q * x(n-2) 的第n项, 前两项a、b为私有输入
+pub struct LinearRecurrenceCircuit<F: PrimeField, const P: u64, const Q: u64> {
+    a: Value<F>,
+    b: Value<F>,
+    n: usize,
+}
+
+/// 佩尔数列 x(n) = 2 * x(n-1) + x(n-2)
+pub type PellCircuit<F> = LinearRecurrenceCircuit<F, 2, 1>;
+
+/// 雅各布斯塔尔数列 x(n) = x(n-1) + 2 * x(n-2)
+pub type JacobsthalCircuit<F> = LinearRecurrenceCircuit<F, 1, 2>;
+
+impl<F: PrimeField, const P: u64, const Q: u64> LinearRecurrenceCircuit<F, P, Q> {
+    /// 以a、b作为第1、2项, 证明第n项(n >= 3)
+    pub fn new(a: F, b: F, n: usize) -> Self {
+        assert!(n >= 3, "n至少为3");
+        Self { a: Value::known(a), b: Value::known(b), n }
+    }
+}
+
+impl<F: PrimeField> PellCircuit<F> {
+    /// 标准佩尔数列 1, 2, 5, 12, 29, ... 的第n项
+    ///
+    /// ```
+    /// use halo2_fib::PellCircuit;
+    /// use halo2_proofs::dev::MockProver;
+    /// use halo2_proofs::pasta::Fp;
+    ///
+    /// let circuit = PellCircuit::<Fp>::pell(10);
+    /// let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(2378)]]).unwrap();
+    /// prover.assert_satisfied();
+    /// ```
+    pub fn pell(n: usize) -> Self {
+        Self::new(F::ONE, F::from(2), n)
+    }
+}
+
+impl<F: PrimeField> JacobsthalCircuit<F> {
+    /// 标准雅各布斯塔尔数列 1, 1, 3, 5, 11, ... 的第n项
+    pub fn jacobsthal(n: usize) -> Self {
+        Self::new(F::ONE, F::ONE, n)
+    }
+}
+
+impl<const P: u64, const Q: u64> LinearRecurrenceCircuit<Fp, P, Q> {
+    /// 证明第n项所需的最小k: 数列占n-2行, 盲化行另算
+    pub fn min_k(n: usize) -> u32 {
+        crate::capacity::min_k::<Self>(n.saturating_sub(2))
+    }
+}
+
+impl<const P: u64, const Q: u64> ZeroizeWitness for LinearRecurrenceCircuit<Fp, P, Q> {
+    fn zeroize_witness(&mut self) {
+        zeroize_value(&mut self.a);
+        zeroize_value(&mut self.b);
+    }
+}
+
+impl<F: PrimeField, const P: u64, const Q: u64> Circuit<F> for LinearRecurrenceCircuit<F, P, Q> {
+    type Config = RecurrenceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown(), n: self.n }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        RecurrenceChip::configure(meta, &shared, P, Q)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = RecurrenceChip::construct(config);
+        // 初始化第一行
+        let (mut b, mut c) = chip.assign_first_row(layouter.namespace(|| "填写第一行"), self.a, self.b)?;
+        // 循环填写下一行
+        for _i in 3..self.n {
+            let (next_b, next_c) = chip.assign_next_row(layouter.namespace(|| "填写下一行"), &b, &c)?;
+            b = next_b;
+            c = next_c;
+        }
+        // 暴露结果
+        chip.expose_public(layouter, &c, 0)?;
+        Ok(())
+    }
+}
+
+/// 电路外计算线性递推的第1..=n项
+pub fn recurrence_terms<F: PrimeField>(p: u64, q: u64, a: F, b: F, n: usize) -> Vec<F> {
+    let (p, q) = (F::from(p), F::from(q));
+    let mut terms = vec![a, b];
+    while terms.len() < n {
+        let len = terms.len();
+        terms.push(p * terms[len - 1] + q * terms[len - 2]);
+    }
+    terms.truncate(n);
+    terms
+}
+
+/// 电路外计算佩尔数列的第1..=n项
+pub fn pell_terms<F: PrimeField>(n: usize) -> Vec<F> {
+    recurrence_terms(2, 1, F::ONE, F::from(2), n)
+}
+
+/// 电路外计算雅各布斯塔尔数列的第1..=n项
+pub fn jacobsthal_terms<F: PrimeField>(n: usize) -> Vec<F> {
+    recurrence_terms(1, 2, F::ONE, F::ONE, n)
+}
+
+#[test]
+fn test_reference_terms() {
+    use halo2_proofs::pasta::Fp;
+
+    let pell: Vec<Fp> = [1u64, 2, 5, 12, 29, 70, 169, 408, 985, 2378].iter().map(|&x| Fp::from(x)).collect();
+    assert_eq!(pell_terms::<Fp>(10), pell);
+    let jacobsthal: Vec<Fp> = [1u64, 1, 3, 5, 11, 21, 43, 85, 171, 341].iter().map(|&x| Fp::from(x)).collect();
+    assert_eq!(jacobsthal_terms::<Fp>(10), jacobsthal);
+    // p = q = 1 退化为斐波那契
+    assert_eq!(recurrence_terms(1, 1, Fp::one(), Fp::one(), 10)[9], Fp::from(55));
+}
+
+#[test]
+fn test_pell() {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    let circuit = PellCircuit::<Fp>::pell(12);
+    let target = pell_terms::<Fp>(12)[11];
+    let prover = MockProver::run(5, &circuit, vec![vec![target]]).unwrap();
+    prover.assert_satisfied();
+
+    let prover = MockProver::run(5, &circuit, vec![vec![target + Fp::one()]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn test_jacobsthal() {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    let circuit = JacobsthalCircuit::<Fp>::jacobsthal(10);
+    let prover = MockProver::run(4, &circuit, vec![vec![Fp::from(341)]]).unwrap();
+    prover.assert_satisfied();
+}
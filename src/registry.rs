@@ -0,0 +1,378 @@
+//! 多电路注册表: 每个电路登记名字、参数表和由参数构造电路的函数, 注册表据此生成证明和验证闭包,
+//! 命令行和服务按[`dsl`](crate::dsl)命题里的电路名统一分派, 不必为每个电路手写分支
+//!
+//! 证明带[`proof_file`](crate::proof_file)文件头, 电路标识为注册名, 布局变体由决定形状的参数组成(如"n=10"),
+//! 所以`fib`的证明与命令行`prove --n`生成的证明互通. 证明密钥按"电路名/布局变体"缓存在[`KeyCache`]中,
+//! 同一形状的命题反复证明时只生成一次密钥; 验证密钥另按"电路名/布局变体/k"缓存
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{keygen_vk, Circuit, Error, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+
+use crate::dsl::{format_decimal, DslError, Statement};
+use crate::key_cache::{KeyCache, ProvingSetup, VerifyingSetup};
+use crate::fib_merkle::{merkle_root, FibMerkleCircuit};
+use crate::preimage::{preimage_hash, PreimageCircuit, PREIMAGE_K};
+use crate::profile::{profile, Profile};
+use crate::proof_file::{decode_proof, encode_proof, verify_encoded, ProofFileError, ProofHeader};
+use crate::prover::{keygen_with_retry, prove, setup, VerifyOutcome, ZeroizeOnDrop, ZeroizeWitness};
+use crate::recurrence::{recurrence_terms, JacobsthalCircuit, PellCircuit};
+use crate::{FibCircuit, FibStatement, FibWitness};
+
+pub use crate::prover::MAX_K;
+
+/// 参数表中的一项
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    /// 没有默认值的参数必须给出
+    pub default: Option<u64>,
+    /// 是否决定电路形状, 决定形状的参数进入证明文件头
+    pub shape: bool,
+    pub doc: &'static str,
+}
+
+impl ParamSpec {
+    /// 决定电路形状的必需参数
+    pub const fn shape(name: &'static str, doc: &'static str) -> Self {
+        Self { name, default: None, shape: true, doc }
+    }
+
+    /// 私有见证参数
+    pub const fn witness(name: &'static str, default: u64, doc: &'static str) -> Self {
+        Self { name, default: Some(default), shape: false, doc }
+    }
+}
+
+/// 按参数表补齐默认值后的参数
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Args(BTreeMap<&'static str, u64>);
+
+impl Args {
+    pub fn get(&self, name: &str) -> u64 {
+        *self.0.get(name).expect("参数不在参数表中")
+    }
+
+    /// 数列长度n, 至少为3且min_k(n)不超过[`MAX_K`]. 在生成见证之前检查, 所以验证密钥文件里过大的n不会耗尽内存
+    pub fn n(&self, min_k: fn(usize) -> u32) -> Result<usize, DslError> {
+        let n = self.get("n");
+        // 每项至少占一行, 先排除超过2^MAX_K的n, min_k里的行数计算不会溢出
+        usize::try_from(n).ok().filter(|&n| n >= 3 && n <= 1 << MAX_K && min_k(n) <= MAX_K).ok_or_else(|| DslError::BadValue { arg: "n".to_string(), value: n.to_string() })
+    }
+}
+
+/// 构造出的电路和公开输入, min_k为生成密钥时k的起点
+pub struct Built<C> {
+    pub circuit: C,
+    pub public_inputs: Vec<Fp>,
+    pub min_k: u32,
+}
+
+/// 经注册表生成的证明, proof带文件头
+#[derive(Clone, Debug)]
+pub struct RegisteredProof {
+    pub k: u32,
+    pub public_inputs: Vec<Fp>,
+    pub proof: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    UnknownCircuit(String),
+    Statement(DslError),
+    /// 见证不满足命题, 生成的证明不会通过验证
+    Unsatisfied,
+    /// 无法生成证明文件头
+    ProofFile(ProofFileError),
+    Plonk(Error),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::UnknownCircuit(name) => write!(f, "没有注册名为{}的电路", name),
+            RegistryError::Statement(e) => write!(f, "{}", e),
+            RegistryError::Unsatisfied => write!(f, "见证不满足命题"),
+            RegistryError::ProofFile(e) => write!(f, "{}", e),
+            RegistryError::Plonk(e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+impl From<DslError> for RegistryError {
+    fn from(e: DslError) -> Self {
+        RegistryError::Statement(e)
+    }
+}
+
+impl From<ProofFileError> for RegistryError {
+    fn from(e: ProofFileError) -> Self {
+        RegistryError::ProofFile(e)
+    }
+}
+
+impl From<Error> for RegistryError {
+    fn from(e: Error) -> Self {
+        RegistryError::Plonk(e)
+    }
+}
+
+type ProveFn = dyn Fn(&Args, Option<Fp>) -> Result<RegisteredProof, RegistryError> + Send + Sync;
+type VerifyFn = dyn Fn(&Args, Option<Fp>, &[u8]) -> Result<VerifyOutcome, RegistryError> + Send + Sync;
+type ProfileFn = dyn Fn(&Args, Option<Fp>) -> Result<Profile, RegistryError> + Send + Sync;
+type VkFn = dyn Fn(&Args, &Params<EqAffine>) -> Result<VerifyingKey<EqAffine>, RegistryError> + Send + Sync;
+
+/// 注册表中的一个电路
+pub struct CircuitEntry {
+    pub name: &'static str,
+    pub doc: &'static str,
+    pub params: Vec<ParamSpec>,
+    prove: Box<ProveFn>,
+    verify: Box<VerifyFn>,
+    profile: Box<ProfileFn>,
+    vk: Box<VkFn>,
+}
+
+/// 布局变体: 决定形状的参数依次写成"名字=值", 用逗号分隔
+fn layout(params: &[ParamSpec], args: &Args) -> String {
+    params.iter().filter(|p| p.shape).map(|p| format!("{}={}", p.name, args.get(p.name))).collect::<Vec<_>>().join(",")
+}
+
+impl CircuitEntry {
+    /// 检查命题的电路名和参数, 补齐默认值
+    pub fn args(&self, statement: &Statement) -> Result<Args, DslError> {
+        let names: Vec<&str> = self.params.iter().map(|p| p.name).collect();
+        statement.expect_circuit(self.name, &names)?;
+        self.params.iter().map(|p| Ok((p.name, statement.u64_arg(p.name, p.default)?))).collect::<Result<_, DslError>>().map(Args)
+    }
+
+    pub fn prove(&self, statement: &Statement) -> Result<RegisteredProof, RegistryError> {
+        (self.prove)(&self.args(statement)?, statement.target_field())
+    }
+
+    pub fn verify(&self, statement: &Statement, proof: &[u8]) -> Result<VerifyOutcome, RegistryError> {
+        (self.verify)(&self.args(statement)?, statement.target_field(), proof)
+    }
+
+    /// 去掉见证参数、以target为目标值的命题, 可以交给验证方
+    pub fn public_statement(&self, statement: &Statement, target: &Fp) -> Statement {
+        let args = statement.args.iter().filter(|(name, _)| self.params.iter().any(|p| p.shape && p.name == name.as_str())).map(|(name, value)| (name.clone(), value.clone())).collect();
+        Statement { circuit: statement.circuit.clone(), args, target: Some(format_decimal(target)) }
+    }
+
+    /// 合成一遍电路并计时, 见[`profile`]
+    pub fn profile(&self, statement: &Statement) -> Result<Profile, RegistryError> {
+        (self.profile)(&self.args(statement)?, statement.target_field())
+    }
+
+    /// 按布局变体生成验证密钥, 见证参数取默认值, 不需要调用方构造电路
+    pub fn keygen_vk(&self, layout: &str, params: &Params<EqAffine>) -> Result<VerifyingKey<EqAffine>, RegistryError> {
+        let statement = Statement::parse(&format!("{}({})", self.name, layout))?;
+        (self.vk)(&self.args(&statement)?, params)
+    }
+}
+
+/// 按名字索引的电路
+#[derive(Default)]
+pub struct CircuitRegistry {
+    entries: BTreeMap<&'static str, CircuitEntry>,
+    keys: Arc<KeyCache>,
+}
+
+impl CircuitRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记一个电路. build由参数和命题里的目标值构造电路和公开输入, 验证时也用它得到电路形状和公开输入,
+    /// 所以见证参数取默认值时也要能构造出电路. 证明和计时结束后抹掉电路中的见证
+    pub fn register<C, B>(&mut self, name: &'static str, doc: &'static str, params: &[ParamSpec], build: B)
+    where
+        C: Circuit<Fp> + ZeroizeWitness + 'static,
+        B: Fn(&Args, Option<Fp>) -> Result<Built<C>, DslError> + Send + Sync + 'static,
+    {
+        let build = Arc::new(build);
+        let (prove_build, prove_params, keys) = (build.clone(), params.to_vec(), self.keys.clone());
+        let prove_fn = move |args: &Args, target: Option<Fp>| -> Result<RegisteredProof, RegistryError> {
+            let Built { circuit, public_inputs, min_k } = prove_build(args, target)?;
+            let circuit = ZeroizeOnDrop(circuit);
+            let layout = layout(&prove_params, args);
+            let setup = keys.get_or_keygen(&format!("{}/{}", name, layout), || {
+                keygen_with_retry(min_k, MAX_K, &circuit.0.without_witnesses()).map(|(k, params, pk)| ProvingSetup { k, params, pk })
+            })?;
+            let k = setup.k;
+            if MockProver::run(k, &circuit.0, vec![public_inputs.clone()])?.verify().is_err() {
+                return Err(RegistryError::Unsatisfied);
+            }
+            let proof = prove(&setup.params, &setup.pk, &circuit.0, &public_inputs)?;
+            let header = ProofHeader::new(name, &layout, k, setup.pk.get_vk())?;
+            Ok(RegisteredProof { k, public_inputs, proof: encode_proof(&header, &proof) })
+        };
+        let profile_build = build.clone();
+        let vk_build = build.clone();
+        let vk_fn = move |args: &Args, params: &Params<EqAffine>| -> Result<VerifyingKey<EqAffine>, RegistryError> {
+            let built = vk_build(args, None)?;
+            Ok(keygen_vk(params, &built.circuit.without_witnesses())?)
+        };
+        let profile_fn = move |args: &Args, target: Option<Fp>| -> Result<Profile, RegistryError> {
+            let built = profile_build(args, target)?;
+            let circuit = ZeroizeOnDrop(built.circuit);
+            Ok(profile(&circuit.0, vec![built.public_inputs])?)
+        };
+        let (verify_params, verify_keys) = (params.to_vec(), self.keys.clone());
+        let verify_fn = move |args: &Args, target: Option<Fp>, bytes: &[u8]| -> Result<VerifyOutcome, RegistryError> {
+            let built = build(args, target)?;
+            // k取自证明文件头, 验证密钥由验证方按参数重新生成
+            let k = match decode_proof(bytes) {
+                Ok((header, _)) if header.k <= MAX_K => header.k,
+                Ok((header, _)) => return Ok(VerifyOutcome::BadProof { reason: format!("k = {}超过上限{}", header.k, MAX_K) }),
+                Err(e) => return Ok(e.into()),
+            };
+            // 参数和验证密钥按形状和k缓存, 同一形状反复验证时不再对fixed列和选择器做承诺
+            let layout = layout(&verify_params, args);
+            let cached = verify_keys.get_or_keygen_vk(&format!("{}/{}/k={}", name, layout, k), || {
+                let params = setup(k);
+                keygen_vk(&params, &built.circuit.without_witnesses()).map(|vk| VerifyingSetup { params, vk })
+            });
+            // 文件头的k可能被篡改得放不下电路
+            let cached = match cached {
+                Ok(cached) => cached,
+                Err(Error::NotEnoughRowsAvailable { .. }) => return Ok(VerifyOutcome::BadProof { reason: format!("k = {}放不下电路", k) }),
+                Err(e) => return Err(e.into()),
+            };
+            let header = ProofHeader::new(name, &layout, k, &cached.vk)?;
+            Ok(verify_encoded(&cached.params, &cached.vk, &header, &built.public_inputs, bytes))
+        };
+        let entry = CircuitEntry { name, doc, params: params.to_vec(), prove: Box::new(prove_fn), verify: Box::new(verify_fn), profile: Box::new(profile_fn), vk: Box::new(vk_fn) };
+        assert!(self.entries.insert(name, entry).is_none(), "电路{}重复注册", name);
+    }
+
+    /// 本crate自带的电路: fib、pell、jacobsthal、fib_merkle、preimage
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        let n = ParamSpec::shape("n", "证明第n项");
+        registry.register("fib", "斐波那契数列的第n项", &[n, ParamSpec::witness("a", 1, "第1项"), ParamSpec::witness("b", 1, "第2项")], |args, target| {
+            let n = args.n(FibCircuit::<Fp>::min_k)?;
+            let witness = FibWitness::new(Fp::from(args.get("a")), Fp::from(args.get("b")));
+            let statement = FibStatement::new(n, target.unwrap_or_else(|| witness.nth(n))).expect("n已检查");
+            Ok(Built { circuit: FibCircuit::new(&statement, &witness), public_inputs: statement.public_inputs(), min_k: FibCircuit::min_k(n) })
+        });
+        registry.register("pell", "佩尔数列 x(n) = 2x(n-1) + x(n-2) 的第n项", &[n, ParamSpec::witness("a", 1, "第1项"), ParamSpec::witness("b", 2, "第2项")], |args, target| {
+            let n = args.n(PellCircuit::<Fp>::min_k)?;
+            let (a, b) = (Fp::from(args.get("a")), Fp::from(args.get("b")));
+            let target = target.unwrap_or_else(|| recurrence_terms(2, 1, a, b, n)[n - 1]);
+            Ok(Built { circuit: PellCircuit::new(a, b, n), public_inputs: vec![target], min_k: PellCircuit::<Fp>::min_k(n) })
+        });
+        registry.register("jacobsthal", "雅各布斯塔尔数列 x(n) = x(n-1) + 2x(n-2) 的第n项", &[n, ParamSpec::witness("a", 1, "第1项"), ParamSpec::witness("b", 1, "第2项")], |args, target| {
+            let n = args.n(JacobsthalCircuit::<Fp>::min_k)?;
+            let (a, b) = (Fp::from(args.get("a")), Fp::from(args.get("b")));
+            let target = target.unwrap_or_else(|| recurrence_terms(1, 2, a, b, n)[n - 1]);
+            Ok(Built { circuit: JacobsthalCircuit::new(a, b, n), public_inputs: vec![target], min_k: JacobsthalCircuit::<Fp>::min_k(n) })
+        });
+        registry.register("fib_merkle", "斐波那契数列第1..=n项的Poseidon默克尔树根", &[n, ParamSpec::witness("a", 1, "第1项"), ParamSpec::witness("b", 1, "第2项")], |args, target| {
+            let n = args.n(FibMerkleCircuit::min_k)?;
+            let (a, b) = (Fp::from(args.get("a")), Fp::from(args.get("b")));
+            let root = target.unwrap_or_else(|| merkle_root(&recurrence_terms(1, 1, a, b, n)));
+            Ok(Built { circuit: FibMerkleCircuit::new(a, b, n), public_inputs: vec![root], min_k: FibMerkleCircuit::min_k(n) })
+        });
+        // 没有决定形状的参数, 布局变体为空; 验证时只需给出哈希值, 如"preimage() == h"
+        registry.register("preimage", "知道x使得 Poseidon(x) = h, 只公开h", &[ParamSpec::witness("x", 0, "私有原像")], |args, target| {
+            let x = Fp::from(args.get("x"));
+            let h = target.unwrap_or_else(|| preimage_hash(x));
+            Ok(Built { circuit: PreimageCircuit::new(x), public_inputs: vec![h], min_k: PREIMAGE_K })
+        });
+        registry
+    }
+
+    /// 各电路共用的密钥缓存
+    pub fn key_cache(&self) -> &KeyCache {
+        &self.keys
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CircuitEntry> {
+        self.entries.get(name)
+    }
+
+    /// 按名字排序的全部电路
+    pub fn entries(&self) -> impl Iterator<Item = &CircuitEntry> {
+        self.entries.values()
+    }
+
+    fn entry(&self, statement: &Statement) -> Result<&CircuitEntry, RegistryError> {
+        self.get(&statement.circuit).ok_or_else(|| RegistryError::UnknownCircuit(statement.circuit.clone()))
+    }
+
+    /// 按命题的电路名分派证明
+    pub fn prove(&self, statement: &Statement) -> Result<RegisteredProof, RegistryError> {
+        self.entry(statement)?.prove(statement)
+    }
+
+    /// 按命题的电路名分派验证
+    pub fn verify(&self, statement: &Statement, proof: &[u8]) -> Result<VerifyOutcome, RegistryError> {
+        self.entry(statement)?.verify(statement, proof)
+    }
+
+    /// 按命题的电路名分派合成计时
+    pub fn profile(&self, statement: &Statement) -> Result<Profile, RegistryError> {
+        self.entry(statement)?.profile(statement)
+    }
+
+    /// 按电路名和布局变体生成验证密钥, 供[`vk_file`](crate::vk_file)读取后重建
+    pub fn keygen_vk(&self, circuit: &str, layout: &str, params: &Params<EqAffine>) -> Result<VerifyingKey<EqAffine>, RegistryError> {
+        self.get(circuit).ok_or_else(|| RegistryError::UnknownCircuit(circuit.to_string()))?.keygen_vk(layout, params)
+    }
+}
+
+#[test]
+fn test_registry() {
+    let registry = CircuitRegistry::builtin();
+    assert_eq!(registry.entries().map(|e| e.name).collect::<Vec<_>>(), ["fib", "fib_merkle", "jacobsthal", "pell", "preimage"]);
+
+    let statement = Statement::parse("pell(n=10) == 2378").unwrap();
+    let proved = registry.prove(&statement).unwrap();
+    assert_eq!(proved.public_inputs, vec![Fp::from(2378)]);
+    assert!(registry.verify(&statement, &proved.proof).unwrap().is_valid());
+    // 公开输入不同
+    assert!(!registry.verify(&Statement::parse("pell(n=10) == 2379").unwrap(), &proved.proof).unwrap().is_valid());
+    // 形状不同, 文件头的布局变体不一致
+    assert!(matches!(registry.verify(&Statement::parse("pell(n=9)").unwrap(), &proved.proof).unwrap(), VerifyOutcome::VkMismatch { .. }));
+    // 同一形状的第二个命题复用密钥
+    let other = Statement::parse("pell(n=10, b=3)").unwrap();
+    assert!(registry.verify(&other, &registry.prove(&other).unwrap().proof).unwrap().is_valid());
+    assert_eq!(registry.key_cache().stats(), (1, 1));
+    // n=10和n=9各生成一次验证密钥, 其余验证命中
+    assert_eq!(registry.key_cache().vk_stats(), (2, 2));
+
+    // 验证方只知道哈希值
+    let proved = registry.prove(&Statement::parse("preimage(x=42)").unwrap()).unwrap();
+    let h = preimage_hash(Fp::from(42));
+    assert_eq!(proved.public_inputs, vec![h]);
+    let public = registry.get("preimage").unwrap().public_statement(&Statement::parse("preimage(x=42)").unwrap(), &h);
+    assert_eq!(public.to_string(), format!("preimage() == {}", format_decimal(&h)));
+    assert!(registry.verify(&public, &proved.proof).unwrap().is_valid());
+
+    // n较大时k从min_k起步, 不靠重试
+    let statement = Statement::parse("pell(n=30)").unwrap();
+    let proved = registry.prove(&statement).unwrap();
+    assert_eq!(proved.k, PellCircuit::<Fp>::min_k(30));
+    assert!(registry.verify(&statement, &proved.proof).unwrap().is_valid());
+    // 文件头的k改小后放不下电路, 应报告证明无效而不是panic
+    let mut tampered = proved.proof.clone();
+    tampered[40..44].copy_from_slice(&3u32.to_le_bytes());
+    assert!(matches!(registry.verify(&statement, &tampered).unwrap(), VerifyOutcome::BadProof { .. }));
+
+    assert!(matches!(registry.prove(&Statement::parse("fib(n=10) == 56").unwrap()), Err(RegistryError::Unsatisfied)));
+    assert!(matches!(registry.prove(&Statement::parse("factorial(n=5)").unwrap()), Err(RegistryError::UnknownCircuit(_))));
+    assert!(matches!(registry.prove(&Statement::parse("jacobsthal(n=2)").unwrap()), Err(RegistryError::Statement(DslError::BadValue { .. }))));
+    // n过大时在生成见证之前拒绝, 验证方按布局变体重建验证密钥也一样
+    assert!(matches!(registry.prove(&Statement::parse("fib(n=1000000000000)").unwrap()), Err(RegistryError::Statement(DslError::BadValue { .. }))));
+    assert!(matches!(registry.prove(&Statement::parse("fib_merkle(n=100000)").unwrap()), Err(RegistryError::Statement(DslError::BadValue { .. }))));
+    assert!(matches!(registry.keygen_vk("pell", "n=1000000000000", &setup(4)), Err(RegistryError::Statement(DslError::BadValue { .. }))));
+}
@@ -0,0 +1,128 @@
+//! 电路布局图(需要`dev` feature)
+//!
+//! 只用SVG后端, 文字用内置的DejaVu Sans Mono字体(许可见`assets/fonts/DejaVu-LICENSE.txt`),
+//! 不依赖系统字体和显示设备, 在最小化的CI容器里也能运行. 内置字体没有汉字, 中文区域名会显示成方框
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Once;
+
+use halo2_proofs::dev::{circuit_dot_graph, CircuitLayout};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::Circuit;
+use plotters::prelude::*;
+use plotters::style::{register_font, FontStyle};
+
+/// 内置字体注册成的字体族名; halo2画区域名时用的也是这个名字
+pub const FONT_FAMILY: &str = "sans-serif";
+
+static FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSansMono.ttf");
+
+#[derive(Debug)]
+pub enum RenderError {
+    Io(io::Error),
+    /// 绘图失败, plotters的错误类型带后端参数, 这里只保留信息
+    Draw(String),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Io(e) => write!(f, "写入布局图失败: {}", e),
+            RenderError::Draw(e) => write!(f, "绘制布局图失败: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<io::Error> for RenderError {
+    fn from(e: io::Error) -> Self {
+        RenderError::Io(e)
+    }
+}
+
+fn draw_error(e: impl fmt::Display) -> RenderError {
+    RenderError::Draw(e.to_string())
+}
+
+/// 布局图的选项
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// 宽和高, 单位为像素
+    pub size: (u32, u32),
+    pub title: Option<String>,
+    /// 是否在区域上标出区域名
+    pub labels: bool,
+    /// 是否标出参与拷贝约束的单元格
+    pub equality: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self { size: (1024, 3096), title: None, labels: false, equality: true }
+    }
+}
+
+/// 把电路的列布局画成SVG文本
+///
+/// ```
+/// use halo2_fib::render::{render_svg, RenderOptions};
+/// use halo2_fib::{FibCircuit, FibStatement, FibWitness};
+/// use halo2_proofs::pasta::Fp;
+///
+/// let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+/// let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+/// let options = RenderOptions { title: Some("Fib Layout".to_string()), ..RenderOptions::default() };
+/// assert!(render_svg(4, &circuit, &options).unwrap().starts_with("<svg"));
+/// ```
+pub fn render_svg<C: Circuit<Fp>>(k: u32, circuit: &C, options: &RenderOptions) -> Result<String, RenderError> {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| register_font(FONT_FAMILY, FontStyle::Normal, FONT).unwrap_or_else(|_| panic!("内置字体无效")));
+
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, options.size).into_drawing_area();
+        root.fill(&WHITE).map_err(draw_error)?;
+        let root = match &options.title {
+            Some(title) => root.titled(title, (FONT_FAMILY, 40)).map_err(draw_error)?,
+            None => root,
+        };
+        CircuitLayout::default()
+            .show_labels(options.labels)
+            .mark_equality_cells(options.equality)
+            .show_equality_constraints(options.equality)
+            .render(k, circuit, &root)
+            .map_err(draw_error)?;
+        root.present().map_err(draw_error)?;
+    }
+    Ok(svg)
+}
+
+/// 画成SVG文件
+pub fn write_svg<C: Circuit<Fp>>(path: &Path, k: u32, circuit: &C, options: &RenderOptions) -> Result<(), RenderError> {
+    fs::write(path, render_svg(k, circuit, options)?)?;
+    Ok(())
+}
+
+/// Graphviz格式的电路结构图
+pub fn dot_graph<C: Circuit<Fp>>(circuit: &C) -> String {
+    circuit_dot_graph(circuit)
+}
+
+#[test]
+fn test_render_svg() {
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let statement = FibStatement::new(10, Fp::from(55)).unwrap();
+    let circuit = FibCircuit::new(&statement, &FibWitness::new(Fp::one(), Fp::one()));
+    let options = RenderOptions { title: Some("Fib Layout".to_string()), labels: true, ..RenderOptions::default() };
+    let svg = render_svg(4, &circuit, &options).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("Fib Layout"));
+    let plain = render_svg(4, &circuit, &RenderOptions::default()).unwrap();
+    assert!(!plain.contains("Fib Layout"));
+    assert!(dot_graph(&circuit).contains("digraph"));
+}
@@ -0,0 +1,213 @@
+//! 极简rollup状态转移演示: 余额存放在Poseidon默克尔树的叶子上, 电路对一批转账逐笔更新树,
+//! 只公开更新前后的两个树根
+//!
+//! 每笔转账(from, to, amount)做四次路径计算: 用旧叶子确认from在当前树中, 扣款后得到中间树根,
+//! 再确认to在中间树中, 入账后得到新树根. 金额和扣款后、入账后的余额都做64位范围检查, 因此不能透支也不会溢出
+
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::*;
+use zeroize::Zeroize;
+
+use crate::fib::{FibChip, FibConfig};
+use crate::fib_merkle::{merkle_root, InclusionProof};
+use crate::gadgets::merkle::{MerklePathChip, MerklePathConfig};
+use crate::gadgets::range_check::{RangeCheckChip, RangeCheckConfig};
+use crate::prover::{zeroize_field, ZeroizeWitness};
+use crate::shared::SharedColumns;
+
+/// 树深度, 共2^DEPTH个账户
+pub const DEPTH: usize = 3;
+pub const ACCOUNTS: usize = 1 << DEPTH;
+
+/// 余额为64位, 即8个8位limb
+const BALANCE_LIMBS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: usize,
+    pub to: usize,
+    pub amount: u64,
+}
+
+/// 按u64执行一批转账, 透支或溢出时返回None
+pub fn apply_transfers(balances: &[u64], transfers: &[Transfer]) -> Option<Vec<u64>> {
+    let mut balances = balances.to_vec();
+    for t in transfers {
+        balances[t.from] = balances[t.from].checked_sub(t.amount)?;
+        balances[t.to] = balances[t.to].checked_add(t.amount)?;
+    }
+    Some(balances)
+}
+
+fn leaves(balances: &[u64]) -> Vec<Fp> {
+    assert_eq!(balances.len(), ACCOUNTS, "账户数必须为{}", ACCOUNTS);
+    balances.iter().map(|&b| Fp::from(b)).collect()
+}
+
+/// 余额树的根
+pub fn balance_root(balances: &[u64]) -> Fp {
+    merkle_root(&leaves(balances))
+}
+
+/// 一笔转账的见证, 按域上的加减计算, 不检查透支, 约束是否满足交给电路
+#[derive(Clone, Debug)]
+struct TransferWitness {
+    transfer: Transfer,
+    from_balance: Fp,
+    to_balance: Fp,
+    /// from在转账前的树中的路径
+    from_siblings: Vec<Fp>,
+    /// to在扣款后的树中的路径
+    to_siblings: Vec<Fp>,
+}
+
+/// 逐笔生成见证, 返回见证和最终的叶子
+fn transfer_witnesses(balances: &[u64], transfers: &[Transfer]) -> (Vec<TransferWitness>, Vec<Fp>) {
+    let mut leaves = leaves(balances);
+    let mut witnesses = Vec::with_capacity(transfers.len());
+    for &transfer in transfers {
+        let from_balance = leaves[transfer.from];
+        let from_siblings = InclusionProof::new(&leaves, transfer.from).siblings;
+        leaves[transfer.from] -= Fp::from(transfer.amount);
+        let to_balance = leaves[transfer.to];
+        let to_siblings = InclusionProof::new(&leaves, transfer.to).siblings;
+        leaves[transfer.to] += Fp::from(transfer.amount);
+        witnesses.push(TransferWitness { transfer, from_balance, to_balance, from_siblings, to_siblings });
+    }
+    (witnesses, leaves)
+}
+
+/// 实例列内容: 旧树根和新树根, 透支时新树根按域上的结果计算(电路不会满足)
+pub fn public_inputs(balances: &[u64], transfers: &[Transfer]) -> Vec<Fp> {
+    let (_, leaves) = transfer_witnesses(balances, transfers);
+    vec![balance_root(balances), merkle_root(&leaves)]
+}
+
+#[derive(Clone, Debug)]
+pub struct RollupConfig {
+    pub shared: SharedColumns,
+    pub fib: FibConfig,
+    pub range: RangeCheckConfig,
+    pub merkle: MerklePathConfig,
+}
+
+/// 批量转账电路, 电路形状只和批大小有关, 转账内容和余额都是私有的
+pub struct RollupCircuit {
+    steps: Value<Vec<TransferWitness>>,
+    batch: usize,
+}
+
+impl RollupCircuit {
+    pub fn new(balances: &[u64], transfers: &[Transfer]) -> Self {
+        let (steps, _) = transfer_witnesses(balances, transfers);
+        Self { steps: Value::known(steps), batch: transfers.len() }
+    }
+
+    /// 某一步见证中的一个值
+    fn step<T>(&self, i: usize, f: impl FnOnce(&TransferWitness) -> T) -> Value<T> {
+        self.steps.as_ref().map(|steps| f(&steps[i]))
+    }
+}
+
+impl ZeroizeWitness for RollupCircuit {
+    /// 余额、金额和路径都是秘密
+    fn zeroize_witness(&mut self) {
+        let _ = self.steps.as_mut().map(|steps| {
+            for step in steps.iter_mut() {
+                step.transfer.from.zeroize();
+                step.transfer.to.zeroize();
+                step.transfer.amount.zeroize();
+                zeroize_field(&mut step.from_balance);
+                zeroize_field(&mut step.to_balance);
+                step.from_siblings.iter_mut().chain(step.to_siblings.iter_mut()).for_each(zeroize_field);
+            }
+        });
+        self.steps = Value::unknown();
+    }
+}
+
+impl Circuit<Fp> for RollupCircuit {
+    type Config = RollupConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { steps: Value::unknown(), batch: self.batch }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let fib = FibChip::configure(meta, &shared);
+        let range = RangeCheckChip::configure(meta, &shared);
+        let merkle = MerklePathChip::configure(meta, &shared);
+        RollupConfig { shared, fib, range, merkle }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let fib = FibChip::construct(config.fib);
+        let range = RangeCheckChip::construct(config.range);
+        let merkle = MerklePathChip::construct(config.merkle.clone());
+        range.load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let mut root: Option<AssignedCell<Fp, Fp>> = None;
+        for i in 0..self.batch {
+            let mut layouter = layouter.namespace(|| format!("第{}笔转账", i));
+            let amount = range.witness_check(layouter.namespace(|| "金额"), self.step(i, |s| Fp::from(s.transfer.amount)), BALANCE_LIMBS)?;
+            let (from_balance, to_balance) = layouter.assign_region(|| "旧余额", |mut region| {
+                let from = region.assign_advice(|| "from余额", config.shared.advice[0], 0, || self.step(i, |s| s.from_balance))?;
+                let to = region.assign_advice(|| "to余额", config.shared.advice[1], 0, || self.step(i, |s| s.to_balance))?;
+                Ok((from, to))
+            })?;
+
+            // 扣款: new_from + amount = from_balance, new_from经范围检查不为负
+            let from_path = merkle.witness_path(layouter.namespace(|| "from路径"), self.step(i, |s| s.transfer.from), self.step(i, |s| s.from_siblings.clone()), DEPTH)?;
+            let old_root = merkle.root(layouter.namespace(|| "确认from"), &from_balance, &from_path)?;
+            match &root {
+                Some(root) => layouter.assign_region(|| "接上一笔", |mut region| region.constrain_equal(root.cell(), old_root.cell()))?,
+                None => layouter.constrain_instance(old_root.cell(), config.shared.instance, 0)?,
+            }
+            let new_from = range.witness_check(layouter.namespace(|| "扣款后余额"), self.step(i, |s| s.from_balance - Fp::from(s.transfer.amount)), BALANCE_LIMBS)?;
+            let debited = fib.add(layouter.namespace(|| "扣款"), &new_from, &amount)?;
+            layouter.assign_region(|| "扣款前后一致", |mut region| region.constrain_equal(debited.cell(), from_balance.cell()))?;
+            let mid_root = merkle.root(layouter.namespace(|| "扣款后树根"), &new_from, &from_path)?;
+
+            // 入账: new_to = to_balance + amount, new_to经范围检查不溢出
+            let to_path = merkle.witness_path(layouter.namespace(|| "to路径"), self.step(i, |s| s.transfer.to), self.step(i, |s| s.to_siblings.clone()), DEPTH)?;
+            let check_root = merkle.root(layouter.namespace(|| "确认to"), &to_balance, &to_path)?;
+            layouter.assign_region(|| "to在中间树中", |mut region| region.constrain_equal(check_root.cell(), mid_root.cell()))?;
+            let new_to = fib.add(layouter.namespace(|| "入账"), &to_balance, &amount)?;
+            range.copy_check(layouter.namespace(|| "入账后余额"), &new_to, BALANCE_LIMBS)?;
+            root = Some(merkle.root(layouter.namespace(|| "新树根"), &new_to, &to_path)?);
+        }
+
+        match root {
+            Some(root) => layouter.constrain_instance(root.cell(), config.shared.instance, 1),
+            None => Err(Error::Synthesis),
+        }
+    }
+}
+
+#[test]
+fn test_rollup() {
+    use halo2_proofs::dev::MockProver;
+
+    let balances = [100, 50, 0, 7, 0, 0, 0, 1];
+    let transfers = [Transfer { from: 0, to: 2, amount: 30 }, Transfer { from: 2, to: 7, amount: 30 }];
+    let after = apply_transfers(&balances, &transfers).unwrap();
+    assert_eq!(after, [70, 50, 0, 7, 0, 0, 0, 31]);
+    let instances = public_inputs(&balances, &transfers);
+    assert_eq!(instances[1], balance_root(&after));
+
+    let prover = MockProver::run(12, &RollupCircuit::new(&balances, &transfers), vec![instances.clone()]).unwrap();
+    prover.assert_satisfied();
+    let mut wrong = instances;
+    wrong[1] = balance_root(&balances);
+    let prover = MockProver::run(12, &RollupCircuit::new(&balances, &transfers), vec![wrong]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 透支
+    let overdraft = [Transfer { from: 1, to: 0, amount: 51 }, Transfer { from: 0, to: 1, amount: 1 }];
+    assert_eq!(apply_transfers(&balances, &overdraft), None);
+    let prover = MockProver::run(12, &RollupCircuit::new(&balances, &overdraft), vec![public_inputs(&balances, &overdraft)]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,152 @@
+//! 命题和见证文件的静态加密(需要`encrypt` feature)
+//!
+//! 批量证明的输入里有隐私示例的秘密见证, 两次运行之间不应以明文留在磁盘上.
+//! 加密文件为魔数`FIBENC\0\x01`、12字节随机nonce和ChaCha20-Poly1305密文(含16字节标签), 魔数同时作为附加数据.
+//! 密钥由用户提供, 密钥文件为64个十六进制字符
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand_core::{OsRng, RngCore};
+use zeroize::Zeroize;
+
+/// 文件头魔数, 最后一个字节为格式版本
+pub const SEALED_MAGIC: [u8; 8] = *b"FIBENC\0\x01";
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+#[derive(Debug)]
+pub enum SealError {
+    Io(io::Error),
+    /// 密钥文件不是64个十六进制字符
+    BadKey,
+    /// 不是加密文件, 或文件被截断
+    NotSealed,
+    /// 密钥不对或密文被篡改
+    Decrypt,
+}
+
+impl fmt::Display for SealError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SealError::Io(e) => write!(f, "读写加密文件失败: {}", e),
+            SealError::BadKey => write!(f, "密钥应为64个十六进制字符"),
+            SealError::NotSealed => write!(f, "不是加密文件或文件不完整"),
+            SealError::Decrypt => write!(f, "解密失败: 密钥不对或文件被篡改"),
+        }
+    }
+}
+
+impl std::error::Error for SealError {}
+
+impl From<io::Error> for SealError {
+    fn from(e: io::Error) -> Self {
+        SealError::Io(e)
+    }
+}
+
+/// 256位对称密钥, 丢弃时抹掉
+pub struct SealKey([u8; 32]);
+
+impl Drop for SealKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl SealKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        SealKey(key)
+    }
+
+    /// 由64个十六进制字符解析, 忽略首尾空白
+    pub fn from_hex(hex: &str) -> Result<Self, SealError> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(SealError::BadKey);
+        }
+        let mut key = SealKey([0u8; 32]);
+        for (i, byte) in key.0.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| SealError::BadKey)?;
+        }
+        Ok(key)
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn read_file(path: &Path) -> Result<Self, SealError> {
+        let mut hex = fs::read_to_string(path)?;
+        let key = Self::from_hex(&hex);
+        hex.zeroize();
+        key
+    }
+
+    /// 写入密钥文件, unix上只有所有者可读写
+    pub fn write_file(&self, path: &Path) -> Result<(), SealError> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        io::Write::write_all(&mut options.open(path)?, format!("{}\n", self.to_hex()).as_bytes())?;
+        Ok(())
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(Key::from_slice(&self.0))
+    }
+}
+
+/// 是否以加密文件的魔数开头
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&SEALED_MAGIC)
+}
+
+/// 用随机nonce加密
+pub fn seal(key: &SealKey, plaintext: &[u8]) -> Vec<u8> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = key.cipher().encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: &SEALED_MAGIC }).expect("加密失败");
+    let mut out = Vec::with_capacity(SEALED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&SEALED_MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+/// 解密并校验标签
+pub fn open(key: &SealKey, sealed: &[u8]) -> Result<Vec<u8>, SealError> {
+    if !is_sealed(sealed) || sealed.len() < SEALED_MAGIC.len() + NONCE_LEN + TAG_LEN {
+        return Err(SealError::NotSealed);
+    }
+    let (nonce, ciphertext) = sealed[SEALED_MAGIC.len()..].split_at(NONCE_LEN);
+    key.cipher().decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &SEALED_MAGIC }).map_err(|_| SealError::Decrypt)
+}
+
+#[test]
+fn test_seal() {
+    let key = SealKey::generate();
+    assert_eq!(SealKey::from_hex(&format!(" {}\n", key.to_hex())).unwrap().to_hex(), key.to_hex());
+    assert!(matches!(SealKey::from_hex("00"), Err(SealError::BadKey)));
+
+    let plaintext = b"n,a,b\n10,3,5\n";
+    let sealed = seal(&key, plaintext);
+    assert!(is_sealed(&sealed));
+    assert_ne!(seal(&key, plaintext), sealed);
+    assert_eq!(open(&key, &sealed).unwrap(), plaintext);
+
+    assert!(matches!(open(&SealKey::generate(), &sealed), Err(SealError::Decrypt)));
+    let mut tampered = sealed.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(matches!(open(&key, &tampered), Err(SealError::Decrypt)));
+    assert!(matches!(open(&key, plaintext), Err(SealError::NotSealed)));
+    assert!(matches!(open(&key, &sealed[..20]), Err(SealError::NotSealed)));
+}
@@ -0,0 +1,343 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::*;
+use halo2_proofs::poly::Rotation;
+
+use crate::fib::{FibChip, FibConfig};
+use crate::gadgets::pack::{pack, truncate, unpack, PackChip, PackConfig};
+use crate::gadgets::poseidon::{hash2, PoseidonChip, PoseidonConfig};
+use crate::instance::{InstanceAllocator, InstanceType};
+use crate::shared::SharedColumns;
+
+/// 由公开开关控制的分段的列配置
+///
+/// 开关从实例列拷贝到flag列并约束为0或1. 分段内每行也拷贝一份开关, 门写成 q * flag * (..),
+/// 开关为0时整段约束失效, 证明者可以随便填; 分段的结果再经 out = off + flag * (on - off) 选出
+#[derive(Clone, Debug, Copy)]
+pub struct SegmentConfig {
+    pub q_bool: Selector,
+    pub q_step: Selector,
+    pub q_select: Selector,
+    pub flag: Column<Advice>,
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+}
+
+/// 开关分段芯片: 同一个电路按公开开关包含或跳过某些计算, 电路形状不变
+pub struct SegmentChip<F: PrimeField> {
+    config: SegmentConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> SegmentChip<F> {
+    pub fn construct(config: SegmentConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    /// 共享的三个advice列作为a、b、c, 另外分配一个advice列放开关
+    pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> SegmentConfig {
+        let q_bool = meta.selector();
+        let q_step = meta.selector();
+        let q_select = meta.selector();
+        let flag = meta.advice_column();
+        meta.enable_equality(flag);
+        let [a, b, c] = shared.advice;
+
+        meta.create_gate("segment_bool", |meta| {
+            let q = meta.query_selector(q_bool);
+            let flag = meta.query_advice(flag, Rotation::cur());
+            vec![("flag * (1 - flag) = 0", q * flag.clone() * (Expression::Constant(F::ONE) - flag))]
+        });
+
+        meta.create_gate("segment_step", |meta| {
+            let q = meta.query_selector(q_step);
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            vec![("flag * (a + b - c) = 0", q * flag * (a + b - c))]
+        });
+
+        meta.create_gate("segment_select", |meta| {
+            let q = meta.query_selector(q_select);
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let on = meta.query_advice(a, Rotation::cur());
+            let off = meta.query_advice(b, Rotation::cur());
+            let out = meta.query_advice(c, Rotation::cur());
+            vec![("out = off + flag * (on - off)", q * (out - off.clone() - flag * (on - off)))]
+        });
+        SegmentConfig { q_bool, q_step, q_select, flag, a, b, c }
+    }
+
+    /// 从实例列的第row行拷贝开关, 并约束其为0或1
+    pub fn load_flag(&self, mut layouter: impl Layouter<F>, instance: Column<Instance>, row: usize) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "加载开关", |mut region| {
+            self.config.q_bool.enable(&mut region, 0)?;
+            region.assign_advice_from_instance(|| "开关", instance, row, self.config.flag, 0)
+        })
+    }
+
+    /// 填写一个私有开关, 并约束其为0或1
+    pub fn witness_flag(&self, mut layouter: impl Layouter<F>, value: Value<F>) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "填写开关", |mut region| {
+            self.config.q_bool.enable(&mut region, 0)?;
+            region.assign_advice(|| "开关", self.config.flag, 0, || value)
+        })
+    }
+
+    /// 接着(b, c)再算steps项斐波那契数列, 每行只在开关为1时约束, 返回最后一项
+    pub fn gated_sequence(&self, mut layouter: impl Layouter<F>, flag: &AssignedCell<F, F>, b: &AssignedCell<F, F>, c: &AssignedCell<F, F>, steps: usize) -> Result<AssignedCell<F, F>, Error> {
+        assert!(steps > 0, "至少一步");
+        layouter.assign_region(|| "分段数列", |mut region| {
+            let (mut pre_b, mut pre_c) = (b.clone(), c.clone());
+            for row in 0..steps {
+                self.config.q_step.enable(&mut region, row)?;
+                flag.copy_advice(|| "拷贝开关", &mut region, self.config.flag, row)?;
+                let a = pre_b.copy_advice(|| "拷贝上一行b到当前a", &mut region, self.config.a, row)?;
+                let b = pre_c.copy_advice(|| "拷贝上一行c到当前b", &mut region, self.config.b, row)?;
+                let c = region.assign_advice(|| "c", self.config.c, row, || a.value().copied() + b.value().copied())?;
+                pre_b = b;
+                pre_c = c;
+            }
+            Ok(pre_c)
+        })
+    }
+
+    /// 开关为1时取on, 为0时取off
+    pub fn select(&self, mut layouter: impl Layouter<F>, flag: &AssignedCell<F, F>, on: &AssignedCell<F, F>, off: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(|| "选择", |mut region| {
+            self.config.q_select.enable(&mut region, 0)?;
+            let flag = flag.copy_advice(|| "拷贝开关", &mut region, self.config.flag, 0)?;
+            let on = on.copy_advice(|| "拷贝on", &mut region, self.config.a, 0)?;
+            let off = off.copy_advice(|| "拷贝off", &mut region, self.config.b, 0)?;
+            let value = flag.value().zip(on.value()).zip(off.value()).map(|((flag, on), off)| *off + *flag * (*on - off));
+            region.assign_advice(|| "out", self.config.c, 0, || value)
+        })
+    }
+}
+
+/// [`SegmentedFibCircuit`]的公开选项
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SegmentFlags {
+    /// 是否再延长extra项
+    pub extend: bool,
+    /// 是否公开Poseidon(结果, 0)而不是结果本身
+    pub hash: bool,
+}
+
+/// 带两个可选分段的斐波那契电路, 四种选项共用一套密钥
+///
+/// 先算第1..=n项; 延长段再算extra项; 哈希段对结果做Poseidon哈希.
+/// 哈希芯片的门不受开关控制, 跳过时仍会在原结果上算一次哈希, 只是不被选中
+pub struct SegmentedFibCircuit {
+    a: Value<Fp>,
+    b: Value<Fp>,
+    n: usize,
+    extra: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct SegmentedFibConfig {
+    pub shared: SharedColumns,
+    pub fib: FibConfig,
+    pub segment: SegmentConfig,
+    pub poseidon: PoseidonConfig,
+}
+
+impl SegmentedFibCircuit {
+    pub fn new(a: Fp, b: Fp, n: usize, extra: usize) -> Self {
+        assert!(n >= 3 && extra > 0, "n至少为3, extra至少为1");
+        Self { a: Value::known(a), b: Value::known(b), n, extra }
+    }
+
+    /// 实例列布局: 两个开关和输出
+    pub fn instance_layout() -> InstanceAllocator<Fp> {
+        let mut layout = InstanceAllocator::new();
+        layout.alloc_typed("extend", InstanceType::Bool);
+        layout.alloc_typed("hash", InstanceType::Bool);
+        layout.alloc("output");
+        layout
+    }
+
+    /// 电路外按选项计算公开输入
+    pub fn public_inputs(&self, a: Fp, b: Fp, flags: SegmentFlags) -> Vec<Fp> {
+        let terms = crate::recurrence::recurrence_terms(1, 1, a, b, self.n + if flags.extend { self.extra } else { 0 });
+        let result = terms[terms.len() - 1];
+        let output = if flags.hash { hash2(result, Fp::zero()) } else { result };
+        let mut layout = Self::instance_layout();
+        layout.set("extend", Fp::from(flags.extend as u64)).expect("缺少extend实例行");
+        layout.set("hash", Fp::from(flags.hash as u64)).expect("缺少hash实例行");
+        layout.set("output", output).expect("缺少output实例行");
+        layout.public_inputs().expect("公开输入不完整")
+    }
+
+    /// 按两个开关单元格算出输出
+    fn output(&self, config: &SegmentedFibConfig, mut layouter: impl Layouter<Fp>, extend: &AssignedCell<Fp, Fp>, hash: &AssignedCell<Fp, Fp>) -> Result<AssignedCell<Fp, Fp>, Error> {
+        let fib = FibChip::construct(config.fib);
+        let segment = SegmentChip::construct(config.segment);
+        let poseidon = PoseidonChip::construct(config.poseidon.clone());
+
+        let terms = fib.assign_sequence(layouter.namespace(|| "填写数列"), self.a, self.b, self.n)?;
+        let (pre_b, base) = (&terms[terms.len() - 2], &terms[terms.len() - 1]);
+        let extended = segment.gated_sequence(layouter.namespace(|| "延长段"), extend, pre_b, base, self.extra)?;
+        let result = segment.select(layouter.namespace(|| "选择延长结果"), extend, &extended, base)?;
+
+        let zero = layouter.assign_region(|| "零", |mut region| {
+            region.assign_advice_from_constant(|| "零", config.shared.advice[0], 0, Fp::zero())
+        })?;
+        let digest = poseidon.hash2(layouter.namespace(|| "哈希段"), &result, &zero)?;
+        segment.select(layouter.namespace(|| "选择哈希结果"), hash, &digest, &result)
+    }
+}
+
+impl Circuit<Fp> for SegmentedFibCircuit {
+    type Config = SegmentedFibConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown(), n: self.n, extra: self.extra }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let fib = FibChip::configure(meta, &shared);
+        let segment = SegmentChip::configure(meta, &shared);
+        let poseidon = PoseidonChip::configure(meta, &shared);
+        SegmentedFibConfig { shared, fib, segment, poseidon }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let segment = SegmentChip::construct(config.segment);
+        let layout = Self::instance_layout();
+        let row = |label: &str| layout.row(label).expect("缺少实例行");
+
+        let extend = segment.load_flag(layouter.namespace(|| "延长开关"), config.shared.instance, row("extend"))?;
+        let hash = segment.load_flag(layouter.namespace(|| "哈希开关"), config.shared.instance, row("hash"))?;
+        let output = self.output(&config, layouter.namespace(|| "计算输出"), &extend, &hash)?;
+        FibChip::construct(config.fib).expose_public(layouter.namespace(|| "公开输出"), &output, row("output"))
+    }
+}
+
+/// 打包模式下n、开关和输出各占的字节数, 按此顺序从低字节排起
+pub const PACKED_WIDTHS: [usize; 3] = [4, 1, 16];
+
+/// 打包公开输入的[`SegmentedFibCircuit`]: 三个实例值合成一个
+///
+/// 打包值为 n | (extend + 2 * hash) << 32 | (输出的低16字节) << 40, 开关成了私有见证, 由打包值约束.
+/// 输出只公开低128位, 哈希模式下截断后仍有128位的抗原像; 输出不小于2^254时无法证明, 见[`PackChip::truncate`]
+pub struct PackedSegmentedFibCircuit {
+    inner: SegmentedFibCircuit,
+    flags: Value<SegmentFlags>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PackedSegmentedFibConfig {
+    pub inner: SegmentedFibConfig,
+    pub pack: PackConfig,
+}
+
+impl PackedSegmentedFibCircuit {
+    pub fn new(a: Fp, b: Fp, n: usize, extra: usize, flags: SegmentFlags) -> Self {
+        assert!(n < 1 << 32, "n超过4字节");
+        Self { inner: SegmentedFibCircuit::new(a, b, n, extra), flags: Value::known(flags) }
+    }
+
+    /// 电路外计算唯一的公开输入
+    pub fn public_inputs(&self, a: Fp, b: Fp, flags: SegmentFlags) -> Vec<Fp> {
+        let output = self.inner.public_inputs(a, b, flags)[2];
+        let fields = [Fp::from(self.inner.n as u64), Fp::from(flags.extend as u64 + 2 * flags.hash as u64), truncate(output, PACKED_WIDTHS[2])];
+        vec![pack(&[(fields[0], PACKED_WIDTHS[0]), (fields[1], PACKED_WIDTHS[1]), (fields[2], PACKED_WIDTHS[2])]).expect("打包字段超出宽度")]
+    }
+
+    /// 验证方拆出n、开关和输出的低16字节
+    pub fn unpack_public(packed: Fp) -> Option<(u64, SegmentFlags, Fp)> {
+        let fields = unpack(packed, &PACKED_WIDTHS);
+        let n = u64::from_le_bytes(fields[0].to_repr()[..8].try_into().expect("repr不足8字节"));
+        let flags = match fields[1].to_repr()[0] {
+            bits @ 0..=3 => SegmentFlags { extend: bits & 1 == 1, hash: bits & 2 == 2 },
+            _ => return None,
+        };
+        // 打包值必须正好是这三个字段
+        (pack(&[(fields[0], PACKED_WIDTHS[0]), (fields[1], PACKED_WIDTHS[1]), (fields[2], PACKED_WIDTHS[2])]) == Some(packed)).then_some((n, flags, fields[2]))
+    }
+}
+
+impl Circuit<Fp> for PackedSegmentedFibCircuit {
+    type Config = PackedSegmentedFibConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { inner: self.inner.without_witnesses(), flags: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let inner = SegmentedFibCircuit::configure(meta);
+        let pack = PackChip::configure(meta, &inner.shared);
+        PackedSegmentedFibConfig { inner, pack }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        let segment = SegmentChip::construct(config.inner.segment);
+        let pack = PackChip::construct(config.pack);
+        pack.load_table(layouter.namespace(|| "加载查找表"))?;
+
+        let extend = segment.witness_flag(layouter.namespace(|| "延长开关"), self.flags.map(|flags| Fp::from(flags.extend as u64)))?;
+        let hash = segment.witness_flag(layouter.namespace(|| "哈希开关"), self.flags.map(|flags| Fp::from(flags.hash as u64)))?;
+        let output = self.inner.output(&config.inner, layouter.namespace(|| "计算输出"), &extend, &hash)?;
+
+        let n = layouter.assign_region(|| "n", |mut region| {
+            region.assign_advice_from_constant(|| "n", config.inner.shared.advice[0], 0, Fp::from(self.inner.n as u64))
+        })?;
+        let flags = pack.combine(layouter.namespace(|| "合并开关"), &[(extend, Fp::one()), (hash, Fp::from(2))])?;
+        let output = pack.truncate(layouter.namespace(|| "截取输出"), &output, PACKED_WIDTHS[2])?;
+        let packed = pack.pack(layouter.namespace(|| "打包"), &[(n, PACKED_WIDTHS[0]), (flags, PACKED_WIDTHS[1]), (output, PACKED_WIDTHS[2])])?;
+        layouter.constrain_instance(packed.cell(), config.inner.shared.instance, 0)
+    }
+}
+
+#[test]
+fn test_segmented_fib() {
+    use halo2_proofs::dev::MockProver;
+
+    let (a, b) = (Fp::one(), Fp::one());
+    let circuit = SegmentedFibCircuit::new(a, b, 5, 3);
+    for extend in [false, true] {
+        for hash in [false, true] {
+            let public_inputs = circuit.public_inputs(a, b, SegmentFlags { extend, hash });
+            let prover = MockProver::run(10, &circuit, vec![public_inputs.clone()]).unwrap();
+            prover.assert_satisfied();
+            // 输出与开关不符
+            let mut wrong = public_inputs;
+            wrong[0] = Fp::from(!extend as u64);
+            let prover = MockProver::run(10, &circuit, vec![wrong]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+    // F(5) = 5, F(8) = 21
+    assert_eq!(circuit.public_inputs(a, b, SegmentFlags { extend: false, hash: false })[2], Fp::from(5));
+    assert_eq!(circuit.public_inputs(a, b, SegmentFlags { extend: true, hash: false })[2], Fp::from(21));
+
+    // 开关不是0或1
+    let mut public_inputs = circuit.public_inputs(a, b, SegmentFlags { extend: false, hash: false });
+    public_inputs[1] = Fp::from(2);
+    let prover = MockProver::run(10, &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 打包模式只有一个实例值
+    let flags = SegmentFlags { extend: true, hash: true };
+    let packed = PackedSegmentedFibCircuit::new(a, b, 5, 3, flags);
+    let public_inputs = packed.public_inputs(a, b, flags);
+    assert_eq!(public_inputs.len(), 1);
+    let output = truncate(circuit.public_inputs(a, b, flags)[2], PACKED_WIDTHS[2]);
+    assert_eq!(PackedSegmentedFibCircuit::unpack_public(public_inputs[0]), Some((5, flags, output)));
+    let prover = MockProver::run(10, &packed, vec![public_inputs.clone()]).unwrap();
+    prover.assert_satisfied();
+    // 私有开关与打包值不符
+    let other = PackedSegmentedFibCircuit::new(a, b, 5, 3, SegmentFlags { extend: false, hash: true });
+    let prover = MockProver::run(10, &other, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,235 @@
+//! 由递推式生成芯片、门和填写代码的[`sequence_circuit!`]宏
+//!
+//! ```ignore
+//! halo2_fib::sequence_circuit! {
+//!     /// 泰波那契数列
+//!     pub tribonacci { state: [a, b, c], step: a + b + c }
+//! }
+//! let circuit = tribonacci::SequenceCircuit::new(&[Fp::zero(), Fp::zero(), Fp::one()], 20);
+//! ```
+//!
+//! 状态变量依次是最早到最近的项, 递推式可以用`+`、`-`、`*`和括号, 常量写作`T::constant(2)`,
+//! 同一个变量用到多次时除最后一次外要写`a.clone()`. 生成的模块里有:
+//! - `step`、`terms`: 递推式本身和电路外计算前n项
+//! - `Config`、`Chip`: 只用一个advice列, 第i行放第i项, 门用旋转取到后面WIDTH项
+//! - `SequenceCircuit`: 初值私有、公开第n项的电路
+
+use std::ops::{Add, Mul, Sub};
+
+use ff::PrimeField;
+use halo2_proofs::plonk::Expression;
+
+/// 递推式里状态变量的类型: 门里是`Expression`, 计算见证时是[`Num`]
+pub trait StepOps: Clone + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    fn constant(value: u64) -> Self;
+}
+
+impl<F: PrimeField> StepOps for Expression<F> {
+    fn constant(value: u64) -> Self {
+        Expression::Constant(F::from(value))
+    }
+}
+
+/// 计算见证时包装域元素
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Num<F>(pub F);
+
+impl<F: PrimeField> Add for Num<F> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Num(self.0 + rhs.0)
+    }
+}
+
+impl<F: PrimeField> Sub for Num<F> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Num(self.0 - rhs.0)
+    }
+}
+
+impl<F: PrimeField> Mul for Num<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Num(self.0 * rhs.0)
+    }
+}
+
+impl<F: PrimeField> StepOps for Num<F> {
+    fn constant(value: u64) -> Self {
+        Num(F::from(value))
+    }
+}
+
+/// 宏展开时用到的依赖, 使用者不必自己引入
+#[doc(hidden)]
+pub mod __private {
+    pub use ff::PrimeField;
+    pub use halo2_proofs;
+}
+
+/// 由递推式生成一个模块, 见[模块文档](crate::sequence)
+#[macro_export]
+macro_rules! sequence_circuit {
+    ($(#[$meta:meta])* $vis:vis $name:ident { state: [$($var:ident),+ $(,)?], step: $step:expr $(,)? }) => {
+        $(#[$meta])*
+        $vis mod $name {
+            use std::marker::PhantomData;
+
+            use $crate::sequence::__private::halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+            use $crate::sequence::__private::halo2_proofs::plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector};
+            use $crate::sequence::__private::halo2_proofs::poly::Rotation;
+            use $crate::sequence::__private::PrimeField;
+            use $crate::sequence::{Num, StepOps};
+            use $crate::SharedColumns;
+
+            /// 状态变量个数
+            pub const WIDTH: usize = [$(stringify!($var)),+].len();
+
+            /// 递推式
+            #[allow(clippy::redundant_clone)]
+            pub fn step<T: StepOps>($($var: T),+) -> T {
+                $step
+            }
+
+            fn apply<T: StepOps>(window: &[T]) -> T {
+                let mut window = window.iter().cloned();
+                $(let $var = window.next().expect("窗口长度不足");)+
+                step($($var),+)
+            }
+
+            /// 电路外计算前n项, init为前WIDTH项
+            pub fn terms<F: PrimeField>(init: &[F], n: usize) -> Vec<F> {
+                assert_eq!(init.len(), WIDTH, "初值个数应为{}", WIDTH);
+                let mut terms: Vec<Num<F>> = init.iter().map(|v| Num(*v)).collect();
+                while terms.len() < n {
+                    let next = apply(&terms[terms.len() - WIDTH..]);
+                    terms.push(next);
+                }
+                terms.truncate(n);
+                terms.into_iter().map(|v| v.0).collect()
+            }
+
+            #[derive(Clone, Debug, Copy)]
+            pub struct Config {
+                pub selector: Selector,
+                pub x: Column<Advice>,
+                pub instance: Column<Instance>,
+            }
+
+            /// n项占n行, 门在前n - WIDTH行启用
+            pub struct Chip<F: PrimeField> {
+                config: Config,
+                _marker: PhantomData<F>,
+            }
+
+            impl<F: PrimeField> Chip<F> {
+                pub fn construct(config: Config) -> Self {
+                    Self { config, _marker: PhantomData }
+                }
+
+                /// 使用共享的第一个advice列和instance列
+                pub fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> Config {
+                    let selector = meta.selector();
+                    let x = shared.advice[0];
+                    meta.create_gate(stringify!($name), |meta| {
+                        let q = meta.query_selector(selector);
+                        let window: Vec<_> = (0..WIDTH).map(|i| meta.query_advice(x, Rotation(i as i32))).collect();
+                        let next = meta.query_advice(x, Rotation(WIDTH as i32));
+                        vec![(stringify!($step), q * (next - apply(&window)))]
+                    });
+                    Config { selector, x, instance: shared.instance }
+                }
+
+                /// 填写前n项, 初值为私有输入
+                pub fn assign(&self, mut layouter: impl Layouter<F>, init: &[Value<F>], n: usize) -> Result<Vec<AssignedCell<F, F>>, Error> {
+                    assert!(init.len() == WIDTH && n > WIDTH, "初值个数应为{}且项数大于它", WIDTH);
+                    layouter.assign_region(|| "填写数列", |mut region| {
+                        let mut values = init.to_vec();
+                        let mut cells = Vec::with_capacity(n);
+                        for i in 0..n {
+                            if i >= WIDTH {
+                                let window = values[i - WIDTH..i].iter().fold(Value::known(vec![]), |acc, v| acc.zip(*v).map(|(mut acc, v)| {
+                                    acc.push(Num(v));
+                                    acc
+                                }));
+                                values.push(window.map(|window| apply(&window).0));
+                            }
+                            if i + WIDTH < n {
+                                self.config.selector.enable(&mut region, i)?;
+                            }
+                            cells.push(region.assign_advice(|| "项", self.config.x, i, || values[i])?);
+                        }
+                        Ok(cells)
+                    })
+                }
+
+                pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+                    layouter.constrain_instance(cell.cell(), self.config.instance, row)
+                }
+            }
+
+            /// 证明第n项的电路, 初值私有, 实例列为第n项
+            #[derive(Clone, Debug)]
+            pub struct SequenceCircuit<F: PrimeField> {
+                pub init: Vec<Value<F>>,
+                pub n: usize,
+            }
+
+            impl<F: PrimeField> SequenceCircuit<F> {
+                pub fn new(init: &[F], n: usize) -> Self {
+                    Self { init: init.iter().map(|v| Value::known(*v)).collect(), n }
+                }
+            }
+
+            impl<F: PrimeField> Circuit<F> for SequenceCircuit<F> {
+                type Config = Config;
+                type FloorPlanner = SimpleFloorPlanner;
+
+                fn without_witnesses(&self) -> Self {
+                    Self { init: vec![Value::unknown(); WIDTH], n: self.n }
+                }
+
+                fn configure(meta: &mut ConstraintSystem<F>) -> Config {
+                    let shared = SharedColumns::configure(meta);
+                    Chip::configure(meta, &shared)
+                }
+
+                fn synthesize(&self, config: Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+                    let chip = Chip::construct(config);
+                    let cells = chip.assign(layouter.namespace(|| "数列"), &self.init, self.n)?;
+                    chip.expose_public(layouter.namespace(|| "公开第n项"), cells.last().expect("至少有一项"), 0)
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    use crate::recurrence::recurrence_terms;
+
+    sequence_circuit! { fibonacci { state: [a, b], step: a + b } }
+    sequence_circuit! { pell { state: [a, b], step: a + b * T::constant(2) } }
+    sequence_circuit! { tribonacci { state: [a, b, c], step: a + b + c } }
+
+    #[test]
+    fn test_sequence_circuit() {
+        let (zero, one) = (Fp::zero(), Fp::one());
+        assert_eq!(fibonacci::terms(&[one, one], 20), recurrence_terms(1, 1, one, one, 20));
+        assert_eq!(pell::terms(&[zero, one], 20), recurrence_terms(2, 1, zero, one, 20));
+        assert_eq!(tribonacci::terms(&[zero, zero, one], 10), [0u64, 0, 1, 1, 2, 4, 7, 13, 24, 44].map(Fp::from));
+
+        let circuit = tribonacci::SequenceCircuit::new(&[zero, zero, one], 10);
+        MockProver::run(5, &circuit, vec![vec![Fp::from(44)]]).unwrap().assert_satisfied();
+        assert!(MockProver::run(5, &circuit, vec![vec![Fp::from(45)]]).unwrap().verify().is_err());
+        let circuit = pell::SequenceCircuit::new(&[zero, one], 10);
+        MockProver::run(5, &circuit, vec![vec![pell::terms(&[zero, one], 10)[9]]]).unwrap().assert_satisfied();
+    }
+}
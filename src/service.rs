@@ -0,0 +1,429 @@
+//! 给异步服务用的证明接口(需要`server` feature)
+//!
+//! 证明是CPU密集的, 直接在异步任务里调用会占住运行时的工作线程. 这里把证明放到tokio的阻塞线程池,
+//! future被丢弃(例如客户端断开)时通过[`CancelToken`]让证明在下一个阶段放弃.
+//! [`ProveQueue`]限制同时证明、验证和排队的请求数, 队列满时立即拒绝, 由调用方回复"繁忙";
+//! 加上[`RateLimiter`]后每个客户端另有令牌桶限流, 被拒绝时[`QueueError::http_status`]为429.
+//! 给队列配上[`AuditLog`]后, 经队列的每次证明和验证都在阻塞线程池中追加一行审计记录; [`prove_async`]和[`verify_async`]本身不记录.
+//! 队列还记录请求数和耗时, 由[`ProveQueue::render_metrics`]输出为`/metrics`的Prometheus文本
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use halo2_proofs::pasta::{EqAffine, Fp};
+use halo2_proofs::plonk::{Circuit, ProvingKey, VerifyingKey};
+use halo2_proofs::poly::commitment::Params;
+use tokio::sync::Semaphore;
+
+use crate::audit::{instance_hash, AuditError, AuditLog, AuditRecord};
+use crate::cancel::{prove_with_cancel, CancelToken, ProveError};
+use crate::key_cache::KeyCache;
+use crate::metrics::{counters, gauge, resident_memory_bytes, Metrics};
+use crate::prover::{params_k, verify, VerifyOutcome, ZeroizeOnDrop, ZeroizeWitness};
+
+/// 丢弃时取消证明
+struct CancelOnDrop(CancelToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// 在阻塞线程池中生成证明, token取消或返回的future被丢弃时放弃. 证明线程结束时抹掉电路中的见证
+pub async fn prove_async<C: Circuit<Fp> + ZeroizeWitness + Send + 'static>(params: Arc<Params<EqAffine>>, pk: Arc<ProvingKey<EqAffine>>, circuit: C, public_inputs: Vec<Fp>, token: CancelToken) -> Result<Vec<u8>, ProveError> {
+    let _guard = CancelOnDrop(token.clone());
+    let task = tokio::task::spawn_blocking(move || {
+        let circuit = ZeroizeOnDrop(circuit);
+        prove_with_cancel(&params, &pk, &circuit.0, &public_inputs, &token)
+    });
+    match task.await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(ProveError::Cancelled),
+    }
+}
+
+/// 在阻塞线程池中验证证明
+pub async fn verify_async(params: Arc<Params<EqAffine>>, vk: Arc<VerifyingKey<EqAffine>>, public_inputs: Vec<Fp>, proof: Vec<u8>) -> VerifyOutcome {
+    let task = tokio::task::spawn_blocking(move || verify(&params, &vk, &public_inputs, &proof));
+    match task.await {
+        Ok(outcome) => outcome,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => VerifyOutcome::BadProof { reason: format!("验证任务被取消: {}", e) },
+    }
+}
+
+#[derive(Debug)]
+pub enum QueueError {
+    /// 正在证明和排队的请求都已满
+    Full { capacity: usize },
+    /// 客户端超过了限流, retry_after后才有新令牌
+    RateLimited { retry_after: Duration },
+    Prove(ProveError),
+    /// 写审计日志失败, 证明结果不返回
+    Audit(AuditError),
+}
+
+impl QueueError {
+    /// 对应的HTTP状态码: 队列满和限流为429, 取消为503, 证明失败和写审计日志失败为500
+    pub fn http_status(&self) -> u16 {
+        match self {
+            QueueError::Full { .. } | QueueError::RateLimited { .. } => 429,
+            QueueError::Prove(ProveError::Cancelled) => 503,
+            QueueError::Prove(ProveError::Plonk(_)) | QueueError::Audit(_) => 500,
+        }
+    }
+
+    /// 指标里的结果标签
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QueueError::Full { .. } => "full",
+            QueueError::RateLimited { .. } => "rate_limited",
+            QueueError::Prove(ProveError::Cancelled) => "cancelled",
+            QueueError::Prove(ProveError::Plonk(_)) => "error",
+            QueueError::Audit(_) => "audit_error",
+        }
+    }
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::Full { capacity } => write!(f, "证明队列已满({}个请求)", capacity),
+            QueueError::RateLimited { retry_after } => write!(f, "请求过于频繁, {:.1}秒后再试", retry_after.as_secs_f64()),
+            QueueError::Prove(e) => write!(f, "{}", e),
+            QueueError::Audit(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for QueueError {}
+
+impl From<ProveError> for QueueError {
+    fn from(e: ProveError) -> Self {
+        QueueError::Prove(e)
+    }
+}
+
+impl From<AuditError> for QueueError {
+    fn from(e: AuditError) -> Self {
+        QueueError::Audit(e)
+    }
+}
+
+/// 审计记录里的电路名: 类型名去掉模块路径和泛型参数
+fn circuit_name<C>() -> String {
+    let name = std::any::type_name::<C>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name).to_string()
+}
+
+/// 桶数超过这个值时清掉已经补满的桶
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 按客户端的令牌桶限流: 每个客户端至多积攒burst个令牌, 每秒补充rate个, 每个请求消耗一个
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64, burst: u32) -> Self {
+        assert!(rate > 0.0 && burst > 0, "限流速率和容量必须为正");
+        Self { rate, burst: burst as f64, buckets: Mutex::default() }
+    }
+
+    /// 取一个令牌, 不够时返回还要等多久
+    pub fn try_acquire(&self, client: &str) -> Result<(), Duration> {
+        self.try_acquire_at(client, Instant::now())
+    }
+
+    fn try_acquire_at(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().expect("限流锁被污染");
+        if buckets.len() >= MAX_IDLE_BUCKETS && !buckets.contains_key(client) {
+            let (rate, burst) = (self.rate, self.burst);
+            buckets.retain(|_, bucket| bucket.tokens + now.saturating_duration_since(bucket.updated).as_secs_f64() * rate < burst);
+        }
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket { tokens: self.burst, updated: now });
+        bucket.tokens = (bucket.tokens + now.saturating_duration_since(bucket.updated).as_secs_f64() * self.rate).min(self.burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+}
+
+/// 有界的证明队列: 至多workers个证明或验证同时进行, 另有至多backlog个排队, 再多的请求立即拒绝
+#[derive(Clone)]
+pub struct ProveQueue {
+    running: Arc<Semaphore>,
+    pending: Arc<AtomicUsize>,
+    capacity: usize,
+    limiter: Option<Arc<RateLimiter>>,
+    audit: Option<Arc<AuditLog>>,
+    metrics: Arc<Metrics>,
+}
+
+/// 离开队列时减少计数
+struct Pending(Arc<AtomicUsize>);
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ProveQueue {
+    pub fn new(workers: usize, backlog: usize) -> Self {
+        assert!(workers > 0, "至少要有一个证明线程");
+        Self { running: Arc::new(Semaphore::new(workers)), pending: Arc::default(), capacity: workers + backlog, limiter: None, audit: None, metrics: Arc::default() }
+    }
+
+    /// 按客户端限流, 见[`RateLimiter`]
+    pub fn with_rate_limit(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = Some(Arc::new(limiter));
+        self
+    }
+
+    /// 经队列的证明和验证都追加到审计日志, 被队列拒绝的请求不记录
+    pub fn with_audit(mut self, log: Arc<AuditLog>) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// 有审计日志时在阻塞线程池中追加一行, 文件读写不占用异步运行时的工作线程
+    async fn audit<C>(&self, op: &'static str, k: u32, public_inputs: &[Fp], start: Instant, outcome: &str) -> Result<(), AuditError> {
+        let Some(log) = self.audit.clone() else {
+            return Ok(());
+        };
+        let record = AuditRecord {
+            op,
+            circuit: circuit_name::<C>(),
+            layout: String::new(),
+            k,
+            instance_hash: Some(instance_hash(public_inputs)),
+            millis: start.elapsed().as_secs_f64() * 1000.0,
+            outcome: outcome.to_string(),
+        };
+        match tokio::task::spawn_blocking(move || log.append(&record)).await {
+            Ok(result) => result.map(|_| ()),
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => Err(AuditError::Io(io::Error::new(io::ErrorKind::Interrupted, e.to_string()))),
+        }
+    }
+
+    /// 设置了限流且给出client时先取令牌, 再占一个队列位置, 返回的计数离开队列时归还
+    fn admit(&self, client: Option<&str>) -> Result<Pending, QueueError> {
+        if let (Some(limiter), Some(client)) = (&self.limiter, client) {
+            limiter.try_acquire(client).map_err(|retry_after| QueueError::RateLimited { retry_after })?;
+        }
+        if self.pending.fetch_add(1, Ordering::SeqCst) >= self.capacity {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            return Err(QueueError::Full { capacity: self.capacity });
+        }
+        Ok(Pending(self.pending.clone()))
+    }
+
+    /// 队列容量: 同时证明和排队的请求数上限
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 正在证明和排队的请求数
+    pub fn len(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 经队列的请求数和耗时, 克隆出的队列共用同一份
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// `/metrics`的响应体: 请求数、按k的耗时直方图、队列深度和容量、进程常驻内存, 给出keys时加上密钥缓存的命中和未命中次数
+    pub fn render_metrics(&self, keys: Option<&KeyCache>) -> String {
+        let mut out = String::new();
+        self.metrics.render(&mut out);
+        gauge(&mut out, "halo2_fib_queue_depth", "正在证明和排队的请求数", self.len());
+        gauge(&mut out, "halo2_fib_queue_capacity", "同时证明和排队的请求数上限", self.capacity);
+        if let Some(keys) = keys {
+            let ((hits, misses), (vk_hits, vk_misses)) = (keys.stats(), keys.vk_stats());
+            counters(&mut out, "halo2_fib_key_cache_hits_total", "密钥缓存命中次数", &[("key", "proving", hits), ("key", "verifying", vk_hits)]);
+            counters(&mut out, "halo2_fib_key_cache_misses_total", "密钥缓存未命中次数", &[("key", "proving", misses), ("key", "verifying", vk_misses)]);
+        }
+        if let Some(bytes) = resident_memory_bytes() {
+            gauge(&mut out, "halo2_fib_resident_memory_bytes", "进程常驻内存", bytes);
+        }
+        out
+    }
+
+    /// 排队生成证明, 队列满时返回[`QueueError::Full`]
+    pub async fn prove<C: Circuit<Fp> + ZeroizeWitness + Send + 'static>(&self, params: Arc<Params<EqAffine>>, pk: Arc<ProvingKey<EqAffine>>, circuit: C, public_inputs: Vec<Fp>, token: CancelToken) -> Result<Vec<u8>, QueueError> {
+        self.prove_as(None, params, pk, circuit, public_inputs, token).await
+    }
+
+    /// 先按client限流再排队, 没有设置限流时同[`prove`](ProveQueue::prove)
+    pub async fn prove_for<C: Circuit<Fp> + ZeroizeWitness + Send + 'static>(&self, client: &str, params: Arc<Params<EqAffine>>, pk: Arc<ProvingKey<EqAffine>>, circuit: C, public_inputs: Vec<Fp>, token: CancelToken) -> Result<Vec<u8>, QueueError> {
+        self.prove_as(Some(client), params, pk, circuit, public_inputs, token).await
+    }
+
+    async fn prove_as<C: Circuit<Fp> + ZeroizeWitness + Send + 'static>(&self, client: Option<&str>, params: Arc<Params<EqAffine>>, pk: Arc<ProvingKey<EqAffine>>, circuit: C, public_inputs: Vec<Fp>, token: CancelToken) -> Result<Vec<u8>, QueueError> {
+        let result = self.prove_queued(client, params, pk, circuit, public_inputs, token).await;
+        self.metrics.record_request("prove", result.as_ref().map_or_else(QueueError::kind, |_| "ok"));
+        result
+    }
+
+    async fn prove_queued<C: Circuit<Fp> + ZeroizeWitness + Send + 'static>(&self, client: Option<&str>, params: Arc<Params<EqAffine>>, pk: Arc<ProvingKey<EqAffine>>, circuit: C, public_inputs: Vec<Fp>, token: CancelToken) -> Result<Vec<u8>, QueueError> {
+        let _pending = self.admit(client)?;
+        let _permit = self.running.acquire().await.expect("信号量不会被关闭");
+        let (k, audited_inputs) = (params_k(&params), self.audit.as_ref().map(|_| public_inputs.clone()));
+        let start = Instant::now();
+        let result = prove_async(params, pk, circuit, public_inputs, token).await;
+        self.metrics.observe_latency("prove", k, start.elapsed());
+        if let Some(public_inputs) = audited_inputs {
+            let outcome = result.as_ref().err().map_or("ok".to_string(), |e| e.to_string());
+            self.audit::<C>("prove", k, &public_inputs, start, &outcome).await?;
+        }
+        Ok(result?)
+    }
+
+    /// 排队验证, 与证明共用队列容量和并发上限; 有审计日志时追加一行, outcome为[`VerifyOutcome::kind`]. C只用作记录里的电路名
+    pub async fn verify<C>(&self, params: Arc<Params<EqAffine>>, vk: Arc<VerifyingKey<EqAffine>>, public_inputs: Vec<Fp>, proof: Vec<u8>) -> Result<VerifyOutcome, QueueError> {
+        self.verify_as::<C>(None, params, vk, public_inputs, proof).await
+    }
+
+    /// 先按client限流再排队验证, 没有设置限流时同[`verify`](ProveQueue::verify)
+    pub async fn verify_for<C>(&self, client: &str, params: Arc<Params<EqAffine>>, vk: Arc<VerifyingKey<EqAffine>>, public_inputs: Vec<Fp>, proof: Vec<u8>) -> Result<VerifyOutcome, QueueError> {
+        self.verify_as::<C>(Some(client), params, vk, public_inputs, proof).await
+    }
+
+    async fn verify_as<C>(&self, client: Option<&str>, params: Arc<Params<EqAffine>>, vk: Arc<VerifyingKey<EqAffine>>, public_inputs: Vec<Fp>, proof: Vec<u8>) -> Result<VerifyOutcome, QueueError> {
+        let result = self.verify_queued::<C>(client, params, vk, public_inputs, proof).await;
+        self.metrics.record_request("verify", result.as_ref().map_or_else(QueueError::kind, VerifyOutcome::kind));
+        result
+    }
+
+    async fn verify_queued<C>(&self, client: Option<&str>, params: Arc<Params<EqAffine>>, vk: Arc<VerifyingKey<EqAffine>>, public_inputs: Vec<Fp>, proof: Vec<u8>) -> Result<VerifyOutcome, QueueError> {
+        let _pending = self.admit(client)?;
+        let _permit = self.running.acquire().await.expect("信号量不会被关闭");
+        let (k, audited_inputs) = (params_k(&params), self.audit.as_ref().map(|_| public_inputs.clone()));
+        let start = Instant::now();
+        let outcome = verify_async(params, vk, public_inputs, proof).await;
+        self.metrics.observe_latency("verify", k, start.elapsed());
+        if let Some(public_inputs) = audited_inputs {
+            self.audit::<C>("verify", k, &public_inputs, start, outcome.kind()).await?;
+        }
+        Ok(outcome)
+    }
+}
+
+#[test]
+fn test_prove_queue() {
+    use crate::prover::{keygen, setup};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(10, &witness).unwrap();
+    let circuit = || FibCircuit::new(&statement, &witness);
+    let params = Arc::new(setup(4));
+    let pk = Arc::new(keygen(&params, &circuit()).unwrap());
+    let vk = Arc::new(pk.get_vk().clone());
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    runtime.block_on(async {
+        let queue = ProveQueue::new(1, 1);
+        let prove = || queue.prove(params.clone(), pk.clone(), circuit(), statement.public_inputs(), CancelToken::new());
+        // 第三个请求在前两个完成前到达, 被拒绝
+        let (first, second, third) = tokio::join!(prove(), prove(), prove());
+        assert!(matches!(third, Err(QueueError::Full { capacity: 2 })));
+        let proof = first.unwrap();
+        for proof in [proof.clone(), second.unwrap()] {
+            assert!(verify_async(params.clone(), vk.clone(), statement.public_inputs(), proof).await.is_valid());
+        }
+        assert!(queue.is_empty());
+
+        // 验证与证明共用队列容量和限流
+        let queue = ProveQueue::new(1, 0).with_rate_limit(RateLimiter::new(0.001, 1));
+        let verify = |client: &'static str| queue.verify_for::<FibCircuit<Fp>>(client, params.clone(), vk.clone(), statement.public_inputs(), proof.clone());
+        let (proved, verified) = tokio::join!(queue.prove(params.clone(), pk.clone(), circuit(), statement.public_inputs(), CancelToken::new()), verify("a"));
+        assert!(proved.is_ok());
+        assert!(matches!(verified, Err(QueueError::Full { capacity: 1 })));
+        assert!(verify("b").await.unwrap().is_valid());
+        assert!(matches!(verify("b").await, Err(QueueError::RateLimited { .. })));
+        assert_eq!(queue.metrics().requests("verify", "full"), 1);
+        assert_eq!(queue.metrics().requests("verify", "valid"), 1);
+        assert_eq!(queue.metrics().requests("verify", "rate_limited"), 1);
+        let text = queue.render_metrics(None);
+        assert!(text.contains("halo2_fib_requests_total{op=\"prove\",result=\"ok\"} 1\n"));
+        assert!(text.contains("halo2_fib_latency_seconds_count{op=\"verify\",k=\"4\"} 1\n"));
+        assert!(text.contains("halo2_fib_queue_depth 0\n") && text.contains("halo2_fib_queue_capacity 1\n"));
+
+        let token = CancelToken::new();
+        token.cancel();
+        let result = prove_async(params.clone(), pk.clone(), circuit(), statement.public_inputs(), token).await;
+        assert!(matches!(result, Err(ProveError::Cancelled)));
+    });
+}
+
+#[test]
+fn test_rate_limiter() {
+    let limiter = RateLimiter::new(2.0, 3);
+    let start = Instant::now();
+    for _ in 0..3 {
+        assert!(limiter.try_acquire_at("a", start).is_ok());
+    }
+    // 令牌用完, 每秒补2个, 半秒后才有下一个
+    assert_eq!(limiter.try_acquire_at("a", start), Err(Duration::from_millis(500)));
+    assert!(limiter.try_acquire_at("b", start).is_ok());
+    assert!(limiter.try_acquire_at("a", start + Duration::from_millis(500)).is_ok());
+    assert!(limiter.try_acquire_at("a", start + Duration::from_millis(500)).is_err());
+    // 闲置再久也只积攒burst个
+    let later = start + Duration::from_secs(60);
+    assert_eq!((0..5).filter(|_| limiter.try_acquire_at("a", later).is_ok()).count(), 3);
+    assert_eq!(QueueError::RateLimited { retry_after: Duration::ZERO }.http_status(), 429);
+}
+
+#[test]
+fn test_queue_audit() {
+    use crate::audit::verify_file;
+    use crate::prover::{keygen, setup};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let path = std::env::temp_dir().join(format!("halo2-fib-queue-audit-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(10, &witness).unwrap();
+    let params = Arc::new(setup(4));
+    let pk = Arc::new(keygen(&params, &FibCircuit::new(&statement, &witness)).unwrap());
+    let vk = Arc::new(pk.get_vk().clone());
+    let queue = ProveQueue::new(1, 0).with_audit(Arc::new(AuditLog::open(&path).unwrap()));
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    runtime.block_on(async {
+        let proof = queue.prove(params.clone(), pk.clone(), FibCircuit::new(&statement, &witness), statement.public_inputs(), CancelToken::new()).await.unwrap();
+        let outcome = queue.verify::<FibCircuit<Fp>>(params.clone(), vk.clone(), statement.public_inputs(), proof).await.unwrap();
+        assert!(outcome.is_valid());
+    });
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(verify_file(&path).unwrap().0, 2);
+    assert!(text.contains(r#""op": "prove", "circuit": "FibCircuit""#));
+    assert!(text.contains(r#""op": "verify""#) && text.contains(r#""outcome": "valid""#));
+    let _ = std::fs::remove_file(&path);
+}
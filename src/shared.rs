@@ -0,0 +1,27 @@
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::plonk::*;
+
+/// 多个芯片共用的列, 组合电路时各芯片复用同一组advice列, 减少列数和证明大小
+///
+/// advice列和instance列都已开启相等约束, fixed列已开启常量约束
+#[derive(Clone, Debug, Copy)]
+pub struct SharedColumns {
+    pub advice: [Column<Advice>; 3],
+    pub instance: Column<Instance>,
+    pub constant: Column<Fixed>,
+}
+
+impl SharedColumns {
+    pub fn configure<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+        meta.enable_constant(constant);
+        SharedColumns { advice, instance, constant }
+    }
+}
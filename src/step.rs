@@ -0,0 +1,135 @@
+//! IVC风格的单步电路接口: 状态z_i进, z_{i+1}出, 加上这一步的约束
+//!
+//! 折叠/IVC框架(Nova风格的适配器或halo2累加)只需要单步的约束逻辑, 实现[`StepCircuit`]后不用重写门,
+//! 这里的[`StepChainCircuit`]在单个电路里把若干步串起来, 也用来测试单步实现
+
+use std::marker::PhantomData;
+
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::plonk::*;
+
+use crate::fib::{FibChip, FibConfig};
+use crate::shared::SharedColumns;
+
+/// 单步电路: 状态是ARITY个域元素
+pub trait StepCircuit<F: Field>: Clone {
+    /// 状态的长度
+    const ARITY: usize;
+
+    type Config: Clone;
+
+    fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> Self::Config;
+
+    /// 电路外计算下一步的状态
+    fn output(&self, z: &[F]) -> Vec<F>;
+
+    /// 约束一步, 输入状态的单元格来自上一步, 返回下一步状态的单元格
+    fn synthesize_step(&self, config: &Self::Config, layouter: impl Layouter<F>, z: &[AssignedCell<F, F>]) -> Result<Vec<AssignedCell<F, F>>, Error>;
+}
+
+/// 斐波那契的一步: (a, b) -> (b, a + b)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FibStep;
+
+impl<F: Field> StepCircuit<F> for FibStep {
+    const ARITY: usize = 2;
+
+    type Config = FibConfig;
+
+    fn configure(meta: &mut ConstraintSystem<F>, shared: &SharedColumns) -> Self::Config {
+        FibChip::configure(meta, shared)
+    }
+
+    fn output(&self, z: &[F]) -> Vec<F> {
+        vec![z[1], z[0] + z[1]]
+    }
+
+    fn synthesize_step(&self, config: &Self::Config, layouter: impl Layouter<F>, z: &[AssignedCell<F, F>]) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let (b, c) = FibChip::construct(*config).assign_next_row(layouter, &z[0], &z[1])?;
+        Ok(vec![b, c])
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StepChainConfig<C: Clone> {
+    pub shared: SharedColumns,
+    pub step: C,
+}
+
+/// 把单步电路连续执行num_steps步, 初始状态z_0和最终状态z_n都公开
+///
+/// 实例列前ARITY行为z_0, 后ARITY行为z_n
+pub struct StepChainCircuit<F: Field, S: StepCircuit<F>> {
+    step: S,
+    z0: Vec<Value<F>>,
+    num_steps: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, S: StepCircuit<F>> StepChainCircuit<F, S> {
+    pub fn new(step: S, z0: &[F], num_steps: usize) -> Self {
+        assert_eq!(z0.len(), S::ARITY, "初始状态长度不是ARITY");
+        Self { step, z0: z0.iter().map(|v| Value::known(*v)).collect(), num_steps, _marker: PhantomData }
+    }
+
+    /// 电路外执行num_steps步, 得到按实例列布局排好的公开输入
+    pub fn public_inputs(step: &S, z0: &[F], num_steps: usize) -> Vec<F> {
+        let mut z = z0.to_vec();
+        for _i in 0..num_steps {
+            z = step.output(&z);
+        }
+        z0.iter().copied().chain(z).collect()
+    }
+}
+
+impl<F: Field, S: StepCircuit<F>> Circuit<F> for StepChainCircuit<F, S> {
+    type Config = StepChainConfig<S::Config>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { step: self.step.clone(), z0: vec![Value::unknown(); S::ARITY], num_steps: self.num_steps, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared = SharedColumns::configure(meta);
+        let step = S::configure(meta, &shared);
+        StepChainConfig { shared, step }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let z0 = layouter.assign_region(|| "初始状态", |mut region| {
+            self.z0.iter().enumerate()
+                .map(|(i, v)| region.assign_advice(|| "z_0", config.shared.advice[0], i, || *v))
+                .collect::<Result<Vec<_>, Error>>()
+        })?;
+        let mut z = z0.clone();
+        for _i in 0..self.num_steps {
+            z = self.step.synthesize_step(&config.step, layouter.namespace(|| "单步"), &z)?;
+        }
+        for (row, cell) in z0.iter().chain(z.iter()).enumerate() {
+            layouter.constrain_instance(cell.cell(), config.shared.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_fib_step_chain() {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pasta::Fp;
+
+    let z0 = [Fp::one(), Fp::one()];
+    let public_inputs = StepChainCircuit::<Fp, FibStep>::public_inputs(&FibStep, &z0, 10);
+    // 走10步到(F(11), F(12))
+    assert_eq!(public_inputs[2..], [Fp::from(89), Fp::from(144)]);
+
+    let circuit = StepChainCircuit::new(FibStep, &z0, 10);
+    let prover = MockProver::run(5, &circuit, vec![public_inputs.clone()]).unwrap();
+    prover.assert_satisfied();
+
+    let mut wrong = public_inputs;
+    wrong[3] += Fp::one();
+    let prover = MockProver::run(5, &circuit, vec![wrong]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,278 @@
+//! 为新电路生成测试的[`circuit_test!`]宏及其用到的检查函数, 以及MockProver与真实证明的差分测试
+
+use std::fmt;
+
+use halo2_proofs::dev::{MockProver, VerifyFailure};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::Circuit;
+use rand_core::{OsRng, RngCore};
+
+use crate::prover::{keygen, prove, setup, verify};
+
+/// 预期的检查结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Satisfied,
+    /// 任意失败
+    Unsatisfied,
+    /// 标识为该字符串的门有约束不满足, 标识见[`crate::names`]
+    Gate(&'static str),
+    /// 有查找失败
+    Lookup,
+    /// 有拷贝约束失败
+    Permutation,
+}
+
+impl Expected {
+    /// 失败列表是否符合预期
+    pub fn matches(&self, failures: &[VerifyFailure]) -> bool {
+        match self {
+            Expected::Satisfied => failures.is_empty(),
+            Expected::Unsatisfied => !failures.is_empty(),
+            // 约束的Display以"in gate i ('门名')"结尾
+            Expected::Gate(gate) => failures.iter().any(|f| match f {
+                VerifyFailure::ConstraintNotSatisfied { constraint, .. } => constraint.to_string().ends_with(&format!("('{}')", gate)),
+                _ => false,
+            }),
+            Expected::Lookup => failures.iter().any(|f| matches!(f, VerifyFailure::Lookup { .. })),
+            Expected::Permutation => failures.iter().any(|f| matches!(f, VerifyFailure::Permutation { .. })),
+        }
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Satisfied => write!(f, "约束全部满足"),
+            Expected::Unsatisfied => write!(f, "约束不满足"),
+            Expected::Gate(gate) => write!(f, "门{}不满足", gate),
+            Expected::Lookup => write!(f, "查找失败"),
+            Expected::Permutation => write!(f, "拷贝约束失败"),
+        }
+    }
+}
+
+/// MockProver的结果不符合预期, 带上实际的每一条失败
+#[derive(Debug)]
+pub struct MockMismatch {
+    pub expected: Expected,
+    pub failures: Vec<VerifyFailure>,
+}
+
+impl fmt::Display for MockMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "预期{}, 实际有{}条失败", self.expected, self.failures.len())?;
+        for failure in &self.failures {
+            write!(f, "\n  {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MockMismatch {}
+
+/// 用MockProver检查, 结果不符合预期时返回实际的失败列表
+pub fn check_mock<C: Circuit<Fp>>(k: u32, circuit: &C, public_inputs: &[Fp], expected: Expected) -> Result<(), MockMismatch> {
+    let prover = MockProver::run(k, circuit, vec![public_inputs.to_vec()]).expect("运行MockProver失败");
+    let failures = prover.verify().err().unwrap_or_default();
+    if expected.matches(&failures) {
+        Ok(())
+    } else {
+        Err(MockMismatch { expected, failures })
+    }
+}
+
+/// 真实地生成证明再验证, 预期满足时要求验证通过, 否则要求证明或验证失败
+pub fn check_real<C: Circuit<Fp>>(k: u32, circuit: &C, public_inputs: &[Fp], expected: Expected) {
+    let params = setup(k);
+    let pk = keygen(&params, circuit).expect("生成密钥失败");
+    // 查找失败时生成证明就会出错
+    let outcome = prove(&params, &pk, circuit, public_inputs).map(|proof| verify(&params, pk.get_vk(), public_inputs, &proof));
+    match expected {
+        Expected::Satisfied => outcome.expect("生成证明失败").into_result().expect("验证失败"),
+        _ => assert!(!outcome.map_or(false, |outcome| outcome.is_valid()), "预期{}, 但证明通过了验证", expected),
+    }
+}
+
+/// 同一组输入在MockProver和真实证明下的结论
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Verdicts {
+    pub mock: bool,
+    pub real: bool,
+}
+
+impl Verdicts {
+    pub fn agree(&self) -> bool {
+        self.mock == self.real
+    }
+}
+
+/// 差分测试: 用generate随机生成cases组(电路, 公开输入), 要求MockProver接受的都能生成通过验证的证明,
+/// MockProver拒绝的都无法证明或验证, 不一致时panic并打印该组输入. 返回MockProver接受的组数
+///
+/// 各组的电路形状必须相同, 参数和密钥只用第一组生成一次
+pub fn check_differential<C, G>(k: u32, cases: usize, mut generate: G) -> usize
+where
+    C: Circuit<Fp>,
+    G: FnMut(&mut dyn RngCore) -> (C, Vec<Fp>),
+{
+    let mut rng = OsRng;
+    let params = setup(k);
+    let mut pk = None;
+    let mut accepted = 0;
+    for case in 0..cases {
+        let (circuit, public_inputs) = generate(&mut rng);
+        let pk = pk.get_or_insert_with(|| keygen(&params, &circuit).expect("生成密钥失败"));
+        let mock = MockProver::run(k, &circuit, vec![public_inputs.clone()]).expect("运行MockProver失败").verify().is_ok();
+        let real = prove(&params, pk, &circuit, &public_inputs).map_or(false, |proof| verify(&params, pk.get_vk(), &public_inputs, &proof).is_valid());
+        let verdicts = Verdicts { mock, real };
+        assert!(verdicts.agree(), "第{}组输入结论不一致: {:?}, 公开输入 {:?}", case, verdicts, public_inputs);
+        accepted += mock as usize;
+    }
+    accepted
+}
+
+/// 为一个电路生成MockProver测试和真实证明测试, 放在名为`$name`的模块中
+///
+/// ```
+/// use halo2_fib::{circuit_test, FibCircuit, FibStatement, FibWitness};
+/// use halo2_proofs::pasta::Fp;
+///
+/// circuit_test!(fib_10,
+///     circuit: FibCircuit::new(&FibStatement::new(10, Fp::from(55)).unwrap(), &FibWitness::new(Fp::one(), Fp::one())),
+///     k: 4,
+///     public: vec![Fp::from(55)],
+///     expect: Satisfied);
+/// # fn main() {}
+/// ```
+///
+/// `expect`可以是[`Expected`]的任一变体, 例如`Gate("fib_add")`、`Permutation`
+#[macro_export]
+macro_rules! circuit_test {
+    ($name:ident, circuit: $circuit:expr, k: $k:expr, public: $public:expr, expect: $($expect:tt)+) => {
+        #[allow(unused_imports)]
+        pub mod $name {
+            use super::*;
+
+            #[test]
+            pub fn mock() {
+                let public_inputs: Vec<_> = $public;
+                if let Err(mismatch) = $crate::testing::check_mock($k, &$circuit, &public_inputs, $crate::testing::Expected::$($expect)+) {
+                    panic!("{}", mismatch);
+                }
+            }
+
+            #[test]
+            pub fn prove_verify() {
+                let public_inputs: Vec<_> = $public;
+                $crate::testing::check_real($k, &$circuit, &public_inputs, $crate::testing::Expected::$($expect)+);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fib_range::FibRangeCircuit;
+    use crate::recurrence::recurrence_terms;
+    use crate::{FibCircuit, FibStatement, FibWitness};
+    use halo2_proofs::pasta::Fp;
+
+    use super::{check_differential, check_mock, Expected};
+
+    fn fib(n: usize) -> FibCircuit<Fp> {
+        let witness = FibWitness::new(Fp::one(), Fp::one());
+        FibCircuit::new(&FibStatement::from_witness(n, &witness).unwrap(), &witness)
+    }
+
+    circuit_test!(fib_satisfied, circuit: fib(10), k: 4, public: vec![Fp::from(55)], expect: Satisfied);
+    circuit_test!(fib_wrong_target, circuit: fib(10), k: 4, public: vec![Fp::from(56)], expect: Permutation);
+    // F(94)超过2^64
+    circuit_test!(fib_range_overflow,
+        circuit: FibRangeCircuit::new(Fp::one(), Fp::one(), 94),
+        k: 9,
+        public: vec![recurrence_terms(1, 1, Fp::one(), Fp::one(), 94)[93]],
+        expect: Gate("range_check_zero"));
+
+    #[test]
+    fn mismatch_carries_failures() {
+        let mismatch = check_mock(4, &fib(10), &[Fp::from(56)], Expected::Satisfied).unwrap_err();
+        assert!(!mismatch.failures.is_empty());
+        assert!(mismatch.to_string().lines().count() > 1);
+        assert!(check_mock(4, &fib(10), &[Fp::from(56)], Expected::Permutation).is_ok());
+    }
+
+    #[test]
+    fn differential() {
+        // 随机初值, 奇数组的目标也随机
+        let mut case = 0;
+        let accepted = check_differential(4, 6, |rng| {
+            let witness = FibWitness::new(Fp::from(rng.next_u64()), Fp::from(rng.next_u64()));
+            let statement = FibStatement::from_witness(10, &witness).unwrap();
+            case += 1;
+            let public = if case % 2 == 0 { statement.public_inputs() } else { vec![Fp::from(rng.next_u64())] };
+            (FibCircuit::new(&statement, &witness), public)
+        });
+        assert_eq!(accepted, 3);
+
+        // 初值的位数随机, 第60项有的小于2^64, 有的溢出
+        check_differential(9, 4, |rng| {
+            let bits = rng.next_u32() % 40 + 1;
+            let (a, b) = (Fp::from(rng.next_u64() >> (64 - bits)), Fp::from(rng.next_u64() >> (64 - bits)));
+            (FibRangeCircuit::new(a, b, 60), vec![recurrence_terms(1, 1, a, b, 60)[59]])
+        });
+    }
+
+    #[test]
+    fn differential_circuits() {
+        use crate::fib_u64::FibU64Circuit;
+        use crate::preimage::{preimage_hash, PreimageCircuit, PREIMAGE_K};
+        use crate::segment::{SegmentFlags, SegmentedFibCircuit};
+        use crate::PellCircuit;
+
+        // 佩尔数列: 随机初值, 奇数组的目标也随机
+        let mut case = 0;
+        let accepted = check_differential(5, 4, |rng| {
+            let (a, b) = (Fp::from(rng.next_u64()), Fp::from(rng.next_u64()));
+            case += 1;
+            let target = if case % 2 == 0 { recurrence_terms(2, 1, a, b, 12)[11] } else { Fp::from(rng.next_u64()) };
+            (PellCircuit::new(a, b, 12), vec![target])
+        });
+        assert_eq!(accepted, 2);
+
+        // u64回绕: 奇数组公开域上不回绕的结果, 随机初值下两者几乎总是不同
+        let mut case = 0;
+        let accepted = check_differential(11, 4, |rng| {
+            let (a, b) = (rng.next_u64(), rng.next_u64());
+            case += 1;
+            let public = if case % 2 == 0 { FibU64Circuit::public_inputs(a, b, 30) } else { vec![recurrence_terms(1, 1, Fp::from(a), Fp::from(b), 30)[29]] };
+            (FibU64Circuit::new(a, b, 30), public)
+        });
+        assert_eq!(accepted, 2);
+
+        // 分段电路: 开关随机, 奇数组的输出改掉
+        let mut case = 0;
+        let accepted = check_differential(10, 4, |rng| {
+            let (a, b) = (Fp::from(rng.next_u64()), Fp::from(rng.next_u64()));
+            let flags = SegmentFlags { extend: rng.next_u32() % 2 == 0, hash: rng.next_u32() % 2 == 0 };
+            let circuit = SegmentedFibCircuit::new(a, b, 8, 4);
+            let mut public = circuit.public_inputs(a, b, flags);
+            case += 1;
+            if case % 2 == 1 {
+                public[2] += Fp::one();
+            }
+            (circuit, public)
+        });
+        assert_eq!(accepted, 2);
+
+        // 基于Poseidon芯片的原像证明: 奇数组的原像不对
+        let mut case = 0;
+        let accepted = check_differential(PREIMAGE_K, 4, |rng| {
+            let x = Fp::from(rng.next_u64());
+            case += 1;
+            let h = if case % 2 == 0 { preimage_hash(x) } else { preimage_hash(x + Fp::one()) };
+            (PreimageCircuit::new(x), vec![h])
+        });
+        assert_eq!(accepted, 2);
+    }
+}
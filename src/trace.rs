@@ -0,0 +1,284 @@
+//! 合成过程的逐格记录, 用作教学演示: 看到每个区域在哪一行哪一列填了什么值
+//!
+//! [`trace`]用自己的`Assignment`合成一遍电路, 芯片的每次填写都转成一个[`TraceEvent`]交给[`Recorder`].
+//! 内置的[`HtmlTable`]把结果画成一张按区域着色的HTML表格
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ff::PrimeField;
+use halo2_proofs::circuit::Value;
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed, FloorPlanner, Instance, Selector};
+
+use crate::prover::{zeroize_field, ZeroizeWitness};
+
+/// 被填写的列
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TraceColumn {
+    Instance(usize),
+    Advice(usize),
+    Fixed(usize),
+    Selector(usize),
+}
+
+impl From<Column<Any>> for TraceColumn {
+    fn from(column: Column<Any>) -> Self {
+        match column.column_type() {
+            Any::Advice => TraceColumn::Advice(column.index()),
+            Any::Fixed => TraceColumn::Fixed(column.index()),
+            Any::Instance => TraceColumn::Instance(column.index()),
+        }
+    }
+}
+
+impl fmt::Display for TraceColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceColumn::Instance(i) => write!(f, "instance[{}]", i),
+            TraceColumn::Advice(i) => write!(f, "advice[{}]", i),
+            TraceColumn::Fixed(i) => write!(f, "fixed[{}]", i),
+            TraceColumn::Selector(i) => write!(f, "selector[{}]", i),
+        }
+    }
+}
+
+/// 一次填写: 区域, 绝对行号, 列, 值(未知时为None)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// 区域按进入的顺序编号, 区域外的填写(查找表、常量、实例)为None
+    pub region_index: Option<usize>,
+    /// 区域外为空字符串
+    pub region: String,
+    pub row: usize,
+    pub column: TraceColumn,
+    pub value: Option<Fp>,
+}
+
+/// 接收填写事件
+pub trait Recorder {
+    fn record(&mut self, event: TraceEvent);
+}
+
+impl Recorder for Vec<TraceEvent> {
+    fn record(&mut self, event: TraceEvent) {
+        self.push(event);
+    }
+}
+
+/// 把合成过程转成事件的`Assignment`
+struct Tracer<'r, R: Recorder> {
+    k: u32,
+    instances: Vec<Vec<Fp>>,
+    regions: usize,
+    region: Option<String>,
+    recorder: &'r mut R,
+}
+
+impl<R: Recorder> Tracer<'_, R> {
+    fn emit(&mut self, column: TraceColumn, row: usize, value: Option<Fp>) -> Result<(), Error> {
+        if row >= 1 << self.k {
+            return Err(Error::NotEnoughRowsAvailable { current_k: self.k });
+        }
+        let region_index = self.region.is_some().then(|| self.regions - 1);
+        let region = self.region.clone().unwrap_or_default();
+        self.recorder.record(TraceEvent { region_index, region, row, column, value });
+        Ok(())
+    }
+}
+
+/// 取出已知的值
+fn known<VR: Into<Assigned<Fp>>>(value: Value<VR>) -> Option<Fp> {
+    let mut known = None;
+    value.map(|v| known = Some(v.into().evaluate()));
+    known
+}
+
+impl<R: Recorder> Assignment<Fp> for Tracer<'_, R> {
+    fn enter_region<NR, N>(&mut self, name: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.region = Some(name().into());
+        self.regions += 1;
+    }
+
+    fn exit_region(&mut self) {
+        self.region = None;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.emit(TraceColumn::Selector(selector.index()), row, Some(Fp::one()))
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<Fp>, Error> {
+        Ok(self.instances.get(column.index()).and_then(|values| values.get(row)).map_or(Value::unknown(), |v| Value::known(*v)))
+    }
+
+    fn assign_advice<V, VR, A, AR>(&mut self, _: A, column: Column<Advice>, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.emit(TraceColumn::Advice(column.index()), row, known(to()))
+    }
+
+    fn assign_fixed<V, VR, A, AR>(&mut self, _: A, column: Column<Fixed>, row: usize, to: V) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<Fp>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.emit(TraceColumn::Fixed(column.index()), row, known(to()))
+    }
+
+    fn copy(&mut self, _: Column<Any>, _: usize, _: Column<Any>, _: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn fill_from_row(&mut self, _: Column<Fixed>, _: usize, _: Value<Assigned<Fp>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+/// 合成一遍电路, 依次把实例值和每次填写交给recorder
+pub fn trace<C: Circuit<Fp>, R: Recorder>(k: u32, circuit: &C, instances: Vec<Vec<Fp>>, recorder: &mut R) -> Result<(), Error> {
+    let mut cs = ConstraintSystem::<Fp>::default();
+    let config = C::configure(&mut cs);
+    for (i, values) in instances.iter().enumerate() {
+        for (row, value) in values.iter().enumerate() {
+            recorder.record(TraceEvent { region_index: None, region: String::new(), row, column: TraceColumn::Instance(i), value: Some(*value) });
+        }
+    }
+    let mut tracer = Tracer { k, instances, regions: 0, region: None, recorder };
+    C::FloorPlanner::synthesize(&mut tracer, circuit, config, cs.constants().clone())
+}
+
+/// 小的值按有符号十进制显示, 其余显示十六进制的首尾
+pub fn format_value(value: &Fp) -> String {
+    let small = |v: &Fp| {
+        let repr = v.to_repr();
+        let (low, high) = repr.as_ref().split_at(8);
+        high.iter().all(|&b| b == 0).then(|| u64::from_le_bytes(low.try_into().expect("repr不足8字节")))
+    };
+    if let Some(v) = small(value) {
+        v.to_string()
+    } else if let Some(v) = small(&-*value) {
+        format!("-{}", v)
+    } else {
+        let hex: String = value.to_repr().as_ref().iter().rev().map(|b| format!("{:02x}", b)).collect();
+        format!("0x{}…{}", &hex[..4], &hex[hex.len() - 4..])
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// 把填写事件画成HTML表格的[`Recorder`], 同一区域的单元格颜色相同, 悬停显示区域名
+#[derive(Clone, Debug, Default)]
+pub struct HtmlTable {
+    cells: BTreeMap<(usize, TraceColumn), (Option<usize>, String, Option<Fp>)>,
+}
+
+impl HtmlTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 完整的HTML页面, 只画到最后一个被填写的行
+    pub fn render(&self) -> String {
+        let columns: Vec<TraceColumn> = {
+            let mut columns: Vec<_> = self.cells.keys().map(|(_, column)| *column).collect();
+            columns.sort();
+            columns.dedup();
+            columns
+        };
+        let rows = self.cells.keys().map(|(row, _)| row + 1).max().unwrap_or(0);
+
+        let mut html = String::from("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>电路填写情况</title>\n<style>\n");
+        html += "table { border-collapse: collapse; font-family: monospace; font-size: 12px; }\n";
+        html += "td, th { border: 1px solid #ccc; padding: 2px 6px; text-align: right; }\n";
+        html += "</style></head><body>\n<table>\n<tr><th>行</th>";
+        for column in &columns {
+            html += &format!("<th>{}</th>", column);
+        }
+        html += "</tr>\n";
+        for row in 0..rows {
+            html += &format!("<tr><th>{}</th>", row);
+            for column in &columns {
+                match self.cells.get(&(row, *column)) {
+                    Some((index, region, value)) => {
+                        let background = index.map_or("#eee".to_string(), |i| format!("hsl({}, 70%, 85%)", i * 47 % 360));
+                        let text = value.as_ref().map_or("?".to_string(), format_value);
+                        html += &format!("<td title=\"{}\" style=\"background: {}\">{}</td>", escape(region), background, text);
+                    }
+                    None => html += "<td></td>",
+                }
+            }
+            html += "</tr>\n";
+        }
+        html += "</table>\n</body></html>\n";
+        html
+    }
+}
+
+impl Recorder for HtmlTable {
+    fn record(&mut self, event: TraceEvent) {
+        self.cells.insert((event.row, event.column), (event.region_index, event.region, event.value));
+    }
+}
+
+/// 记录下的填写值包含见证, 画完表格后可以抹掉
+impl ZeroizeWitness for HtmlTable {
+    fn zeroize_witness(&mut self) {
+        for (_, _, value) in self.cells.values_mut() {
+            if let Some(value) = value {
+                zeroize_field(value);
+            }
+        }
+        self.cells.clear();
+    }
+}
+
+#[test]
+fn test_trace() {
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    // 数列1, 1, 2, 3, 5占三行
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let statement = FibStatement::from_witness(5, &witness).unwrap();
+    let circuit = FibCircuit::new(&statement, &witness);
+    let mut events = vec![];
+    trace(4, &circuit, vec![statement.public_inputs()], &mut events).unwrap();
+    assert!(events.contains(&TraceEvent { region_index: None, region: String::new(), row: 0, column: TraceColumn::Instance(0), value: Some(Fp::from(5)) }));
+    let c: Vec<_> = events.iter().filter(|e| e.column == TraceColumn::Advice(2)).map(|e| e.value.unwrap()).collect();
+    assert_eq!(c, [2u64, 3, 5].map(Fp::from));
+    assert_eq!(events.iter().filter(|e| matches!(e.column, TraceColumn::Selector(_))).count(), 3);
+
+    let mut table = HtmlTable::new();
+    trace(4, &circuit, vec![statement.public_inputs()], &mut table).unwrap();
+    let html = table.render();
+    assert!(html.contains("<th>advice[2]</th>"));
+    assert!(html.contains("填写第一行"));
+
+    assert_eq!(format_value(&Fp::from(42)), "42");
+    assert_eq!(format_value(&-Fp::from(3)), "-3");
+}
@@ -0,0 +1,307 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use halo2_proofs::pasta::EqAffine;
+use halo2_proofs::plonk::VerifyingKey;
+use halo2_proofs::poly::commitment::Params;
+
+use crate::instance::{json_string, InstanceSlot};
+use crate::params_file::{check_params, params_len};
+use crate::prover::MAX_K;
+
+// 按注册表重建验证密钥只在prover feature下提供
+#[cfg(feature = "prover")]
+use std::io::BufReader;
+#[cfg(feature = "prover")]
+use halo2_proofs::pasta::Fp;
+#[cfg(feature = "prover")]
+use crate::prover::{verify, VerifyOutcome};
+#[cfg(feature = "prover")]
+use crate::registry::{CircuitRegistry, RegistryError};
+
+/// 文件头魔数, 最后一个字节为格式版本
+pub const VK_MAGIC: [u8; 8] = *b"FIBVK\0\0\x02";
+
+/// 目前只支持pasta的vesta曲线
+pub const CURVE_NAME: &str = "vesta";
+
+/// 验证密钥文件: 文件头(曲线、电路标识、布局变体、k、电路形状哈希)加上公共参数
+///
+/// 文件里没有序列化的验证密钥: zcash版halo2_proofs的`VerifyingKey`没有公开的反序列化接口和构造函数, 而且其中的
+/// 约束系统只能由电路的`configure`得到. 所以文件保存的是参数和电路在[`CircuitRegistry`]中的注册名、布局变体,
+/// 读取后由注册表按布局变体重新生成验证密钥, 再用形状哈希确认与导出时是同一个电路.
+///
+/// 也就是说验证方仍然需要本crate中该电路的代码, 不能脱离电路代码单独验证; 要做到这一点需要换用支持
+/// 验证密钥序列化的halo2版本
+pub struct VkFile {
+    pub curve: String,
+    /// 注册名, 如"fib"
+    pub circuit: String,
+    /// 布局变体, 如"n=10"
+    pub layout: String,
+    pub k: u32,
+    pub shape_hash: [u8; 32],
+    pub params: Params<EqAffine>,
+}
+
+#[derive(Debug)]
+pub enum VkFileError {
+    Io(io::Error),
+    /// 文件头不合法
+    BadHeader(String),
+    /// 重建的验证密钥和文件中的形状哈希不一致
+    ShapeMismatch,
+    /// 注册表里没有文件中的电路, 或布局变体不合法
+    #[cfg(feature = "prover")]
+    Registry(RegistryError),
+}
+
+impl fmt::Display for VkFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VkFileError::Io(e) => write!(f, "读写验证密钥文件失败: {}", e),
+            VkFileError::BadHeader(reason) => write!(f, "验证密钥文件头不合法: {}", reason),
+            VkFileError::ShapeMismatch => write!(f, "电路形状与验证密钥文件不一致"),
+            #[cfg(feature = "prover")]
+            VkFileError::Registry(e) => write!(f, "重建验证密钥失败: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for VkFileError {}
+
+impl From<io::Error> for VkFileError {
+    fn from(e: io::Error) -> Self {
+        VkFileError::Io(e)
+    }
+}
+
+#[cfg(feature = "prover")]
+impl From<RegistryError> for VkFileError {
+    fn from(e: RegistryError) -> Self {
+        VkFileError::Registry(e)
+    }
+}
+
+/// 写入一个字节长度加UTF-8的字符串
+fn write_str<W: Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    let len = u8::try_from(s.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("{}超过255字节", s)))?;
+    writer.write_all(&[len])?;
+    writer.write_all(s.as_bytes())
+}
+
+fn read_str<R: Read>(reader: &mut R, field: &str) -> Result<String, VkFileError> {
+    let mut len = [0u8; 1];
+    reader.read_exact(&mut len)?;
+    let mut bytes = vec![0u8; len[0] as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| VkFileError::BadHeader(format!("{}不是utf8", field)))
+}
+
+/// 验证密钥的形状哈希: 对`vk.pinned()`的调试输出取blake2b-256, 与halo2写入transcript的内容一致
+pub fn shape_hash(vk: &VerifyingKey<EqAffine>) -> [u8; 32] {
+    let pinned = format!("{:?}", vk.pinned());
+    let hash = blake2b_simd::Params::new().hash_length(32).hash(pinned.as_bytes());
+    let mut out = [0u8; 32];
+    out.copy_from_slice(hash.as_bytes());
+    out
+}
+
+impl VkFile {
+    pub fn new(circuit: &str, layout: &str, k: u32, params: Params<EqAffine>, vk: &VerifyingKey<EqAffine>) -> Self {
+        Self { curve: CURVE_NAME.to_string(), circuit: circuit.to_string(), layout: layout.to_string(), k, shape_hash: shape_hash(vk), params }
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&VK_MAGIC)?;
+        write_str(writer, &self.curve)?;
+        write_str(writer, &self.circuit)?;
+        write_str(writer, &self.layout)?;
+        writer.write_all(&self.k.to_le_bytes())?;
+        writer.write_all(&self.shape_hash)?;
+        self.params.write(writer)
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, VkFileError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != VK_MAGIC {
+            return Err(VkFileError::BadHeader("魔数或版本不匹配".to_string()));
+        }
+        let curve = read_str(reader, "曲线名")?;
+        if curve != CURVE_NAME {
+            return Err(VkFileError::BadHeader(format!("不支持的曲线{}", curve)));
+        }
+        let circuit = read_str(reader, "电路标识")?;
+        let layout = read_str(reader, "布局变体")?;
+        let mut k = [0u8; 4];
+        reader.read_exact(&mut k)?;
+        let k = u32::from_le_bytes(k);
+        // 参数有2^k个点, 先检查k再读, 篡改的k不会导致超大的分配
+        if k == 0 || k > MAX_K {
+            return Err(VkFileError::BadHeader(format!("k = {}不在1到{}之间", k, MAX_K)));
+        }
+        let mut shape_hash = [0u8; 32];
+        reader.read_exact(&mut shape_hash)?;
+        // 参数自带的k也要先与文件头比对, 再按文件头的k读出定长的参数, 交给check_params检查长度和各点
+        let mut inner_k = [0u8; 4];
+        reader.read_exact(&mut inner_k)?;
+        let inner_k = u32::from_le_bytes(inner_k);
+        if inner_k != k {
+            return Err(VkFileError::BadHeader(format!("文件头的k = {}与参数的k = {}不一致", k, inner_k)));
+        }
+        let mut bytes = vec![0u8; params_len(k) as usize];
+        bytes[..4].copy_from_slice(&k.to_le_bytes());
+        reader.read_exact(&mut bytes[4..])?;
+        let params = check_params(&bytes, Some(k), None).map_err(|e| VkFileError::BadHeader(e.to_string()))?.params;
+        if reader.read(&mut [0u8; 1])? != 0 {
+            return Err(VkFileError::BadHeader("参数之后还有多余的字节".to_string()));
+        }
+        Ok(Self { curve, circuit, layout, k, shape_hash, params })
+    }
+
+    /// 由注册表按文件中的电路标识和布局变体重建验证密钥, 形状哈希不一致时报错
+    #[cfg(feature = "prover")]
+    pub fn rebuild_vk(&self, registry: &CircuitRegistry) -> Result<VerifyingKey<EqAffine>, VkFileError> {
+        let vk = registry.keygen_vk(&self.circuit, &self.layout, &self.params)?;
+        if shape_hash(&vk) != self.shape_hash {
+            return Err(VkFileError::ShapeMismatch);
+        }
+        Ok(vk)
+    }
+}
+
+/// 把验证密钥导出到文件, circuit和layout为电路在注册表中的名字和布局变体
+pub fn export_vk_file(path: &Path, circuit: &str, layout: &str, k: u32, params: &Params<EqAffine>, vk: &VerifyingKey<EqAffine>) -> Result<(), VkFileError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    VkFile::new(circuit, layout, k, params.clone(), vk).write(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 验证密钥文件旁边的实例清单路径, 如`vk.bin`对应`vk.manifest.json`
+pub fn manifest_path(vk_path: &Path) -> PathBuf {
+    vk_path.with_extension("manifest.json")
+}
+
+/// 实例清单的JSON: 电路标识、布局变体、k、曲线、形状哈希, 以及实例列每一行的名字、类型和编码,
+/// 外部验证者据此构造公开输入, 不必读Rust源码
+pub fn instance_manifest_json(circuit: &str, layout: &str, k: u32, vk: &VerifyingKey<EqAffine>, slots: &[InstanceSlot]) -> String {
+    let shape_hash: String = shape_hash(vk).iter().map(|b| format!("{:02x}", b)).collect();
+    let rows: Vec<String> = slots.iter().map(|slot| format!("    {}", slot.to_json())).collect();
+    format!(
+        "{{\n  \"circuit\": {},\n  \"layout\": {},\n  \"k\": {},\n  \"curve\": \"{}\",\n  \"shape_hash\": \"{}\",\n  \"instances\": [\n{}\n  ]\n}}\n",
+        json_string(circuit), json_string(layout), k, CURVE_NAME, shape_hash, rows.join(",\n"),
+    )
+}
+
+/// 导出验证密钥, 同时写出实例清单, 返回清单路径
+pub fn export_vk_file_with_manifest(path: &Path, circuit: &str, layout: &str, k: u32, params: &Params<EqAffine>, vk: &VerifyingKey<EqAffine>, slots: &[InstanceSlot]) -> Result<PathBuf, VkFileError> {
+    export_vk_file(path, circuit, layout, k, params, vk)?;
+    let manifest = manifest_path(path);
+    std::fs::write(&manifest, instance_manifest_json(circuit, layout, k, vk, slots))?;
+    Ok(manifest)
+}
+
+/// 从文件读取验证密钥并验证证明, 电路由[`CircuitRegistry::builtin`]按文件中的布局变体重建
+///
+/// 文件本身读不出或重建不了验证密钥时返回`Err`, 否则验证的结论见[`VerifyOutcome`]
+#[cfg(feature = "prover")]
+pub fn verify_with_vk_file(path: &Path, public_inputs: &[Fp], proof: &[u8]) -> Result<VerifyOutcome, VkFileError> {
+    verify_with_vk_file_in(&CircuitRegistry::builtin(), path, public_inputs, proof)
+}
+
+/// 同[`verify_with_vk_file`], 电路从给定的注册表中查找
+#[cfg(feature = "prover")]
+pub fn verify_with_vk_file_in(registry: &CircuitRegistry, path: &Path, public_inputs: &[Fp], proof: &[u8]) -> Result<VerifyOutcome, VkFileError> {
+    let vk_file = VkFile::read(&mut BufReader::new(File::open(path)?))?;
+    let vk = vk_file.rebuild_vk(registry)?;
+    Ok(verify(&vk_file.params, &vk, public_inputs, proof))
+}
+
+#[cfg(feature = "prover")]
+#[test]
+fn test_vk_file() {
+    use crate::prover::{keygen, prove, setup};
+    use crate::{FibCircuit, FibStatement, FibWitness};
+
+    let witness = FibWitness::new(Fp::one(), Fp::one());
+    let circuit = FibCircuit::new(&FibStatement::from_witness(10, &witness).unwrap(), &witness);
+    let params = setup(4);
+    let pk = keygen(&params, &circuit).unwrap();
+    let proof = prove(&params, &pk, &circuit, &[Fp::from(55)]).unwrap();
+
+    let path = std::env::temp_dir().join("halo2_fib_test_vk_file.bin");
+    let layout = FibCircuit::<Fp>::instance_layout();
+    let manifest = export_vk_file_with_manifest(&path, "fib", "n=10", 4, &params, pk.get_vk(), layout.slots()).unwrap();
+    // 验证方只有文件、公开输入和证明
+    assert_eq!(verify_with_vk_file(&path, &[Fp::from(55)], &proof).unwrap(), VerifyOutcome::Valid);
+    assert!(matches!(verify_with_vk_file(&path, &[Fp::from(56)], &proof).unwrap(), VerifyOutcome::BadProof { .. }));
+    let text = std::fs::read_to_string(&manifest).unwrap();
+    assert!(text.contains(r#""layout": "n=10""#));
+    assert!(text.contains(r#"{"row": 0, "name": "target", "type": "field", "encoding": "le_hex32"}"#));
+    std::fs::remove_file(&manifest).unwrap();
+
+    // 布局变体与导出的验证密钥不符
+    export_vk_file(&path, "fib", "n=9", 4, &params, pk.get_vk()).unwrap();
+    assert!(matches!(verify_with_vk_file(&path, &[Fp::from(55)], &proof), Err(VkFileError::ShapeMismatch)));
+    export_vk_file(&path, "factorial", "n=10", 4, &params, pk.get_vk()).unwrap();
+    assert!(matches!(verify_with_vk_file(&path, &[Fp::from(55)], &proof), Err(VkFileError::Registry(RegistryError::UnknownCircuit(_)))));
+
+    // 篡改文件头的k, 在读取参数之前拒绝: 魔数8字节, 曲线、电路标识、布局变体各1字节长度加内容
+    let mut bytes = Vec::new();
+    VkFile::new("fib", "n=10", 4, params, pk.get_vk()).write(&mut bytes).unwrap();
+    let k_offset = 8 + (1 + CURVE_NAME.len()) + (1 + "fib".len()) + (1 + "n=10".len());
+    for k in [0, MAX_K + 1, u32::MAX] {
+        bytes[k_offset..k_offset + 4].copy_from_slice(&k.to_le_bytes());
+        assert!(matches!(VkFile::read(&mut bytes.as_slice()), Err(VkFileError::BadHeader(_))));
+    }
+    // k在范围内但与参数不一致
+    bytes[k_offset..k_offset + 4].copy_from_slice(&5u32.to_le_bytes());
+    assert!(matches!(VkFile::read(&mut bytes.as_slice()), Err(VkFileError::BadHeader(_))));
+    // 参数自带的k被改大, 同样在分配之前拒绝; 截断或多出字节也报错
+    bytes[k_offset..k_offset + 4].copy_from_slice(&4u32.to_le_bytes());
+    let params_offset = k_offset + 4 + 32;
+    for k in [5, 30, u32::MAX] {
+        let mut tampered = bytes.clone();
+        tampered[params_offset..params_offset + 4].copy_from_slice(&k.to_le_bytes());
+        assert!(matches!(VkFile::read(&mut tampered.as_slice()), Err(VkFileError::BadHeader(_))));
+    }
+    assert!(matches!(VkFile::read(&mut &bytes[..bytes.len() - 1]), Err(VkFileError::Io(_))));
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert!(matches!(VkFile::read(&mut longer.as_slice()), Err(VkFileError::BadHeader(_))));
+    assert!(VkFile::read(&mut bytes.as_slice()).is_ok());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "prover")]
+#[test]
+fn test_golden_fixtures() {
+    use crate::instance::decode_instances;
+    use crate::params_file::{params_bytes, params_hash, parse_hash};
+    use crate::prover::setup;
+
+    // 由testdata生成的固定用例, 见src/bin/testdata.rs
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/golden");
+    let entries = std::fs::read_dir(&root).unwrap_or_else(|e| panic!("读取{}失败: {}, 先用testdata生成固定用例", root.display(), e));
+    let mut cases: Vec<PathBuf> = entries.map(|entry| entry.expect("读取目录项失败").path()).filter(|path| path.is_dir()).collect();
+    cases.sort();
+    assert!(!cases.is_empty(), "{}中没有用例", root.display());
+    for dir in cases {
+        let read = |name: &str| std::fs::read(dir.join(name)).unwrap_or_else(|e| panic!("读取{}失败: {}", dir.join(name).display(), e));
+        // 参数与生成时记录的哈希一致, 也与当前的setup一致
+        let vk_file = VkFile::read(&mut read("vk.bin").as_slice()).unwrap();
+        let hash = params_hash(&params_bytes(&vk_file.params));
+        assert_eq!(parse_hash(String::from_utf8(read("params.hash")).unwrap().trim()), Some(hash), "{}", dir.display());
+        assert_eq!(params_hash(&params_bytes(&setup(vk_file.k))), hash, "{}", dir.display());
+        // 重建的验证密钥形状不变, 旧证明仍能通过验证
+        let proof = read("proof.bin");
+        let public_inputs = decode_instances(&read("instances.bin")).unwrap();
+        assert_eq!(verify_with_vk_file(&dir.join("vk.bin"), &public_inputs, &proof).unwrap(), VerifyOutcome::Valid, "{}", dir.display());
+        let wrong_inputs = decode_instances(&read("wrong_instances.bin")).unwrap();
+        assert!(!verify_with_vk_file(&dir.join("vk.bin"), &wrong_inputs, &proof).unwrap().is_valid(), "{}", dir.display());
+    }
+}